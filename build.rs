@@ -0,0 +1,61 @@
+//! Embeds the git revision and build time the DLL reports at startup
+
+use std::path::Path;
+use std::process::Command;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Trimmed stdout of a successful git command
+fn git(args: &[&str]) -> Option<String> {
+    Command::new("git")
+        .args(args)
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .and_then(|output| String::from_utf8(output.stdout).ok())
+        .map(|output| output.trim().to_string())
+}
+
+fn main() {
+    let git_hash = git(&["rev-parse", "--short=12", "HEAD"])
+        .filter(|hash| !hash.is_empty())
+        .map(|hash| {
+            // Tracked files edited since the commit don't match the hash
+            let dirty = git(&["status", "--porcelain", "--untracked-files=no"])
+                .is_some_and(|status| !status.is_empty());
+            if dirty {
+                format!("{}-dirty", hash)
+            } else {
+                hash
+            }
+        })
+        .unwrap_or_else(|| "unknown".to_string());
+
+    // SOURCE_DATE_EPOCH keeps reproducible builds byte-for-byte identical
+    let timestamp = std::env::var("SOURCE_DATE_EPOCH")
+        .ok()
+        .and_then(|epoch| epoch.parse::<u64>().ok())
+        .unwrap_or_else(|| {
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |elapsed| elapsed.as_secs())
+        });
+
+    println!("cargo:rustc-env=BUILD_GIT_HASH={}", git_hash);
+    println!("cargo:rustc-env=BUILD_TIMESTAMP={}", timestamp);
+    println!("cargo:rerun-if-env-changed=SOURCE_DATE_EPOCH");
+    // Any rerun-if-changed line replaces cargo's default of rerunning on
+    // every package change, so the sources have to be listed explicitly
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-changed=Cargo.toml");
+    println!("cargo:rerun-if-changed=src");
+
+    // Worktrees and git dependency checkouts keep these elsewhere, or not
+    // at all; a missing path would make cargo rerun on every build
+    for name in ["HEAD", "index", "refs/heads"] {
+        if let Some(path) = git(&["rev-parse", "--git-path", name])
+            && Path::new(&path).exists()
+        {
+            println!("cargo:rerun-if-changed={}", path);
+        }
+    }
+}
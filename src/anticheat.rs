@@ -0,0 +1,141 @@
+//! Detection of anti-cheat modules loaded into the game process
+//!
+//! Walks the loader list in load order, so the report also shows whether
+//! ACE came up before or after the game module and this DLL.
+
+use std::fmt;
+
+use crate::logger::Logger;
+use crate::memory::peb::{self, LoadedModule};
+
+/// Anti-cheat products recognised by module name
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AntiCheatVendor {
+    /// Tencent Anti-Cheat Expert, shipped with the game
+    Ace,
+    EasyAntiCheat,
+    BattlEye,
+}
+
+impl fmt::Display for AntiCheatVendor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::Ace => "ACE",
+            Self::EasyAntiCheat => "EasyAntiCheat",
+            Self::BattlEye => "BattlEye",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Module name prefixes identifying each vendor, compared case-insensitively
+const KNOWN_PREFIXES: [(&str, AntiCheatVendor); 4] = [
+    ("ACE-", AntiCheatVendor::Ace),
+    ("AntiCheatExpert", AntiCheatVendor::Ace),
+    ("EasyAntiCheat", AntiCheatVendor::EasyAntiCheat),
+    ("BEClient", AntiCheatVendor::BattlEye),
+];
+
+/// An anti-cheat module found in the process
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AntiCheatModule {
+    pub vendor: AntiCheatVendor,
+    pub name: String,
+    pub base: usize,
+    /// Position in the loader's load-order list
+    pub load_index: usize,
+}
+
+/// Identify the anti-cheat vendor of a module by its name
+pub fn classify(module_name: &str) -> Option<AntiCheatVendor> {
+    KNOWN_PREFIXES.iter().find_map(|&(prefix, vendor)| {
+        module_name
+            .get(..prefix.len())
+            .filter(|head| head.eq_ignore_ascii_case(prefix))
+            .map(|_| vendor)
+    })
+}
+
+/// Pick out the anti-cheat modules from a load-ordered module list
+pub fn detect_in(modules: &[LoadedModule]) -> Vec<AntiCheatModule> {
+    modules
+        .iter()
+        .enumerate()
+        .filter_map(|(load_index, module)| {
+            classify(&module.name).map(|vendor| AntiCheatModule {
+                vendor,
+                name: module.name.clone(),
+                base: module.base as usize,
+                load_index,
+            })
+        })
+        .collect()
+}
+
+/// List the anti-cheat modules currently loaded, in load order
+pub fn detect() -> Vec<AntiCheatModule> {
+    detect_in(&peb::loaded_modules())
+}
+
+/// Check if any ACE module is loaded in the process
+pub fn is_ace_loaded() -> bool {
+    detect()
+        .iter()
+        .any(|module| module.vendor == AntiCheatVendor::Ace)
+}
+
+/// Log the detected anti-cheat modules and their load order
+pub fn report() -> Vec<AntiCheatModule> {
+    let detected = detect();
+    if detected.is_empty() {
+        Logger::info("No anti-cheat modules detected");
+        return detected;
+    }
+
+    for module in &detected {
+        Logger::info(&format!(
+            "Anti-cheat module: {} ({}) at {:#x}, load order #{}",
+            module.name, module.vendor, module.base, module.load_index
+        ));
+    }
+    detected
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn module(name: &str, base: usize) -> LoadedModule {
+        LoadedModule {
+            base: base as _,
+            size: 0x1000,
+            name: name.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_classify_known_modules() {
+        assert_eq!(classify("ACE-Base64.dll"), Some(AntiCheatVendor::Ace));
+        assert_eq!(classify("ace-csi64.DLL"), Some(AntiCheatVendor::Ace));
+        assert_eq!(
+            classify("EasyAntiCheat_x64.dll"),
+            Some(AntiCheatVendor::EasyAntiCheat)
+        );
+        assert_eq!(classify("kernel32.dll"), None);
+        assert_eq!(classify("AC"), None);
+    }
+
+    #[test]
+    fn test_detect_in_keeps_load_order() {
+        let modules = [
+            module("Client-Win64-Shipping.exe", 0x1_4000_0000),
+            module("ntdll.dll", 0x7FF8_0000_0000),
+            module("ACE-Base64.dll", 0x7FF9_0000_0000),
+        ];
+
+        let detected = detect_in(&modules);
+        assert_eq!(detected.len(), 1);
+        assert_eq!(detected[0].load_index, 2);
+        assert_eq!(detected[0].base, 0x7FF9_0000_0000);
+    }
+}
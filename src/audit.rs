@@ -0,0 +1,156 @@
+//! Audit of pak-related file opens
+//!
+//! Hooks the game module's `CreateFileW` import and logs every opened path
+//! ending in one of [`AUDITED_EXTENSIONS`], showing the whole asset loading
+//! sequence rather than only the verification calls. The hook goes through
+//! the import table, so only opens made by the game module itself are seen;
+//! direct `NtCreateFile` calls from other modules are not.
+
+use std::cell::Cell;
+use std::sync::OnceLock;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use winapi::shared::minwindef::{DWORD, HMODULE, LPVOID};
+use winapi::um::errhandlingapi::{GetLastError, SetLastError};
+use winapi::um::handleapi::INVALID_HANDLE_VALUE;
+use winapi::um::winnt::{HANDLE, LPCWSTR};
+
+use crate::error::{AppError, Result};
+use crate::hooks::IatHook;
+use crate::logger::Logger;
+use crate::safety::StringConverter;
+
+/// File extensions whose opens are logged, compared case-insensitively
+pub const AUDITED_EXTENSIONS: [&str; 3] = [".pak", ".sig", ".utoc"];
+
+/// Module the game imports `CreateFileW` from
+const CREATE_FILE_DLL: &str = "KERNEL32.dll";
+
+type CreateFileWFn =
+    unsafe extern "system" fn(LPCWSTR, DWORD, DWORD, LPVOID, DWORD, DWORD, HANDLE) -> HANDLE;
+
+/// Real `CreateFileW`, stored before the slot is patched
+static ORIGINAL_CREATE_FILE_W: AtomicUsize = AtomicUsize::new(0);
+
+/// The installed import hook, kept for the lifetime of the process
+static AUDIT_HOOK: OnceLock<IatHook> = OnceLock::new();
+
+thread_local! {
+    /// Set while the current thread is logging, so opens made by the
+    /// logger itself are passed through silently
+    static IN_AUDIT: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Check whether opening `path` should be logged
+pub fn is_audited_path(path: &str) -> bool {
+    let lowercase = path.to_lowercase();
+    AUDITED_EXTENSIONS
+        .iter()
+        .any(|extension| lowercase.ends_with(extension))
+}
+
+/// Start logging pak-related file opens made by `module`
+pub fn install(module: HMODULE) -> Result<()> {
+    if AUDIT_HOOK.get().is_some() {
+        return Err(AppError::HookFailed {
+            message: "File access audit is already installed".to_string(),
+        });
+    }
+
+    let hook = IatHook::new(module, CREATE_FILE_DLL, "CreateFileW")?;
+
+    // Calls can arrive as soon as the slot is swapped, before the hook
+    // records the original, so publish it first
+    let original = unsafe { std::ptr::read_volatile(hook.slot()) };
+    ORIGINAL_CREATE_FILE_W.store(original, Ordering::Release);
+
+    hook.apply(create_file_w_audit as CreateFileWFn as usize)?;
+    let _ = AUDIT_HOOK.set(hook);
+    Ok(())
+}
+
+/// Stop logging file opens, restoring the original `CreateFileW`
+pub fn uninstall() -> Result<()> {
+    match AUDIT_HOOK.get() {
+        Some(hook) if hook.is_active() => hook.remove(),
+        _ => Ok(()),
+    }
+}
+
+/// Check if the audit hook is installed and active
+pub fn is_installed() -> bool {
+    AUDIT_HOOK.get().is_some_and(IatHook::is_active)
+}
+
+/// `CreateFileW` replacement logging audited paths
+unsafe extern "system" fn create_file_w_audit(
+    file_name: LPCWSTR,
+    desired_access: DWORD,
+    share_mode: DWORD,
+    security_attributes: LPVOID,
+    creation_disposition: DWORD,
+    flags_and_attributes: DWORD,
+    template_file: HANDLE,
+) -> HANDLE {
+    let original: CreateFileWFn =
+        unsafe { std::mem::transmute(ORIGINAL_CREATE_FILE_W.load(Ordering::Acquire)) };
+    let handle = unsafe {
+        original(
+            file_name,
+            desired_access,
+            share_mode,
+            security_attributes,
+            creation_disposition,
+            flags_and_attributes,
+            template_file,
+        )
+    };
+
+    if IN_AUDIT.with(Cell::get) {
+        return handle;
+    }
+
+    // Logging must not clobber the error code the caller is about to read
+    let last_error = unsafe { GetLastError() };
+    IN_AUDIT.with(|flag| flag.set(true));
+    if let Ok(path) = unsafe { StringConverter::pcwstr_to_string(file_name) }
+        && is_audited_path(&path)
+    {
+        if handle == INVALID_HANDLE_VALUE {
+            Logger::info(&format!(
+                "File open: '{}' failed (error {})",
+                path, last_error
+            ));
+        } else {
+            Logger::info(&format!(
+                "File open: '{}' (access {:#x})",
+                path, desired_access
+            ));
+        }
+    }
+    IN_AUDIT.with(|flag| flag.set(false));
+    unsafe { SetLastError(last_error) };
+
+    handle
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_audited_paths_match_extensions_case_insensitively() {
+        assert!(is_audited_path(
+            "../../../Client/Content/Paks/pakchunk0-WindowsNoEditor.pak"
+        ));
+        assert!(is_audited_path("C:\\Game\\Paks\\PAKCHUNK0.SIG"));
+        assert!(is_audited_path("global.utoc"));
+        assert!(!is_audited_path("global.ucas"));
+        assert!(!is_audited_path("Engine.ini"));
+    }
+
+    #[test]
+    fn test_not_installed_by_default() {
+        assert!(!is_installed());
+    }
+}
@@ -0,0 +1,245 @@
+//! Offline signature check against a game executable on disk
+//!
+//! Maps the executable without running it and scans it with the same
+//! [`PatternScanner`] the DLL uses, printing the RVA, section and function
+//! start of every match. Signature maintainers can validate patterns for a
+//! new game build before launching it.
+//!
+//! Exits with 0 when every signature matched exactly once inside an
+//! executable section, 1 when one did not, and 2 on usage or file errors.
+
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use wuwa_sig_rs::config::Config;
+use wuwa_sig_rs::error::{AppError, Result};
+use wuwa_sig_rs::memory::pe::PeImage;
+use wuwa_sig_rs::memory::{MappedImage, PatternScanner, Rva};
+use wuwa_sig_rs::signatures::{Signature, parse_pattern};
+
+const USAGE: &str = "\
+Usage: wuwa-sig-scan <game.exe> [options]
+
+Options:
+  --config <file>      read signatures from this configuration file
+  --signature <name>   only check this signature; may be repeated
+  --pattern <bytes>    check a pattern such as \"48 8B ?? 05\" instead
+  --help               show this help";
+
+/// What to scan and which signatures to check
+#[derive(Debug, Default, PartialEq, Eq)]
+struct Options {
+    executable: PathBuf,
+    config: Option<PathBuf>,
+    names: Vec<String>,
+    patterns: Vec<String>,
+}
+
+impl Options {
+    /// Parse the arguments after the program name; `None` asks for help
+    fn parse(args: impl IntoIterator<Item = String>) -> std::result::Result<Option<Self>, String> {
+        let mut options = Options::default();
+        let mut executable = None;
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            let mut value = || args.next().ok_or_else(|| format!("{} needs a value", arg));
+            match arg.as_str() {
+                "--help" | "-h" => return Ok(None),
+                "--config" => options.config = Some(PathBuf::from(value()?)),
+                "--signature" => options.names.push(value()?),
+                "--pattern" => options.patterns.push(value()?),
+                _ if arg.starts_with("--") => return Err(format!("unknown option '{}'", arg)),
+                _ if executable.is_some() => return Err(format!("unexpected argument '{}'", arg)),
+                _ => executable = Some(PathBuf::from(arg)),
+            }
+        }
+        options.executable = executable.ok_or("missing the executable to scan")?;
+        Ok(Some(options))
+    }
+
+    /// The signatures to check: the given patterns, or else the configured
+    /// signatures, limited to `names` if any
+    fn signatures(&self) -> Result<Vec<Signature>> {
+        if !self.patterns.is_empty() {
+            return self
+                .patterns
+                .iter()
+                .enumerate()
+                .map(|(index, text)| {
+                    let (pattern, mask) =
+                        parse_pattern(text).map_err(|reason| AppError::InvalidConfig {
+                            field: "--pattern".to_string(),
+                            reason,
+                        })?;
+                    let signature =
+                        Signature::new(&format!("pattern {}", index + 1), &pattern, &mask, 0);
+                    signature.validate()?;
+                    Ok(signature)
+                })
+                .collect();
+        }
+
+        let db = Config::load(self.config.as_deref())?.signature_db();
+        if self.names.is_empty() {
+            return Ok(db.entries().to_vec());
+        }
+        self.names
+            .iter()
+            .map(|name| {
+                db.get(name)
+                    .cloned()
+                    .ok_or_else(|| AppError::InvalidConfig {
+                        field: "--signature".to_string(),
+                        reason: format!("no signature named '{}'", name),
+                    })
+            })
+            .collect()
+    }
+}
+
+/// Print the matches of `signature` in `image`, returning whether it is
+/// usable: exactly one match, in executable code and the required section
+fn check(
+    scanner: &PatternScanner,
+    image: &MappedImage,
+    pe: &PeImage,
+    signature: &Signature,
+) -> Result<bool> {
+    let offsets = scanner.find_matches(image.bytes(), &signature.pattern, &signature.mask)?;
+    let versions = if signature.versions.is_any() {
+        "any version".to_string()
+    } else {
+        signature.versions.to_string()
+    };
+
+    let mut clean = offsets.len() == 1;
+    let verdict = match offsets.len() {
+        0 => "no match".to_string(),
+        1 => "unique".to_string(),
+        count => format!("{} matches, not unique", count),
+    };
+    println!("{} ({}): {}", signature.name, versions, verdict);
+
+    for offset in offsets {
+        // Offsets within `SizeOfImage` fit in 32 bits
+        let rva = Rva(offset as u32);
+        let section = pe.section_for_rva(rva.0);
+        let mut notes = Vec::new();
+        match section {
+            Some(section) if !section.is_executable() => notes.push("not executable"),
+            Some(_) => {}
+            None => notes.push("outside any section"),
+        }
+        if let Some(required) = &signature.section
+            && section.is_none_or(|section| section.name != *required)
+        {
+            notes.push("outside the required section");
+        }
+        clean &= notes.is_empty();
+
+        let function = signature
+            .function_offset
+            .checked_neg()
+            .and_then(|delta| offset.checked_add_signed(delta))
+            .filter(|&start| start < image.bytes().len())
+            .map_or("outside the image".to_string(), |start| {
+                format!("{:#x}", start)
+            });
+        println!(
+            "  {} in {}, function at {}{}",
+            rva,
+            section.map_or("-", |section| section.name.as_str()),
+            function,
+            if notes.is_empty() {
+                String::new()
+            } else {
+                format!(" ({})", notes.join(", "))
+            }
+        );
+    }
+    Ok(clean)
+}
+
+fn run(options: &Options) -> Result<bool> {
+    let signatures = options.signatures()?;
+    let image = MappedImage::open(&options.executable)?;
+    let pe = image.pe()?;
+    println!(
+        "{}: {:#x} bytes, {} sections, timestamp {:#010x}",
+        image.path().display(),
+        image.bytes().len(),
+        pe.sections().len(),
+        pe.timestamp()
+    );
+
+    let scanner = PatternScanner::with_caching(false);
+    let mut clean = true;
+    for signature in &signatures {
+        clean &= check(&scanner, &image, &pe, signature)?;
+    }
+    Ok(clean)
+}
+
+fn main() -> ExitCode {
+    let options = match Options::parse(std::env::args().skip(1)) {
+        Ok(Some(options)) => options,
+        Ok(None) => {
+            println!("{}", USAGE);
+            return ExitCode::SUCCESS;
+        }
+        Err(message) => {
+            eprintln!("wuwa-sig-scan: {}\n\n{}", message, USAGE);
+            return ExitCode::from(2);
+        }
+    };
+
+    match run(&options) {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::from(1),
+        Err(e) => {
+            eprintln!("wuwa-sig-scan: {}", e);
+            ExitCode::from(2)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> std::result::Result<Option<Options>, String> {
+        Options::parse(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn test_parse_options() {
+        let options = parse(&["game.exe", "--signature", "global", "--signature", "cn"])
+            .unwrap()
+            .unwrap();
+        assert_eq!(options.executable, PathBuf::from("game.exe"));
+        assert_eq!(options.names, ["global", "cn"]);
+        assert_eq!(options.config, None);
+
+        assert_eq!(parse(&["--help"]).unwrap(), None);
+        assert!(parse(&[]).is_err());
+        assert!(parse(&["game.exe", "--config"]).is_err());
+        assert!(parse(&["game.exe", "other.exe"]).is_err());
+        assert!(parse(&["game.exe", "--verbose"]).is_err());
+    }
+
+    #[test]
+    fn test_patterns_replace_configured_signatures() {
+        let options = parse(&["game.exe", "--pattern", "48 8B ?? 05"])
+            .unwrap()
+            .unwrap();
+        let signatures = options.signatures().unwrap();
+        assert_eq!(signatures.len(), 1);
+        assert_eq!(signatures[0].pattern, [0x48, 0x8B, 0, 0x05]);
+        assert_eq!(signatures[0].mask, "xx?x");
+
+        let options = parse(&["game.exe", "--signature", "missing"])
+            .unwrap()
+            .unwrap();
+        assert!(options.signatures().is_err());
+    }
+}
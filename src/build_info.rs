@@ -0,0 +1,103 @@
+//! Version, revision and build time of this build
+//!
+//! `build.rs` records the git revision and the build time, so support can
+//! tell exactly which build a log, crash report or loader is talking to.
+//! The DLL logs [`BuildInfo::CURRENT`] at startup and loaders read it
+//! through `wuwa_sig_build_info`.
+
+use std::time::{Duration, UNIX_EPOCH};
+
+use crate::constants::constants::SIGNATURE_DB_VERSION;
+use crate::logger::CivilTime;
+
+/// Identification of a build of the crate
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BuildInfo {
+    /// Crate version from `Cargo.toml`
+    pub version: &'static str,
+    /// Abbreviated git commit the build was made from, suffixed `-dirty` if
+    /// tracked files had uncommitted changes, or `unknown` when built
+    /// outside a git checkout
+    pub git_hash: &'static str,
+    /// Seconds since the Unix epoch when the build ran, or
+    /// `SOURCE_DATE_EPOCH` if set
+    pub timestamp: u64,
+    /// Revision of the built-in signature
+    pub signature_db_version: u32,
+}
+
+impl BuildInfo {
+    /// This build
+    pub const CURRENT: Self = Self {
+        version: env!("CARGO_PKG_VERSION"),
+        git_hash: env!("BUILD_GIT_HASH"),
+        timestamp: parse_timestamp(env!("BUILD_TIMESTAMP")),
+        signature_db_version: SIGNATURE_DB_VERSION,
+    };
+
+    /// Build time as a date, in UTC
+    pub fn built_at(&self) -> CivilTime {
+        CivilTime::utc(UNIX_EPOCH + Duration::from_secs(self.timestamp))
+    }
+}
+
+impl std::fmt::Display for BuildInfo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let built_at = self.built_at();
+        write!(
+            f,
+            "{} ({}, built {:04}-{:02}-{:02} {:02}:{:02}Z, signatures v{})",
+            self.version,
+            self.git_hash,
+            built_at.year,
+            built_at.month,
+            built_at.day,
+            built_at.hour,
+            built_at.minute,
+            self.signature_db_version
+        )
+    }
+}
+
+/// Parse the decimal `BUILD_TIMESTAMP` at compile time; zero if malformed
+const fn parse_timestamp(text: &str) -> u64 {
+    let bytes = text.as_bytes();
+    let mut value: u64 = 0;
+    let mut index = 0;
+    while index < bytes.len() {
+        let digit = bytes[index];
+        if !digit.is_ascii_digit() {
+            return 0;
+        }
+        value = value * 10 + (digit - b'0') as u64;
+        index += 1;
+    }
+    value
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_timestamp() {
+        assert_eq!(parse_timestamp("1760659200"), 1_760_659_200);
+        assert_eq!(parse_timestamp("0"), 0);
+        assert_eq!(parse_timestamp("17x"), 0);
+    }
+
+    #[test]
+    fn test_display_names_every_part() {
+        let info = BuildInfo {
+            version: "1.2.3",
+            git_hash: "0123456789ab",
+            timestamp: 1_760_659_200,
+            signature_db_version: 4,
+        };
+        assert_eq!(
+            info.to_string(),
+            "1.2.3 (0123456789ab, built 2025-10-17 00:00Z, signatures v4)"
+        );
+        assert_eq!(BuildInfo::CURRENT.version, env!("CARGO_PKG_VERSION"));
+    }
+}
@@ -0,0 +1,1092 @@
+//! The pak check hook and the steps installing it
+//!
+//! Everything between loading the configuration and a running hook that
+//! does not depend on how the library was loaded: locating the game module,
+//! scanning for the signatures, waiting for ACE and installing the hooks,
+//! plus the replacement pak check itself and its watchdog checks.
+
+use winapi::um::psapi::MODULEINFO;
+
+use crate::error::{AppError, Result};
+use crate::logger::Logger;
+use crate::memory::PatternScanner;
+use crate::memory::pe::PeImage;
+use crate::signatures::Signature;
+
+// Only the DLL flow installs the hook, the scan helpers above are shared
+#[cfg(feature = "dll")]
+use std::path::Path;
+#[cfg(feature = "dll")]
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+#[cfg(feature = "dll")]
+use std::sync::{Arc, Mutex, OnceLock, RwLock};
+#[cfg(feature = "dll")]
+use std::thread;
+#[cfg(feature = "dll")]
+use std::time::{Duration, Instant};
+
+#[cfg(feature = "dll")]
+use ilhook::x64::Registers;
+#[cfg(feature = "dll")]
+use winapi::shared::minwindef::HMODULE;
+
+#[cfg(feature = "dll")]
+use crate::config::Config;
+#[cfg(feature = "dll")]
+use crate::constants::constants::memory::{
+    ACE_PROGRESS_INTERVAL_MS, HOOK_BACKUP_LEN, PARENT_POINTER_OFFSET, SIGNATURE_HINT_WINDOW,
+    V4_POINTER_OFFSET,
+};
+#[cfg(feature = "dll")]
+use crate::constants::constants::*;
+#[cfg(feature = "dll")]
+use crate::filter::{PakFilter, UnmatchedPakAction};
+#[cfg(feature = "dll")]
+use crate::hooks::{
+    ArgReader, CallbackChain, HookAction, HookCall, HookSpec, HookStats, InvocationHistory,
+    OneShot, SpoofSetting,
+};
+#[cfg(feature = "dll")]
+use crate::logger::{BurstLimiter, LogCategory, LogLevel, LogOnce};
+#[cfg(feature = "dll")]
+use crate::memory::{ModuleScanner, Rva};
+#[cfg(feature = "dll")]
+use crate::reload::ReloadableSetting;
+#[cfg(feature = "dll")]
+use crate::retry::retry;
+#[cfg(feature = "dll")]
+use crate::safety::{Field, Layout, MemoryAccess, MemorySnapshot, StructReader, unreal::FString};
+#[cfg(feature = "dll")]
+use crate::signatures::SignatureDb;
+#[cfg(feature = "dll")]
+use crate::{hooks, logger, memory, overlay, reload, sig, update};
+
+/// Statistics of the installed pak check hook, set once it is applied
+#[cfg(feature = "dll")]
+static PAK_CHECK_STATS: OnceLock<Arc<HookStats>> = OnceLock::new();
+
+/// Recent calls of the installed pak check hook, set once it is applied
+#[cfg(feature = "dll")]
+static PAK_CHECK_HISTORY: OnceLock<Arc<InvocationHistory>> = OnceLock::new();
+
+/// Spoofed return value of the installed pak check hook, set once it is applied
+#[cfg(feature = "dll")]
+static PAK_CHECK_SPOOF: OnceLock<Arc<SpoofSetting>> = OnceLock::new();
+
+/// Callbacks of the installed pak check hook, set once it is applied
+#[cfg(feature = "dll")]
+static PAK_CHECK_CHAIN: OnceLock<Arc<CallbackChain>> = OnceLock::new();
+
+/// One-shot setting of the installed pak check hook, set once it is applied
+#[cfg(feature = "dll")]
+static PAK_CHECK_ONCE: OnceLock<Arc<OneShot>> = OnceLock::new();
+
+/// Whether the pak check hook only observes, set before it is applied
+#[cfg(feature = "dll")]
+static PAK_CHECK_DRY_RUN: AtomicBool = AtomicBool::new(false);
+
+/// Allow/deny lists applied by the pak check hook, set before it is applied
+/// and replaced when the configuration file is reloaded
+#[cfg(feature = "dll")]
+static PAK_FILTER: RwLock<Option<PakFilter>> = RwLock::new(None);
+
+/// Limit on the per-pak verification lines, burst set from the configuration
+#[cfg(feature = "dll")]
+pub(crate) static PAK_LOG_LIMIT: BurstLimiter =
+    BurstLimiter::new(PAK_LOG_BURST, Duration::from_secs(1));
+
+/// Warning for a missing original pak check, logged on the first call only
+#[cfg(feature = "dll")]
+static MISSING_ORIGINAL: LogOnce = LogOnce::new();
+
+/// Start of the pak check as the hook left it, compared by the watchdog
+#[cfg(feature = "dll")]
+static PAK_CHECK_BYTES: OnceLock<MemorySnapshot> = OnceLock::new();
+
+/// State changes of the pak check hook made through the hook manager since
+/// it was applied, so the watchdog doesn't report our own writes
+#[cfg(feature = "dll")]
+static PAK_CHECK_CHANGES: AtomicU64 = AtomicU64::new(0);
+
+/// Signature of the original pak verification function
+#[cfg(feature = "dll")]
+type PakCheckFn = unsafe extern "win64" fn(usize, usize, usize, usize) -> usize;
+
+/// Main hook replacement function for PAK file verification bypass
+///
+/// This function is called instead of the original PAK file verification function.
+/// It extracts the PAK file name from the register context, logs the verification
+/// attempt, and returns success for every pak the configured filter bypasses.
+///
+/// # Safety
+///
+/// This function is marked as unsafe because it:
+/// - Accesses raw pointers from the register context
+/// - Performs memory operations that could potentially access invalid memory
+/// - Is called from native code with specific calling conventions
+///
+/// # Arguments
+///
+/// * `reg` - Pointer to the x64 register context containing function arguments
+/// * `original` - Address of the original function, 0 if the backend has none
+/// * `_` - Unused parameter (reserved for future use)
+///
+/// # Returns
+///
+/// The configured spoof value (`BYPASS_SUCCESS` by default) for bypassed
+/// paks, otherwise the result of the configured [`UnmatchedPakAction`], or
+/// of the original check when spoofing is disabled or in a dry run
+#[cfg(feature = "dll")]
+pub(crate) unsafe extern "win64" fn pak_file_check_replacement(
+    reg: *mut Registers,
+    original: usize,
+    _: usize,
+) -> usize {
+    // Unwinding into the game would abort it; a panic gets the real check
+    std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| unsafe {
+        check_pak(reg, original)
+    }))
+    .unwrap_or_else(|_| unsafe { call_original(reg, original) }.unwrap_or(BYPASS_FAILURE))
+}
+
+/// Body of [`pak_file_check_replacement`]
+#[cfg(feature = "dll")]
+unsafe fn check_pak(reg: *mut Registers, original: usize) -> usize {
+    // In one-shot mode only the first call runs the hook; later ones race
+    // the restore and get the real check
+    let once = PAK_CHECK_ONCE.get().filter(|once| once.is_armed());
+    if once.is_some_and(|once| !once.fire()) {
+        return unsafe { call_original(reg, original) }.unwrap_or(BYPASS_FAILURE);
+    }
+
+    let pak_name = unsafe { extract_pak_name_unsafe(reg) };
+
+    // Built-in behaviour, used unless a registered callback decides the call
+    let mut bypass = false;
+    let mut decide = |registers: *mut Registers| {
+        let spoof_value = PAK_CHECK_SPOOF
+            .get()
+            .map_or(Some(BYPASS_SUCCESS), |spoof| spoof.value());
+        bypass = spoof_value.is_some()
+            && with_pak_filter(|filter| {
+                filter.is_none_or(|filter| filter.should_bypass(pak_name.as_deref().ok()))
+            });
+        match spoof_value {
+            Some(value) if bypass => value,
+            Some(_) => unsafe { verify_unmatched(registers, original) },
+            None => unsafe { call_original(registers, original) }.unwrap_or(BYPASS_FAILURE),
+        }
+    };
+
+    // A dry run reports the real verdict of every pak and lets callbacks
+    // neither rewrite arguments nor decide the result
+    let dry_run = PAK_CHECK_DRY_RUN.load(Ordering::Relaxed);
+    let chain = PAK_CHECK_CHAIN
+        .get()
+        .filter(|chain| !dry_run && !chain.is_empty());
+
+    let result = match chain {
+        _ if dry_run => unsafe { call_original(reg, original) }.unwrap_or(BYPASS_FAILURE),
+        Some(chain) => {
+            let mut call = HookCall {
+                registers: unsafe { &mut *reg },
+                original,
+                name: pak_name.as_deref().ok(),
+            };
+            chain.run(&mut call, |call| decide(call.registers))
+        }
+        None => decide(reg),
+    };
+
+    let published = overlay::global_overlay();
+    if let Some(stats) = PAK_CHECK_STATS.get() {
+        stats.record(pak_name.as_deref().ok());
+    }
+    if let Some(history) = PAK_CHECK_HISTORY.get() {
+        history.record(pak_name.as_deref().ok(), result);
+    }
+
+    match pak_name {
+        Ok(name) => {
+            if let Some(overlay) = published {
+                overlay.record_pak(&name);
+            }
+            log_pak_check(&name, result, bypass, dry_run);
+        }
+        Err(e) => {
+            if let Some(overlay) = published {
+                overlay.record_name_failure();
+            }
+            Logger::bypass(&format!(
+                "Could not read pak name: {}, returning {:#x}",
+                e, result
+            ));
+        }
+    }
+
+    if once.is_some()
+        && let Err(e) = hooks::global_hook_manager().finish_once(PAK_CHECK_HOOK_NAME)
+    {
+        Logger::error(&format!("Failed to remove one-shot pak check hook: {}", e));
+    }
+
+    result
+}
+
+/// Log the verdict on one pak, within the configured burst per second
+#[cfg(feature = "dll")]
+fn log_pak_check(name: &str, result: usize, bypass: bool, dry_run: bool) {
+    let Some(held_back) = PAK_LOG_LIMIT.admit() else {
+        return;
+    };
+    report_held_back(held_back);
+
+    let result_text = format!("{:#x}", result);
+    let fields = [("pak", name), ("result", result_text.as_str())];
+    let message = if dry_run {
+        let verdict = if result == BYPASS_SUCCESS {
+            "passed"
+        } else {
+            "FAILED"
+        };
+        format!("Verifying pak: '{}' -> {} (dry run)", name, verdict)
+    } else if bypass {
+        format!("Verifying pak: '{}' -> OK", name)
+    } else {
+        format!("Verifying pak: '{}' -> returned {:#x}", name, result)
+    };
+    Logger::event_in(LogCategory::Pak, LogLevel::Info, &message, &fields);
+}
+
+/// Log how many pak checks the burst limit held back, if any
+#[cfg(feature = "dll")]
+pub(crate) fn report_held_back(held_back: u64) {
+    if held_back > 0 {
+        let message = format!("{} more pak check(s) not shown", held_back);
+        Logger::event_in(LogCategory::Pak, LogLevel::Info, &message, &[]);
+    }
+}
+
+/// Log a configuration message at info level in the config category
+#[cfg(feature = "dll")]
+fn log_config(msg: &str) {
+    Logger::event_in(LogCategory::Config, LogLevel::Info, msg, &[]);
+}
+
+/// Verification result for a pak the filter does not bypass
+#[cfg(feature = "dll")]
+unsafe fn verify_unmatched(reg: *mut Registers, original: usize) -> usize {
+    let action =
+        with_pak_filter(|filter| filter.map(PakFilter::unmatched_action)).unwrap_or_default();
+
+    match action {
+        UnmatchedPakAction::CallOriginal => {
+            unsafe { call_original(reg, original) }.unwrap_or(BYPASS_FAILURE)
+        }
+        UnmatchedPakAction::Fail => BYPASS_FAILURE,
+    }
+}
+
+/// Run `f` with the current pak filter, `None` before the hook is set up
+#[cfg(feature = "dll")]
+fn with_pak_filter<T>(f: impl FnOnce(Option<&PakFilter>) -> T) -> T {
+    let filter = PAK_FILTER.read().ok();
+    f(filter.as_ref().and_then(|filter| filter.as_ref()))
+}
+
+/// Build the pak filter from `config` and hand it to the pak check hook
+#[cfg(feature = "dll")]
+fn set_pak_filter(config: &Config) {
+    let filter = PakFilter::new(&config.pak_allow, &config.pak_deny)
+        .with_unmatched_action(config.unmatched_pak_action);
+    if !filter.is_pass_through() {
+        Logger::info(&format!(
+            "Bypassing paks matching {:?}, except {:?}; others: {:?}",
+            config.pak_allow, config.pak_deny, config.unmatched_pak_action
+        ));
+    }
+    if let Ok(mut current) = PAK_FILTER.write() {
+        *current = Some(filter);
+    }
+}
+
+/// Run the original pak check with the intercepted arguments
+///
+/// Returns `None` when the backend provided no original function.
+#[cfg(feature = "dll")]
+unsafe fn call_original(reg: *mut Registers, original: usize) -> Option<usize> {
+    if original == 0 {
+        if MISSING_ORIGINAL.first() {
+            Logger::warning("Original pak check is unavailable, reporting failure");
+        }
+        return None;
+    }
+
+    unsafe {
+        let original: PakCheckFn = std::mem::transmute(original);
+        let reg = &*reg;
+        Some(original(
+            reg.rcx as usize,
+            reg.rdx as usize,
+            reg.r8 as usize,
+            reg.r9 as usize,
+        ))
+    }
+}
+
+/// The pak check's first argument, up to the field leading to the pak name
+#[cfg(feature = "dll")]
+const PAK_CHECK_ARG: Layout = Layout::new("pak check argument", V4_POINTER_OFFSET + 8);
+
+#[cfg(feature = "dll")]
+const PAK_CHECK_ARG_V4: Field<usize> = Field::new("v4", V4_POINTER_OFFSET);
+
+#[cfg(feature = "dll")]
+const PAK_CHECK_V4: Layout = Layout::new("v4", 8);
+
+#[cfg(feature = "dll")]
+const PAK_CHECK_V4_PARENT: Field<usize> = Field::new("parent", 0);
+
+#[cfg(feature = "dll")]
+const PAK_CHECK_PARENT: Layout =
+    Layout::new("parent", PARENT_POINTER_OFFSET + size_of::<FString>());
+
+#[cfg(feature = "dll")]
+const PAK_CHECK_PARENT_NAME: Field<FString> = Field::new("pak name", PARENT_POINTER_OFFSET);
+
+#[cfg(feature = "dll")]
+pub(crate) unsafe fn extract_pak_name_unsafe(reg: *mut Registers) -> Result<String> {
+    unsafe {
+        let this = ArgReader::new(reg)?.arg(0)?;
+        let name = StructReader::new(this, &PAK_CHECK_ARG)?
+            .follow(PAK_CHECK_ARG_V4, &PAK_CHECK_V4)?
+            .follow(PAK_CHECK_V4_PARENT, &PAK_CHECK_PARENT)?
+            .read(PAK_CHECK_PARENT_NAME)?;
+        name.text()
+    }
+}
+
+/// Map a signature match back to the start of the pak-check function,
+/// checking it stays inside the module
+pub(crate) fn function_start(
+    match_addr: *mut u8,
+    signature: &Signature,
+    module_info: &MODULEINFO,
+) -> Result<*mut u8> {
+    let base = module_info.lpBaseOfDll as usize;
+    let rva = (match_addr as usize).wrapping_sub(base);
+    signature
+        .function_offset
+        .checked_neg()
+        .and_then(|delta| rva.checked_add_signed(delta))
+        .filter(|start| *start < module_info.SizeOfImage as usize)
+        .map(|start| (base + start) as *mut u8)
+        .ok_or_else(|| AppError::FunctionOutsideModule {
+            name: signature.name.clone(),
+            offset: signature.function_offset,
+            rva,
+        })
+}
+
+/// Target function located during the scan phase
+#[cfg(feature = "dll")]
+pub(crate) struct ScanOutcome {
+    /// Adjusted address of the function to hook
+    pub(crate) target_func: *mut u8,
+    /// Whether the signature matched exactly once in executable memory
+    pub(crate) signature_clean: bool,
+}
+
+/// How long the hook thread waited for ACE to finish initializing
+#[cfg(feature = "dll")]
+pub(crate) struct AceWaitOutcome {
+    /// Time spent waiting for the preamble to settle
+    pub(crate) waited: Duration,
+    /// Number of preamble reads performed
+    pub(crate) polls: u32,
+}
+
+/// Warn about bytes of the hooked pak check changed since the last check
+///
+/// After the hook manager removed or reapplied the hook, such as through
+/// IPC or a one-shot hook finishing, the current bytes become the new
+/// baseline instead.
+#[cfg(feature = "dll")]
+pub(crate) fn check_pak_check_bytes() {
+    static LAST: Mutex<Option<(u64, MemorySnapshot)>> = Mutex::new(None);
+
+    let Some(hooked) = PAK_CHECK_BYTES.get() else {
+        return;
+    };
+    let changes = PAK_CHECK_CHANGES.load(Ordering::Acquire);
+    let Ok(current) = (unsafe { hooked.recapture() }) else {
+        return;
+    };
+    // The manager counts a change after writing, so one in progress may
+    // already show in `current`
+    if PAK_CHECK_CHANGES.load(Ordering::Acquire) != changes {
+        return;
+    }
+
+    let mut last = LAST.lock().unwrap_or_else(|e| e.into_inner());
+    let (seen, previous) = last
+        .as_ref()
+        .map_or((0, hooked), |(seen, snapshot)| (*seen, snapshot));
+    if seen == changes {
+        for change in previous.diff(&current) {
+            Logger::warning(&format!("Pak check was modified at {}", change));
+        }
+    }
+    *last = Some((changes, current));
+}
+
+/// Apply the safe settings of a reloaded configuration
+#[cfg(feature = "dll")]
+pub(crate) fn apply_reload(config: &mut Config, updated: &Config) {
+    log_config("Configuration file changed, reloading...");
+    let outcome = reload::apply_safe_changes(config, updated);
+    for setting in &outcome.applied {
+        match setting {
+            ReloadableSetting::LogLevel => {
+                logger::set_global_min_level(config.log_level);
+                logger::set_global_filters(config.log_filters.clone());
+                log_config(&format!("Log level is now {}", config.log_level.as_str()));
+            }
+            ReloadableSetting::PakFilter => {
+                set_pak_filter(config);
+                log_config("Pak filter reloaded");
+            }
+            ReloadableSetting::WatchdogInterval => log_config(&format!(
+                "Watchdog interval is now {}ms",
+                config.watchdog_interval_ms
+            )),
+        }
+    }
+    if outcome.restart_required {
+        Logger::warning("Other changed settings take effect after restarting the game");
+    }
+}
+
+/// Add the signatures of the update URL, or of its cache, to `config`
+///
+/// Failures only cost the downloaded signatures, so they are logged and the
+/// configured ones are used alone.
+#[cfg(feature = "dll")]
+pub(crate) fn update_signatures(config: &mut Config, cache_dir: Option<&Path>) {
+    log_config("Checking for signature updates...");
+    match update::fetch(config, cache_dir) {
+        Ok(downloaded) => {
+            let added = update::merge(config, downloaded);
+            log_config(&format!("{} downloaded signature(s) added", added));
+        }
+        Err(e) => Logger::warning(&format!(
+            "Continuing with the configured signatures only: {}",
+            e
+        )),
+    }
+}
+
+/// Load the embedded defaults, the configuration file at `config_path` and
+/// environment overrides, and validate the result
+#[cfg(feature = "dll")]
+pub(crate) fn load_config(config_path: Option<&Path>) -> Result<Config> {
+    log_config("Creating configuration...");
+    match config_path {
+        Some(path) => log_config(&format!("Loading configuration from '{}'", path.display())),
+        None => log_config(&format!(
+            "No {} next to the DLL, using built-in defaults",
+            CONFIG_FILE_NAME
+        )),
+    }
+    let config = Config::load(config_path).inspect_err(|e| {
+        Logger::error(&format!("Configuration rejected: {}", e));
+    })?;
+    logger::set_global_format(config.log_format);
+    logger::set_global_timestamps(config.log_timestamps);
+    logger::set_global_colors(config.log_color, config.log_theme);
+    logger::set_global_min_level(config.log_level);
+    logger::set_global_filters(config.log_filters.clone());
+    if config.log_queue_capacity > 0 {
+        match logger::start_global_worker(config.log_queue_capacity, config.log_overflow) {
+            Ok(()) => Logger::info(&format!(
+                "Logging through a writer thread ({} records, {} when full)",
+                config.log_queue_capacity,
+                config.log_overflow.as_str()
+            )),
+            Err(e) => Logger::warning(&format!("Logging on the calling thread: {}", e)),
+        }
+    }
+    log_config("Effective configuration:");
+    for line in config.effective().lines() {
+        log_config(&format!("  {}", line));
+    }
+    log_config("Configuration created successfully");
+
+    log_config("Validating configuration...");
+    match config.validate() {
+        Ok(_) => {
+            log_config("Configuration validated successfully");
+            Ok(config)
+        }
+        Err(e) => {
+            Logger::error(&format!("Configuration validation failed: {}", e));
+            Err(e)
+        }
+    }
+}
+
+/// Wait for the target module or one of its fallbacks to load
+///
+/// Returns the candidates loaded by then, in configuration order.
+#[cfg(feature = "dll")]
+pub(crate) fn wait_for_target_modules<'c>(
+    config: &'c Config,
+    scanner: &ModuleScanner,
+) -> Result<Vec<&'c str>> {
+    let candidates = config.target_modules();
+    Logger::info(&format!("Looking for module: {}", candidates.join(", ")));
+    scanner
+        .wait_for_any_module(
+            &candidates,
+            Duration::from_millis(config.module_wait_timeout_ms),
+        )
+        .inspect_err(|e| Logger::error(&format!("Failed to find module: {}", e)))
+}
+
+/// Scan the loaded candidate modules in order and use the first holding a
+/// signature
+#[cfg(feature = "dll")]
+pub(crate) fn locate_target(
+    config: &Config,
+    scanner: &ModuleScanner,
+    candidates: &[&str],
+) -> Result<(HMODULE, MODULEINFO, ScanOutcome)> {
+    let policy = config.retry_policy();
+    let mut last_error = None;
+    for name in candidates {
+        let located = retry(
+            &policy,
+            &format!("Locating the target in '{}'", name),
+            || {
+                let (module_base, module_info) = locate_module(scanner, name)?;
+                let scan = scan_target(config, module_base, &module_info)?;
+                Ok((module_base, module_info, scan))
+            },
+        );
+        match located {
+            Ok(found) => {
+                if *name != config.target_module {
+                    Logger::warning(&format!("Using fallback module '{}'", name));
+                }
+                return Ok(found);
+            }
+            Err(e) if candidates.len() > 1 => {
+                Logger::warning(&format!("Module '{}' skipped: {}", name, e));
+                last_error = Some(e);
+            }
+            Err(e) => return Err(e),
+        }
+    }
+
+    Err(last_error.unwrap_or_else(|| AppError::ModuleNotFound {
+        name: config.target_module.clone(),
+    }))
+}
+
+/// Find a loaded module and retrieve its information
+#[cfg(feature = "dll")]
+fn locate_module(scanner: &ModuleScanner, name: &str) -> Result<(HMODULE, MODULEINFO)> {
+    Logger::info(&format!("Looking up module: {}", name));
+    let module_base = match scanner.find_module(name) {
+        Ok(addr) => {
+            Logger::info(&format!("Module found at: {:?}", addr));
+            addr
+        }
+        Err(e) => {
+            Logger::error(&format!("Failed to find module: {}", e));
+            return Err(e);
+        }
+    };
+
+    Logger::info("Getting module information...");
+    let module_info = match scanner.get_module_info(module_base) {
+        Ok(info) => {
+            Logger::info("Module information retrieved successfully");
+            info
+        }
+        Err(e) => {
+            Logger::error(&format!("Failed to get module info: {}", e));
+            return Err(e);
+        }
+    };
+
+    match scanner.get_version_info(module_base) {
+        Ok(version) => Logger::info(&format!("Client version: {}", version)),
+        Err(e) => Logger::warning(&format!("Client version unknown: {}", e)),
+    }
+    match scanner.hash_module_file(module_base) {
+        Ok(hash) => Logger::info(&format!("Client SHA-256: {}", hash)),
+        Err(e) => Logger::warning(&format!("Client hash unavailable: {}", e)),
+    }
+
+    Logger::scan(&format!("Module base address: {:?}", module_base));
+    Logger::scan(&format!("Module size: {} bytes", module_info.SizeOfImage));
+
+    Ok((module_base, module_info))
+}
+
+/// Scan the module for the target function and adjust to its entry point
+///
+/// Tries each signature of the configured database that applies to the
+/// game's file version in order, and uses the first one that matches.
+#[cfg(feature = "dll")]
+fn scan_target(
+    config: &Config,
+    module_base: HMODULE,
+    module_info: &MODULEINFO,
+) -> Result<ScanOutcome> {
+    Logger::info("Creating pattern scanner...");
+    let mut pattern_scanner = PatternScanner::with_caching(config.cache_patterns);
+    if !config.cache_patterns {
+        Logger::info("Pattern caching disabled, every scan reads the module");
+    }
+
+    let game_version = match memory::version::module_version(module_base) {
+        Ok(version) => {
+            Logger::info(&format!("Game version: {}", version));
+            Some(version.file_version)
+        }
+        Err(e) => {
+            Logger::warning(&format!(
+                "Game version unknown, trying every signature: {}",
+                e
+            ));
+            None
+        }
+    };
+    let signatures = match config
+        .hook_spec(PAK_CHECK_HOOK_NAME)
+        .and_then(|spec| spec.signature)
+    {
+        Some(name) => {
+            Logger::info(&format!("Pak check hook pinned to signature '{}'", name));
+            SignatureDb::new(
+                config
+                    .signature_db()
+                    .get(&name)
+                    .cloned()
+                    .into_iter()
+                    .collect(),
+            )
+        }
+        None => config.signature_db().for_version(game_version),
+    };
+    Logger::info(&format!(
+        "Starting pattern scan with {} signature(s): {}",
+        signatures.len(),
+        signatures
+            .entries()
+            .iter()
+            .map(|entry| entry.name.as_str())
+            .collect::<Vec<_>>()
+            .join(", ")
+    ));
+
+    let mut last_error = None;
+    let mut matched = None;
+    for signature in signatures.entries() {
+        match find_signature(&mut pattern_scanner, signature, module_info) {
+            Ok(found) => {
+                matched = Some((signature, found));
+                break;
+            }
+            Err(e) => {
+                Logger::warning(&format!("Signature '{}' not found: {}", signature.name, e));
+                last_error = Some(e);
+            }
+        }
+    }
+
+    let Some((signature, target_func)) = matched else {
+        Logger::error("No signature matched");
+        Logger::error(
+            "This might indicate the game version has changed or the pattern is incorrect",
+        );
+        if let Some(hint_rva) = config.pattern_hint_rva {
+            suggest_signature_update(&pattern_scanner, config, module_info, hint_rva);
+        }
+        if config.dump_memory_map_on_failure {
+            memory::dump_memory_map();
+        }
+        return Err(last_error.unwrap_or(AppError::PatternNotFound {
+            size: module_info.SizeOfImage as usize,
+        }));
+    };
+    Logger::info(&format!(
+        "Signature '{}' found at: {}",
+        signature.name,
+        memory::describe_address(target_func as usize)
+    ));
+    Logger::success(&format!(
+        "Using signature profile '{}' ({})",
+        signature.name, signature.versions
+    ));
+
+    let mut signature_clean = true;
+    if config.verify_uniqueness {
+        Logger::info("Verifying signature uniqueness...");
+        let report =
+            pattern_scanner.verify_unique(module_info, &signature.pattern, &signature.mask)?;
+        Logger::scan(&format!(
+            "Signature matches in module: {}",
+            report.match_count()
+        ));
+        signature_clean = report.is_clean();
+    }
+
+    let new_target_func = function_start(target_func, signature, module_info)?;
+
+    Logger::scan(&format!(
+        "Found target function (original scan result) at: {}",
+        memory::describe_address(target_func as usize)
+    ));
+    Logger::scan(&format!(
+        "Adjusted target function (new offset) at: {}",
+        memory::describe_address(new_target_func as usize)
+    ));
+
+    if let Some(offset) = Rva::from_address(new_target_func as usize, module_base as usize) {
+        Logger::scan(&format!("Target function offset: {}", offset));
+    }
+
+    Ok(ScanOutcome {
+        target_func: new_target_func,
+        signature_clean,
+    })
+}
+
+/// Scan the module for one signature, checking the section it matched in
+pub(crate) fn find_signature(
+    pattern_scanner: &mut PatternScanner,
+    signature: &Signature,
+    module_info: &MODULEINFO,
+) -> Result<*mut u8> {
+    Logger::scan(&format!(
+        "Scanning for '{}': {:02X?}",
+        signature.name, signature.pattern
+    ));
+    Logger::scan(&format!("Using mask: {}", signature.mask));
+
+    let found = pattern_scanner.scan(
+        module_info.lpBaseOfDll as *mut u8,
+        module_info.SizeOfImage as usize,
+        &signature.pattern,
+        &signature.mask,
+    )?;
+
+    if let Some(section) = &signature.section {
+        let rva = found as usize - module_info.lpBaseOfDll as usize;
+        let pe = unsafe { PeImage::from_module(module_info)? };
+        let inside = pe
+            .section_for_rva(rva as u32)
+            .is_some_and(|info| info.name == *section);
+        if !inside {
+            return Err(AppError::SignatureOutsideSection {
+                name: signature.name.clone(),
+                section: section.clone(),
+                rva,
+            });
+        }
+    }
+
+    Ok(found)
+}
+
+/// Log a suggested replacement signature found near the last known RVA
+#[cfg(feature = "dll")]
+fn suggest_signature_update(
+    pattern_scanner: &PatternScanner,
+    config: &Config,
+    module_info: &MODULEINFO,
+    hint_rva: Rva,
+) {
+    Logger::info(&format!(
+        "Searching for a drifted signature near RVA {}...",
+        hint_rva
+    ));
+
+    match pattern_scanner.suggest_signature(
+        module_info,
+        &config.pattern,
+        &config.mask,
+        hint_rva,
+        SIGNATURE_HINT_WINDOW,
+    ) {
+        Some(suggestion) => {
+            Logger::warning(&format!(
+                "Closest match at RVA {} differs in {} byte(s)",
+                suggestion.rva, suggestion.changed
+            ));
+            Logger::warning(&format!(
+                "Suggested pattern: {}",
+                suggestion.pattern_literal()
+            ));
+            Logger::warning(&format!("Suggested mask: {}", suggestion.mask));
+        }
+        None => Logger::warning("No similar byte sequence found near the hinted RVA"),
+    }
+}
+
+/// Install every enabled hook of [`Config::hook_specs`] through the global
+/// hook manager
+///
+/// All hooks are registered first and then applied in one
+/// [`HookTransaction`](crate::hooks::HookTransaction). Failing to hook the
+/// pak check is fatal; the other hooks only warn.
+///
+/// # Safety
+///
+/// `target_func` must point to the start of the PAK verification function.
+#[cfg(feature = "dll")]
+pub(crate) unsafe fn install_hooks(
+    config: &Config,
+    module_info: &MODULEINFO,
+    target_func: *mut u8,
+) -> Result<()> {
+    hooks::global_hook_manager().set_backend(config.hook_backend);
+    if config.dry_run {
+        Logger::warning("Dry run: every hook only logs, nothing is bypassed");
+    }
+
+    let mut staged = Vec::new();
+    let mut pak_registered = false;
+    let mut pak_bytes = None;
+    for spec in config.hook_specs() {
+        if !spec.enabled {
+            Logger::info(&format!("Hook '{}' is disabled", spec.name));
+            continue;
+        }
+
+        let registered = match spec.name.as_str() {
+            PAK_CHECK_HOOK_NAME => {
+                unsafe { register_hook(config, spec.action, target_func) }.map(|before| {
+                    pak_registered = true;
+                    pak_bytes = before;
+                })
+            }
+            SIG_CHECK_HOOK_NAME => sig::locate(config, &spec, module_info)
+                .and_then(|address| sig::register(spec.action, address)),
+            _ => Err(AppError::InvalidConfig {
+                field: format!("hooks.{}", spec.name),
+                reason: "unknown hook".to_string(),
+            }),
+        };
+        match registered {
+            Ok(()) => staged.push(spec),
+            Err(e) if spec.name == PAK_CHECK_HOOK_NAME => {
+                Logger::error(&format!("Failed to apply hook: {}", e));
+                return Err(e);
+            }
+            Err(e) => Logger::warning(&format!("Hook '{}' not installed: {}", spec.name, e)),
+        }
+    }
+
+    for spec in apply_staged(config, staged)? {
+        Logger::info(&format!("Hook '{}' installed ({})", spec.name, spec.action));
+    }
+    if pak_registered {
+        report_hook(pak_bytes);
+    }
+    Ok(())
+}
+
+/// Apply the registered hooks of `staged` together, returning those applied
+///
+/// If the transaction keeps failing, the optional hooks are dropped and the
+/// pak check hook is applied alone.
+#[cfg(feature = "dll")]
+fn apply_staged(config: &Config, mut staged: Vec<HookSpec>) -> Result<Vec<HookSpec>> {
+    let manager = hooks::global_hook_manager();
+    let names: Vec<&str> = staged.iter().map(|spec| spec.name.as_str()).collect();
+    let applied = retry(&config.retry_policy(), "Applying the hooks", || {
+        manager.apply_together(&names)
+    });
+    match applied {
+        Ok(_) => return Ok(staged),
+        Err(e) if staged.iter().all(|spec| spec.name == PAK_CHECK_HOOK_NAME) => {
+            Logger::error(&format!("Failed to apply hook: {}", e));
+            return Err(e);
+        }
+        Err(e) => Logger::warning(&format!(
+            "Applying the hooks together failed: {}; retrying without the optional hooks",
+            e
+        )),
+    }
+
+    for spec in staged
+        .iter()
+        .filter(|spec| spec.name != PAK_CHECK_HOOK_NAME)
+    {
+        let _ = manager.unregister(&spec.name);
+        Logger::warning(&format!("Hook '{}' not installed", spec.name));
+    }
+    staged.retain(|spec| spec.name == PAK_CHECK_HOOK_NAME);
+    if !staged.is_empty() {
+        retry(
+            &config.retry_policy(),
+            "Applying the pak check hook",
+            || manager.apply_together(&[PAK_CHECK_HOOK_NAME]),
+        )
+        .inspect_err(|e| Logger::error(&format!("Failed to apply hook: {}", e)))?;
+    }
+    Ok(staged)
+}
+
+/// Register the PAK verification hook on the target function
+///
+/// Returns the target's bytes from before the hook for [`report_hook`], or
+/// `None` if they could not be read; that only skips the comparison.
+///
+/// # Safety
+///
+/// `target_func` must point to the start of the PAK verification function.
+#[cfg(feature = "dll")]
+unsafe fn register_hook(
+    config: &Config,
+    action: HookAction,
+    target_func: *mut u8,
+) -> Result<Option<MemorySnapshot>> {
+    let manager = hooks::global_hook_manager();
+
+    set_pak_filter(config);
+    PAK_LOG_LIMIT.set_burst(config.pak_log_burst);
+    PAK_CHECK_DRY_RUN.store(action.is_observe(), Ordering::Relaxed);
+    Logger::info(&format!(
+        "Applying hook via {} backend...",
+        config.hook_backend
+    ));
+
+    let before = unsafe { MemorySnapshot::capture(target_func, HOOK_BACKUP_LEN) }
+        .inspect_err(|e| Logger::warning(&format!("Could not save the unhooked bytes: {}", e)))
+        .ok();
+    manager.register(
+        PAK_CHECK_HOOK_NAME,
+        target_func as usize,
+        pak_file_check_replacement,
+    )?;
+
+    // Wire up statistics before the first call can arrive
+    if let Some(stats) = manager.stats(PAK_CHECK_HOOK_NAME) {
+        let _ = PAK_CHECK_STATS.set(stats);
+    }
+    if let Some(history) = manager.history(PAK_CHECK_HOOK_NAME) {
+        let _ = PAK_CHECK_HISTORY.set(history);
+    }
+    if let Some(chain) = manager.chain(PAK_CHECK_HOOK_NAME) {
+        let _ = PAK_CHECK_CHAIN.set(chain);
+    }
+    if let Some(spoof) = manager.spoof(PAK_CHECK_HOOK_NAME) {
+        spoof.set(action.spoof_value());
+        let _ = PAK_CHECK_SPOOF.set(spoof);
+    }
+    if let Some(once) = manager.once(PAK_CHECK_HOOK_NAME) {
+        once.arm(config.hook_once);
+        let _ = PAK_CHECK_ONCE.set(once);
+    }
+    Ok(before)
+}
+
+/// Log the applied PAK verification hook and remember its patched bytes
+#[cfg(feature = "dll")]
+fn report_hook(before: Option<MemorySnapshot>) {
+    let manager = hooks::global_hook_manager();
+    Logger::info("Hook applied successfully");
+    if let Some(info) = manager.status(PAK_CHECK_HOOK_NAME) {
+        Logger::info(&format!("Hook state after application: {:?}", info.state));
+        Logger::info(&format!("Hook target address: {:?}", info.target_address));
+        Logger::info(&format!("Hook is active: {}", info.is_active));
+    }
+
+    // Verify the hook was applied by comparing the memory
+    let Some(before) = before else {
+        return;
+    };
+    match unsafe { before.recapture() } {
+        Ok(after) => {
+            for change in before.diff(&after) {
+                Logger::info(&format!("Hook changed {}", change));
+            }
+            if PAK_CHECK_BYTES.set(after).is_ok() {
+                manager.on_state_change(|change| {
+                    if change.name == Some(PAK_CHECK_HOOK_NAME) {
+                        PAK_CHECK_CHANGES.fetch_add(1, Ordering::AcqRel);
+                    }
+                });
+            }
+        }
+        Err(e) => Logger::warning(&format!("Could not compare the hooked bytes: {}", e)),
+    }
+}
+
+/// Read `len` bytes of the function preamble starting at `address`
+#[cfg(feature = "dll")]
+fn read_preamble(address: *const u8, len: usize) -> Result<Vec<u8>> {
+    (0..len)
+        .map(|i| unsafe { MemoryAccess::read_volatile_safe(address.wrapping_add(i)) })
+        .collect()
+}
+
+/// Wait for ACE initialization to complete using safe memory access
+///
+/// Polls until the bytes at `check_address` match `expected`, where `?` in
+/// `mask` skips a byte. Gives up with [`AppError::Timeout`] once `timeout`
+/// has passed, logging the current and expected preamble every
+/// [`ACE_PROGRESS_INTERVAL_MS`] until then.
+#[cfg(feature = "dll")]
+pub(crate) fn wait_for_ace_init(
+    check_address: *const u8,
+    expected: &[u8],
+    mask: &str,
+    timeout: Duration,
+) -> Result<AceWaitOutcome> {
+    Logger::info("Waiting for ACE init...");
+
+    let start = Instant::now();
+    let progress_interval = Duration::from_millis(ACE_PROGRESS_INTERVAL_MS);
+    let mut next_progress = progress_interval;
+    let mut polls = 0u32;
+    loop {
+        let current = read_preamble(check_address, expected.len())?;
+        polls = polls.saturating_add(1);
+
+        let settled = current
+            .iter()
+            .zip(expected)
+            .zip(mask.chars())
+            .all(|((current, expected), mask)| mask == '?' || current == expected);
+        if settled {
+            Logger::success("ACE Initialization finished");
+            return Ok(AceWaitOutcome {
+                waited: start.elapsed(),
+                polls,
+            });
+        }
+
+        let elapsed = start.elapsed();
+        if elapsed >= timeout {
+            return Err(AppError::Timeout {
+                operation: "ACE initialization".to_string(),
+                elapsed,
+            });
+        }
+        if elapsed >= next_progress {
+            Logger::info(&format!(
+                "Still waiting for ACE after {:?}: preamble is {:02X?}, expecting {:02X?} ({})",
+                elapsed, current, expected, mask
+            ));
+            next_progress += progress_interval;
+        }
+
+        thread::sleep(Duration::from_millis(1));
+    }
+}
@@ -1,46 +1,366 @@
+use std::path::Path;
+use std::time::Duration;
+
+use crossterm::style::Color;
+
+use crate::constants::constants::memory::{
+    MAX_FUNCTION_OFFSET, MAX_PREAMBLE_LEN, TARGET_FUNCTION_OFFSET,
+};
+use crate::constants::constants::{
+    BYPASS_SUCCESS, CONFIG_ENV_PREFIX, CONFIG_SCHEMA_VERSION, EXTRA_PAK_ORDER, PAK_CHECK_HOOK_NAME,
+    PAK_LOG_BURST, PATTERN_MASK, SIG_CHECK_HOOK_NAME, TARGET_MODULE, TARGET_PATTERN,
+};
 use crate::error::{AppError, Result};
+use crate::filter::UnmatchedPakAction;
+use crate::hooks::{CONFIGURABLE_HOOKS, HookAction, HookBackendKind, HookSpec};
+use crate::hotkey::Hotkey;
+use crate::logger::theme::{color_name, parse_color};
+use crate::logger::{ColorTheme, LogCategory, LogFilters, LogFormat, LogLevel, OverflowPolicy};
+use crate::memory::Rva;
+use crate::memory::version::VersionNumber;
+use crate::retry::RetryPolicy;
+use crate::signatures::{DEFAULT_SIGNATURE, Signature, SignatureDb, parse_pattern};
+
+mod toml;
+
+use toml::{Entry, Value};
+
+/// Configuration compiled into the DLL, the lowest layer of [`Config::load`]
+pub const DEFAULT_CONFIG: &str = include_str!("config/default.toml");
+
+/// Keys accepted at the top level of a configuration file, besides
+/// `schema_version`, `[signatures.<name>]` and `[hooks.<name>]` tables and
+/// the `[log_filters]` and `[log_theme]` tables
+const FILE_KEYS: &[&str] = &[
+    "target_module",
+    "fallback_modules",
+    "pattern",
+    "function_offset",
+    "max_scan_size",
+    "ace_init_timeout_ms",
+    "ace_init_warn_ms",
+    "preamble_offset",
+    "ace_preamble",
+    "module_wait_timeout_ms",
+    "retry_attempts",
+    "retry_delay_ms",
+    "verify_uniqueness",
+    "pattern_hint_rva",
+    "publish_overlay",
+    "cache_modules",
+    "cache_patterns",
+    "dump_memory_map_on_failure",
+    "hook_backend",
+    "pak_allow",
+    "pak_deny",
+    "unmatched_pak_action",
+    "spoof_value",
+    "hook_once",
+    "dry_run",
+    "audit_file_access",
+    "mount_pattern",
+    "mount_function_offset",
+    "sig_pattern",
+    "sig_function_offset",
+    "sig_spoof_value",
+    "extra_pak_dir",
+    "extra_pak_order",
+    "log_level",
+    "log_format",
+    "log_timestamps",
+    "log_color",
+    "log_queue_capacity",
+    "log_overflow",
+    "pak_log_burst",
+    "log_level_hotkey",
+    "console",
+    "console_hotkey",
+    "unload_hotkey",
+    "command_pipe",
+    "watch_config",
+    "watchdog_interval_ms",
+    "signature_update_url",
+    "signature_update_key",
+    "signature_update_timeout_ms",
+];
+
+/// Keys accepted in a `[signatures.<name>]` table
+const SIGNATURE_KEYS: &[&str] = &[
+    "pattern",
+    "function_offset",
+    "section",
+    "min_version",
+    "max_version",
+];
+
+/// Keys accepted in a `[hooks.<name>]` table
+const HOOK_KEYS: &[&str] = &["enabled", "action", "spoof_value", "signature"];
+
+/// Key renames between two configuration file schema versions
+struct Migration {
+    /// Schema version the step upgrades from
+    from: u32,
+    /// Old and new key names
+    renamed: &'static [(&'static str, &'static str)],
+}
+
+/// Steps upgrading older files to [`CONFIG_SCHEMA_VERSION`], oldest first
+const MIGRATIONS: &[Migration] = &[];
 
 /// Configuration for the memory scanner and hook system
-#[derive(Debug, Clone)]
-pub struct Config<'a> {
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Config {
     /// Target module name to scan
-    pub target_module: &'a str,
+    pub target_module: String,
+    /// Modules tried in order when `target_module` is not loaded or holds
+    /// no signature, such as a renamed client (default: empty)
+    pub fallback_modules: Vec<String>,
     /// Byte pattern to search for
-    pub pattern: &'a [u8],
+    pub pattern: Vec<u8>,
     /// Mask string where 'x' means exact match, '?' means wildcard
-    pub mask: &'a str,
+    pub mask: String,
+    /// Distance from the signature match back to the pak check function
+    /// start, negative when the function starts after the match
+    /// (default: 0x45)
+    pub function_offset: isize,
     /// Maximum scan size in bytes (default: 100MB)
     pub max_scan_size: usize,
-    /// Timeout for ACE initialization in milliseconds (default: 5000ms)
+    /// Give up waiting for ACE initialization after this many milliseconds
+    /// (default: 5000ms)
     pub ace_init_timeout_ms: u64,
+    /// Warn when ACE initialization takes longer than this in milliseconds
+    /// (default: 2000ms)
+    pub ace_init_warn_ms: u64,
+    /// Offset from the function start of the bytes compared by the ACE
+    /// readiness check (default: 0)
+    pub preamble_offset: usize,
+    /// Bytes the function has at `preamble_offset` once ACE finished
+    /// initializing; empty to hook without waiting (default: empty)
+    pub ace_preamble: Vec<u8>,
+    /// Mask for `ace_preamble` (default: empty)
+    pub ace_preamble_mask: String,
+    /// How long to wait for the target module to load in milliseconds,
+    /// 0 to require it immediately (default: 60000ms)
+    pub module_wait_timeout_ms: u64,
+    /// Attempts at the module lookup, scan and hook application before
+    /// initialization fails, the first one included (default: 3)
+    pub retry_attempts: u32,
+    /// Delay before the first retry in milliseconds, doubled before each
+    /// further one (default: 100ms)
+    pub retry_delay_ms: u64,
+    /// Verify the signature matches exactly once before hooking (default: true)
+    pub verify_uniqueness: bool,
+    /// Last known offset of the pattern from the module base, used to suggest
+    /// an updated signature when the exact scan fails (default: none)
+    pub pattern_hint_rva: Option<Rva>,
+    /// Publish the live status and statistics in shared memory for overlay
+    /// tools and launchers (default: true)
+    pub publish_overlay: bool,
+    /// Cache module lookups by name; disable when the module may be remapped
+    /// mid-session (default: true)
+    pub cache_modules: bool,
+    /// Cache pattern scan results; disable to always rescan (default: true)
+    pub cache_patterns: bool,
+    /// Log the process memory map when the signature scan fails (default: true)
+    pub dump_memory_map_on_failure: bool,
+    /// Backend used to install the pak check hook (default: interceptor)
+    pub hook_backend: HookBackendKind,
+    /// Glob patterns of paks to bypass; empty bypasses every pak not denied
+    /// (default: empty)
+    pub pak_allow: Vec<String>,
+    /// Glob patterns of paks never to bypass (default: empty)
+    pub pak_deny: Vec<String>,
+    /// What to do with paks that are not bypassed (default: call original)
+    pub unmatched_pak_action: UnmatchedPakAction,
+    /// Value the pak check hook returns for bypassed paks, or `None` to only
+    /// observe calls and run the original check (default: 1)
+    pub spoof_value: Option<usize>,
+    /// Remove the pak check hook after the first call it sees (default: false)
+    pub hook_once: bool,
+    /// Apply the hooks but always run the original check, only logging
+    /// whether each pak passed; nothing is bypassed (default: false)
+    pub dry_run: bool,
+    /// Log every `.pak`, `.sig` and `.utoc` file the game opens (default: false)
+    pub audit_file_access: bool,
+    /// Signature of the pak mount routine; empty disables mount logging
+    /// (default: empty)
+    pub mount_pattern: Vec<u8>,
+    /// Mask for `mount_pattern` (default: empty)
+    pub mount_mask: String,
+    /// Distance from the mount signature match back to the routine start,
+    /// negative when the routine starts after the match (default: 0)
+    pub mount_function_offset: isize,
+    /// Signature of the `.sig` file check routine; empty leaves that check
+    /// unhooked (default: empty)
+    pub sig_pattern: Vec<u8>,
+    /// Mask for `sig_pattern` (default: empty)
+    pub sig_mask: String,
+    /// Distance from the `.sig` check signature match back to the routine
+    /// start, negative when the routine starts after the match (default: 0)
+    pub sig_function_offset: isize,
+    /// Value the `.sig` check hook returns, or `None` to only observe calls
+    /// (default: 1)
+    pub sig_spoof_value: Option<usize>,
+    /// Directory whose paks are mounted once the game has mounted its own;
+    /// requires the mount signature (default: none)
+    pub extra_pak_dir: Option<String>,
+    /// Mount order given to paks from `extra_pak_dir` (default: 1000)
+    pub extra_pak_order: u32,
+    /// Most verbose level logged once the configuration is loaded
+    /// (default: info)
+    pub log_level: LogLevel,
+    /// Levels of log categories (`general`, `scan`, `hook`, `pak`,
+    /// `config`) that do not follow `log_level`, from the `[log_filters]`
+    /// table (default: empty)
+    pub log_filters: LogFilters,
+    /// Layout of logged lines once the configuration is loaded, `text` or
+    /// one JSON object per line with `json` (default: text)
+    pub log_format: LogFormat,
+    /// Whether text lines start with the local time and the time since the
+    /// DLL was attached (default: true)
+    pub log_timestamps: bool,
+    /// Whether console lines are colored by level; redirected output and
+    /// `NO_COLOR` turn colors off regardless (default: true)
+    pub log_color: bool,
+    /// Console color of each level, changed per level name in the
+    /// `[log_theme]` table (default: red errors, yellow warnings and scans,
+    /// cyan info, green successes and bypasses, magenta hooks)
+    pub log_theme: ColorTheme,
+    /// Records queued for the log writer thread; 0 formats and writes them
+    /// on the thread that logs (default: 1024)
+    pub log_queue_capacity: usize,
+    /// What happens to records logged while the queue is full, `drop` or
+    /// `block` (default: drop)
+    pub log_overflow: OverflowPolicy,
+    /// Pak verification lines logged per second; the rest are counted and
+    /// reported, 0 logs every one (default: 20)
+    pub pak_log_burst: u32,
+    /// Function key cycling `log_level` through every level while the game
+    /// has the focus, `false` for none (default: F9)
+    pub log_level_hotkey: Option<Hotkey>,
+    /// Open a console window for the log; without one the log goes to
+    /// `wuwa-sig.log` next to the DLL and to an attached debugger
+    /// (default: true)
+    pub console: bool,
+    /// Function key showing and hiding the console window, opening one when
+    /// running without, `false` for none (default: F10)
+    pub console_hotkey: Option<Hotkey>,
+    /// Function key removing the hooks and unloading the DLL, `false` for
+    /// none (default: none)
+    pub unload_hotkey: Option<Hotkey>,
+    /// Answer status and control commands on the `\\.\pipe\wuwa-sig`
+    /// named pipe (default: true)
+    pub command_pipe: bool,
+    /// Named signatures tried in order before `pattern` (default: empty)
+    pub signatures: Vec<Signature>,
+    /// Signatures of the `signature_update_url` database, tried before
+    /// `signatures`; set at startup, never read from the file (default: empty)
+    pub downloaded_signatures: Vec<Signature>,
+    /// Hooks defined by `[hooks.<name>]` tables, replacing what the
+    /// top-level keys set up for them (default: empty)
+    pub hooks: Vec<HookSpec>,
+    /// Reload the log level, pak lists and watchdog interval when the
+    /// configuration file changes (default: true)
+    pub watch_config: bool,
+    /// How often the maintenance loop checks the hook and the configuration
+    /// file in milliseconds (default: 1000ms)
+    pub watchdog_interval_ms: u64,
+    /// URL of a signed signature database downloaded at startup; none keeps
+    /// updates off (default: none)
+    pub signature_update_url: Option<String>,
+    /// ECDSA P-256 public key checking the downloaded database, as 128 hex
+    /// digits of its X and Y coordinates (default: none)
+    pub signature_update_key: Option<String>,
+    /// Timeout of each signature update request in milliseconds
+    /// (default: 5000ms)
+    pub signature_update_timeout_ms: u64,
 }
 
-impl<'a> Config<'a> {
+impl Config {
     /// Create a new configuration with default values
-    pub fn new(target_module: &'a str, pattern: &'a [u8], mask: &'a str) -> Self {
+    pub fn new(target_module: &str, pattern: &[u8], mask: &str) -> Self {
         Self {
-            target_module,
-            pattern,
-            mask,
+            target_module: target_module.to_string(),
+            fallback_modules: Vec::new(),
+            pattern: pattern.to_vec(),
+            mask: mask.to_string(),
+            function_offset: TARGET_FUNCTION_OFFSET,
             max_scan_size: 100 * 1024 * 1024, // 100MB default
             ace_init_timeout_ms: 5000,        // 5 seconds default
+            ace_init_warn_ms: 2000,           // 2 seconds default
+            preamble_offset: 0,
+            ace_preamble: Vec::new(),
+            ace_preamble_mask: String::new(),
+            module_wait_timeout_ms: 60_000, // 1 minute default
+            retry_attempts: 3,
+            retry_delay_ms: 100,
+            verify_uniqueness: true,
+            pattern_hint_rva: None,
+            publish_overlay: true,
+            cache_modules: true,
+            cache_patterns: true,
+            dump_memory_map_on_failure: true,
+            hook_backend: HookBackendKind::Interceptor,
+            pak_allow: Vec::new(),
+            pak_deny: Vec::new(),
+            unmatched_pak_action: UnmatchedPakAction::CallOriginal,
+            spoof_value: Some(BYPASS_SUCCESS),
+            hook_once: false,
+            dry_run: false,
+            audit_file_access: false,
+            mount_pattern: Vec::new(),
+            mount_mask: String::new(),
+            mount_function_offset: 0,
+            sig_pattern: Vec::new(),
+            sig_mask: String::new(),
+            sig_function_offset: 0,
+            sig_spoof_value: Some(BYPASS_SUCCESS),
+            extra_pak_dir: None,
+            extra_pak_order: EXTRA_PAK_ORDER,
+            log_level: LogLevel::Info,
+            log_filters: LogFilters::default(),
+            log_format: LogFormat::Text,
+            log_timestamps: true,
+            log_color: true,
+            log_theme: ColorTheme::default(),
+            log_queue_capacity: 1024,
+            log_overflow: OverflowPolicy::Drop,
+            pak_log_burst: PAK_LOG_BURST,
+            signatures: Vec::new(),
+            downloaded_signatures: Vec::new(),
+            hooks: Vec::new(),
+            log_level_hotkey: Some(Hotkey::F9),
+            console: true,
+            console_hotkey: Some(Hotkey::F10),
+            unload_hotkey: None,
+            command_pipe: true,
+            watch_config: true,
+            watchdog_interval_ms: 1000, // 1 second default
+            signature_update_url: None,
+            signature_update_key: None,
+            signature_update_timeout_ms: 5000, // 5 seconds default
         }
     }
 
     /// Create a new configuration with custom scan size and timeout
     pub fn with_limits(
-        target_module: &'a str,
-        pattern: &'a [u8],
-        mask: &'a str,
+        target_module: &str,
+        pattern: &[u8],
+        mask: &str,
         max_scan_size: usize,
         ace_init_timeout_ms: u64,
     ) -> Self {
         Self {
-            target_module,
-            pattern,
-            mask,
             max_scan_size,
             ace_init_timeout_ms,
+            ..Self::new(target_module, pattern, mask)
+        }
+    }
+
+    /// Start building a configuration for the given target and signature
+    pub fn builder(target_module: &str, pattern: &[u8], mask: &str) -> ConfigBuilder {
+        ConfigBuilder {
+            config: Self::new(target_module, pattern, mask),
         }
     }
 
@@ -53,6 +373,13 @@ impl<'a> Config<'a> {
             });
         }
 
+        if self.fallback_modules.iter().any(|name| name.is_empty()) {
+            return Err(AppError::InvalidConfig {
+                field: "fallback_modules".to_string(),
+                reason: "Module names cannot be empty".to_string(),
+            });
+        }
+
         if self.pattern.is_empty() {
             return Err(AppError::InvalidConfig {
                 field: "pattern".to_string(),
@@ -105,9 +432,276 @@ impl<'a> Config<'a> {
             });
         }
 
+        if self.retry_attempts == 0 {
+            return Err(AppError::InvalidConfig {
+                field: "retry_attempts".to_string(),
+                reason: "At least one attempt is required".to_string(),
+            });
+        }
+
+        if self.watchdog_interval_ms == 0 {
+            return Err(AppError::InvalidConfig {
+                field: "watchdog_interval_ms".to_string(),
+                reason: "Watchdog interval must be greater than 0".to_string(),
+            });
+        }
+
+        if self.ace_preamble.len() != self.ace_preamble_mask.len() {
+            return Err(AppError::InvalidConfig {
+                field: "ace_preamble/ace_preamble_mask".to_string(),
+                reason: format!(
+                    "ACE preamble length ({}) doesn't match mask length ({})",
+                    self.ace_preamble.len(),
+                    self.ace_preamble_mask.len()
+                ),
+            });
+        }
+
+        if self.ace_preamble.len() > MAX_PREAMBLE_LEN {
+            return Err(AppError::InvalidConfig {
+                field: "ace_preamble".to_string(),
+                reason: format!("ACE preamble must be at most {} bytes", MAX_PREAMBLE_LEN),
+            });
+        }
+
+        if self.mount_pattern.len() != self.mount_mask.len() {
+            return Err(AppError::InvalidConfig {
+                field: "mount_pattern/mount_mask".to_string(),
+                reason: format!(
+                    "Mount pattern length ({}) must match mount mask length ({})",
+                    self.mount_pattern.len(),
+                    self.mount_mask.len()
+                ),
+            });
+        }
+
+        if self.sig_pattern.len() != self.sig_mask.len() {
+            return Err(AppError::InvalidConfig {
+                field: "sig_pattern/sig_mask".to_string(),
+                reason: format!(
+                    "Signature check pattern length ({}) must match its mask length ({})",
+                    self.sig_pattern.len(),
+                    self.sig_mask.len()
+                ),
+            });
+        }
+        for (field, offset) in [
+            ("mount_function_offset", self.mount_function_offset),
+            ("sig_function_offset", self.sig_function_offset),
+        ] {
+            if offset.unsigned_abs() > MAX_FUNCTION_OFFSET {
+                return Err(AppError::InvalidConfig {
+                    field: field.to_string(),
+                    reason: format!(
+                        "Function offset {} is further than {:#x} bytes from the match",
+                        offset, MAX_FUNCTION_OFFSET
+                    ),
+                });
+            }
+        }
+
+        if self.extra_pak_dir.is_some() && self.mount_pattern.is_empty() {
+            return Err(AppError::InvalidConfig {
+                field: "extra_pak_dir".to_string(),
+                reason: "Pak injection needs the mount signature to find the mount routine"
+                    .to_string(),
+            });
+        }
+
+        if self.console_hotkey.is_some() && self.console_hotkey == self.log_level_hotkey {
+            return Err(AppError::InvalidConfig {
+                field: "console_hotkey".to_string(),
+                reason: "Already used as log_level_hotkey".to_string(),
+            });
+        }
+
+        if let Some(used_as) = [
+            ("log_level_hotkey", self.log_level_hotkey),
+            ("console_hotkey", self.console_hotkey),
+        ]
+        .iter()
+        .find(|(_, key)| self.unload_hotkey.is_some() && *key == self.unload_hotkey)
+        .map(|(name, _)| name)
+        {
+            return Err(AppError::InvalidConfig {
+                field: "unload_hotkey".to_string(),
+                reason: format!("Already used as {}", used_as),
+            });
+        }
+
+        if self.dry_run && self.extra_pak_dir.is_some() {
+            return Err(AppError::InvalidConfig {
+                field: "extra_pak_dir".to_string(),
+                reason: "Pak injection changes what the game loads, which a dry run must not"
+                    .to_string(),
+            });
+        }
+
+        if let Some(url) = &self.signature_update_url {
+            crate::update::parse_url(url)?;
+            let key = self.signature_update_key.as_deref().unwrap_or_default();
+            crate::update::parse_public_key(key)?;
+        }
+
+        for (field, patterns) in [("pak_allow", &self.pak_allow), ("pak_deny", &self.pak_deny)] {
+            if patterns.iter().any(|pattern| pattern.is_empty()) {
+                return Err(AppError::InvalidConfig {
+                    field: field.to_string(),
+                    reason: "Pak patterns cannot be empty".to_string(),
+                });
+            }
+        }
+
+        // Every hook the bypass installs replaces the target routine
+        if !self.hook_backend.can_replace() {
+            return Err(AppError::InvalidConfig {
+                field: "hook_backend".to_string(),
+                reason: format!(
+                    "The {} backend cannot replace the hooked functions",
+                    self.hook_backend
+                ),
+            });
+        }
+
+        self.validate_hooks()?;
+        self.signature_db().validate()
+    }
+
+    /// Check the `[hooks.<name>]` tables name known hooks and signatures
+    fn validate_hooks(&self) -> Result<()> {
+        let signatures = self.signature_db();
+        for spec in &self.hooks {
+            let field = format!("hooks.{}", spec.name);
+            if !CONFIGURABLE_HOOKS.contains(&spec.name.as_str()) {
+                return Err(AppError::InvalidConfig {
+                    field,
+                    reason: format!(
+                        "unknown hook, expected one of {}",
+                        CONFIGURABLE_HOOKS.join(", ")
+                    ),
+                });
+            }
+            if let Some(name) = &spec.signature
+                && signatures.get(name).is_none()
+            {
+                return Err(AppError::InvalidConfig {
+                    field: format!("{}.signature", field),
+                    reason: format!("no signature named '{}'", name),
+                });
+            }
+        }
+
+        let sig_check = self.hook_spec(SIG_CHECK_HOOK_NAME);
+        if sig_check.is_some_and(|spec| {
+            spec.enabled && spec.signature.is_none() && self.sig_pattern.is_empty()
+        }) {
+            return Err(AppError::InvalidConfig {
+                field: format!("hooks.{}", SIG_CHECK_HOOK_NAME),
+                reason: "The signature check hook needs sig_pattern or a signature".to_string(),
+            });
+        }
         Ok(())
     }
 
+    /// Retry policy of the module lookup, scan and hook application
+    pub fn retry_policy(&self) -> RetryPolicy {
+        RetryPolicy::new(
+            self.retry_attempts,
+            Duration::from_millis(self.retry_delay_ms),
+        )
+    }
+
+    /// Hooks to install, in installation order
+    ///
+    /// A hook without a `[hooks.<name>]` table follows the top-level keys: the
+    /// pak check spoofs `spoof_value`, and the `.sig` check spoofs
+    /// `sig_spoof_value` once `sig_pattern` is set. A dry run makes every
+    /// hook observe.
+    pub fn hook_specs(&self) -> Vec<HookSpec> {
+        let pak_check = HookSpec::new(
+            PAK_CHECK_HOOK_NAME,
+            HookAction::from_spoof(self.spoof_value),
+        );
+        let sig_check = HookSpec {
+            enabled: !self.sig_pattern.is_empty(),
+            ..HookSpec::new(
+                SIG_CHECK_HOOK_NAME,
+                HookAction::from_spoof(self.sig_spoof_value),
+            )
+        };
+
+        [pak_check, sig_check]
+            .into_iter()
+            .map(|default| {
+                let mut spec = self
+                    .hooks
+                    .iter()
+                    .find(|spec| spec.name == default.name)
+                    .cloned()
+                    .unwrap_or(default);
+                if self.dry_run {
+                    spec.action = HookAction::Observe;
+                }
+                spec
+            })
+            .collect()
+    }
+
+    /// The hook named `name` as [`Config::hook_specs`] gives it
+    pub fn hook_spec(&self, name: &str) -> Option<HookSpec> {
+        self.hook_specs().into_iter().find(|spec| spec.name == name)
+    }
+
+    /// Modules searched for the signatures, `target_module` first
+    pub fn target_modules(&self) -> Vec<&str> {
+        std::iter::once(self.target_module.as_str())
+            .chain(self.fallback_modules.iter().map(String::as_str))
+            .collect()
+    }
+
+    /// Signatures in the order the scan tries them, ending with `pattern`
+    ///
+    /// Downloaded signatures come first, except those named like a
+    /// configured one.
+    pub fn signature_db(&self) -> SignatureDb {
+        let mut local = self.signatures.clone();
+        local.push(Signature::new(
+            DEFAULT_SIGNATURE,
+            &self.pattern,
+            &self.mask,
+            self.function_offset,
+        ));
+
+        let mut entries: Vec<Signature> = self
+            .downloaded_signatures
+            .iter()
+            .filter(|downloaded| !local.iter().any(|entry| entry.name == downloaded.name))
+            .cloned()
+            .collect();
+        entries.append(&mut local);
+        SignatureDb::new(entries)
+    }
+
+    /// Signature of the mount routine built from `mount_pattern`
+    pub fn mount_signature(&self) -> Signature {
+        Signature::new(
+            "mount",
+            &self.mount_pattern,
+            &self.mount_mask,
+            self.mount_function_offset,
+        )
+    }
+
+    /// Signature of the `.sig` check built from `sig_pattern`
+    pub fn sig_check_signature(&self) -> Signature {
+        Signature::new(
+            SIG_CHECK_HOOK_NAME,
+            &self.sig_pattern,
+            &self.sig_mask,
+            self.sig_function_offset,
+        )
+    }
+
     /// Get the pattern length
     pub fn pattern_len(&self) -> usize {
         self.pattern.len()
@@ -117,62 +711,1534 @@ impl<'a> Config<'a> {
     pub fn has_wildcards(&self) -> bool {
         self.mask.contains('?')
     }
+
+    /// The configuration compiled into the DLL, see [`DEFAULT_CONFIG`]
+    pub fn embedded() -> Result<Self> {
+        Self::from_toml(DEFAULT_CONFIG)
+    }
+
+    /// Load the configuration in layers: the embedded defaults, then the file
+    /// at `path` if any, then `WUWA_SIG_*` environment variables
+    pub fn load(path: Option<&Path>) -> Result<Self> {
+        let text = path.map(read_config_file).transpose()?;
+        Self::layered(text.as_deref(), std::env::vars())
+    }
+
+    /// Merge the layers of [`Config::load`], later ones overriding earlier
+    /// ones key by key
+    ///
+    /// Variables in `env` without the `WUWA_SIG_` prefix are ignored; the rest
+    /// name a top-level key in upper case. Values are read as in the file,
+    /// and as a string when they are not valid there, so quotes are optional.
+    pub fn layered(
+        file: Option<&str>,
+        env: impl IntoIterator<Item = (String, String)>,
+    ) -> Result<Self> {
+        let mut config = Self::embedded()?;
+        if let Some(text) = file {
+            config.apply_toml(text)?;
+        }
+
+        for (name, raw) in env {
+            let Some(key) = name.strip_prefix(CONFIG_ENV_PREFIX) else {
+                continue;
+            };
+            let value = toml::parse_inline(&raw).unwrap_or(Value::String(raw));
+            config
+                .set(&key.to_ascii_lowercase(), value)
+                .map_err(|e| match e {
+                    AppError::InvalidConfig { reason, .. } => AppError::InvalidConfig {
+                        field: name.clone(),
+                        reason,
+                    },
+                    other => other,
+                })?;
+        }
+        Ok(config)
+    }
+
+    /// Load a configuration file
+    ///
+    /// Keys the file leaves out keep the defaults of [`Config::new`] for the
+    /// built-in target.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self> {
+        Self::from_toml(&read_config_file(path.as_ref())?)
+    }
+
+    /// Parse the contents of a configuration file, see [`Config::from_file`]
+    ///
+    /// Files written for an older `schema_version` are upgraded first; files
+    /// without one are read as version 1.
+    pub fn from_toml(text: &str) -> Result<Self> {
+        let mut config = Config::new(TARGET_MODULE, &TARGET_PATTERN, PATTERN_MASK);
+        config.apply_toml(text)?;
+        Ok(config)
+    }
+
+    /// Set every key of a configuration file
+    fn apply_toml(&mut self, text: &str) -> Result<()> {
+        for entry in migrate(toml::parse(text)?, MIGRATIONS)? {
+            self.set(&entry.key, entry.value)?;
+        }
+        Ok(())
+    }
+
+    /// Every setting in configuration file syntax, for logging the merged
+    /// result of [`Config::load`]
+    ///
+    /// Reading the text back with [`Config::from_toml`] gives an equal
+    /// configuration.
+    pub fn effective(&self) -> String {
+        let mut lines = vec![format!("schema_version = {}", CONFIG_SCHEMA_VERSION)];
+        let mut set = |key: &str, value: String| lines.push(format!("{} = {}", key, value));
+
+        set("target_module", quoted(&self.target_module));
+        set("fallback_modules", quoted_list(&self.fallback_modules));
+        set("pattern", signature_text(&self.pattern, &self.mask));
+        set("function_offset", offset_text(self.function_offset));
+        set("max_scan_size", self.max_scan_size.to_string());
+        set("ace_init_timeout_ms", self.ace_init_timeout_ms.to_string());
+        set("ace_init_warn_ms", self.ace_init_warn_ms.to_string());
+        set("preamble_offset", format!("{:#x}", self.preamble_offset));
+        set(
+            "ace_preamble",
+            signature_text(&self.ace_preamble, &self.ace_preamble_mask),
+        );
+        set(
+            "module_wait_timeout_ms",
+            self.module_wait_timeout_ms.to_string(),
+        );
+        set("retry_attempts", self.retry_attempts.to_string());
+        set("retry_delay_ms", self.retry_delay_ms.to_string());
+        set("verify_uniqueness", self.verify_uniqueness.to_string());
+        if let Some(rva) = self.pattern_hint_rva {
+            set("pattern_hint_rva", format!("{:#x}", rva));
+        }
+        set("publish_overlay", self.publish_overlay.to_string());
+        set("cache_modules", self.cache_modules.to_string());
+        set("cache_patterns", self.cache_patterns.to_string());
+        set(
+            "dump_memory_map_on_failure",
+            self.dump_memory_map_on_failure.to_string(),
+        );
+        set("hook_backend", quoted(self.hook_backend.as_str()));
+        set("pak_allow", quoted_list(&self.pak_allow));
+        set("pak_deny", quoted_list(&self.pak_deny));
+        set(
+            "unmatched_pak_action",
+            quoted(self.unmatched_pak_action.as_str()),
+        );
+        set("spoof_value", spoof_text(self.spoof_value));
+        set("hook_once", self.hook_once.to_string());
+        set("dry_run", self.dry_run.to_string());
+        set("audit_file_access", self.audit_file_access.to_string());
+        set(
+            "mount_pattern",
+            signature_text(&self.mount_pattern, &self.mount_mask),
+        );
+        set(
+            "mount_function_offset",
+            offset_text(self.mount_function_offset),
+        );
+        set(
+            "sig_pattern",
+            signature_text(&self.sig_pattern, &self.sig_mask),
+        );
+        set("sig_function_offset", offset_text(self.sig_function_offset));
+        set("sig_spoof_value", spoof_text(self.sig_spoof_value));
+        if let Some(directory) = &self.extra_pak_dir {
+            set("extra_pak_dir", quoted(directory));
+        }
+        set("extra_pak_order", self.extra_pak_order.to_string());
+        set("log_level", quoted(self.log_level.as_str()));
+        set("log_format", quoted(self.log_format.as_str()));
+        set("log_timestamps", self.log_timestamps.to_string());
+        set("log_color", self.log_color.to_string());
+        set("log_queue_capacity", self.log_queue_capacity.to_string());
+        set("log_overflow", quoted(self.log_overflow.as_str()));
+        set("pak_log_burst", self.pak_log_burst.to_string());
+        set("log_level_hotkey", hotkey_text(self.log_level_hotkey));
+        set("console", self.console.to_string());
+        set("console_hotkey", hotkey_text(self.console_hotkey));
+        set("unload_hotkey", hotkey_text(self.unload_hotkey));
+        set("command_pipe", self.command_pipe.to_string());
+        set("watch_config", self.watch_config.to_string());
+        set(
+            "watchdog_interval_ms",
+            self.watchdog_interval_ms.to_string(),
+        );
+        if let Some(url) = &self.signature_update_url {
+            set("signature_update_url", quoted(url));
+        }
+        if let Some(key) = &self.signature_update_key {
+            set("signature_update_key", quoted(key));
+        }
+        set(
+            "signature_update_timeout_ms",
+            self.signature_update_timeout_ms.to_string(),
+        );
+
+        for signature in &self.signatures {
+            lines.push(String::new());
+            lines.push(format!("[signatures.{}]", signature.name));
+            lines.push(format!(
+                "pattern = {}",
+                signature_text(&signature.pattern, &signature.mask)
+            ));
+            lines.push(format!(
+                "function_offset = {}",
+                offset_text(signature.function_offset)
+            ));
+            if let Some(section) = &signature.section {
+                lines.push(format!("section = {}", quoted(section)));
+            }
+            if let Some(min) = signature.versions.min {
+                lines.push(format!("min_version = \"{}\"", min));
+            }
+            if let Some(max) = signature.versions.max {
+                lines.push(format!("max_version = \"{}\"", max));
+            }
+        }
+
+        if !self.log_filters.is_empty() {
+            lines.push(String::new());
+            lines.push("[log_filters]".to_string());
+            for (category, level) in self.log_filters.iter() {
+                lines.push(format!(
+                    "{} = {}",
+                    category.as_str(),
+                    quoted(level.as_str())
+                ));
+            }
+        }
+
+        if self.log_theme.changes().next().is_some() {
+            lines.push(String::new());
+            lines.push("[log_theme]".to_string());
+            for (level, color) in self.log_theme.changes() {
+                lines.push(format!(
+                    "{} = {}",
+                    level.as_str(),
+                    quoted(&color_name(color))
+                ));
+            }
+        }
+
+        for spec in &self.hooks {
+            lines.push(String::new());
+            lines.push(format!("[hooks.{}]", spec.name));
+            lines.push(format!("enabled = {}", spec.enabled));
+            lines.push(format!("action = {}", quoted(spec.action.as_str())));
+            if let Some(value) = spec.action.spoof_value() {
+                lines.push(format!("spoof_value = {:#x}", value));
+            }
+            if let Some(signature) = &spec.signature {
+                lines.push(format!("signature = {}", quoted(signature)));
+            }
+        }
+
+        lines.join("\n") + "\n"
+    }
+
+    /// Parse a signature database: a file holding only `[signatures.<name>]`
+    /// tables, in the same format as the configuration file, and an optional
+    /// top-level `version`
+    ///
+    /// Returns the version, 0 when the file has none, and the signatures.
+    pub fn signatures_from_toml(text: &str) -> Result<(u32, Vec<Signature>)> {
+        let mut config = Config::new(TARGET_MODULE, &TARGET_PATTERN, PATTERN_MASK);
+        let mut version = 0;
+        for Entry { key, value, .. } in migrate(toml::parse(text)?, MIGRATIONS)? {
+            let field = FileValue { key: &key, value };
+            match key.strip_prefix("signatures.") {
+                Some(rest) => config.set_signature(rest, field)?,
+                None if key == "version" => version = field.integer()?,
+                None => {
+                    return Err(field.invalid("only signature tables are allowed here".to_string()));
+                }
+            }
+        }
+        Ok((version, config.signatures))
+    }
+
+    /// Set the field named `key` from a file value
+    fn set(&mut self, key: &str, value: Value) -> Result<()> {
+        let field = FileValue { key, value };
+        match key {
+            "target_module" => self.target_module = field.string()?,
+            "fallback_modules" => self.fallback_modules = field.strings()?,
+            "pattern" => (self.pattern, self.mask) = field.signature()?,
+            "function_offset" => self.function_offset = field.integer()?,
+            "max_scan_size" => self.max_scan_size = field.integer()?,
+            "ace_init_timeout_ms" => self.ace_init_timeout_ms = field.integer()?,
+            "ace_init_warn_ms" => self.ace_init_warn_ms = field.integer()?,
+            "preamble_offset" => self.preamble_offset = field.integer()?,
+            "ace_preamble" => (self.ace_preamble, self.ace_preamble_mask) = field.signature()?,
+            "module_wait_timeout_ms" => self.module_wait_timeout_ms = field.integer()?,
+            "retry_attempts" => self.retry_attempts = field.integer()?,
+            "retry_delay_ms" => self.retry_delay_ms = field.integer()?,
+            "verify_uniqueness" => self.verify_uniqueness = field.boolean()?,
+            "pattern_hint_rva" => self.pattern_hint_rva = Some(field.integer()?),
+            "publish_overlay" => self.publish_overlay = field.boolean()?,
+            "cache_modules" => self.cache_modules = field.boolean()?,
+            "cache_patterns" => self.cache_patterns = field.boolean()?,
+            "dump_memory_map_on_failure" => self.dump_memory_map_on_failure = field.boolean()?,
+            "hook_backend" => self.hook_backend = field.string()?.parse()?,
+            "pak_allow" => self.pak_allow = field.strings()?,
+            "pak_deny" => self.pak_deny = field.strings()?,
+            "unmatched_pak_action" => self.unmatched_pak_action = field.string()?.parse()?,
+            "spoof_value" => self.spoof_value = field.spoof()?,
+            "hook_once" => self.hook_once = field.boolean()?,
+            "dry_run" => self.dry_run = field.boolean()?,
+            "audit_file_access" => self.audit_file_access = field.boolean()?,
+            "mount_pattern" => (self.mount_pattern, self.mount_mask) = field.signature()?,
+            "mount_function_offset" => self.mount_function_offset = field.integer()?,
+            "sig_pattern" => (self.sig_pattern, self.sig_mask) = field.signature()?,
+            "sig_function_offset" => self.sig_function_offset = field.integer()?,
+            "sig_spoof_value" => self.sig_spoof_value = field.spoof()?,
+            "extra_pak_dir" => self.extra_pak_dir = Some(field.string()?),
+            "extra_pak_order" => self.extra_pak_order = field.integer()?,
+            "log_level" => self.log_level = field.string()?.parse()?,
+            "log_format" => self.log_format = field.string()?.parse()?,
+            "log_timestamps" => self.log_timestamps = field.boolean()?,
+            "log_color" => self.log_color = field.boolean()?,
+            "log_queue_capacity" => self.log_queue_capacity = field.integer()?,
+            "log_overflow" => self.log_overflow = field.string()?.parse()?,
+            "pak_log_burst" => self.pak_log_burst = field.integer()?,
+            "log_level_hotkey" => self.log_level_hotkey = field.hotkey()?,
+            "console" => self.console = field.boolean()?,
+            "console_hotkey" => self.console_hotkey = field.hotkey()?,
+            "unload_hotkey" => self.unload_hotkey = field.hotkey()?,
+            "command_pipe" => self.command_pipe = field.boolean()?,
+            "watch_config" => self.watch_config = field.boolean()?,
+            "watchdog_interval_ms" => self.watchdog_interval_ms = field.integer()?,
+            "signature_update_url" => self.signature_update_url = Some(field.string()?),
+            "signature_update_key" => self.signature_update_key = Some(field.string()?),
+            "signature_update_timeout_ms" => self.signature_update_timeout_ms = field.integer()?,
+            _ => {
+                if let Some(rest) = key.strip_prefix("signatures.") {
+                    self.set_signature(rest, field)?
+                } else if let Some(rest) = key.strip_prefix("hooks.") {
+                    self.set_hook(rest, field)?
+                } else if let Some(category) = key.strip_prefix("log_filters.") {
+                    let level = field.string()?.parse()?;
+                    self.log_filters.set(category.parse()?, Some(level));
+                } else if let Some(level) = key.strip_prefix("log_theme.") {
+                    let color = parse_color(&field.string()?)?;
+                    self.log_theme.set(level.parse()?, color);
+                } else {
+                    return Err(field.invalid(unknown_key(key, FILE_KEYS)));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Set one field of a `[signatures.<name>]` table, given `<name>.<field>`
+    fn set_signature(&mut self, name_and_field: &str, field: FileValue<'_>) -> Result<()> {
+        let Some((name, key)) = name_and_field.rsplit_once('.') else {
+            return Err(field.invalid("expected a [signatures.<name>] table".to_string()));
+        };
+
+        let index = match self.signatures.iter().position(|entry| entry.name == name) {
+            Some(index) => index,
+            None => {
+                self.signatures.push(Signature::new(name, &[], "", 0));
+                self.signatures.len() - 1
+            }
+        };
+        let signature = &mut self.signatures[index];
+        match key {
+            "pattern" => (signature.pattern, signature.mask) = field.signature()?,
+            "function_offset" => signature.function_offset = field.integer()?,
+            "section" => signature.section = Some(field.string()?),
+            "min_version" => signature.versions.min = Some(field.version()?),
+            "max_version" => signature.versions.max = Some(field.version()?),
+            _ => return Err(field.invalid(unknown_key(key, SIGNATURE_KEYS))),
+        }
+        Ok(())
+    }
+
+    /// Set one field of a `[hooks.<name>]` table, given `<name>.<field>`
+    ///
+    /// A new table starts as an enabled hook spoofing `BYPASS_SUCCESS`.
+    fn set_hook(&mut self, name_and_field: &str, field: FileValue<'_>) -> Result<()> {
+        let Some((name, key)) = name_and_field.rsplit_once('.') else {
+            return Err(field.invalid("expected a [hooks.<name>] table".to_string()));
+        };
+
+        let index = match self.hooks.iter().position(|spec| spec.name == name) {
+            Some(index) => index,
+            None => {
+                self.hooks
+                    .push(HookSpec::new(name, HookAction::Spoof(BYPASS_SUCCESS)));
+                self.hooks.len() - 1
+            }
+        };
+        let spec = &mut self.hooks[index];
+        match key {
+            "enabled" => spec.enabled = field.boolean()?,
+            "action" => {
+                let value = spec.action.spoof_value().unwrap_or(BYPASS_SUCCESS);
+                spec.action = match field.string()?.to_ascii_lowercase().as_str() {
+                    "spoof" => HookAction::Spoof(value),
+                    "observe" => HookAction::Observe,
+                    "call-original" => HookAction::CallOriginal,
+                    other => return Err(field.invalid(format!("unknown action '{}'", other))),
+                }
+            }
+            "spoof_value" => spec.action = HookAction::Spoof(field.integer()?),
+            "signature" => spec.signature = Some(field.string()?),
+            _ => return Err(field.invalid(unknown_key(key, HOOK_KEYS))),
+        }
+        Ok(())
+    }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+/// Builder for a validated [`Config`], created by [`Config::builder`]
+///
+/// Settings left unset keep the defaults of [`Config::new`].
+#[derive(Debug, Clone)]
+pub struct ConfigBuilder {
+    config: Config,
+}
 
-    #[test]
-    fn test_config_validation_valid() {
-        let config = Config::new("test.exe", &[0x55, 0x53], "xx");
-        assert!(config.validate().is_ok());
+impl ConfigBuilder {
+    /// Modules tried in order when the target module holds no signature
+    pub fn fallback_modules(
+        mut self,
+        modules: impl IntoIterator<Item = impl Into<String>>,
+    ) -> Self {
+        self.config.fallback_modules = modules.into_iter().map(Into::into).collect();
+        self
     }
 
-    #[test]
-    fn test_config_validation_empty_module() {
-        let config = Config::new("", &[0x55, 0x53], "xx");
-        assert!(config.validate().is_err());
+    /// Distance from the signature match back to the function start
+    pub fn function_offset(mut self, function_offset: isize) -> Self {
+        self.config.function_offset = function_offset;
+        self
     }
 
-    #[test]
-    fn test_config_validation_mismatched_lengths() {
-        let config = Config::new("test.exe", &[0x55, 0x53], "x");
-        assert!(config.validate().is_err());
+    /// Maximum scan size in bytes
+    pub fn max_scan_size(mut self, max_scan_size: usize) -> Self {
+        self.config.max_scan_size = max_scan_size;
+        self
     }
 
-    #[test]
-    fn test_config_validation_empty_pattern() {
-        let config = Config::new("test.exe", &[], "xx");
-        assert!(config.validate().is_err());
+    /// Timeout for ACE initialization in milliseconds
+    pub fn ace_init_timeout_ms(mut self, ace_init_timeout_ms: u64) -> Self {
+        self.config.ace_init_timeout_ms = ace_init_timeout_ms;
+        self
     }
 
-    #[test]
-    fn test_config_validation_invalid_mask_character() {
-        let config = Config::new("test.exe", &[0x55, 0x53], "xz");
-        assert!(config.validate().is_err());
+    /// Soft threshold for ACE initialization in milliseconds
+    pub fn ace_init_warn_ms(mut self, ace_init_warn_ms: u64) -> Self {
+        self.config.ace_init_warn_ms = ace_init_warn_ms;
+        self
     }
 
-    #[test]
-    fn test_config_with_limits() {
-        let config = Config::with_limits("test.exe", &[0x55, 0x53], "xx", 1024, 1000);
-        assert!(config.validate().is_ok());
-        assert_eq!(config.max_scan_size, 1024);
-        assert_eq!(config.ace_init_timeout_ms, 1000);
+    /// Offset of the bytes compared by the ACE readiness check
+    pub fn preamble_offset(mut self, preamble_offset: usize) -> Self {
+        self.config.preamble_offset = preamble_offset;
+        self
     }
 
-    #[test]
-    fn test_config_has_wildcards() {
-        let config_with_wildcards = Config::new("test.exe", &[0x55, 0x53], "x?");
-        let config_without_wildcards = Config::new("test.exe", &[0x55, 0x53], "xx");
+    /// Bytes the ACE readiness check waits for at the preamble offset
+    pub fn ace_preamble(mut self, pattern: impl Into<Vec<u8>>, mask: impl Into<String>) -> Self {
+        self.config.ace_preamble = pattern.into();
+        self.config.ace_preamble_mask = mask.into();
+        self
+    }
 
-        assert!(config_with_wildcards.has_wildcards());
-        assert!(!config_without_wildcards.has_wildcards());
+    /// How long to wait for the target module to load
+    pub fn module_wait_timeout_ms(mut self, module_wait_timeout_ms: u64) -> Self {
+        self.config.module_wait_timeout_ms = module_wait_timeout_ms;
+        self
     }
 
-    #[test]
-    fn test_config_pattern_len() {
-        let config = Config::new("test.exe", &[0x55, 0x53, 0x56], "xxx");
-        assert_eq!(config.pattern_len(), 3);
+    /// Attempts at each retried startup step
+    pub fn retry_attempts(mut self, retry_attempts: u32) -> Self {
+        self.config.retry_attempts = retry_attempts;
+        self
+    }
+
+    /// Delay before the first retry of a startup step
+    pub fn retry_delay_ms(mut self, retry_delay_ms: u64) -> Self {
+        self.config.retry_delay_ms = retry_delay_ms;
+        self
+    }
+
+    /// Verify the signature matches exactly once
+    pub fn verify_uniqueness(mut self, verify_uniqueness: bool) -> Self {
+        self.config.verify_uniqueness = verify_uniqueness;
+        self
+    }
+
+    /// Last known RVA of the signature, used to suggest updates
+    pub fn pattern_hint_rva(mut self, rva: Rva) -> Self {
+        self.config.pattern_hint_rva = Some(rva);
+        self
+    }
+
+    /// Publish live statistics for overlay tools
+    pub fn publish_overlay(mut self, publish_overlay: bool) -> Self {
+        self.config.publish_overlay = publish_overlay;
+        self
+    }
+
+    /// Cache module lookups by name
+    pub fn cache_modules(mut self, cache_modules: bool) -> Self {
+        self.config.cache_modules = cache_modules;
+        self
+    }
+
+    /// Cache pattern scan results
+    pub fn cache_patterns(mut self, cache_patterns: bool) -> Self {
+        self.config.cache_patterns = cache_patterns;
+        self
+    }
+
+    /// Log the memory map when the scan fails
+    pub fn dump_memory_map_on_failure(mut self, dump_memory_map_on_failure: bool) -> Self {
+        self.config.dump_memory_map_on_failure = dump_memory_map_on_failure;
+        self
+    }
+
+    /// Backend used to install the pak check hook
+    pub fn hook_backend(mut self, hook_backend: HookBackendKind) -> Self {
+        self.config.hook_backend = hook_backend;
+        self
+    }
+
+    /// Glob patterns of paks to bypass
+    pub fn pak_allow(mut self, patterns: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.config.pak_allow = patterns.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Glob patterns of paks never to bypass
+    pub fn pak_deny(mut self, patterns: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.config.pak_deny = patterns.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// What to do with paks that are not bypassed
+    pub fn unmatched_pak_action(mut self, unmatched_pak_action: UnmatchedPakAction) -> Self {
+        self.config.unmatched_pak_action = unmatched_pak_action;
+        self
+    }
+
+    /// Value returned for bypassed paks, `None` to only observe
+    pub fn spoof_value(mut self, spoof_value: Option<usize>) -> Self {
+        self.config.spoof_value = spoof_value;
+        self
+    }
+
+    /// Remove the pak check hook after its first call
+    pub fn hook_once(mut self, hook_once: bool) -> Self {
+        self.config.hook_once = hook_once;
+        self
+    }
+
+    /// Only log pak check results, bypassing nothing
+    pub fn dry_run(mut self, dry_run: bool) -> Self {
+        self.config.dry_run = dry_run;
+        self
+    }
+
+    /// Log pak-related file opens
+    pub fn audit_file_access(mut self, audit_file_access: bool) -> Self {
+        self.config.audit_file_access = audit_file_access;
+        self
+    }
+
+    /// Signature of the pak mount routine
+    pub fn mount_signature(mut self, pattern: impl Into<Vec<u8>>, mask: impl Into<String>) -> Self {
+        self.config.mount_pattern = pattern.into();
+        self.config.mount_mask = mask.into();
+        self
+    }
+
+    /// Distance from the mount signature match to the routine start
+    pub fn mount_function_offset(mut self, mount_function_offset: isize) -> Self {
+        self.config.mount_function_offset = mount_function_offset;
+        self
+    }
+
+    /// Signature of the `.sig` file check routine
+    pub fn sig_signature(mut self, pattern: impl Into<Vec<u8>>, mask: impl Into<String>) -> Self {
+        self.config.sig_pattern = pattern.into();
+        self.config.sig_mask = mask.into();
+        self
+    }
+
+    /// Distance from the `.sig` check signature match to the routine start
+    pub fn sig_function_offset(mut self, sig_function_offset: isize) -> Self {
+        self.config.sig_function_offset = sig_function_offset;
+        self
+    }
+
+    /// Value the `.sig` check hook returns, `None` to only observe
+    pub fn sig_spoof_value(mut self, sig_spoof_value: Option<usize>) -> Self {
+        self.config.sig_spoof_value = sig_spoof_value;
+        self
+    }
+
+    /// Directory whose paks are mounted in addition to the game's own
+    pub fn extra_pak_dir(mut self, directory: impl Into<String>) -> Self {
+        self.config.extra_pak_dir = Some(directory.into());
+        self
+    }
+
+    /// Mount order of paks from the extra pak directory
+    pub fn extra_pak_order(mut self, extra_pak_order: u32) -> Self {
+        self.config.extra_pak_order = extra_pak_order;
+        self
+    }
+
+    /// Most verbose level logged
+    pub fn log_level(mut self, log_level: LogLevel) -> Self {
+        self.config.log_level = log_level;
+        self
+    }
+
+    /// Log `category` down to `level` instead of the overall level
+    pub fn log_filter(mut self, category: LogCategory, level: LogLevel) -> Self {
+        self.config.log_filters.set(category, Some(level));
+        self
+    }
+
+    /// Layout of logged lines
+    pub fn log_format(mut self, log_format: LogFormat) -> Self {
+        self.config.log_format = log_format;
+        self
+    }
+
+    /// Whether console lines are colored by level
+    pub fn log_color(mut self, log_color: bool) -> Self {
+        self.config.log_color = log_color;
+        self
+    }
+
+    /// Console color of `level`
+    pub fn log_theme_color(mut self, level: LogLevel, color: Color) -> Self {
+        self.config.log_theme.set(level, color);
+        self
+    }
+
+    /// Whether text lines carry the time columns
+    pub fn log_timestamps(mut self, log_timestamps: bool) -> Self {
+        self.config.log_timestamps = log_timestamps;
+        self
+    }
+
+    /// Records queued for the log writer thread, 0 for none
+    pub fn log_queue_capacity(mut self, log_queue_capacity: usize) -> Self {
+        self.config.log_queue_capacity = log_queue_capacity;
+        self
+    }
+
+    /// What happens to records logged while the queue is full
+    pub fn log_overflow(mut self, log_overflow: OverflowPolicy) -> Self {
+        self.config.log_overflow = log_overflow;
+        self
+    }
+
+    /// Pak verification lines logged per second, 0 for all
+    pub fn pak_log_burst(mut self, pak_log_burst: u32) -> Self {
+        self.config.pak_log_burst = pak_log_burst;
+        self
+    }
+
+    /// Key cycling the log level, `None` for none
+    pub fn log_level_hotkey(mut self, log_level_hotkey: Option<Hotkey>) -> Self {
+        self.config.log_level_hotkey = log_level_hotkey;
+        self
+    }
+
+    /// Open a console window, or log to a file and the debugger only
+    pub fn console(mut self, console: bool) -> Self {
+        self.config.console = console;
+        self
+    }
+
+    /// Key showing and hiding the console, `None` for none
+    pub fn console_hotkey(mut self, console_hotkey: Option<Hotkey>) -> Self {
+        self.config.console_hotkey = console_hotkey;
+        self
+    }
+
+    /// Key unloading the DLL, `None` for none
+    pub fn unload_hotkey(mut self, unload_hotkey: Option<Hotkey>) -> Self {
+        self.config.unload_hotkey = unload_hotkey;
+        self
+    }
+
+    /// Answer commands on the named pipe
+    pub fn command_pipe(mut self, command_pipe: bool) -> Self {
+        self.config.command_pipe = command_pipe;
+        self
+    }
+
+    /// Reload safe settings when the configuration file changes
+    pub fn watch_config(mut self, watch_config: bool) -> Self {
+        self.config.watch_config = watch_config;
+        self
+    }
+
+    /// How often the maintenance loop checks the hook and the file
+    pub fn watchdog_interval_ms(mut self, watchdog_interval_ms: u64) -> Self {
+        self.config.watchdog_interval_ms = watchdog_interval_ms;
+        self
+    }
+
+    /// Download signatures from `url`, checked against the hex public `key`
+    pub fn signature_update(mut self, url: impl Into<String>, key: impl Into<String>) -> Self {
+        self.config.signature_update_url = Some(url.into());
+        self.config.signature_update_key = Some(key.into());
+        self
+    }
+
+    /// Timeout of each signature update request in milliseconds
+    pub fn signature_update_timeout_ms(mut self, signature_update_timeout_ms: u64) -> Self {
+        self.config.signature_update_timeout_ms = signature_update_timeout_ms;
+        self
+    }
+
+    /// Add a named signature, tried after those added before it
+    pub fn signature(mut self, signature: Signature) -> Self {
+        self.config.signatures.push(signature);
+        self
+    }
+
+    /// Define a hook, replacing what the top-level settings set up for it
+    pub fn hook(mut self, spec: HookSpec) -> Self {
+        self.config.hooks.retain(|hook| hook.name != spec.name);
+        self.config.hooks.push(spec);
+        self
+    }
+
+    /// Validate and return the configuration
+    pub fn build(self) -> Result<Config> {
+        self.config.validate()?;
+        Ok(self.config)
+    }
+}
+
+fn read_config_file(path: &Path) -> Result<String> {
+    std::fs::read_to_string(path).map_err(|source| AppError::ConfigFileRead {
+        path: path.display().to_string(),
+        source,
+    })
+}
+
+/// A string in basic string syntax
+fn quoted(value: &str) -> String {
+    let mut text = String::from('"');
+    for c in value.chars() {
+        match c {
+            '"' => text.push_str("\\\""),
+            '\\' => text.push_str("\\\\"),
+            '\n' => text.push_str("\\n"),
+            '\t' => text.push_str("\\t"),
+            '\r' => text.push_str("\\r"),
+            _ => text.push(c),
+        }
+    }
+    text.push('"');
+    text
+}
+
+fn quoted_list(values: &[String]) -> String {
+    let items: Vec<String> = values.iter().map(|value| quoted(value)).collect();
+    format!("[{}]", items.join(", "))
+}
+
+/// A pattern and mask as hex bytes with `??` wildcards
+fn signature_text(pattern: &[u8], mask: &str) -> String {
+    let bytes: Vec<String> = pattern
+        .iter()
+        .zip(mask.chars())
+        .map(|(byte, mask)| match mask {
+            '?' => "??".to_string(),
+            _ => format!("{:02X}", byte),
+        })
+        .collect();
+    quoted(&bytes.join(" "))
+}
+
+fn offset_text(offset: isize) -> String {
+    match offset {
+        0.. => format!("{:#x}", offset),
+        _ => format!("-{:#x}", offset.unsigned_abs()),
+    }
+}
+
+fn spoof_text(value: Option<usize>) -> String {
+    value.map_or_else(|| "false".to_string(), |value| value.to_string())
+}
+
+fn hotkey_text(key: Option<Hotkey>) -> String {
+    key.map_or_else(|| "false".to_string(), |key| quoted(&key.to_string()))
+}
+
+/// Take the schema version out of a file's entries and rename the keys of
+/// every later `migrations` step
+fn migrate(mut entries: Vec<Entry>, migrations: &[Migration]) -> Result<Vec<Entry>> {
+    let version = match entries
+        .iter()
+        .position(|entry| entry.key == "schema_version")
+    {
+        Some(index) => {
+            let entry = entries.remove(index);
+            FileValue {
+                key: "schema_version",
+                value: entry.value,
+            }
+            .integer()?
+        }
+        None => 1,
+    };
+
+    if version == 0 || version > CONFIG_SCHEMA_VERSION {
+        return Err(AppError::InvalidConfig {
+            field: "schema_version".to_string(),
+            reason: format!(
+                "Schema version {} is not supported; this build reads versions 1 to {}",
+                version, CONFIG_SCHEMA_VERSION
+            ),
+        });
+    }
+
+    for migration in migrations.iter().filter(|step| step.from >= version) {
+        for entry in &mut entries {
+            if let Some((_, new)) = migration.renamed.iter().find(|(old, _)| entry.key == *old) {
+                entry.key = new.to_string();
+            }
+        }
+    }
+    Ok(entries)
+}
+
+/// Reason for rejecting an unknown key, suggesting a known one it may be a
+/// typo of
+fn unknown_key(key: &str, known: &[&str]) -> String {
+    let closest = known
+        .iter()
+        .map(|candidate| (edit_distance(key, candidate), *candidate))
+        .min();
+    match closest {
+        Some((distance, candidate)) if distance <= 2 => {
+            format!("unknown key, did you mean '{}'?", candidate)
+        }
+        _ => "unknown key".to_string(),
+    }
+}
+
+/// Levenshtein distance between two keys
+fn edit_distance(a: &str, b: &str) -> usize {
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.bytes().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, cb) in b.bytes().enumerate() {
+            let substitution = previous[j] + usize::from(ca != cb);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+/// A value read from a configuration file for one key
+struct FileValue<'k> {
+    key: &'k str,
+    value: Value,
+}
+
+impl FileValue<'_> {
+    fn invalid(&self, reason: String) -> AppError {
+        AppError::InvalidConfig {
+            field: self.key.to_string(),
+            reason,
+        }
+    }
+
+    fn mismatch(&self, expected: &str) -> AppError {
+        self.invalid(format!(
+            "expected {}, found {}",
+            expected,
+            self.value.type_name()
+        ))
+    }
+
+    fn string(&self) -> Result<String> {
+        match &self.value {
+            Value::String(value) => Ok(value.clone()),
+            _ => Err(self.mismatch("a string")),
+        }
+    }
+
+    fn version(&self) -> Result<VersionNumber> {
+        self.string()?
+            .parse()
+            .map_err(|_| self.invalid("expected a version such as \"2.5.0\"".to_string()))
+    }
+
+    fn boolean(&self) -> Result<bool> {
+        match self.value {
+            Value::Boolean(value) => Ok(value),
+            _ => Err(self.mismatch("a boolean")),
+        }
+    }
+
+    fn integer<T: TryFrom<i64>>(&self) -> Result<T> {
+        match self.value {
+            Value::Integer(value) => {
+                T::try_from(value).map_err(|_| self.invalid(format!("{} is out of range", value)))
+            }
+            _ => Err(self.mismatch("an integer")),
+        }
+    }
+
+    /// A spoofed return value, or `false` to disable spoofing
+    fn spoof(&self) -> Result<Option<usize>> {
+        match self.value {
+            Value::Boolean(false) => Ok(None),
+            Value::Integer(_) => self.integer().map(Some),
+            _ => Err(self.mismatch("an integer or false")),
+        }
+    }
+
+    /// A function key such as `"F9"`, or `false` for none
+    fn hotkey(&self) -> Result<Option<Hotkey>> {
+        match &self.value {
+            Value::Boolean(false) => Ok(None),
+            Value::String(name) => name
+                .parse()
+                .map(Some)
+                .map_err(|_| self.invalid(format!("'{}' is not a function key F1 to F24", name))),
+            _ => Err(self.mismatch("a key name or false")),
+        }
+    }
+
+    fn strings(&self) -> Result<Vec<String>> {
+        let Value::Array(items) = &self.value else {
+            return Err(self.mismatch("an array of strings"));
+        };
+        items
+            .iter()
+            .map(|item| match item {
+                Value::String(value) => Ok(value.clone()),
+                other => Err(self.invalid(format!(
+                    "expected an array of strings, found {} in it",
+                    other.type_name()
+                ))),
+            })
+            .collect()
+    }
+
+    /// A signature written as hex bytes with `??` wildcards, returned with
+    /// its mask
+    fn signature(&self) -> Result<(Vec<u8>, String)> {
+        parse_pattern(&self.string()?).map_err(|reason| self.invalid(reason))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_config_validation_valid() {
+        let config = Config::new("test.exe", &[0x55, 0x53], "xx");
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn test_config_validation_empty_module() {
+        let config = Config::new("", &[0x55, 0x53], "xx");
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_config_validation_mismatched_lengths() {
+        let config = Config::new("test.exe", &[0x55, 0x53], "x");
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_config_validation_empty_pattern() {
+        let config = Config::new("test.exe", &[], "xx");
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_config_validation_invalid_mask_character() {
+        let config = Config::new("test.exe", &[0x55, 0x53], "xz");
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_config_validation_hook_backend_must_replace() {
+        let mut config = Config::new("test.exe", &[0x55, 0x53], "xx");
+        config.hook_backend = HookBackendKind::IlhookJmpBack;
+        assert!(matches!(
+            config.validate(),
+            Err(AppError::InvalidConfig { field, .. }) if field == "hook_backend"
+        ));
+
+        config.hook_backend = HookBackendKind::IlhookRetn;
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn test_config_with_limits() {
+        let config = Config::with_limits("test.exe", &[0x55, 0x53], "xx", 1024, 1000);
+        assert!(config.validate().is_ok());
+        assert_eq!(config.max_scan_size, 1024);
+        assert_eq!(config.ace_init_timeout_ms, 1000);
+    }
+
+    #[test]
+    fn test_config_verifies_uniqueness_by_default() {
+        let config = Config::new("test.exe", &[0x55, 0x53], "xx");
+        assert!(config.verify_uniqueness);
+    }
+
+    #[test]
+    fn test_config_caches_by_default() {
+        let config = Config::new("test.exe", &[0x55, 0x53], "xx");
+        assert!(config.cache_modules);
+        assert!(config.cache_patterns);
+    }
+
+    #[test]
+    fn test_config_uses_interceptor_backend_by_default() {
+        let config = Config::new("test.exe", &[0x48], "x");
+        assert_eq!(config.hook_backend, HookBackendKind::Interceptor);
+    }
+
+    #[test]
+    fn test_config_hook_is_persistent_by_default() {
+        let config = Config::new("test.exe", &[0x48], "x");
+        assert!(!config.hook_once);
+    }
+
+    #[test]
+    fn test_config_file_audit_is_opt_in() {
+        let config = Config::new("test.exe", &[0x48], "x");
+        assert!(!config.audit_file_access);
+    }
+
+    #[test]
+    fn test_config_validation_mount_signature() {
+        let mut config = Config::new("test.exe", &[0x48], "x");
+        assert!(config.mount_pattern.is_empty());
+
+        config.mount_pattern = vec![0x48, 0x89];
+        assert!(config.validate().is_err());
+
+        config.mount_mask = "x?".to_string();
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn test_config_validation_sig_check_signature() {
+        let mut config = Config::new("test.exe", &[0x48], "x");
+        assert!(config.sig_pattern.is_empty());
+        assert_eq!(config.sig_spoof_value, Some(BYPASS_SUCCESS));
+
+        config.sig_pattern = vec![0x40, 0x53];
+        assert!(config.validate().is_err());
+
+        config.sig_mask = "xx".to_string();
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn test_config_validation_pak_injection_needs_mount_signature() {
+        let mut config = Config::new("test.exe", &[0x48], "x");
+        assert_eq!(config.extra_pak_order, EXTRA_PAK_ORDER);
+
+        config.extra_pak_dir = Some("~mods".to_string());
+        assert!(config.validate().is_err());
+
+        config.mount_pattern = vec![0x48];
+        config.mount_mask = "x".to_string();
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn test_config_validation_dry_run_excludes_pak_injection() {
+        let mut config = Config::new("test.exe", &[0x48], "x");
+        assert!(!config.dry_run);
+
+        config.dry_run = true;
+        config.mount_pattern = vec![0x48];
+        config.mount_mask = "x".to_string();
+        assert!(config.validate().is_ok());
+
+        config.extra_pak_dir = Some("~mods".to_string());
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_config_validation_rejects_empty_pak_pattern() {
+        let mut config = Config::new("test.exe", &[0x48], "x");
+        assert!(config.pak_allow.is_empty());
+
+        config.pak_deny = vec!["pakchunk0*".to_string(), String::new()];
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_config_validation_ace_preamble() {
+        let mut config = Config::new("test.exe", &[0x55, 0x53], "xx");
+        assert!(config.ace_preamble.is_empty());
+        config.preamble_offset = 0x10;
+        config.ace_preamble = vec![0x48; 16];
+        config.ace_preamble_mask = "x".repeat(16);
+        assert!(config.validate().is_ok());
+
+        config.ace_preamble_mask.pop();
+        assert!(config.validate().is_err());
+
+        config.ace_preamble = vec![0x48; MAX_PREAMBLE_LEN + 1];
+        config.ace_preamble_mask = "x".repeat(MAX_PREAMBLE_LEN + 1);
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_config_retry_policy() {
+        let mut config = Config::new("test.exe", &[0x55, 0x53], "xx");
+        let policy = config.retry_policy();
+        assert_eq!(policy.attempts, 3);
+        assert_eq!(policy.initial_delay, Duration::from_millis(100));
+
+        config.retry_attempts = 0;
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_config_validation_watchdog_interval() {
+        let mut config = Config::new("test.exe", &[0x55, 0x53], "xx");
+        assert!(config.watch_config);
+        config.watchdog_interval_ms = 0;
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_config_validation_hotkeys_differ() {
+        let mut config = Config::new("test.exe", &[0x55, 0x53], "xx");
+        config.console_hotkey = config.log_level_hotkey;
+        assert!(config.validate().is_err());
+
+        config.console_hotkey = None;
+        config.unload_hotkey = config.log_level_hotkey;
+        assert!(config.validate().is_err());
+
+        config.log_level_hotkey = None;
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn test_config_target_modules_start_with_target() {
+        let mut config = Config::new("test.exe", &[0x55, 0x53], "xx");
+        assert_eq!(config.target_modules(), ["test.exe"]);
+
+        config.fallback_modules = vec!["test-cn.exe".to_string(), "helper.dll".to_string()];
+        assert_eq!(
+            config.target_modules(),
+            ["test.exe", "test-cn.exe", "helper.dll"]
+        );
+        assert!(config.validate().is_ok());
+
+        config.fallback_modules.push(String::new());
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_config_validation_signature_update_needs_key() {
+        let mut config = Config::new("test.exe", &[0x55, 0x53], "xx");
+        config.signature_update_url = Some("https://example.com/signatures.toml".to_string());
+        assert!(config.validate().is_err());
+
+        config.signature_update_key = Some("ab".repeat(64));
+        assert!(config.validate().is_ok());
+
+        config.signature_update_url = Some("example.com/signatures.toml".to_string());
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_config_has_wildcards() {
+        let config_with_wildcards = Config::new("test.exe", &[0x55, 0x53], "x?");
+        let config_without_wildcards = Config::new("test.exe", &[0x55, 0x53], "xx");
+
+        assert!(config_with_wildcards.has_wildcards());
+        assert!(!config_without_wildcards.has_wildcards());
+    }
+
+    #[test]
+    fn test_config_pattern_len() {
+        let config = Config::new("test.exe", &[0x55, 0x53, 0x56], "xxx");
+        assert_eq!(config.pattern_len(), 3);
+    }
+
+    #[test]
+    fn test_builder_sets_fields_and_validates() {
+        let config = Config::builder("test.exe", &[0x48, 0x8B], "xx")
+            .max_scan_size(0x1000)
+            .pak_deny(["pakchunk0*"])
+            .mount_signature([0x40, 0x53], "x?")
+            .dry_run(true)
+            .build()
+            .unwrap();
+
+        assert_eq!(config.max_scan_size, 0x1000);
+        assert_eq!(config.pak_deny, ["pakchunk0*"]);
+        assert_eq!(config.mount_mask, "x?");
+        assert!(config.dry_run);
+        assert!(config.ace_preamble.is_empty());
+
+        let invalid = Config::builder("test.exe", &[0x48], "x")
+            .extra_pak_dir("~mods")
+            .build();
+        assert!(invalid.is_err());
+    }
+
+    #[test]
+    fn test_config_file_overrides_only_given_keys() {
+        let config = Config::from_toml(
+            r#"
+            target_module = "Client-Win64-Shipping-Beta.exe"
+            pattern = "49 81 C3 ?? 0B"
+            max_scan_size = 0x1000_0000
+            spoof_value = false
+            hook_backend = "ilhook-retn"
+            pak_deny = ["pakchunk0*"]
+            log_level = "bypass"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(config.target_module, "Client-Win64-Shipping-Beta.exe");
+        assert_eq!(config.pattern, [0x49, 0x81, 0xC3, 0x00, 0x0B]);
+        assert_eq!(config.mask, "xxx?x");
+        assert_eq!(config.max_scan_size, 0x1000_0000);
+        assert_eq!(config.spoof_value, None);
+        assert_eq!(config.hook_backend, HookBackendKind::IlhookRetn);
+        assert_eq!(config.pak_deny, ["pakchunk0*"]);
+        assert_eq!(config.log_level, LogLevel::Bypass);
+
+        assert_eq!(config.function_offset, TARGET_FUNCTION_OFFSET);
+        assert_eq!(config.sig_spoof_value, Some(BYPASS_SUCCESS));
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn test_config_file_signature_tables_come_before_pattern() {
+        let config = Config::from_toml(
+            r#"
+            [signatures.global]
+            pattern = "49 81 C3 ??"
+            function_offset = 0x40
+            section = ".text"
+            min_version = "2.5"
+
+            [signatures.cn]
+            pattern = "48 8B"
+            function_offset = -0x10
+            max_version = "2.4.9"
+            "#,
+        )
+        .unwrap();
+        assert!(config.validate().is_ok());
+
+        let db = config.signature_db();
+        let names: Vec<&str> = db
+            .entries()
+            .iter()
+            .map(|entry| entry.name.as_str())
+            .collect();
+        assert_eq!(names, ["global", "cn", DEFAULT_SIGNATURE]);
+
+        let global = db.get("global").unwrap();
+        assert_eq!(global.mask, "xxx?");
+        assert_eq!(global.function_offset, 0x40);
+        assert_eq!(global.section.as_deref(), Some(".text"));
+        assert_eq!(global.versions.min, Some(VersionNumber([2, 5, 0, 0])));
+        assert_eq!(db.get("cn").unwrap().function_offset, -0x10);
+        assert_eq!(db.get(DEFAULT_SIGNATURE).unwrap().pattern, config.pattern);
+    }
+
+    #[test]
+    fn test_config_file_rejects_unknown_keys_and_wrong_types() {
+        for text in [
+            "verify_uniquness = true",
+            "dry_run = 1",
+            "preamble_len = 8",
+            "ace_preamble = 1",
+            "pattern = \"49 GG\"",
+            "pak_allow = [1]",
+            "log_level = \"verbose\"",
+            "[signatures.global]\nsection = 1",
+            "[signatures.global]\nmin_version = \"2.x\"",
+            "[signatures.global]\noffset = 1",
+            "[hooks.pak_file_check]\naction = \"skip\"",
+            "[hooks.pak_file_check]\nenable = true",
+            "[log_filters]\nrender = \"info\"",
+            "[log_filters]\nscan = \"quiet\"",
+            "log_level_hotkey = \"F25\"",
+            "log_level_hotkey = true",
+            "[log_theme]\nerror = \"crimson\"",
+            "[log_theme]\nverbose = \"red\"",
+        ] {
+            assert!(
+                matches!(Config::from_toml(text), Err(AppError::InvalidConfig { .. })),
+                "{}",
+                text
+            );
+        }
+    }
+
+    #[test]
+    fn test_config_file_unknown_keys_suggest_a_known_one() {
+        match Config::from_toml("verify_uniquness = true") {
+            Err(AppError::InvalidConfig { field, reason }) => {
+                assert_eq!(field, "verify_uniquness");
+                assert!(reason.contains("'verify_uniqueness'"), "{}", reason);
+            }
+            other => panic!("{:?}", other),
+        }
+        match Config::from_toml("[signatures.global]\nsectoin = \".text\"") {
+            Err(AppError::InvalidConfig { reason, .. }) => {
+                assert!(reason.contains("'section'"), "{}", reason)
+            }
+            other => panic!("{:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_config_file_keys_are_all_handled() {
+        let mut config = Config::new("test.exe", &[0x48], "x");
+        for key in FILE_KEYS {
+            // A value of the wrong type still reaches the key's handler
+            if let Err(AppError::InvalidConfig { reason, .. }) =
+                config.set(key, Value::Array(vec![Value::Array(Vec::new())]))
+            {
+                assert!(!reason.starts_with("unknown key"), "{}", key);
+            }
+        }
+    }
+
+    #[test]
+    fn test_config_file_schema_version() {
+        let current = format!("schema_version = {}\ndry_run = true", CONFIG_SCHEMA_VERSION);
+        assert!(Config::from_toml(&current).unwrap().dry_run);
+
+        for text in [
+            "schema_version = 0",
+            "schema_version = 999",
+            "schema_version = \"1\"",
+        ] {
+            assert!(
+                matches!(Config::from_toml(text), Err(AppError::InvalidConfig { .. })),
+                "{}",
+                text
+            );
+        }
+    }
+
+    #[test]
+    fn test_migration_renames_keys_of_older_files() {
+        const RENAMES: &[Migration] = &[Migration {
+            from: 1,
+            renamed: &[("old_dry_run", "dry_run")],
+        }];
+        let entries = migrate(
+            toml::parse("schema_version = 1\nold_dry_run = true").unwrap(),
+            RENAMES,
+        )
+        .unwrap();
+        let keys: Vec<&str> = entries.iter().map(|entry| entry.key.as_str()).collect();
+        assert_eq!(keys, ["dry_run"]);
+    }
+
+    #[test]
+    fn test_signature_database_holds_only_signatures() {
+        let (version, signatures) = Config::signatures_from_toml(
+            "version = 7\n[signatures.global]\npattern = \"49 81\"\nmin_version = \"2.6\"",
+        )
+        .unwrap();
+        assert_eq!(version, 7);
+        assert_eq!(signatures.len(), 1);
+        assert_eq!(signatures[0].name, "global");
+        assert_eq!(signatures[0].mask, "xx");
+
+        assert!(matches!(
+            Config::signatures_from_toml("dry_run = true"),
+            Err(AppError::InvalidConfig { .. })
+        ));
+    }
+
+    #[test]
+    fn test_embedded_config_matches_built_in_defaults() {
+        assert_eq!(
+            Config::embedded().unwrap(),
+            Config::new(TARGET_MODULE, &TARGET_PATTERN, PATTERN_MASK)
+        );
+    }
+
+    #[test]
+    fn test_layers_override_in_order() {
+        let env = [
+            ("WUWA_SIG_LOG_LEVEL", "hook"),
+            ("WUWA_SIG_DRY_RUN", "true"),
+            ("WUWA_SIG_PAK_DENY", "[\"pakchunk1*\"]"),
+            ("PATH", "ignored"),
+        ]
+        .map(|(name, value)| (name.to_string(), value.to_string()));
+        let file = "log_level = \"bypass\"\npak_deny = [\"pakchunk0*\"]\nhook_once = true";
+
+        let config = Config::layered(Some(file), env).unwrap();
+        assert_eq!(config.log_level, LogLevel::Hook);
+        assert!(config.dry_run);
+        assert!(config.hook_once);
+        assert_eq!(config.pak_deny, ["pakchunk1*"]);
+        assert_eq!(config.target_module, TARGET_MODULE);
+    }
+
+    #[test]
+    fn test_layers_name_the_rejected_variable() {
+        let env = [("WUWA_SIG_VERIFY_UNIQUNESS".to_string(), "true".to_string())];
+        match Config::layered(None, env) {
+            Err(AppError::InvalidConfig { field, .. }) => {
+                assert_eq!(field, "WUWA_SIG_VERIFY_UNIQUNESS")
+            }
+            other => panic!("{:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_effective_config_reads_back_equal() {
+        let mut config = Config::from_toml(
+            r##"
+            fallback_modules = ["Client-Win64-Shipping-CN.exe"]
+            pattern = "49 81 ?? 9A"
+            function_offset = -0x10
+            pattern_hint_rva = 0x1234
+            pak_deny = ["pak \"chunk\" 0*"]
+            spoof_value = false
+            extra_pak_dir = 'C:\Mods'
+            mount_pattern = "48 8B"
+            signature_update_url = "https://example.com/signatures.toml"
+
+            [log_filters]
+            scan = "warning"
+            pak = "error"
+
+            [log_theme]
+            info = "white"
+            hook = "#ff8000"
+
+            [signatures.global]
+            pattern = "49 ??"
+            section = ".text"
+            min_version = "2.5"
+
+            [hooks.sig_file_check]
+            action = "observe"
+            signature = "global"
+            "##,
+        )
+        .unwrap();
+        config.signature_update_key = Some("ab".repeat(64));
+
+        let text = config.effective();
+        assert_eq!(Config::from_toml(&text).unwrap(), config);
+        for key in FILE_KEYS {
+            assert!(text.contains(&format!("\n{} = ", key)), "{}", key);
+        }
+    }
+
+    #[test]
+    fn test_hook_specs_follow_top_level_keys() {
+        let mut config = Config::new("test.exe", &[0x48], "x");
+        config.spoof_value = None;
+        let specs = config.hook_specs();
+        assert_eq!(
+            specs
+                .iter()
+                .map(|spec| spec.name.as_str())
+                .collect::<Vec<_>>(),
+            CONFIGURABLE_HOOKS
+        );
+        assert_eq!(specs[0].action, HookAction::CallOriginal);
+        assert!(specs[0].enabled);
+        assert!(!specs[1].enabled);
+
+        config.sig_pattern = vec![0x48];
+        config.sig_mask = "x".to_string();
+        config.dry_run = true;
+        for spec in config.hook_specs() {
+            assert!(spec.enabled);
+            assert_eq!(spec.action, HookAction::Observe);
+        }
+    }
+
+    #[test]
+    fn test_config_file_hook_tables_replace_top_level_keys() {
+        let config = Config::from_toml(
+            r#"
+            spoof_value = 2
+
+            [signatures.sig_check]
+            pattern = "48 8B ?? 10"
+
+            [hooks.pak_file_check]
+            enabled = false
+
+            [hooks.sig_file_check]
+            action = "spoof"
+            spoof_value = 0
+            signature = "sig_check"
+            "#,
+        )
+        .unwrap();
+        assert!(config.validate().is_ok());
+
+        let pak_check = config.hook_spec(PAK_CHECK_HOOK_NAME).unwrap();
+        assert!(!pak_check.enabled);
+        assert_eq!(pak_check.action, HookAction::Spoof(BYPASS_SUCCESS));
+
+        let sig_check = config.hook_spec(SIG_CHECK_HOOK_NAME).unwrap();
+        assert!(sig_check.enabled);
+        assert_eq!(sig_check.action, HookAction::Spoof(0));
+        assert_eq!(sig_check.signature.as_deref(), Some("sig_check"));
+    }
+
+    #[test]
+    fn test_config_validation_hook_tables() {
+        for text in [
+            "[hooks.mount_log]\nenabled = true",
+            "[hooks.pak_file_check]\nsignature = \"missing\"",
+            "[hooks.sig_file_check]\naction = \"observe\"",
+        ] {
+            let config = Config::from_toml(text).unwrap();
+            assert!(
+                matches!(config.validate(), Err(AppError::InvalidConfig { .. })),
+                "{}",
+                text
+            );
+        }
+    }
+
+    #[test]
+    fn test_config_file_missing_reports_path() {
+        assert!(matches!(
+            Config::from_file("definitely/not/wuwa-sig.toml"),
+            Err(AppError::ConfigFileRead { .. })
+        ));
     }
 }
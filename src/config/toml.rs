@@ -0,0 +1,314 @@
+//! Reader for the subset of TOML used by configuration files
+//!
+//! Supports comments, `[table]` headers, bare keys, basic and literal
+//! strings, decimal, hex, octal and binary integers, booleans and arrays on
+//! a single line. Keys inside a table are reported as `table.key`. Anything
+//! else, such as inline tables, floats or dates, is rejected with its line.
+
+use crate::error::{AppError, Result};
+
+/// A parsed value
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Value {
+    String(String),
+    Integer(i64),
+    Boolean(bool),
+    Array(Vec<Value>),
+}
+
+impl Value {
+    /// Name of the value's type, for error messages
+    pub(crate) fn type_name(&self) -> &'static str {
+        match self {
+            Self::String(_) => "a string",
+            Self::Integer(_) => "an integer",
+            Self::Boolean(_) => "a boolean",
+            Self::Array(_) => "an array",
+        }
+    }
+}
+
+/// One `key = value` line
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Entry {
+    /// Key prefixed with its table, if any
+    pub key: String,
+    pub value: Value,
+    /// Line number, starting at 1
+    pub line: usize,
+}
+
+/// Parse a document into its entries, in file order
+pub(crate) fn parse(text: &str) -> Result<Vec<Entry>> {
+    let mut entries: Vec<Entry> = Vec::new();
+    let mut table = String::new();
+
+    for (index, raw) in text.lines().enumerate() {
+        let line = index + 1;
+        let syntax = |reason: String| AppError::ConfigSyntax { line, reason };
+        let content = raw.trim();
+        if content.is_empty() || content.starts_with('#') {
+            continue;
+        }
+
+        if let Some(header) = content.strip_prefix('[') {
+            let (name, rest) = header
+                .split_once(']')
+                .ok_or_else(|| syntax("unterminated table header".to_string()))?;
+            expect_end(rest).map_err(syntax)?;
+            table = parse_key(name).map_err(syntax)?;
+            continue;
+        }
+
+        let (key, rest) = content
+            .split_once('=')
+            .ok_or_else(|| syntax("expected `key = value`".to_string()))?;
+        let key = parse_key(key).map_err(syntax)?;
+        let key = if table.is_empty() {
+            key
+        } else {
+            format!("{}.{}", table, key)
+        };
+        let (value, rest) = parse_value(rest).map_err(syntax)?;
+        expect_end(rest).map_err(syntax)?;
+
+        if entries.iter().any(|entry| entry.key == key) {
+            return Err(syntax(format!("duplicate key '{}'", key)));
+        }
+        entries.push(Entry { key, value, line });
+    }
+
+    Ok(entries)
+}
+
+/// Parse a lone value, such as one given in an environment variable
+pub(crate) fn parse_inline(text: &str) -> Option<Value> {
+    let (value, rest) = parse_value(text).ok()?;
+    expect_end(rest).ok()?;
+    Some(value)
+}
+
+/// Validate a bare key or dotted table name
+fn parse_key(key: &str) -> std::result::Result<String, String> {
+    let key = key.trim();
+    let valid = |part: &str| {
+        !part.is_empty()
+            && part
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+    };
+    if key.split('.').all(valid) {
+        Ok(key.to_string())
+    } else {
+        Err(format!("invalid key '{}'", key))
+    }
+}
+
+/// Only whitespace or a comment may follow a value
+fn expect_end(rest: &str) -> std::result::Result<(), String> {
+    let rest = rest.trim_start();
+    if rest.is_empty() || rest.starts_with('#') {
+        Ok(())
+    } else {
+        Err(format!("unexpected '{}'", rest))
+    }
+}
+
+/// Parse one value, returning it with the text after it
+fn parse_value(text: &str) -> std::result::Result<(Value, &str), String> {
+    let text = text.trim_start();
+    if let Some(rest) = text.strip_prefix('"') {
+        let (value, rest) = parse_basic_string(rest)?;
+        return Ok((Value::String(value), rest));
+    }
+    if let Some(rest) = text.strip_prefix('\'') {
+        let (value, rest) = rest
+            .split_once('\'')
+            .ok_or_else(|| "unterminated string".to_string())?;
+        return Ok((Value::String(value.to_string()), rest));
+    }
+    if let Some(rest) = text.strip_prefix('[') {
+        return parse_array(rest);
+    }
+
+    let end = text
+        .find(|c: char| c == ',' || c == ']' || c == '#' || c.is_whitespace())
+        .unwrap_or(text.len());
+    let (token, rest) = text.split_at(end);
+    let value = match token {
+        "" => return Err("missing value".to_string()),
+        "true" => Value::Boolean(true),
+        "false" => Value::Boolean(false),
+        _ => Value::Integer(parse_integer(token)?),
+    };
+    Ok((value, rest))
+}
+
+/// Parse a `"..."` string after its opening quote
+fn parse_basic_string(text: &str) -> std::result::Result<(String, &str), String> {
+    let mut value = String::new();
+    let mut chars = text.char_indices();
+    while let Some((index, c)) = chars.next() {
+        match c {
+            '"' => return Ok((value, &text[index + 1..])),
+            '\\' => {
+                let escaped = match chars.next().map(|(_, c)| c) {
+                    Some('"') => '"',
+                    Some('\\') => '\\',
+                    Some('n') => '\n',
+                    Some('t') => '\t',
+                    Some('r') => '\r',
+                    Some(other) => return Err(format!("unsupported escape '\\{}'", other)),
+                    None => break,
+                };
+                value.push(escaped);
+            }
+            _ => value.push(c),
+        }
+    }
+    Err("unterminated string".to_string())
+}
+
+/// Parse an array after its opening bracket
+fn parse_array(mut text: &str) -> std::result::Result<(Value, &str), String> {
+    let mut items = Vec::new();
+    loop {
+        text = text.trim_start();
+        if let Some(rest) = text.strip_prefix(']') {
+            return Ok((Value::Array(items), rest));
+        }
+        if text.is_empty() || text.starts_with('#') {
+            return Err("unterminated array; arrays must fit on one line".to_string());
+        }
+
+        let (item, rest) = parse_value(text)?;
+        items.push(item);
+        text = rest.trim_start();
+        if let Some(rest) = text.strip_prefix(',') {
+            text = rest;
+        } else if !text.starts_with(']') {
+            return Err("expected ',' or ']' in array".to_string());
+        }
+    }
+}
+
+fn parse_integer(token: &str) -> std::result::Result<i64, String> {
+    let digits = token.replace('_', "");
+    let (negative, digits) = match digits.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, digits.strip_prefix('+').unwrap_or(&digits)),
+    };
+    let (radix, digits) = match digits.get(..2) {
+        Some("0x") => (16, &digits[2..]),
+        Some("0o") => (8, &digits[2..]),
+        Some("0b") => (2, &digits[2..]),
+        _ => (10, digits),
+    };
+    // from_str_radix takes a sign of its own, which would allow `--5`
+    if digits.starts_with(['+', '-']) {
+        return Err(format!("invalid value '{}'", token));
+    }
+
+    let magnitude =
+        i64::from_str_radix(digits, radix).map_err(|_| format!("invalid value '{}'", token))?;
+    Ok(if negative { -magnitude } else { magnitude })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn values(text: &str) -> Vec<(String, Value)> {
+        parse(text)
+            .unwrap()
+            .into_iter()
+            .map(|entry| (entry.key, entry.value))
+            .collect()
+    }
+
+    #[test]
+    fn test_parses_scalars_arrays_and_tables() {
+        let text = r#"
+            # leading comment
+            target_module = "Client-Win64-Shipping.exe" # trailing comment
+            dir = 'C:\Mods'
+            offset = 0x45
+            limit = 104_857_600
+            dry_run = true
+            pak_deny = ["pakchunk0*", 'global*', ]
+
+            [log]
+            level = "info"
+        "#;
+
+        assert_eq!(
+            values(text),
+            [
+                (
+                    "target_module".to_string(),
+                    Value::String("Client-Win64-Shipping.exe".to_string())
+                ),
+                ("dir".to_string(), Value::String("C:\\Mods".to_string())),
+                ("offset".to_string(), Value::Integer(0x45)),
+                ("limit".to_string(), Value::Integer(104_857_600)),
+                ("dry_run".to_string(), Value::Boolean(true)),
+                (
+                    "pak_deny".to_string(),
+                    Value::Array(vec![
+                        Value::String("pakchunk0*".to_string()),
+                        Value::String("global*".to_string())
+                    ])
+                ),
+                ("log.level".to_string(), Value::String("info".to_string())),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_basic_strings_unescape() {
+        assert_eq!(
+            values(r#"path = "a\\b \"c\"""#),
+            [("path".to_string(), Value::String("a\\b \"c\"".to_string()))]
+        );
+    }
+
+    #[test]
+    fn test_parse_inline() {
+        assert_eq!(parse_inline("true"), Some(Value::Boolean(true)));
+        assert_eq!(
+            parse_inline("[\"a\"]"),
+            Some(Value::Array(vec![Value::String("a".to_string())]))
+        );
+        assert_eq!(parse_inline("info"), None);
+        assert_eq!(parse_inline("49 81"), None);
+    }
+
+    #[test]
+    fn test_integers_take_one_sign() {
+        assert_eq!(parse_integer("-5"), Ok(-5));
+        assert_eq!(parse_integer("+0x1_0"), Ok(16));
+        for token in ["--5", "+-5", "-+5", "0x-5", "-0b+1"] {
+            assert!(parse_integer(token).is_err(), "{}", token);
+        }
+    }
+
+    #[test]
+    fn test_errors_report_their_line() {
+        for (text, line) in [
+            ("a = 1\nb = 1.5", 2),
+            ("a = \"open", 1),
+            ("a = [1, 2", 1),
+            ("\n\na = 1\na = 2", 4),
+            ("[table", 1),
+            ("just a line", 1),
+            ("a = 1 2", 1),
+        ] {
+            match parse(text) {
+                Err(AppError::ConfigSyntax { line: found, .. }) => {
+                    assert_eq!(found, line, "{:?}", text)
+                }
+                other => panic!("{:?} parsed as {:?}", text, other),
+            }
+        }
+    }
+}
@@ -9,19 +9,149 @@ pub mod constants {
     /// Pattern mask for the target pattern (x = exact match, ? = wildcard)
     pub const PATTERN_MASK: &str = "xxxxxxx";
 
+    /// Revision of the built-in signature, bumped whenever `TARGET_PATTERN`,
+    /// `PATTERN_MASK` or the function offset change
+    pub const SIGNATURE_DB_VERSION: u32 = 1;
+
+    /// Configuration file looked up next to the DLL
+    pub const CONFIG_FILE_NAME: &str = "wuwa-sig.toml";
+
+    /// Newest configuration file schema this build reads
+    pub const CONFIG_SCHEMA_VERSION: u32 = 1;
+
+    /// Prefix of environment variables overriding configuration keys, as in
+    /// `WUWA_SIG_LOG_LEVEL`
+    pub const CONFIG_ENV_PREFIX: &str = "WUWA_SIG_";
+
+    /// Last verified download of the signature database, next to the DLL
+    pub const SIGNATURE_CACHE_FILE_NAME: &str = "wuwa-sig-signatures.toml";
+
+    /// Log lines kept in memory for post-mortem retrieval
+    pub const RECENT_LOG_CAPACITY: usize = 512;
+
+    /// File next to the DLL receiving the recent log lines when
+    /// initialization fails
+    pub const RECENT_LOG_FILE_NAME: &str = "wuwa-sig-last.log";
+
+    /// Start of the name of the crash report file written next to the DLL
+    /// when initialization fails, followed by its date and time
+    pub const CRASH_REPORT_PREFIX: &str = "wuwa-sig-crash";
+
+    /// File next to the DLL receiving every log line when running without a
+    /// console
+    pub const LOG_FILE_NAME: &str = "wuwa-sig.log";
+
+    /// Largest signature database accepted from the update URL
+    pub const MAX_SIGNATURE_DB_SIZE: usize = 1024 * 1024;
+
     /// DLL process attach reason code
     pub const DLL_PROCESS_ATTACH: u32 = 1;
 
+    /// DLL process detach reason code
+    pub const DLL_PROCESS_DETACH: u32 = 0;
+
     /// Success return value for bypass function
     pub const BYPASS_SUCCESS: usize = 1;
 
+    /// Failure return value reported for paks the filter does not bypass
+    pub const BYPASS_FAILURE: usize = 0;
+
+    /// Prefix of the per-process mutex marking an initialized copy of the DLL;
+    /// the process id is appended
+    pub const INSTANCE_MUTEX_PREFIX: &str = "Local\\WuwaSigInstance-";
+
+    /// Mount order of paks injected from the extra pak directory; above the
+    /// engine's own orders so injected paks override the game's
+    pub const EXTRA_PAK_ORDER: u32 = 1000;
+
+    /// Name the PAK verification hook is registered under
+    pub const PAK_CHECK_HOOK_NAME: &str = "pak_file_check";
+
+    /// Name the `.sig` file verification hook is registered under
+    pub const SIG_CHECK_HOOK_NAME: &str = "sig_file_check";
+
+    /// Pak verification lines logged per second before the rest are only
+    /// counted
+    pub const PAK_LOG_BURST: u32 = 20;
+
+    /// Longest delay between two attempts of a retried startup step
+    pub const MAX_RETRY_DELAY_MS: u64 = 2000;
+
+    /// Time given to calls already inside a detour to leave it before the
+    /// DLL unloads
+    pub const UNLOAD_GRACE_MS: u64 = 100;
+
+    /// Time without a game mount after which extra paks are injected
+    pub const INJECT_SETTLE_MS: u64 = 2000;
+
+    /// Named pipe answering status and control commands
+    pub const COMMAND_PIPE_NAME: &str = "\\\\.\\pipe\\wuwa-sig";
+
+    /// Longest command accepted on the command pipe, in bytes
+    pub const MAX_COMMAND_LEN: usize = 256;
+
+    /// Manual-reset event set once the hooks are applied
+    pub const READY_EVENT_NAME: &str = "Global\\WuwaSigReady";
+
+    /// Manual-reset event set when initialization fails
+    pub const FAILED_EVENT_NAME: &str = "Global\\WuwaSigFailed";
+
+    /// Shared-memory section holding the error code behind the failed event
+    pub const LAUNCH_STATUS_NAME: &str = "Local\\WuwaSigLaunch";
+
+    /// Longest `wuwa_sig_shutdown` waits for the hooks to be removed
+    pub const FFI_SHUTDOWN_TIMEOUT_MS: u64 = 5000;
+
     /// Memory access constants
     pub mod memory {
         /// Offset for v4 pointer in the pak file check structure
         pub const V4_POINTER_OFFSET: usize = 16;
 
-        /// Offset for parent pointer in the structure
+        /// Offset of the pak name, an `FString`, in the parent structure
         pub const PARENT_POINTER_OFFSET: usize = 8;
+
+        /// Distance from the signature match back to the pak-check function start
+        pub const TARGET_FUNCTION_OFFSET: isize = 0x45;
+
+        /// Largest distance allowed between a signature match and its function
+        /// start, in either direction
+        pub const MAX_FUNCTION_OFFSET: usize = 0x10000;
+
+        /// Bytes saved from a hook target before patching; covers the longest
+        /// jump stub plus an instruction split by it
+        pub const HOOK_BACKUP_LEN: usize = 32;
+
+        /// Initial number of module handles requested from EnumProcessModules
+        pub const INITIAL_MODULE_CAPACITY: usize = 1024;
+
+        /// Interval between module presence checks while waiting for a module
+        pub const MODULE_POLL_INTERVAL_MS: u64 = 50;
+
+        /// Distance around a hinted RVA searched for a drifted signature
+        pub const SIGNATURE_HINT_WINDOW: usize = 0x1000;
+
+        /// Interval between progress lines while waiting for ACE
+        pub const ACE_PROGRESS_INTERVAL_MS: u64 = 1000;
+
+        /// Largest preamble window compared by the ACE readiness check
+        pub const MAX_PREAMBLE_LEN: usize = 64;
+
+        /// Page size used to align page-granular protection changes
+        pub const PAGE_SIZE: usize = 0x1000;
+
+        /// Longest pak path read from game memory, in UTF-16 units; a
+        /// longer one means the pointer led somewhere else
+        pub const MAX_PAK_PATH_CHARS: usize = 4096;
+
+        /// Number of recent invocations kept per hook
+        pub const HOOK_HISTORY_LEN: usize = 64;
+
+        /// Times a hook transaction re-suspends threads while one of them is
+        /// still executing inside a patch window
+        pub const TRANSACTION_SUSPEND_ATTEMPTS: u32 = 10;
+
+        /// Time other threads are allowed to run between transaction attempts
+        pub const TRANSACTION_RETRY_DELAY_MS: u64 = 1;
     }
 
     /// Logging constants
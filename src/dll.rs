@@ -0,0 +1,574 @@
+//! `DllMain` and the initialization thread it starts
+//!
+//! Built with the `dll` feature. Attaching starts a thread that sets up the
+//! console and logger, runs the steps in [`crate::bypass`] and then watches
+//! the hooks until a shutdown is requested.
+
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::thread;
+use std::time::Duration;
+
+use winapi::shared::minwindef::{BOOL, DWORD, HMODULE, LPVOID, TRUE};
+use winapi::um::libloaderapi::{DisableThreadLibraryCalls, FreeLibraryAndExitThread};
+
+use crate::build_info::BuildInfo;
+use crate::bypass::{
+    PAK_LOG_LIMIT, apply_reload, check_pak_check_bytes, install_hooks, load_config, locate_target,
+    report_held_back, update_signatures, wait_for_ace_init, wait_for_target_modules,
+};
+use crate::config::Config;
+use crate::constants::constants::*;
+use crate::error::{AppError, Result};
+use crate::logger::{
+    ColorTheme, ConsoleSink, DebuggerSink, LogLevel, LogRecord, LogSink, Logger, WriterSink,
+};
+use crate::memory::{ModuleScanner, Rva};
+use crate::reload::ConfigWatcher;
+use crate::report::CrashReport;
+use crate::safety::{
+    ConsoleManager, ConsoleSource, InstanceGuard, ManagedThread, MemoryAccess, ThreadManager,
+};
+use crate::startup::StartupPhase;
+use crate::{
+    anticheat, audit, ffi, hooks, hotkey, inject, ipc, logger, memory, mount, overlay, ready,
+    shutdown, startup,
+};
+
+/// Thread running [`apply_bypass_hook`], kept to wait for it on shutdown
+static INIT_THREAD: OnceLock<ManagedThread> = OnceLock::new();
+
+/// Held while deciding whether to start [`INIT_THREAD`], so `DllMain` and
+/// `wuwa_sig_init` cannot both start it
+static INIT_START: Mutex<()> = Mutex::new(());
+
+/// Initialize the console using safe abstractions
+fn init_console() -> Result<ConsoleSource> {
+    ConsoleManager::init()
+}
+
+/// Apply the bypass hook to the target function
+///
+/// Initialization is driven through the startup phases in `startup`, each of
+/// which is logged on entry so failures can be localized from user reports:
+/// 1. `ConsoleInit` - initializes the console for logging
+/// 2. `ConfigLoad` - loads and validates the configuration
+/// 3. `ModuleWait` - finds the target module
+/// 4. `Scan` - finds the target function using pattern scanning
+/// 5. `AceWait` - waits for ACE initialization to complete
+/// 6. `Hook` - applies the hook to replace the original function
+/// 7. `Running` - maintains the hook until shutdown is requested
+///
+/// # Safety
+///
+/// This function is marked as unsafe because it:
+/// - Performs memory scanning operations
+/// - Accesses process memory
+/// - Modifies function pointers and code execution
+///
+/// # Returns
+///
+/// Returns `Ok(())` once shutdown was requested and the hooks were removed,
+/// or an error if any step before `Running` fails.
+unsafe fn apply_bypass_hook(dll_module: HMODULE) -> Result<()> {
+    startup::enter(StartupPhase::ConsoleInit);
+    let launch_events = ready::init();
+    let config_path = config_path(dll_module);
+    // Errors surface in the full load below, on the console
+    let headless = Config::load(config_path.as_deref()).is_ok_and(|config| !config.console);
+    let console = if headless {
+        None
+    } else {
+        Some(init_console()?)
+    };
+
+    // Initialize the global logger after console is ready
+    logger::init_global_logger();
+    logger::install_panic_hook();
+    logger::install_crash_handler();
+
+    // Try to use the logger
+    match console {
+        Some(console) => Logger::info(&format!(
+            "Console ({}) and logger initialized successfully",
+            console.describe()
+        )),
+        None => log_headless(dll_module),
+    }
+    Logger::info(&format!("wuwa-sig-rs {}", BuildInfo::CURRENT));
+    if let Err(e) = launch_events {
+        Logger::warning(&format!("Launchers cannot wait for the bypass: {}", e));
+    }
+    if let Err(e) = Logger::install_as_log_backend() {
+        Logger::warning(&format!("Dependency logs unavailable: {}", e));
+    }
+
+    startup::enter(StartupPhase::ConfigLoad);
+    let mut config = load_config(config_path.as_deref())?;
+    if config.signature_update_url.is_some() {
+        update_signatures(&mut config, dll_directory(dll_module).as_deref());
+    }
+
+    if config.publish_overlay {
+        match overlay::init_global_overlay() {
+            Ok(_) => Logger::info(&format!(
+                "Overlay statistics published at '{}'",
+                overlay::OVERLAY_SECTION_NAME
+            )),
+            Err(e) => Logger::warning(&format!("Overlay statistics unavailable: {}", e)),
+        }
+    }
+
+    startup::enter(StartupPhase::ModuleWait);
+    let scanner = ModuleScanner::with_caching(config.cache_modules);
+    let candidates = wait_for_target_modules(&config, &scanner)?;
+
+    startup::enter(StartupPhase::Scan);
+    let (module_base, module_info, scan) = locate_target(&config, &scanner, &candidates)?;
+    if let Some(rva) = Rva::from_address(scan.target_func as usize, module_base as usize) {
+        startup::status().set_target(rva);
+    }
+
+    startup::enter(StartupPhase::AceWait);
+    let anti_cheat = anticheat::report();
+    if !anti_cheat
+        .iter()
+        .any(|module| module.vendor == anticheat::AntiCheatVendor::Ace)
+    {
+        Logger::warning("ACE modules not detected, relying on the preamble check alone");
+    }
+
+    if config.ace_preamble.is_empty() {
+        Logger::info("No ace_preamble configured, hooking without waiting for ACE");
+    } else {
+        let check_address = scan.target_func.wrapping_add(config.preamble_offset);
+        Logger::info(&format!(
+            "Waiting for {:02X?} at +{:#x} before hooking",
+            config.ace_preamble, config.preamble_offset
+        ));
+        let ace_wait = wait_for_ace_init(
+            check_address,
+            &config.ace_preamble,
+            &config.ace_preamble_mask,
+            Duration::from_millis(config.ace_init_timeout_ms),
+        )?;
+        Logger::info(&format!(
+            "ACE initialization took {:?} ({} polls)",
+            ace_wait.waited, ace_wait.polls
+        ));
+        if ace_wait.waited > Duration::from_millis(config.ace_init_warn_ms) {
+            Logger::warning(&format!(
+                "ACE initialization exceeded the {}ms soft threshold",
+                config.ace_init_warn_ms
+            ));
+        }
+    }
+
+    startup::enter(StartupPhase::Hook);
+    unsafe { install_hooks(&config, &module_info, scan.target_func)? };
+
+    if config.audit_file_access {
+        match audit::install(module_base) {
+            Ok(()) => Logger::info("Auditing pak-related file opens"),
+            Err(e) => Logger::warning(&format!("File access audit unavailable: {}", e)),
+        }
+    }
+
+    if let Some(directory) = &config.extra_pak_dir {
+        match inject::configure(directory, config.extra_pak_order) {
+            Ok(()) => Logger::info(&format!("Extra paks will be mounted from '{}'", directory)),
+            Err(e) => Logger::warning(&format!("Pak injection unavailable: {}", e)),
+        }
+    }
+    if !config.mount_pattern.is_empty() {
+        match mount::locate(&config, &module_info).and_then(mount::install) {
+            Ok(()) => Logger::info("Logging pak mounts"),
+            Err(e) => Logger::warning(&format!("Mount logging unavailable: {}", e)),
+        }
+    }
+
+    startup::enter(StartupPhase::Running);
+    if !scan.signature_clean {
+        startup::degrade("signature is ambiguous or outside executable memory");
+    }
+
+    Logger::success("Bypass successfully applied!");
+    let workers: Vec<_> = [start_hotkeys(&config), start_command_pipe(&config)]
+        .into_iter()
+        .flatten()
+        .collect();
+
+    Logger::info("Entering maintenance loop...");
+    maintain(config, config_path);
+    shut_down(workers);
+    Ok(())
+}
+
+/// Start the hotkey thread for the configured keys, if any
+fn start_hotkeys(config: &Config) -> Option<thread::JoinHandle<()>> {
+    let mut bindings = Vec::new();
+    let mut help = Vec::new();
+    if let Some(key) = config.log_level_hotkey {
+        bindings.push(hotkey::Binding::new(key, || {
+            logger::cycle_global_level();
+        }));
+        help.push(format!("{} cycles the log level", key));
+    }
+    if let Some(key) = config.console_hotkey {
+        bindings.push(hotkey::Binding::new(key, toggle_console));
+        help.push(format!("{} shows or hides the console", key));
+    }
+    if let Some(key) = config.unload_hotkey {
+        bindings.push(hotkey::Binding::new(key, || shutdown::request(true)));
+        help.push(format!("{} unloads the DLL", key));
+    }
+    if bindings.is_empty() {
+        return None;
+    }
+
+    match hotkey::spawn(bindings) {
+        Ok(handle) => {
+            Logger::info(&format!("Hotkeys: {}", help.join(", ")));
+            Some(handle)
+        }
+        Err(e) => {
+            Logger::warning(&format!("Hotkeys unavailable: {}", e));
+            None
+        }
+    }
+}
+
+/// Start answering commands on the named pipe, if enabled
+fn start_command_pipe(config: &Config) -> Option<thread::JoinHandle<()>> {
+    if !config.command_pipe {
+        return None;
+    }
+    match ipc::spawn() {
+        Ok(handle) => {
+            Logger::info(&format!("Answering commands on '{}'", COMMAND_PIPE_NAME));
+            Some(handle)
+        }
+        Err(e) => {
+            Logger::warning(&format!("Command pipe unavailable: {}", e));
+            None
+        }
+    }
+}
+
+/// Show or hide the console window, opening one with the recent log lines
+/// when running without
+fn toggle_console() {
+    if ConsoleManager::toggle_visibility().is_some() {
+        return;
+    }
+    match init_console() {
+        Ok(_) => {
+            let console = Arc::new(ConsoleSink::new());
+            for line in logger::global_recent(RECENT_LOG_CAPACITY) {
+                console.write(&LogRecord {
+                    level: LogLevel::Info,
+                    message: &line,
+                    fields: &[],
+                    line: &line,
+                    colored: false,
+                    color: ColorTheme::default().color(LogLevel::Info),
+                });
+            }
+            logger::add_global_sink(console);
+            Logger::info("Console opened");
+        }
+        Err(e) => Logger::warning(&format!("Console could not be opened: {}", e)),
+    }
+}
+
+/// Watch the pak check hook and the configuration file until a shutdown is
+/// requested
+///
+/// Every `watchdog_interval_ms` this refreshes the overlay heartbeat, warns
+/// once if the hook was lost and applies the safe settings of a changed
+/// configuration file.
+fn maintain(mut config: Config, config_path: Option<PathBuf>) {
+    let mut watcher = config_path
+        .filter(|_| config.watch_config)
+        .map(ConfigWatcher::new);
+    if let Some(watcher) = &watcher {
+        Logger::info(&format!(
+            "Watching '{}' for changes",
+            watcher.path().display()
+        ));
+    }
+
+    let mut hook_active = hooks::global_hook_manager()
+        .status(PAK_CHECK_HOOK_NAME)
+        .is_some_and(|info| info.is_active);
+    loop {
+        if shutdown::signal().wait(Duration::from_millis(config.watchdog_interval_ms)) {
+            return;
+        }
+        if let Some(overlay) = overlay::global_overlay() {
+            overlay.heartbeat();
+        }
+
+        let active = hooks::global_hook_manager()
+            .status(PAK_CHECK_HOOK_NAME)
+            .is_some_and(|info| info.is_active);
+        if hook_active && !active && !config.hook_once {
+            Logger::warning("Pak check hook is no longer active");
+        }
+        hook_active = active;
+        check_pak_check_bytes();
+
+        report_held_back(PAK_LOG_LIMIT.take_suppressed());
+
+        match watcher.as_mut().and_then(ConfigWatcher::poll) {
+            Some(Ok(updated)) => apply_reload(&mut config, &updated),
+            Some(Err(e)) => Logger::error(&format!(
+                "Changed configuration rejected, keeping the current one: {}",
+                e
+            )),
+            None => {}
+        }
+    }
+}
+
+/// Remove the hooks and every handler pointing into the DLL
+///
+/// After this nothing in the process calls back into the DLL, so it can be
+/// freed. Failures are logged: a hook that can't be removed is still better
+/// than one left behind by a thread that gave up halfway.
+fn shut_down(workers: Vec<thread::JoinHandle<()>>) {
+    Logger::info("Shutting down...");
+    let removals = [
+        ("hooks", hooks::global_hook_manager().remove_all()),
+        ("mount logging", mount::uninstall()),
+        ("file access audit", audit::uninstall()),
+    ];
+    for (what, result) in removals {
+        if let Err(e) = result {
+            Logger::warning(&format!("Could not remove {}: {}", what, e));
+        }
+    }
+    ready::withdraw();
+    for worker in workers {
+        let _ = worker.join();
+    }
+
+    // Let calls already inside a detour leave it before its code goes away
+    thread::sleep(Duration::from_millis(UNLOAD_GRACE_MS));
+    hooks::guard::remove_exception_handler();
+    hooks::hwbp::remove_exception_handler();
+    MemoryAccess::remove_fault_handler();
+    logger::uninstall_crash_handler();
+    InstanceGuard::release();
+
+    Logger::info("Hooks removed");
+    logger::stop_global_worker();
+}
+
+/// Send the log to a file next to the DLL and to the debugger instead of
+/// a console
+fn log_headless(dll_module: HMODULE) {
+    let mut sinks: Vec<Arc<dyn LogSink>> = vec![Arc::new(DebuggerSink::new())];
+    let opened = dll_directory(dll_module)
+        .map(|dir| dir.join(LOG_FILE_NAME))
+        .map(|path| WriterSink::file(&path).map(|file| (path, file)));
+    let message = match opened {
+        Some(Ok((path, file))) => {
+            sinks.push(Arc::new(file));
+            format!("Logging to '{}' and the debugger", path.display())
+        }
+        Some(Err(e)) => format!("Log file unavailable, logging to the debugger only: {}", e),
+        None => "DLL directory unknown, logging to the debugger only".to_string(),
+    };
+    logger::set_global_sinks(sinks);
+    Logger::info(&format!("Running without a console. {}", message));
+}
+
+/// Save the recent log lines next to the DLL, as the console closes with
+/// the game
+fn dump_recent_log(dll_module: HMODULE) {
+    let Some(path) = dll_directory(dll_module).map(|dir| dir.join(RECENT_LOG_FILE_NAME)) else {
+        return;
+    };
+    match logger::dump_global_recent(&path) {
+        Ok(()) => Logger::info(&format!("Recent log saved to '{}'", path.display())),
+        Err(e) => Logger::warning(&format!("Recent log not saved: {}", e)),
+    }
+}
+
+/// Write a crash report for `error` next to this DLL
+fn write_crash_report(dll_module: HMODULE, error: &AppError) {
+    let Some(directory) = dll_directory(dll_module) else {
+        return;
+    };
+    // Loading again gives the configuration the failed run used, or the
+    // reason it could not be loaded
+    let config = Config::load(config_path(dll_module).as_deref());
+    match CrashReport::collect(error, config.as_ref()).write(&directory) {
+        Ok(path) => Logger::info(&format!("Crash report saved to '{}'", path.display())),
+        Err(e) => Logger::warning(&format!("Crash report not saved: {}", e)),
+    }
+}
+
+/// Directory holding this DLL
+fn dll_directory(dll_module: HMODULE) -> Option<PathBuf> {
+    memory::hash::module_path(dll_module)
+        .ok()
+        .and_then(|path| Some(path.parent()?.to_path_buf()))
+}
+
+/// Path of the configuration file chosen by a loader, or else of the one
+/// next to the DLL, if there is one
+fn config_path(dll_module: HMODULE) -> Option<PathBuf> {
+    ffi::resolve_config_path(|| {
+        dll_directory(dll_module)
+            .map(|directory| directory.join(CONFIG_FILE_NAME))
+            .filter(|path| path.is_file())
+    })
+}
+
+/// Thread entry point for the hook application
+///
+/// This function is called when the DLL is loaded and creates a new thread
+/// to perform the hooking operation. This prevents blocking the main thread
+/// and allows the DLL to load successfully.
+///
+/// # Safety
+///
+/// This function is marked as unsafe because it calls `apply_bypass_hook()`
+/// which performs unsafe memory operations.
+///
+/// # Arguments
+///
+/// * `lp_parameter` - Module handle of this DLL, passed by `DllMain`
+///
+/// # Returns
+///
+/// Returns 0 on success, otherwise the failure's [`AppError::code`], so a
+/// loader waiting on the thread can tell why it failed
+unsafe extern "system" fn start_address(lp_parameter: LPVOID) -> DWORD {
+    let _ = ThreadManager::name_current_thread("wuwa-sig-init");
+    // A second copy must not race the first one to patch the same code;
+    // any other marker failure is no reason to give up on the bypass
+    if let Err(error @ AppError::AlreadyLoaded { .. }) = InstanceGuard::acquire() {
+        return report_duplicate_instance(&error);
+    }
+
+    match unsafe { apply_bypass_hook(lp_parameter as HMODULE) } {
+        Ok(()) if shutdown::signal().unload_requested() => {
+            // Never returns: the code the thread would return to is freed
+            unsafe { FreeLibraryAndExitThread(lp_parameter as HMODULE, 0) };
+            0
+        }
+        Ok(()) => {
+            // Success - hooks applied, then removed on shutdown
+            0
+        }
+        Err(e) => {
+            // Try to log the error, but don't fail if logger isn't available
+            let error_msg = format!("Hook application failed: {}", e);
+            let _ = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                startup::fail(&e);
+                Logger::error(&error_msg);
+                dump_recent_log(lp_parameter as HMODULE);
+                write_crash_report(lp_parameter as HMODULE, &e);
+            }));
+            e.code()
+        }
+    }
+}
+
+/// Report that another copy of the DLL is loaded, returning the exit code
+/// of the initialization thread
+///
+/// The first copy owns the console and the startup status, so only the
+/// logger is set up to report that this copy stays inactive; the exit code
+/// tells a waiting loader why.
+fn report_duplicate_instance(error: &AppError) -> DWORD {
+    logger::init_global_logger();
+    Logger::warning(&format!(
+        "{}; this copy will not initialize or install hooks",
+        error
+    ));
+    error.code()
+}
+
+/// Start the initialization thread for the DLL loaded at `dll_module`,
+/// unless it is already running
+///
+/// Safe to call from `DllMain`: it only creates the thread, which runs
+/// once the loader lock is released.
+fn spawn_init_thread(dll_module: HMODULE) -> Result<()> {
+    let _guard = INIT_START.lock().unwrap_or_else(|e| e.into_inner());
+    if INIT_THREAD.get().is_some() {
+        return Ok(());
+    }
+    let thread = ThreadManager::create_thread(start_address, dll_module as LPVOID)?;
+    let _ = INIT_THREAD.set(thread);
+    Ok(())
+}
+
+/// Start initialization, unless `DllMain` or an earlier call already did
+pub(crate) fn start_initialization() -> Result<()> {
+    if INIT_THREAD.get().is_some() {
+        return Ok(());
+    }
+    let entry: unsafe extern "system" fn(LPVOID) -> DWORD = start_address;
+    let dll_module = memory::module_for_address(entry as usize)
+        .ok_or_else(|| AppError::last_error("GetModuleHandleExW"))?;
+    spawn_init_thread(dll_module.base as HMODULE)
+}
+
+/// Wait up to `timeout` for the initialization thread to exit, if started
+pub(crate) fn join_initialization(timeout: Duration) -> Result<()> {
+    match INIT_THREAD.get() {
+        Some(thread) if !thread.join(timeout)? => Err(AppError::Timeout {
+            operation: "Waiting for the initialization thread".to_string(),
+            elapsed: timeout,
+        }),
+        _ => Ok(()),
+    }
+}
+
+/// DLL entry point
+///
+/// This is the main entry point for the DLL. It's called by the Windows loader
+/// when the DLL is loaded or unloaded from a process.
+///
+/// # Safety
+///
+/// This function is marked as unsafe because it's a Windows API callback
+/// that must follow specific calling conventions and handle system-level operations.
+///
+/// # Arguments
+///
+/// * `h_module` - Handle to the DLL module, passed to the initialization thread
+/// * `ul_reason_for_call` - Reason for the call (DLL_PROCESS_ATTACH, etc.)
+/// * `lp_reserved` - On detach, non-null when the whole process is exiting
+///
+/// # Returns
+///
+/// Always returns `TRUE` to indicate successful DLL initialization
+#[unsafe(no_mangle)]
+pub unsafe extern "system" fn DllMain(
+    h_module: HMODULE,
+    ul_reason_for_call: DWORD,
+    lp_reserved: LPVOID,
+) -> BOOL {
+    // Under the loader lock only trivial work is done here; the console,
+    // configuration and logging all wait for the initialization thread,
+    // which tracks its progress through the startup phases
+    if ul_reason_for_call == DLL_PROCESS_ATTACH {
+        // Nothing needs the per-thread notifications
+        unsafe { DisableThreadLibraryCalls(h_module) };
+        logger::mark_attach();
+        // A thread that fails to start leaves the game untouched until a
+        // loader calls `wuwa_sig_init`
+        let _ = spawn_init_thread(h_module);
+    } else if ul_reason_for_call == DLL_PROCESS_DETACH {
+        shutdown::request(false);
+        logger::log_exit(!lp_reserved.is_null());
+    }
+
+    TRUE
+}
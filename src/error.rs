@@ -24,6 +24,22 @@ pub enum AppError {
     #[error("Hook application failed: {message}")]
     HookFailed { message: String },
 
+    #[error("Address {address:#x} is already hooked by '{owner}'")]
+    HookConflict { address: usize, owner: String },
+
+    #[error("Patch '{name}' is already applied")]
+    PatchConflict { name: String },
+
+    #[error("Patch '{name}' is not applied")]
+    PatchNotApplied { name: String },
+
+    #[error("Patch '{name}' at {address:#x} overlaps patch '{owner}'")]
+    PatchOverlap {
+        name: String,
+        address: usize,
+        owner: String,
+    },
+
     #[error("Console initialization failed: {source}")]
     ConsoleInitFailed {
         #[source]
@@ -35,10 +51,243 @@ pub enum AppError {
 
     #[error("Memory access violation at address {address:#x}")]
     MemoryAccessViolation { address: usize },
+
+    #[error("Field '{field}' at offset {offset:#x} does not fit in '{layout}' of {size:#x} bytes")]
+    FieldOutOfBounds {
+        layout: String,
+        field: String,
+        offset: usize,
+        size: usize,
+    },
+
+    #[error("Invalid PE image: {reason}")]
+    InvalidPeImage { reason: String },
+
+    #[error("Export '{name}' not found")]
+    ExportNotFound { name: String },
+
+    #[error("Export '{name}' is forwarded to '{forwarder}'")]
+    ExportForwarded { name: String, forwarder: String },
+
+    #[error("Import '{module}!{name}' not found")]
+    ImportNotFound { module: String, name: String },
+
+    #[error("Version information unavailable: {reason}")]
+    VersionInfoUnavailable { reason: String },
+
+    #[error("Failed to read module file '{path}': {source}")]
+    ModuleFileRead {
+        path: String,
+        #[source]
+        source: std::io::Error,
+    },
+
+    #[error("Failed to change memory protection at {address:#x}: {source}")]
+    ProtectionChangeFailed {
+        address: usize,
+        #[source]
+        source: std::io::Error,
+    },
+
+    #[error("Failed to read config file '{path}': {source}")]
+    ConfigFileRead {
+        path: String,
+        #[source]
+        source: std::io::Error,
+    },
+
+    #[error("Config syntax error on line {line}: {reason}")]
+    ConfigSyntax { line: usize, reason: String },
+
+    #[error("Configuration '{requested}' requested after initialization read '{in_use}'")]
+    ConfigInUse { requested: String, in_use: String },
+
+    #[error("Signature '{name}' matched at RVA {rva:#x}, outside section '{section}'")]
+    SignatureOutsideSection {
+        name: String,
+        section: String,
+        rva: usize,
+    },
+
+    #[error(
+        "Signature '{name}' matched at RVA {rva:#x}, but its function offset of {offset} bytes leads outside the module"
+    )]
+    FunctionOutsideModule {
+        name: String,
+        offset: isize,
+        rva: usize,
+    },
+
+    #[error("Signature update from '{url}' failed: {reason}")]
+    SignatureUpdate { url: String, reason: String },
+
+    #[error("Signature database failed verification: {reason}")]
+    SignatureInvalid { reason: String },
+
+    #[error("Another copy of the library is already loaded in process {process_id}")]
+    AlreadyLoaded { process_id: u32 },
+
+    #[error("Another log backend is already installed")]
+    LogBackendInstalled,
+
+    #[error("Failed to write recent log lines to '{path}': {source}")]
+    LogExportFailed {
+        path: String,
+        #[source]
+        source: std::io::Error,
+    },
+
+    #[error("Log worker thread could not be started: {source}")]
+    LogWorkerFailed {
+        #[source]
+        source: std::io::Error,
+    },
+
+    #[error("Failed to {action} thread {thread_id}: {source}")]
+    ThreadFailed {
+        action: String,
+        thread_id: u32,
+        #[source]
+        source: std::io::Error,
+    },
+
+    #[error("Hotkey thread could not be started: {source}")]
+    HotkeyThreadFailed {
+        #[source]
+        source: std::io::Error,
+    },
+
+    #[error("Shared memory section '{name}' could not be created: {source}")]
+    SharedMemoryFailed {
+        name: String,
+        #[source]
+        source: std::io::Error,
+    },
+
+    #[error("Command pipe '{name}' failed: {source}")]
+    CommandPipeFailed {
+        name: String,
+        #[source]
+        source: std::io::Error,
+    },
+
+    #[error("Failed to write crash report '{path}': {source}")]
+    ReportWriteFailed {
+        path: String,
+        #[source]
+        source: std::io::Error,
+    },
+
+    #[error("{operation} timed out after {elapsed:?}")]
+    Timeout {
+        operation: String,
+        elapsed: std::time::Duration,
+    },
+
+    #[error("{context} failed with Win32 error {code}: {}", win32_message(.code))]
+    Win32 { code: u32, context: String },
 }
 
 pub type Result<T> = std::result::Result<T, AppError>;
 
+/// Facility bits of an `HRESULT` wrapping a Win32 error code
+const FACILITY_WIN32_HRESULT: u32 = 0x8007_0000;
+
+impl AppError {
+    /// Stable numeric code of the error, for callers outside Rust
+    ///
+    /// Codes are grouped by area in hundreds: 1xx finding the target,
+    /// 2xx hooks and patches, 3xx memory, 4xx configuration, 5xx runtime
+    /// and 6xx raw Win32 failures. A code is never reused or renumbered.
+    /// 0 means success and 259 is `STILL_ACTIVE`, so neither is assigned.
+    pub fn code(&self) -> u32 {
+        match self {
+            Self::ModuleNotFound { .. } => 100,
+            Self::ModuleInfoFailed { .. } => 101,
+            Self::ModuleFileRead { .. } => 102,
+            Self::VersionInfoUnavailable { .. } => 103,
+            Self::InvalidPeImage { .. } => 104,
+            Self::ExportNotFound { .. } => 105,
+            Self::ExportForwarded { .. } => 106,
+            Self::ImportNotFound { .. } => 107,
+            Self::PatternNotFound { .. } => 110,
+            Self::SignatureOutsideSection { .. } => 111,
+            Self::FunctionOutsideModule { .. } => 112,
+            Self::SignatureUpdate { .. } => 113,
+            Self::SignatureInvalid { .. } => 114,
+            Self::HookFailed { .. } => 200,
+            Self::HookConflict { .. } => 201,
+            Self::PatchConflict { .. } => 202,
+            Self::PatchNotApplied { .. } => 203,
+            Self::PatchOverlap { .. } => 204,
+            Self::MemoryAccessViolation { .. } => 300,
+            Self::ProtectionChangeFailed { .. } => 301,
+            Self::FieldOutOfBounds { .. } => 302,
+            Self::StringConversion { .. } => 303,
+            Self::SharedMemoryFailed { .. } => 304,
+            Self::InvalidConfig { .. } => 400,
+            Self::ConfigFileRead { .. } => 401,
+            Self::ConfigSyntax { .. } => 402,
+            Self::ConfigInUse { .. } => 403,
+            Self::AlreadyLoaded { .. } => 500,
+            Self::ConsoleInitFailed { .. } => 501,
+            Self::PanicRecovery { .. } => 502,
+            Self::ThreadFailed { .. } => 503,
+            Self::HotkeyThreadFailed { .. } => 504,
+            Self::LogBackendInstalled => 505,
+            Self::LogExportFailed { .. } => 506,
+            Self::LogWorkerFailed { .. } => 507,
+            Self::Timeout { .. } => 508,
+            Self::ReportWriteFailed { .. } => 509,
+            Self::CommandPipeFailed { .. } => 510,
+            Self::Win32 { .. } => 600,
+        }
+    }
+
+    /// A `Win32` error for `code`, returned by the call named in `context`
+    pub fn win32(code: u32, context: &str) -> Self {
+        Self::Win32 {
+            code,
+            context: context.to_string(),
+        }
+    }
+
+    /// A `Win32` error for the calling thread's last error code
+    ///
+    /// Call straight after the failing function, before anything else can
+    /// overwrite the code.
+    pub fn last_error(context: &str) -> Self {
+        let code = std::io::Error::last_os_error().raw_os_error().unwrap_or(0);
+        Self::win32(code as u32, context)
+    }
+
+    /// Win32 error code of a `Win32` error
+    pub fn win32_code(&self) -> Option<u32> {
+        match self {
+            Self::Win32 { code, .. } => Some(*code),
+            _ => None,
+        }
+    }
+}
+
+impl From<windows::core::Error> for AppError {
+    fn from(error: windows::core::Error) -> Self {
+        // Unwrap HRESULT_FROM_WIN32 so codes compare equal to GetLastError's
+        let hresult = error.code().0 as u32;
+        let code = if hresult & 0xFFFF_0000 == FACILITY_WIN32_HRESULT {
+            hresult & 0xFFFF
+        } else {
+            hresult
+        };
+        Self::win32(code, "Windows API call")
+    }
+}
+
+/// System description of a Win32 error code
+fn win32_message(code: &u32) -> std::io::Error {
+    std::io::Error::from_raw_os_error(*code as i32)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -57,6 +306,151 @@ mod tests {
         assert!(std::error::Error::source(&error).is_none());
     }
 
+    #[test]
+    fn test_win32_error_names_the_call() {
+        let error = AppError::win32(5, "OpenThread");
+        assert_eq!(error.win32_code(), Some(5));
+        assert!(
+            error
+                .to_string()
+                .starts_with("OpenThread failed with Win32 error 5: ")
+        );
+        assert_eq!(AppError::PatternNotFound { size: 0 }.win32_code(), None);
+    }
+
+    #[test]
+    fn test_windows_errors_unwrap_win32_codes() {
+        let access_denied = windows::core::HRESULT(0x8007_0005_u32 as i32);
+        let error = AppError::from(windows::core::Error::from_hresult(access_denied));
+        assert_eq!(error.win32_code(), Some(5));
+
+        let no_interface = windows::core::HRESULT(0x8000_4002_u32 as i32);
+        let error = AppError::from(windows::core::Error::from_hresult(no_interface));
+        assert_eq!(error.win32_code(), Some(0x8000_4002));
+    }
+
+    #[test]
+    fn test_error_codes_are_distinct() {
+        let io = || std::io::Error::other("test");
+        let text = String::new;
+        let errors = [
+            AppError::ModuleNotFound { name: text() },
+            AppError::ModuleInfoFailed { source: io() },
+            AppError::PatternNotFound { size: 0 },
+            AppError::StringConversion { details: text() },
+            AppError::PanicRecovery { reason: text() },
+            AppError::HookFailed { message: text() },
+            AppError::HookConflict {
+                address: 0,
+                owner: text(),
+            },
+            AppError::PatchConflict { name: text() },
+            AppError::PatchNotApplied { name: text() },
+            AppError::PatchOverlap {
+                name: text(),
+                address: 0,
+                owner: text(),
+            },
+            AppError::ConsoleInitFailed { source: io() },
+            AppError::InvalidConfig {
+                field: text(),
+                reason: text(),
+            },
+            AppError::MemoryAccessViolation { address: 0 },
+            AppError::FieldOutOfBounds {
+                layout: text(),
+                field: text(),
+                offset: 0,
+                size: 0,
+            },
+            AppError::InvalidPeImage { reason: text() },
+            AppError::ExportNotFound { name: text() },
+            AppError::ExportForwarded {
+                name: text(),
+                forwarder: text(),
+            },
+            AppError::ImportNotFound {
+                module: text(),
+                name: text(),
+            },
+            AppError::VersionInfoUnavailable { reason: text() },
+            AppError::ModuleFileRead {
+                path: text(),
+                source: io(),
+            },
+            AppError::ProtectionChangeFailed {
+                address: 0,
+                source: io(),
+            },
+            AppError::ConfigFileRead {
+                path: text(),
+                source: io(),
+            },
+            AppError::ConfigSyntax {
+                line: 0,
+                reason: text(),
+            },
+            AppError::ConfigInUse {
+                requested: text(),
+                in_use: text(),
+            },
+            AppError::SignatureOutsideSection {
+                name: text(),
+                section: text(),
+                rva: 0,
+            },
+            AppError::FunctionOutsideModule {
+                name: text(),
+                offset: 0,
+                rva: 0,
+            },
+            AppError::SignatureUpdate {
+                url: text(),
+                reason: text(),
+            },
+            AppError::SignatureInvalid { reason: text() },
+            AppError::AlreadyLoaded { process_id: 0 },
+            AppError::LogBackendInstalled,
+            AppError::LogExportFailed {
+                path: text(),
+                source: io(),
+            },
+            AppError::LogWorkerFailed { source: io() },
+            AppError::ThreadFailed {
+                action: text(),
+                thread_id: 0,
+                source: io(),
+            },
+            AppError::HotkeyThreadFailed { source: io() },
+            AppError::SharedMemoryFailed {
+                name: text(),
+                source: io(),
+            },
+            AppError::ReportWriteFailed {
+                path: text(),
+                source: io(),
+            },
+            AppError::CommandPipeFailed {
+                name: text(),
+                source: io(),
+            },
+            AppError::Timeout {
+                operation: text(),
+                elapsed: std::time::Duration::ZERO,
+            },
+            AppError::win32(0, ""),
+        ];
+
+        let mut codes: Vec<u32> = errors.iter().map(AppError::code).collect();
+        codes.sort_unstable();
+        codes.dedup();
+        assert_eq!(codes.len(), errors.len());
+        assert!(!codes.contains(&0) && !codes.contains(&259));
+        assert_eq!(errors[0].code(), 100);
+        assert_eq!(errors[2].code(), 110);
+        assert_eq!(errors[5].code(), 200);
+    }
+
     #[test]
     fn test_error_with_source() {
         let io_error = std::io::Error::new(std::io::ErrorKind::NotFound, "test");
@@ -0,0 +1,245 @@
+//! C API for external loaders
+//!
+//! A loader that maps the DLL itself can drive and observe the bypass
+//! through four functions with a stable C ABI:
+//!
+//! - `wuwa_sig_init(config_path)` picks the configuration file and makes
+//!   sure initialization is running
+//! - `wuwa_sig_status()` reports the startup phase, the RVA of the hooked
+//!   pak check and the error code of a failure
+//! - `wuwa_sig_shutdown()` removes the hooks so the loader can free the DLL
+//! - `wuwa_sig_build_info()` identifies the build: version, git revision,
+//!   build time and signature revision
+//!
+//! Functions returning a `u32` return 0 on success and otherwise an
+//! [`AppError::code`].
+
+use std::ffi::{CStr, c_char};
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use crate::build_info::BuildInfo;
+use crate::constants::constants::FFI_SHUTDOWN_TIMEOUT_MS;
+use crate::error::{AppError, Result};
+use crate::{shutdown, startup};
+
+/// Status of the bypass as seen by a loader
+///
+/// The layout is part of the C ABI: fields are only ever appended.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WuwaSigStatus {
+    /// Current [`startup::StartupPhase`], as its number
+    pub state: u32,
+    /// RVA of the hooked pak check in its module; zero until found
+    pub target_rva: u32,
+    /// [`AppError::code`] of the failure; zero unless `state` is `Failed`
+    pub error_code: u32,
+}
+
+impl WuwaSigStatus {
+    /// Snapshot of the global startup status
+    pub fn current() -> Self {
+        let status = startup::status();
+        Self {
+            state: status.phase() as u32,
+            target_rva: status.target().map_or(0, |rva| rva.0),
+            error_code: status.error_code().unwrap_or(0),
+        }
+    }
+}
+
+/// Identification of the loaded build, for support requests
+///
+/// The strings are null-terminated, UTF-8 and live as long as the DLL. The
+/// layout is part of the C ABI: fields are only ever appended.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WuwaSigBuildInfo {
+    /// Crate version, as in `0.1.0`
+    pub version: *const c_char,
+    /// Abbreviated git commit, `-dirty` if built with local changes, or
+    /// `unknown`
+    pub git_hash: *const c_char,
+    /// Seconds since the Unix epoch when the DLL was built
+    pub build_timestamp: u64,
+    /// Revision of the built-in signature
+    pub signature_db_version: u32,
+}
+
+impl WuwaSigBuildInfo {
+    /// Description of this build
+    pub fn current() -> Self {
+        const VERSION: &CStr = null_terminated(concat!(env!("CARGO_PKG_VERSION"), "\0"));
+        const GIT_HASH: &CStr = null_terminated(concat!(env!("BUILD_GIT_HASH"), "\0"));
+        let info = BuildInfo::CURRENT;
+        Self {
+            version: VERSION.as_ptr(),
+            git_hash: GIT_HASH.as_ptr(),
+            build_timestamp: info.timestamp,
+            signature_db_version: info.signature_db_version,
+        }
+    }
+}
+
+/// `text` with its trailing null as a C string, checked at compile time
+const fn null_terminated(text: &'static str) -> &'static CStr {
+    match CStr::from_bytes_with_nul(text.as_bytes()) {
+        Ok(text) => text,
+        Err(_) => panic!("build strings must not contain null bytes"),
+    }
+}
+
+/// Configuration file for initialization, fixed once initialization reads it
+#[derive(Debug)]
+enum ConfigChoice {
+    /// Not read yet; `Some` when a loader chose a file
+    Open(Option<PathBuf>),
+    /// Read by initialization; `None` when it runs on the defaults
+    Taken(Option<PathBuf>),
+}
+
+static CONFIG_CHOICE: Mutex<ConfigChoice> = Mutex::new(ConfigChoice::Open(None));
+
+/// Configuration file initialization uses, fixing it on the first call
+///
+/// Falls back to `default` unless a loader chose a file first.
+pub(crate) fn resolve_config_path(default: impl FnOnce() -> Option<PathBuf>) -> Option<PathBuf> {
+    let mut choice = CONFIG_CHOICE.lock().unwrap();
+    let path = match &mut *choice {
+        ConfigChoice::Open(chosen) => chosen.take().or_else(default),
+        ConfigChoice::Taken(path) => return path.clone(),
+    };
+    *choice = ConfigChoice::Taken(path.clone());
+    path
+}
+
+/// Use the configuration file at `path`, if initialization did not read
+/// another one already
+fn choose_config_path(path: PathBuf) -> Result<()> {
+    let mut choice = CONFIG_CHOICE.lock().unwrap();
+    match &*choice {
+        ConfigChoice::Open(_) => {
+            *choice = ConfigChoice::Open(Some(path));
+            Ok(())
+        }
+        ConfigChoice::Taken(Some(in_use)) if *in_use == path => Ok(()),
+        ConfigChoice::Taken(in_use) => Err(AppError::ConfigInUse {
+            requested: path.display().to_string(),
+            in_use: in_use
+                .as_ref()
+                .map_or("the defaults".to_string(), |in_use| {
+                    in_use.display().to_string()
+                }),
+        }),
+    }
+}
+
+/// Parse the `config_path` argument of `wuwa_sig_init`
+///
+/// # Safety
+///
+/// `config_path` must be null or point to a null-terminated string.
+unsafe fn config_path_arg(config_path: *const c_char) -> Result<Option<PathBuf>> {
+    if config_path.is_null() {
+        return Ok(None);
+    }
+    let path = unsafe { CStr::from_ptr(config_path) }
+        .to_str()
+        .map_err(|e| AppError::StringConversion {
+            details: format!("config_path is not UTF-8: {}", e),
+        })?;
+    Ok(Some(PathBuf::from(path)))
+}
+
+fn to_code(result: Result<()>) -> u32 {
+    result.err().map_or(0, |e| e.code())
+}
+
+/// Choose the configuration file and make sure initialization runs
+///
+/// `config_path` is a UTF-8 path, or null for `wuwa-sig.toml` next to the
+/// DLL. Initialization started by `DllMain` reads its configuration early,
+/// so a path passed after that fails with the code of
+/// [`AppError::ConfigInUse`] unless it names the file already in use.
+///
+/// # Safety
+///
+/// `config_path` must be null or point to a null-terminated string.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn wuwa_sig_init(config_path: *const c_char) -> u32 {
+    let result = unsafe { config_path_arg(config_path) }.and_then(|path| {
+        if let Some(path) = path {
+            choose_config_path(path)?;
+        }
+        crate::dll::start_initialization()
+    });
+    to_code(result)
+}
+
+/// Report the startup phase, target RVA and error code of the bypass
+#[unsafe(no_mangle)]
+pub extern "C" fn wuwa_sig_status() -> WuwaSigStatus {
+    WuwaSigStatus::current()
+}
+
+/// Report the version, git revision, build time and signature revision of
+/// the DLL
+#[unsafe(no_mangle)]
+pub extern "C" fn wuwa_sig_build_info() -> WuwaSigBuildInfo {
+    WuwaSigBuildInfo::current()
+}
+
+/// Remove the hooks and wait for the initialization thread to exit
+///
+/// Once this returns 0 nothing calls into the DLL anymore and the loader
+/// may free it. The code of [`AppError::Timeout`] means the thread is
+/// still busy, such as waiting for the game module.
+#[unsafe(no_mangle)]
+pub extern "C" fn wuwa_sig_shutdown() -> u32 {
+    shutdown::request(false);
+    to_code(crate::dll::join_initialization(Duration::from_millis(
+        FFI_SHUTDOWN_TIMEOUT_MS,
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_status_layout_is_stable() {
+        assert_eq!(std::mem::size_of::<WuwaSigStatus>(), 12);
+        assert_eq!(std::mem::offset_of!(WuwaSigStatus, target_rva), 4);
+        assert_eq!(std::mem::offset_of!(WuwaSigStatus, error_code), 8);
+    }
+
+    #[test]
+    fn test_build_info_strings() {
+        let info = wuwa_sig_build_info();
+        let version = unsafe { CStr::from_ptr(info.version) };
+        let git_hash = unsafe { CStr::from_ptr(info.git_hash) };
+        assert_eq!(version.to_str().unwrap(), BuildInfo::CURRENT.version);
+        assert_eq!(git_hash.to_str().unwrap(), BuildInfo::CURRENT.git_hash);
+        assert_eq!(
+            info.signature_db_version,
+            BuildInfo::CURRENT.signature_db_version
+        );
+    }
+
+    #[test]
+    fn test_config_path_arg() {
+        assert!(
+            unsafe { config_path_arg(std::ptr::null()) }
+                .unwrap()
+                .is_none()
+        );
+        assert_eq!(
+            unsafe { config_path_arg(c"C:\\wuwa\\sig.toml".as_ptr()) }.unwrap(),
+            Some(PathBuf::from("C:\\wuwa\\sig.toml"))
+        );
+        let invalid = [0xFFu8, 0];
+        assert!(unsafe { config_path_arg(invalid.as_ptr().cast()) }.is_err());
+    }
+}
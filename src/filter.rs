@@ -0,0 +1,165 @@
+//! Allow and deny lists deciding which paks get their verification bypassed
+//!
+//! Patterns are case-insensitive globs on the pak name, where `*` matches
+//! any run of characters and `?` matches exactly one.
+
+use std::str::FromStr;
+
+use crate::error::{AppError, Result};
+
+/// What the hook does for a pak the filter does not bypass
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum UnmatchedPakAction {
+    /// Run the real verification
+    #[default]
+    CallOriginal,
+    /// Report verification failure without running it
+    Fail,
+}
+
+impl UnmatchedPakAction {
+    /// Name used in configuration
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::CallOriginal => "call-original",
+            Self::Fail => "fail",
+        }
+    }
+}
+
+impl FromStr for UnmatchedPakAction {
+    type Err = AppError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        [Self::CallOriginal, Self::Fail]
+            .into_iter()
+            .find(|action| action.as_str().eq_ignore_ascii_case(s))
+            .ok_or_else(|| AppError::InvalidConfig {
+                field: "unmatched_pak_action".to_string(),
+                reason: format!("unknown action '{}'", s),
+            })
+    }
+}
+
+/// Allow/deny lists over pak names
+///
+/// A deny match always wins. With an empty allow list every other pak is
+/// bypassed, otherwise only paks matching an allow pattern are.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PakFilter {
+    allow: Vec<String>,
+    deny: Vec<String>,
+    unmatched: UnmatchedPakAction,
+}
+
+impl PakFilter {
+    pub fn new<S: AsRef<str>>(allow: &[S], deny: &[S]) -> Self {
+        let owned = |patterns: &[S]| patterns.iter().map(|p| p.as_ref().to_string()).collect();
+        Self {
+            allow: owned(allow),
+            deny: owned(deny),
+            unmatched: UnmatchedPakAction::default(),
+        }
+    }
+
+    /// Set what happens to paks that are not bypassed
+    pub fn with_unmatched_action(mut self, action: UnmatchedPakAction) -> Self {
+        self.unmatched = action;
+        self
+    }
+
+    /// What happens to paks that are not bypassed
+    pub fn unmatched_action(&self) -> UnmatchedPakAction {
+        self.unmatched
+    }
+
+    /// Check if the filter bypasses everything
+    pub fn is_pass_through(&self) -> bool {
+        self.allow.is_empty() && self.deny.is_empty()
+    }
+
+    /// Decide whether to spoof success for a pak
+    ///
+    /// An unreadable name matches no pattern, so it is only bypassed when
+    /// there is no allow list.
+    pub fn should_bypass(&self, pak_name: Option<&str>) -> bool {
+        let Some(name) = pak_name else {
+            return self.allow.is_empty();
+        };
+
+        if self.deny.iter().any(|pattern| glob_match(pattern, name)) {
+            return false;
+        }
+        self.allow.is_empty() || self.allow.iter().any(|pattern| glob_match(pattern, name))
+    }
+}
+
+/// Case-insensitive glob match supporting `*` and `?`
+pub fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().flat_map(char::to_lowercase).collect();
+    let text: Vec<char> = text.chars().flat_map(char::to_lowercase).collect();
+
+    // Greedy match with backtracking to the most recent star
+    let (mut p, mut t) = (0, 0);
+    let mut star: Option<(usize, usize)> = None;
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p, t));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match star {
+                Some((star_p, star_t)) => {
+                    p = star_p + 1;
+                    t = star_t + 1;
+                    star = Some((star_p, star_t + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match("*.pak", "pakchunk0-WindowsNoEditor.pak"));
+        assert!(glob_match("~mods/*", "~mods/MyMod_P.pak"));
+        assert!(glob_match("PAKCHUNK?-*", "pakchunk3-Windows.pak"));
+        assert!(glob_match("*", ""));
+        assert!(!glob_match("pakchunk?-*", "pakchunk10-Windows.pak"));
+        assert!(!glob_match("*.pak", "global.utoc"));
+    }
+
+    #[test]
+    fn test_empty_filter_bypasses_everything() {
+        let filter = PakFilter::default();
+        assert!(filter.is_pass_through());
+        assert!(filter.should_bypass(Some("pakchunk0.pak")));
+        assert!(filter.should_bypass(None));
+    }
+
+    #[test]
+    fn test_allow_list_limits_bypass() {
+        let filter = PakFilter::new(&["*_P.pak"], &[]);
+        assert!(filter.should_bypass(Some("MyMod_P.pak")));
+        assert!(!filter.should_bypass(Some("pakchunk0-WindowsNoEditor.pak")));
+        assert!(!filter.should_bypass(None));
+    }
+
+    #[test]
+    fn test_deny_wins_over_allow() {
+        let filter = PakFilter::new(&["*.pak"], &["pakchunk0*"]);
+        assert!(!filter.should_bypass(Some("pakchunk0-WindowsNoEditor.pak")));
+        assert!(filter.should_bypass(Some("pakchunk1-WindowsNoEditor.pak")));
+    }
+}
@@ -0,0 +1,340 @@
+//! Synthetic game module and pak-check structures for deterministic tests
+//!
+//! [`FakeGameModule`] is a mapped PE32+ image whose `.text` section holds the
+//! real pak-check signature at the same distance from the function start as
+//! in the shipping client. [`FakePakCheck`] rebuilds the pointer chain the
+//! hook walks from `rcx` to the pak name:
+//!
+//! ```text
+//! rcx + 0x10 -> v4
+//! v4  + 0x00 -> parent
+//! parent + 0x08 -> FString { UTF-16 pak name, length, capacity }
+//! ```
+
+#[cfg(feature = "dll")]
+use ilhook::x64::Registers;
+use winapi::um::psapi::MODULEINFO;
+use winapi::um::winnt::{
+    IMAGE_DOS_SIGNATURE, IMAGE_NT_OPTIONAL_HDR64_MAGIC, IMAGE_NT_SIGNATURE, IMAGE_SCN_MEM_EXECUTE,
+    IMAGE_SCN_MEM_READ, IMAGE_SCN_MEM_WRITE,
+};
+
+use crate::constants::constants::memory::TARGET_FUNCTION_OFFSET;
+#[cfg(feature = "dll")]
+use crate::constants::constants::memory::{PARENT_POINTER_OFFSET, V4_POINTER_OFFSET};
+use crate::constants::constants::{PATTERN_MASK, TARGET_PATTERN};
+
+/// Size of every synthetic image
+pub(crate) const FIXTURE_IMAGE_SIZE: usize = 0x4000;
+
+/// Offset of the NT headers in synthetic images
+pub(crate) const FIXTURE_NT_OFFSET: usize = 0x80;
+
+/// Size of `IMAGE_OPTIONAL_HEADER64` in synthetic images
+const FIXTURE_OPTIONAL_SIZE: usize = 240;
+
+/// Build a minimal mapped PE32+ image with `(name, rva, size, characteristics)` sections
+pub(crate) fn build_pe_image(sections: &[(&str, u32, u32, u32)]) -> Vec<u8> {
+    let mut image = vec![0u8; FIXTURE_IMAGE_SIZE];
+    let put_u16 = |image: &mut [u8], offset: usize, value: u16| {
+        image[offset..offset + 2].copy_from_slice(&value.to_le_bytes());
+    };
+    let put_u32 = |image: &mut [u8], offset: usize, value: u32| {
+        image[offset..offset + 4].copy_from_slice(&value.to_le_bytes());
+    };
+
+    let nt = FIXTURE_NT_OFFSET;
+    put_u16(&mut image, 0, IMAGE_DOS_SIGNATURE);
+    put_u32(&mut image, 0x3C, nt as u32);
+    put_u32(&mut image, nt, IMAGE_NT_SIGNATURE);
+    put_u16(&mut image, nt + 6, sections.len() as u16);
+    put_u32(&mut image, nt + 8, 0x6500_0000);
+    put_u16(&mut image, nt + 20, FIXTURE_OPTIONAL_SIZE as u16);
+
+    let optional = nt + 24;
+    put_u16(&mut image, optional, IMAGE_NT_OPTIONAL_HDR64_MAGIC);
+    put_u32(&mut image, optional + 16, 0x1010);
+    put_u32(&mut image, optional + 56, FIXTURE_IMAGE_SIZE as u32);
+    put_u32(&mut image, optional + 108, 16);
+
+    let table = optional + FIXTURE_OPTIONAL_SIZE;
+    for (i, &(name, rva, size, characteristics)) in sections.iter().enumerate() {
+        let entry = table + i * 40;
+        image[entry..entry + name.len()].copy_from_slice(name.as_bytes());
+        put_u32(&mut image, entry + 8, size);
+        put_u32(&mut image, entry + 12, rva);
+        put_u32(&mut image, entry + 16, size);
+        put_u32(&mut image, entry + 36, characteristics);
+    }
+
+    image
+}
+
+/// Synthetic `Client-Win64-Shipping.exe` containing the pak-check signature
+pub(crate) struct FakeGameModule {
+    image: Vec<u8>,
+}
+
+impl FakeGameModule {
+    /// RVA of the `.text` section
+    pub const TEXT_RVA: usize = 0x1000;
+
+    /// Size of the `.text` section
+    pub const TEXT_SIZE: usize = 0x1800;
+
+    /// RVA of the pak-check function the hook targets
+    pub const FUNCTION_RVA: usize = 0x1400;
+
+    pub fn new() -> Self {
+        let mut image = build_pe_image(&[
+            (
+                ".text",
+                Self::TEXT_RVA as u32,
+                Self::TEXT_SIZE as u32,
+                IMAGE_SCN_MEM_EXECUTE | IMAGE_SCN_MEM_READ,
+            ),
+            (
+                ".data",
+                0x3000,
+                0x800,
+                IMAGE_SCN_MEM_READ | IMAGE_SCN_MEM_WRITE,
+            ),
+        ]);
+
+        // Fill .text with int3 padding, then lay down a prologue and the signature
+        image[Self::TEXT_RVA..Self::TEXT_RVA + Self::TEXT_SIZE].fill(0xCC);
+        let prologue = [0x48, 0x89, 0x5C, 0x24, 0x08, 0x57, 0x48, 0x83, 0xEC, 0x20];
+        image[Self::FUNCTION_RVA..Self::FUNCTION_RVA + prologue.len()].copy_from_slice(&prologue);
+
+        let match_rva = Self::match_rva();
+        image[match_rva..match_rva + TARGET_PATTERN.len()].copy_from_slice(&TARGET_PATTERN);
+
+        Self { image }
+    }
+
+    /// RVA where the signature scan is expected to match
+    pub fn match_rva() -> usize {
+        Self::FUNCTION_RVA + TARGET_FUNCTION_OFFSET as usize
+    }
+
+    /// The signature and mask the bypass scans for
+    pub fn signature() -> (&'static [u8], &'static str) {
+        (&TARGET_PATTERN, PATTERN_MASK)
+    }
+
+    /// Base address of the mapped image
+    pub fn base(&mut self) -> *mut u8 {
+        self.image.as_mut_ptr()
+    }
+
+    /// Mapped image bytes, indexed by RVA
+    pub fn bytes(&self) -> &[u8] {
+        &self.image
+    }
+
+    /// Module information as `GetModuleInformation` would report it
+    pub fn module_info(&mut self) -> MODULEINFO {
+        MODULEINFO {
+            lpBaseOfDll: self.base().cast(),
+            SizeOfImage: self.image.len() as u32,
+            EntryPoint: std::ptr::null_mut(),
+        }
+    }
+}
+
+/// Pak-check argument structure and pointer chain leading to a pak name
+#[cfg(feature = "dll")]
+pub(crate) struct FakePakCheck {
+    _name: Vec<u16>,
+    _parent: Box<[usize; 3]>,
+    _v4: Box<usize>,
+    argument: Box<[usize; 3]>,
+}
+
+#[cfg(feature = "dll")]
+impl FakePakCheck {
+    pub fn new(pak_name: &str) -> Self {
+        let name: Vec<u16> = pak_name.encode_utf16().chain(std::iter::once(0)).collect();
+
+        // The length counts the terminator; the capacity is the same here
+        let mut parent = Box::new([0usize; 3]);
+        let fstring = PARENT_POINTER_OFFSET / size_of::<usize>();
+        parent[fstring] = name.as_ptr() as usize;
+        parent[fstring + 1] = name.len() | (name.len() << 32);
+
+        let v4 = Box::new(parent.as_ptr() as usize);
+
+        let mut argument = Box::new([0usize; 3]);
+        argument[V4_POINTER_OFFSET / size_of::<usize>()] = &*v4 as *const usize as usize;
+
+        Self {
+            _name: name,
+            _parent: parent,
+            _v4: v4,
+            argument,
+        }
+    }
+
+    /// Argument structure whose v4 pointer is null, as seen for unnamed checks
+    pub fn without_v4() -> Self {
+        let mut check = Self::new("");
+        check.argument[V4_POINTER_OFFSET / size_of::<usize>()] = 0;
+        check
+    }
+
+    /// Register context of a call into the pak-check function
+    pub fn registers(&self) -> Registers {
+        let mut registers: Registers = unsafe { std::mem::zeroed() };
+        registers.rcx = self.argument.as_ptr() as u64;
+        registers
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "dll")]
+    use std::time::Duration;
+
+    use super::*;
+    #[cfg(feature = "dll")]
+    use crate::constants::constants::BYPASS_SUCCESS;
+    use crate::error::AppError;
+    use crate::memory::PatternScanner;
+    use crate::memory::pe::PeImage;
+    use crate::signatures::{DEFAULT_SIGNATURE, Signature};
+
+    #[test]
+    fn test_scan_finds_signature_in_fake_module() {
+        let mut module = FakeGameModule::new();
+        let info = module.module_info();
+        let (pattern, mask) = FakeGameModule::signature();
+
+        let mut scanner = PatternScanner::new();
+        let found = scanner
+            .scan(
+                info.lpBaseOfDll.cast(),
+                info.SizeOfImage as usize,
+                pattern,
+                mask,
+            )
+            .unwrap();
+        assert_eq!(
+            found as usize - module.base() as usize,
+            FakeGameModule::match_rva()
+        );
+    }
+
+    #[test]
+    fn test_match_adjusts_to_function_start() {
+        let mut module = FakeGameModule::new();
+        let base = module.base();
+        let found = base.wrapping_add(FakeGameModule::match_rva());
+
+        let info = module.module_info();
+        let (pattern, mask) = FakeGameModule::signature();
+        let mut signature =
+            Signature::new(DEFAULT_SIGNATURE, pattern, mask, TARGET_FUNCTION_OFFSET);
+
+        let function = crate::bypass::function_start(found, &signature, &info).unwrap();
+        assert_eq!(
+            function as usize - base as usize,
+            FakeGameModule::FUNCTION_RVA
+        );
+        assert_eq!(module.bytes()[FakeGameModule::FUNCTION_RVA], 0x48);
+
+        // Offsets may point past the match, but not out of the module
+        signature.function_offset = -1;
+        let after = crate::bypass::function_start(found, &signature, &info).unwrap();
+        assert_eq!(after as usize, found as usize + 1);
+        signature.function_offset = FakeGameModule::match_rva() as isize + 1;
+        assert!(matches!(
+            crate::bypass::function_start(found, &signature, &info),
+            Err(AppError::FunctionOutsideModule { .. })
+        ));
+    }
+
+    #[test]
+    #[cfg(feature = "dll")]
+    fn test_ace_wait_returns_once_the_preamble_settles() {
+        let mut module = FakeGameModule::new();
+        // A jump standing in for the prologue until ACE puts it back
+        module.image[FakeGameModule::FUNCTION_RVA..][..6]
+            .copy_from_slice(&[0xE9, 0x00, 0x10, 0x00, 0x00, 0x90]);
+        let function = module.module_info().lpBaseOfDll as usize + FakeGameModule::FUNCTION_RVA;
+        let settled = [0x48, 0x89, 0x5C, 0x24, 0x08, 0x57];
+        let delay = Duration::from_millis(30);
+
+        let writer = std::thread::spawn(move || {
+            std::thread::sleep(delay);
+            for (i, byte) in settled.iter().enumerate() {
+                unsafe { ((function + i) as *mut u8).write_volatile(*byte) };
+            }
+        });
+        let expected = [0x48, 0x89, 0x5C, 0x24, 0x00, 0x57];
+        let outcome = crate::bypass::wait_for_ace_init(
+            function as *const u8,
+            &expected,
+            "xxxx?x",
+            Duration::from_secs(5),
+        );
+        writer.join().unwrap();
+
+        let outcome = outcome.unwrap();
+        assert!(outcome.polls > 1);
+        assert!(outcome.waited >= delay);
+    }
+
+    #[test]
+    #[cfg(feature = "dll")]
+    fn test_ace_wait_gives_up_after_the_timeout() {
+        let module = FakeGameModule::new();
+        let function = module.bytes()[FakeGameModule::FUNCTION_RVA..].as_ptr();
+
+        let expected = [0xCC; 8];
+        let timeout = Duration::from_millis(20);
+        match crate::bypass::wait_for_ace_init(function, &expected, "xxxxxxxx", timeout) {
+            Err(AppError::Timeout { elapsed, .. }) => assert!(elapsed >= timeout),
+            other => panic!("expected a timeout, got {:?}", other.map(|o| o.polls)),
+        }
+    }
+
+    #[test]
+    fn test_fake_module_function_is_in_text() {
+        let module = FakeGameModule::new();
+        let pe = PeImage::parse(module.bytes()).unwrap();
+        let section = pe
+            .section_for_rva(FakeGameModule::FUNCTION_RVA as u32)
+            .unwrap();
+        assert_eq!(section.name, ".text");
+        assert!(section.is_executable());
+    }
+
+    #[test]
+    #[cfg(feature = "dll")]
+    fn test_extract_pak_name_from_fake_chain() {
+        let check = FakePakCheck::new("pakchunk0-WindowsNoEditor.pak");
+        let mut registers = check.registers();
+
+        assert_eq!(
+            unsafe { crate::bypass::extract_pak_name_unsafe(&mut registers) }.unwrap(),
+            "pakchunk0-WindowsNoEditor.pak"
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "dll")]
+    fn test_extract_pak_name_rejects_null_v4() {
+        let check = FakePakCheck::without_v4();
+        let mut registers = check.registers();
+        assert!(unsafe { crate::bypass::extract_pak_name_unsafe(&mut registers) }.is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "dll")]
+    fn test_replacement_always_reports_success() {
+        let check = FakePakCheck::new("pakchunk1-WindowsNoEditor.pak");
+        let mut registers = check.registers();
+        let result = unsafe { crate::bypass::pak_file_check_replacement(&mut registers, 0, 0) };
+        assert_eq!(result, BYPASS_SUCCESS);
+    }
+}
@@ -1,9 +1,79 @@
-use ilhook::x64::Registers;
-use interceptor_rs::Interceptor;
-use std::sync::{Arc, Mutex};
+use ilhook::x64::{JmpBackRoutine, Registers};
+use std::collections::HashMap;
+use std::marker::PhantomData;
+use std::sync::{Arc, Mutex, OnceLock};
 
+use crate::constants::constants::memory::HOOK_HISTORY_LEN;
 use crate::error::{AppError, Result};
 use crate::logger::Logger;
+use crate::memory::describe_address;
+
+pub mod args;
+pub mod backend;
+pub mod chain;
+pub mod guard;
+pub mod history;
+pub mod hwbp;
+pub mod iat;
+pub mod mid;
+#[cfg(feature = "minhook")]
+pub mod minhook;
+pub mod observers;
+pub mod once;
+pub mod spec;
+pub mod spoof;
+pub mod stats;
+pub mod transaction;
+pub mod vmt;
+
+pub use args::{ArgReader, RegisterDump};
+pub use backend::{BackendRoutine, HookBackend, HookBackendKind, WriteGate};
+pub use chain::{CallbackChain, DecisionCallback, HookCall, PostCallback, PreCallback};
+pub use guard::GuardPageHook;
+pub use history::{InvocationHistory, InvocationRecord};
+pub use hwbp::{BreakpointHandler, HwBpHook};
+pub use iat::IatHook;
+pub use mid::{MidFunctionHook, MidHookCallback};
+#[cfg(feature = "minhook")]
+pub use minhook::MinHookBackend;
+pub use observers::{ListenerId, StateChange, StateListener, StateObservers};
+pub use once::OneShot;
+pub use spec::{CONFIGURABLE_HOOKS, HookAction, HookSpec};
+pub use spoof::SpoofSetting;
+pub use stats::{HookStats, HookStatsSnapshot};
+pub use transaction::HookTransaction;
+pub use vmt::VmtHook;
+
+/// Replacement routine signature used by interceptor-rs hooks
+pub type HookReplacement = unsafe extern "win64" fn(*mut Registers, usize, usize) -> usize;
+
+/// A routine that can be installed over a target function
+///
+/// Implemented per routine signature so [`FunctionHook`] stays generic over
+/// which kind of routine its backend installs.
+pub trait HookRoutine: Copy + Send + Sync + 'static {
+    /// Backend able to install this kind of routine
+    const DEFAULT_BACKEND: HookBackendKind;
+
+    /// Wrap the routine for a hook backend
+    fn routine(self) -> BackendRoutine;
+}
+
+impl HookRoutine for HookReplacement {
+    const DEFAULT_BACKEND: HookBackendKind = HookBackendKind::Interceptor;
+
+    fn routine(self) -> BackendRoutine {
+        BackendRoutine::Replace(self)
+    }
+}
+
+impl HookRoutine for JmpBackRoutine {
+    const DEFAULT_BACKEND: HookBackendKind = HookBackendKind::IlhookJmpBack;
+
+    fn routine(self) -> BackendRoutine {
+        BackendRoutine::Observe(self, 0)
+    }
+}
 
 /// Hook state for tracking and management
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -14,28 +84,71 @@ pub enum HookState {
     Removed,
 }
 
-/// Enhanced PAK file hook with better error handling and state management
-pub struct PakFileHook {
-    interceptor: Arc<Mutex<Interceptor>>,
+/// Hook on a single function with state tracking and restore on removal
+///
+/// `F` is the routine signature installed over the target; the patching
+/// itself is done by a [`HookBackend`].
+pub struct FunctionHook<F: HookRoutine> {
+    backend: Mutex<Box<dyn HookBackend>>,
     state: Arc<Mutex<HookState>>,
     target_address: Arc<Mutex<Option<usize>>>,
+    stats: Arc<HookStats>,
+    history: Arc<InvocationHistory>,
+    spoof: Arc<SpoofSetting>,
+    chain: Arc<CallbackChain>,
+    once: Arc<OneShot>,
+    observers: Arc<StateObservers>,
+    routine: PhantomData<F>,
 }
 
-impl PakFileHook {
-    /// Create a new PAK file hook
+/// Hook over the PAK file verification function
+pub type PakFileHook = FunctionHook<HookReplacement>;
+
+impl<F: HookRoutine> FunctionHook<F> {
+    /// Create a new, not yet applied hook on the routine's default backend
     pub fn new() -> Self {
+        Self::with_backend(F::DEFAULT_BACKEND)
+    }
+
+    /// Create a new, not yet applied hook on the given backend
+    pub fn with_backend(kind: HookBackendKind) -> Self {
         Self {
-            interceptor: Arc::new(Mutex::new(Interceptor::new())),
+            backend: Mutex::new(kind.create()),
             state: Arc::new(Mutex::new(HookState::Uninitialized)),
             target_address: Arc::new(Mutex::new(None)),
+            stats: Arc::new(HookStats::new()),
+            history: Arc::new(InvocationHistory::new(HOOK_HISTORY_LEN)),
+            spoof: Arc::new(SpoofSetting::default()),
+            chain: Arc::new(CallbackChain::new()),
+            once: Arc::new(OneShot::new()),
+            observers: Arc::new(StateObservers::new()),
+            routine: PhantomData,
         }
     }
 
     /// Apply the hook to the target address
-    pub fn apply(
+    pub fn apply(&self, target_address: usize, replacement: F) -> Result<()> {
+        self.apply_with(target_address, replacement, None)
+    }
+
+    /// Apply the hook with the backend's patch write bracketed by `gate`
+    ///
+    /// Used by [`HookTransaction`], whose gate takes the written patch back
+    /// out so it can be written again together with the other hooks.
+    pub(crate) fn apply_gated(
+        &self,
+        target_address: usize,
+        replacement: F,
+        gate: Arc<dyn WriteGate>,
+    ) -> Result<()> {
+        self.apply_with(target_address, replacement, Some(gate))
+    }
+
+    fn apply_with(
         &self,
         target_address: usize,
-        replacement: unsafe extern "win64" fn(*mut Registers, usize, usize) -> usize,
+        replacement: F,
+        gate: Option<Arc<dyn WriteGate>>,
     ) -> Result<()> {
         // Check current state
         {
@@ -47,46 +160,47 @@ impl PakFileHook {
             }
         }
 
-        Logger::hook(&format!("Applying hook to address: {:#x}", target_address));
+        Logger::hook(&format!(
+            "Applying hook to address: {}",
+            describe_address(target_address)
+        ));
 
         // Apply the hook
         let result = {
-            let mut interceptor = self.interceptor.lock().unwrap();
+            let mut backend = self.backend.lock().unwrap();
             Logger::hook(&format!(
-                "Calling interceptor.replace with address: {:#x}",
-                target_address
+                "Installing hook routine at address {} via {}",
+                describe_address(target_address),
+                backend.kind()
             ));
-            let res = interceptor.replace(target_address, replacement, None);
-            Logger::hook(&format!("Interceptor.replace result: {:?}", res));
+            let res = match gate {
+                Some(gate) => backend.apply_gated(target_address, replacement.routine(), gate),
+                None => backend.apply(target_address, replacement.routine()),
+            };
+            Logger::hook(&format!("Hook routine install result: {:?}", res));
             res
         };
 
         match result {
             Ok(_) => {
-                // Update state and target address
-                {
-                    let mut state = self.state.lock().unwrap();
-                    *state = HookState::Applied;
-                }
+                // Update target address and state
                 {
                     let mut addr = self.target_address.lock().unwrap();
                     *addr = Some(target_address);
                 }
+                self.set_state(HookState::Applied);
 
                 Logger::success(&format!(
-                    "Hook successfully applied to {:#x}",
-                    target_address
+                    "Hook successfully applied to {}",
+                    describe_address(target_address)
                 ));
                 Ok(())
             }
             Err(e) => {
                 // Update state to failed
-                {
-                    let mut state = self.state.lock().unwrap();
-                    *state = HookState::Failed;
-                }
+                self.set_state(HookState::Failed);
 
-                let error_msg = format!("Failed to apply hook: {:?}", e);
+                let error_msg = format!("Failed to apply hook: {}", e);
                 Logger::error(&error_msg);
                 Err(AppError::HookFailed { message: error_msg })
             }
@@ -106,15 +220,19 @@ impl PakFileHook {
             });
         }
 
-        // Note: The interceptor-rs library doesn't provide a remove method
-        // This is a placeholder for future implementation
-        Logger::warning("Hook removal not implemented in interceptor-rs");
+        let target_address = self.target_address().unwrap_or_default();
+        Logger::hook(&format!(
+            "Removing hook at {}",
+            describe_address(target_address)
+        ));
+        self.backend.lock().unwrap().remove()?;
 
-        {
-            let mut state = self.state.lock().unwrap();
-            *state = HookState::Removed;
-        }
+        self.set_state(HookState::Removed);
 
+        Logger::success(&format!(
+            "Hook removed from {}",
+            describe_address(target_address)
+        ));
         Ok(())
     }
 
@@ -124,6 +242,94 @@ impl PakFileHook {
         *state
     }
 
+    /// Call `listener` on every state transition of this hook
+    pub fn on_state_change(
+        &self,
+        listener: impl Fn(&StateChange<'_>) + Send + Sync + 'static,
+    ) -> ListenerId {
+        self.observers.subscribe(listener)
+    }
+
+    /// Remove a listener added with [`on_state_change`](Self::on_state_change)
+    pub fn remove_state_listener(&self, id: ListenerId) -> bool {
+        self.observers.unsubscribe(id)
+    }
+
+    /// Move to `new` and notify listeners once the state lock is released
+    fn set_state(&self, new: HookState) {
+        let from = std::mem::replace(&mut *self.state.lock().unwrap(), new);
+        self.observers.notify(&StateChange {
+            name: None,
+            from,
+            to: new,
+            target_address: self.target_address(),
+        });
+    }
+
+    /// Invocation statistics, for the hook routine to record into
+    pub fn stats(&self) -> Arc<HookStats> {
+        Arc::clone(&self.stats)
+    }
+
+    /// Recent invocations, for the hook routine to record into
+    pub fn history(&self) -> Arc<InvocationHistory> {
+        Arc::clone(&self.history)
+    }
+
+    /// Spoofed return value, for the hook routine to read
+    pub fn spoof(&self) -> Arc<SpoofSetting> {
+        Arc::clone(&self.spoof)
+    }
+
+    /// Callbacks the hook routine runs around each call
+    pub fn chain(&self) -> Arc<CallbackChain> {
+        Arc::clone(&self.chain)
+    }
+
+    /// One-shot setting, for the hook routine to claim its single call
+    pub fn once(&self) -> Arc<OneShot> {
+        Arc::clone(&self.once)
+    }
+
+    /// Restore the original bytes after a one-shot hook has fired
+    ///
+    /// Meant to be called from the hook routine itself once it has recorded
+    /// what it needed: the routine's stub is kept allocated so the call in
+    /// progress can still return through it.
+    pub fn finish_once(&self) -> Result<()> {
+        if !self.once.has_fired() {
+            return Err(AppError::HookFailed {
+                message: "One-shot hook has not fired".to_string(),
+            });
+        }
+
+        let state = self.state();
+        if state != HookState::Applied {
+            return Err(AppError::HookFailed {
+                message: format!("Cannot finish one-shot hook in state: {:?}", state),
+            });
+        }
+
+        self.backend.lock().unwrap().detach()?;
+        self.set_state(HookState::Removed);
+
+        Logger::hook(&format!(
+            "One-shot hook at {} fired and removed itself",
+            describe_address(self.target_address().unwrap_or_default())
+        ));
+        Ok(())
+    }
+
+    /// Backend patching the target
+    pub fn backend_kind(&self) -> HookBackendKind {
+        self.backend.lock().unwrap().kind()
+    }
+
+    /// Address that runs the original function, if the backend provides one
+    pub fn trampoline(&self) -> Option<usize> {
+        self.backend.lock().unwrap().trampoline()
+    }
+
     /// Get the target address if the hook is applied
     pub fn target_address(&self) -> Option<usize> {
         let addr = self.target_address.lock().unwrap();
@@ -157,12 +363,418 @@ pub struct HookInfo {
     pub is_active: bool,
 }
 
-impl Default for PakFileHook {
+impl<F: HookRoutine> Default for FunctionHook<F> {
     fn default() -> Self {
         Self::new()
     }
 }
 
 // Thread-safe implementation
-unsafe impl Send for PakFileHook {}
-unsafe impl Sync for PakFileHook {}
+unsafe impl<F: HookRoutine> Send for FunctionHook<F> {}
+unsafe impl<F: HookRoutine> Sync for FunctionHook<F> {}
+
+/// A hook owned by the manager together with what it will install
+struct ManagedHook {
+    hook: PakFileHook,
+    target_address: usize,
+    replacement: HookReplacement,
+}
+
+/// Registry of named hooks with bulk apply, remove and status queries
+///
+/// Each target address can only be owned by one hook, so two features can
+/// never patch the same function on top of each other.
+pub struct HookManager {
+    hooks: Mutex<HashMap<String, ManagedHook>>,
+    backend: Mutex<HookBackendKind>,
+    observers: Arc<StateObservers>,
+}
+
+impl HookManager {
+    /// Create an empty manager
+    pub fn new() -> Self {
+        Self {
+            hooks: Mutex::new(HashMap::new()),
+            backend: Mutex::new(HookBackendKind::default()),
+            observers: Arc::new(StateObservers::new()),
+        }
+    }
+
+    /// Backend used for hooks registered from now on
+    pub fn set_backend(&self, kind: HookBackendKind) {
+        *self.backend.lock().unwrap() = kind;
+    }
+
+    /// Backend used for newly registered hooks
+    pub fn backend(&self) -> HookBackendKind {
+        *self.backend.lock().unwrap()
+    }
+
+    /// Call `listener` on every state transition of any managed hook,
+    /// including hooks registered later
+    ///
+    /// Listeners run while the manager is locked and must not call back
+    /// into it; the change carries the hook's name and target instead.
+    pub fn on_state_change(
+        &self,
+        listener: impl Fn(&StateChange<'_>) + Send + Sync + 'static,
+    ) -> ListenerId {
+        self.observers.subscribe(listener)
+    }
+
+    /// Remove a listener added with [`on_state_change`](Self::on_state_change)
+    pub fn remove_state_listener(&self, id: ListenerId) -> bool {
+        self.observers.unsubscribe(id)
+    }
+
+    /// Register a hook under `name` without applying it
+    pub fn register(
+        &self,
+        name: &str,
+        target_address: usize,
+        replacement: HookReplacement,
+    ) -> Result<()> {
+        let mut hooks = self.hooks.lock().unwrap();
+        if hooks.contains_key(name) {
+            return Err(AppError::HookFailed {
+                message: format!("A hook named '{}' is already registered", name),
+            });
+        }
+
+        if let Some((owner, _)) = hooks
+            .iter()
+            .find(|(_, managed)| managed.target_address == target_address)
+        {
+            return Err(AppError::HookConflict {
+                address: target_address,
+                owner: owner.clone(),
+            });
+        }
+
+        let hook = PakFileHook::with_backend(self.backend());
+        let observers = Arc::clone(&self.observers);
+        let hook_name = name.to_string();
+        hook.on_state_change(move |change| {
+            observers.notify(&StateChange {
+                name: Some(&hook_name),
+                ..*change
+            })
+        });
+
+        hooks.insert(
+            name.to_string(),
+            ManagedHook {
+                hook,
+                target_address,
+                replacement,
+            },
+        );
+        Ok(())
+    }
+
+    /// Register and immediately apply a hook
+    pub fn install(
+        &self,
+        name: &str,
+        target_address: usize,
+        replacement: HookReplacement,
+    ) -> Result<()> {
+        self.register(name, target_address, replacement)?;
+        self.apply(name)
+    }
+
+    /// Apply a registered hook
+    pub fn apply(&self, name: &str) -> Result<()> {
+        let hooks = self.hooks.lock().unwrap();
+        let managed = Self::get(&hooks, name)?;
+        managed
+            .hook
+            .apply(managed.target_address, managed.replacement)
+    }
+
+    /// Apply several registered hooks in one [`HookTransaction`]
+    ///
+    /// Either all of them end up applied or none; returns the number applied.
+    pub fn apply_together(&self, names: &[&str]) -> Result<usize> {
+        let hooks = self.hooks.lock().unwrap();
+        let mut transaction = HookTransaction::new();
+        for name in names {
+            let managed = Self::get(&hooks, name)?;
+            transaction.stage(&managed.hook, managed.target_address, managed.replacement);
+        }
+        transaction.commit()
+    }
+
+    /// Remove an applied hook, keeping it registered
+    pub fn remove(&self, name: &str) -> Result<()> {
+        let hooks = self.hooks.lock().unwrap();
+        Self::get(&hooks, name)?.hook.remove()
+    }
+
+    /// Remove a hook if applied and forget it
+    pub fn unregister(&self, name: &str) -> Result<()> {
+        let mut hooks = self.hooks.lock().unwrap();
+        let managed = Self::get(&hooks, name)?;
+        if managed.hook.is_active() {
+            managed.hook.remove()?;
+        }
+        hooks.remove(name);
+        Ok(())
+    }
+
+    /// Apply every registered hook that is not active yet
+    ///
+    /// All hooks are attempted; the names of those that failed are reported
+    /// in the returned error.
+    pub fn apply_all(&self) -> Result<()> {
+        let hooks = self.hooks.lock().unwrap();
+        let failed: Vec<&str> = hooks
+            .iter()
+            .filter(|(_, managed)| !managed.hook.is_active())
+            .filter(|(_, managed)| {
+                managed
+                    .hook
+                    .apply(managed.target_address, managed.replacement)
+                    .is_err()
+            })
+            .map(|(name, _)| name.as_str())
+            .collect();
+
+        Self::bulk_result("apply", failed)
+    }
+
+    /// Remove every active hook
+    pub fn remove_all(&self) -> Result<()> {
+        let hooks = self.hooks.lock().unwrap();
+        let failed: Vec<&str> = hooks
+            .iter()
+            .filter(|(_, managed)| managed.hook.is_active())
+            .filter(|(_, managed)| managed.hook.remove().is_err())
+            .map(|(name, _)| name.as_str())
+            .collect();
+
+        Self::bulk_result("remove", failed)
+    }
+
+    /// Get the status of one hook
+    pub fn status(&self, name: &str) -> Option<HookInfo> {
+        let hooks = self.hooks.lock().unwrap();
+        hooks.get(name).map(|managed| managed.hook.info())
+    }
+
+    /// Get the status of every hook, sorted by name
+    pub fn statuses(&self) -> Vec<(String, HookInfo)> {
+        let hooks = self.hooks.lock().unwrap();
+        let mut statuses: Vec<_> = hooks
+            .iter()
+            .map(|(name, managed)| (name.clone(), managed.hook.info()))
+            .collect();
+        statuses.sort_by(|a, b| a.0.cmp(&b.0));
+        statuses
+    }
+
+    /// Get the invocation statistics of one hook
+    pub fn stats(&self, name: &str) -> Option<Arc<HookStats>> {
+        let hooks = self.hooks.lock().unwrap();
+        hooks.get(name).map(|managed| managed.hook.stats())
+    }
+
+    /// Get the recent invocations of one hook
+    pub fn history(&self, name: &str) -> Option<Arc<InvocationHistory>> {
+        let hooks = self.hooks.lock().unwrap();
+        hooks.get(name).map(|managed| managed.hook.history())
+    }
+
+    /// Get the spoofed return value setting of one hook
+    pub fn spoof(&self, name: &str) -> Option<Arc<SpoofSetting>> {
+        let hooks = self.hooks.lock().unwrap();
+        hooks.get(name).map(|managed| managed.hook.spoof())
+    }
+
+    /// Get the callback chain of one hook, for plugins to extend
+    pub fn chain(&self, name: &str) -> Option<Arc<CallbackChain>> {
+        let hooks = self.hooks.lock().unwrap();
+        hooks.get(name).map(|managed| managed.hook.chain())
+    }
+
+    /// Get the one-shot setting of one hook
+    pub fn once(&self, name: &str) -> Option<Arc<OneShot>> {
+        let hooks = self.hooks.lock().unwrap();
+        hooks.get(name).map(|managed| managed.hook.once())
+    }
+
+    /// Restore the original bytes of a one-shot hook that has fired
+    pub fn finish_once(&self, name: &str) -> Result<()> {
+        let hooks = self.hooks.lock().unwrap();
+        Self::get(&hooks, name)?.hook.finish_once()
+    }
+
+    /// Log the recent invocations of one hook
+    pub fn dump_history(&self, name: &str) -> Result<()> {
+        let history = self.history(name).ok_or_else(|| AppError::HookFailed {
+            message: format!("No hook named '{}' is registered", name),
+        })?;
+        history.dump(name);
+        Ok(())
+    }
+
+    /// Log the invocation statistics of every hook
+    pub fn log_stats(&self) {
+        let hooks = self.hooks.lock().unwrap();
+        let mut names: Vec<&String> = hooks.keys().collect();
+        names.sort_unstable();
+
+        for name in names {
+            let snapshot = hooks[name].hook.stats().snapshot();
+            Logger::hook(&format!("Hook '{}': {}", name, snapshot));
+        }
+    }
+
+    /// Number of registered hooks
+    pub fn len(&self) -> usize {
+        self.hooks.lock().unwrap().len()
+    }
+
+    /// Check if no hooks are registered
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn get<'a>(hooks: &'a HashMap<String, ManagedHook>, name: &str) -> Result<&'a ManagedHook> {
+        hooks.get(name).ok_or_else(|| AppError::HookFailed {
+            message: format!("No hook named '{}' is registered", name),
+        })
+    }
+
+    fn bulk_result(action: &str, mut failed: Vec<&str>) -> Result<()> {
+        if failed.is_empty() {
+            return Ok(());
+        }
+
+        failed.sort_unstable();
+        Err(AppError::HookFailed {
+            message: format!("Failed to {} hooks: {}", action, failed.join(", ")),
+        })
+    }
+}
+
+impl Default for HookManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Global hook manager instance
+static GLOBAL_HOOK_MANAGER: OnceLock<HookManager> = OnceLock::new();
+
+/// Get the global hook manager
+pub fn global_hook_manager() -> &'static HookManager {
+    GLOBAL_HOOK_MANAGER.get_or_init(HookManager::new)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_new_hook_is_uninitialized() {
+        let hook = PakFileHook::new();
+        assert_eq!(hook.state(), HookState::Uninitialized);
+        assert!(!hook.is_active());
+        assert!(hook.target_address().is_none());
+    }
+
+    #[test]
+    fn test_remove_requires_applied_hook() {
+        let hook = PakFileHook::new();
+        assert!(hook.remove().is_err());
+        assert_eq!(hook.state(), HookState::Uninitialized);
+    }
+
+    #[test]
+    fn test_remove_without_saved_bytes_keeps_hook_applied() {
+        let hook = PakFileHook::new();
+        *hook.state.lock().unwrap() = HookState::Applied;
+        *hook.target_address.lock().unwrap() = Some(0x1000);
+
+        assert!(hook.remove().is_err());
+        assert_eq!(hook.state(), HookState::Applied);
+    }
+
+    #[test]
+    fn test_failed_apply_notifies_listeners() {
+        let manager = HookManager::new();
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&seen);
+        manager.on_state_change(move |change| {
+            sink.lock()
+                .unwrap()
+                .push((change.name.map(str::to_string), change.to))
+        });
+
+        manager.register("null", 0, noop_replacement).unwrap();
+        assert!(manager.apply("null").is_err());
+        assert_eq!(
+            *seen.lock().unwrap(),
+            [(Some("null".to_string()), HookState::Failed)]
+        );
+    }
+
+    #[test]
+    fn test_finish_once_requires_fired_hook() {
+        let hook = PakFileHook::new();
+        *hook.state.lock().unwrap() = HookState::Applied;
+        assert!(hook.finish_once().is_err());
+
+        hook.once().arm(true);
+        assert!(hook.once().fire());
+        // Nothing was patched, so there are no bytes to restore
+        assert!(hook.finish_once().is_err());
+        assert_eq!(hook.state(), HookState::Applied);
+    }
+
+    unsafe extern "win64" fn noop_replacement(_: *mut Registers, _: usize, _: usize) -> usize {
+        0
+    }
+
+    #[test]
+    fn test_manager_rejects_duplicate_names_and_addresses() {
+        let manager = HookManager::new();
+        manager
+            .register("pak_check", 0x1000, noop_replacement)
+            .unwrap();
+
+        assert!(
+            manager
+                .register("pak_check", 0x2000, noop_replacement)
+                .is_err()
+        );
+        assert!(matches!(
+            manager.register("sig_check", 0x1000, noop_replacement),
+            Err(AppError::HookConflict { address: 0x1000, ref owner }) if owner == "pak_check"
+        ));
+        assert_eq!(manager.len(), 1);
+    }
+
+    #[test]
+    fn test_manager_statuses_and_unregister() {
+        let manager = HookManager::new();
+        manager.register("b", 0x2000, noop_replacement).unwrap();
+        manager.register("a", 0x1000, noop_replacement).unwrap();
+
+        let statuses = manager.statuses();
+        assert_eq!(statuses[0].0, "a");
+        assert_eq!(statuses[1].1.state, HookState::Uninitialized);
+
+        assert_eq!(manager.stats("a").unwrap().calls(), 0);
+        assert!(manager.history("a").unwrap().recent().is_empty());
+        manager.spoof("a").unwrap().set(Some(0));
+        assert_eq!(manager.spoof("a").unwrap().value(), Some(0));
+        assert!(manager.dump_history("missing").is_err());
+        assert!(manager.remove("a").is_err());
+        manager.unregister("a").unwrap();
+        assert!(manager.status("a").is_none());
+        assert!(manager.unregister("missing").is_err());
+        assert!(manager.remove_all().is_ok());
+    }
+}
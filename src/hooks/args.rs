@@ -0,0 +1,201 @@
+//! Typed access to the arguments of a hooked call
+//!
+//! Follows the Windows x64 calling convention: the first four integer or
+//! pointer arguments arrive in rcx, rdx, r8 and r9, the first four floating
+//! point ones in xmm0-xmm3, and the rest on the stack above the 32-byte
+//! shadow space. Stack arguments are only where this expects them when the
+//! hook sits at the function entry, before the prologue moves rsp.
+
+use std::fmt;
+
+use ilhook::x64::Registers;
+
+use crate::error::{AppError, Result};
+use crate::safety::MemoryAccess;
+
+/// Number of arguments passed in registers
+pub const REGISTER_ARGS: usize = 4;
+
+/// Reader over the register context of one hooked call
+#[derive(Clone, Copy)]
+pub struct ArgReader<'a> {
+    registers: &'a Registers,
+}
+
+impl<'a> ArgReader<'a> {
+    /// Wrap the register context a hook routine received
+    ///
+    /// # Safety
+    ///
+    /// `registers` must be null or point to a register context that stays
+    /// valid for `'a`, such as the one passed to an ilhook routine.
+    pub unsafe fn new(registers: *const Registers) -> Result<Self> {
+        if registers.is_null() {
+            return Err(AppError::MemoryAccessViolation { address: 0 });
+        }
+        Ok(Self {
+            registers: unsafe { &*registers },
+        })
+    }
+
+    /// Wrap a register context already borrowed
+    pub fn from_registers(registers: &'a Registers) -> Self {
+        Self { registers }
+    }
+
+    /// Underlying register context
+    pub fn registers(&self) -> &'a Registers {
+        self.registers
+    }
+
+    /// Integer or pointer argument `index`, counting from 0
+    ///
+    /// Arguments past the fourth are read from the caller's stack.
+    pub fn arg(&self, index: usize) -> Result<usize> {
+        let registers = self.registers;
+        match index {
+            0 => Ok(registers.rcx as usize),
+            1 => Ok(registers.rdx as usize),
+            2 => Ok(registers.r8 as usize),
+            3 => Ok(registers.r9 as usize),
+            _ => self.stack_slot(index + 1),
+        }
+    }
+
+    /// Argument `index` as a pointer to `T`
+    pub fn arg_ptr<T>(&self, index: usize) -> Result<*const T> {
+        self.arg(index).map(|value| value as *const T)
+    }
+
+    /// Floating point argument `index` passed as a double
+    pub fn float_arg(&self, index: usize) -> Result<f64> {
+        let bits = match self.xmm(index) {
+            Some(xmm) => xmm as u64,
+            None => self.stack_slot(index + 1)? as u64,
+        };
+        Ok(f64::from_bits(bits))
+    }
+
+    /// Full contents of xmm0-xmm3, `None` for higher indices
+    pub fn xmm(&self, index: usize) -> Option<u128> {
+        let registers = self.registers;
+        match index {
+            0 => Some(registers.xmm0),
+            1 => Some(registers.xmm1),
+            2 => Some(registers.xmm2),
+            3 => Some(registers.xmm3),
+            _ => None,
+        }
+    }
+
+    /// Address the hooked function will return to
+    pub fn return_address(&self) -> Result<usize> {
+        self.stack_slot(0)
+    }
+
+    /// Pointer-sized value `slot` slots above rsp
+    fn stack_slot(&self, slot: usize) -> Result<usize> {
+        let address = (self.registers.rsp as usize).wrapping_add(slot * size_of::<usize>());
+        unsafe { MemoryAccess::read_volatile_safe(address as *const usize) }
+    }
+}
+
+/// Multi-line hex dump of a register context
+pub struct RegisterDump<'a>(pub &'a Registers);
+
+impl fmt::Display for RegisterDump<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let r = self.0;
+        let rows = [
+            [
+                ("rax", r.rax),
+                ("rbx", r.rbx),
+                ("rcx", r.rcx),
+                ("rdx", r.rdx),
+            ],
+            [
+                ("rsi", r.rsi),
+                ("rdi", r.rdi),
+                ("rbp", r.rbp),
+                ("rsp", r.rsp),
+            ],
+            [("r8", r.r8), ("r9", r.r9), ("r10", r.r10), ("r11", r.r11)],
+            [
+                ("r12", r.r12),
+                ("r13", r.r13),
+                ("r14", r.r14),
+                ("r15", r.r15),
+            ],
+        ];
+        for row in rows {
+            for (i, (name, value)) in row.iter().enumerate() {
+                let separator = if i == 0 { "" } else { "  " };
+                write!(f, "{}{:>3}={:016x}", separator, name, value)?;
+            }
+            writeln!(f)?;
+        }
+        writeln!(f, "rflags={:016x}", r.rflags)?;
+        for (i, xmm) in [r.xmm0, r.xmm1, r.xmm2, r.xmm3].iter().enumerate() {
+            let separator = if i == 3 { "" } else { "\n" };
+            write!(f, "xmm{}={:032x}{}", i, xmm, separator)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn registers() -> Registers {
+        unsafe { std::mem::zeroed() }
+    }
+
+    #[test]
+    fn test_register_args_follow_win64_order() {
+        let mut registers = registers();
+        registers.rcx = 1;
+        registers.rdx = 2;
+        registers.r8 = 3;
+        registers.r9 = 4;
+        registers.xmm1 = 2.5f64.to_bits() as u128;
+
+        let args = ArgReader::from_registers(&registers);
+        assert_eq!(
+            (0..REGISTER_ARGS)
+                .map(|i| args.arg(i).unwrap())
+                .collect::<Vec<_>>(),
+            [1, 2, 3, 4]
+        );
+        assert_eq!(args.float_arg(1).unwrap(), 2.5);
+        assert!(args.xmm(4).is_none());
+    }
+
+    #[test]
+    fn test_stack_args_skip_return_address_and_shadow_space() {
+        // Return address, four shadow slots, then the fifth and sixth args
+        let stack: [usize; 7] = [0xdead, 0, 0, 0, 0, 5, 6];
+        let mut registers = registers();
+        registers.rsp = stack.as_ptr() as u64;
+
+        let args = ArgReader::from_registers(&registers);
+        assert_eq!(args.return_address().unwrap(), 0xdead);
+        assert_eq!(args.arg(4).unwrap(), 5);
+        assert_eq!(args.arg(5).unwrap(), 6);
+    }
+
+    #[test]
+    fn test_null_context_rejected() {
+        assert!(unsafe { ArgReader::new(std::ptr::null()) }.is_err());
+    }
+
+    #[test]
+    fn test_register_dump_lists_every_register() {
+        let mut registers = registers();
+        registers.r15 = 0xabc;
+        let dump = RegisterDump(&registers).to_string();
+        assert!(dump.contains("r15=0000000000000abc"));
+        assert!(dump.contains("rflags="));
+        assert_eq!(dump.lines().count(), 9);
+    }
+}
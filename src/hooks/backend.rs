@@ -0,0 +1,508 @@
+//! Pluggable inline hook backends
+//!
+//! [`FunctionHook`](crate::hooks::FunctionHook) drives a [`HookBackend`]
+//! instead of a fixed hooking library, so the patching strategy can be
+//! chosen per hook or through [`Config`](crate::config::Config).
+
+use std::fmt;
+use std::mem::ManuallyDrop;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use ilhook::x64::{
+    CallbackOption, HookFlags, HookPoint, HookType, Hooker, JmpBackRoutine, ThreadCallback,
+};
+use interceptor_rs::Interceptor;
+
+use crate::constants::constants::memory::HOOK_BACKUP_LEN;
+use crate::error::{AppError, Result};
+use crate::hooks::HookReplacement;
+use crate::safety::MemoryAccess;
+
+/// Routine a backend installs over its target
+#[derive(Clone, Copy)]
+pub enum BackendRoutine {
+    /// Runs instead of the target and returns to its caller
+    Replace(HookReplacement),
+    /// Runs before the target, which then executes as usual; the `usize`
+    /// is passed to the routine as its user data
+    Observe(JmpBackRoutine, usize),
+}
+
+impl BackendRoutine {
+    fn kind_name(&self) -> &'static str {
+        match self {
+            Self::Replace(_) => "replacement",
+            Self::Observe(..) => "jmp-back",
+        }
+    }
+}
+
+/// Callbacks around the moment a backend writes its patch
+///
+/// Everything the hook needs (trampoline, stub) is allocated before
+/// `before_write` runs, so the gate may suspend other threads there without
+/// the backend allocating behind it.
+pub trait WriteGate: Send + Sync {
+    /// Called right before the patch is written; returning `false` aborts
+    /// the hook
+    fn before_write(&self) -> bool;
+
+    /// Called right after the patch is written
+    fn after_write(&self);
+}
+
+/// Adapter running a [`WriteGate`] as ilhook's thread callback
+struct GateCallback(Arc<dyn WriteGate>);
+
+impl ThreadCallback for GateCallback {
+    fn pre(&self) -> bool {
+        self.0.before_write()
+    }
+
+    fn post(&self) {
+        self.0.after_write()
+    }
+}
+
+/// A strategy for patching one function
+pub trait HookBackend {
+    /// Which backend this is
+    fn kind(&self) -> HookBackendKind;
+
+    /// Patch `target_address` to run `routine`
+    fn apply(&mut self, target_address: usize, routine: BackendRoutine) -> Result<()>;
+
+    /// Like [`apply`](Self::apply), with the patch write bracketed by `gate`
+    fn apply_gated(
+        &mut self,
+        target_address: usize,
+        routine: BackendRoutine,
+        gate: Arc<dyn WriteGate>,
+    ) -> Result<()>;
+
+    /// Undo the patch made by `apply`
+    fn remove(&mut self) -> Result<()>;
+
+    /// Undo the patch while keeping the routine's stub allocated
+    ///
+    /// Unlike `remove`, this is safe to call from inside the installed
+    /// routine, which may still be running in code the backend allocated.
+    fn detach(&mut self) -> Result<()>;
+
+    /// Address of a trampoline running the original function, if the backend has one
+    fn trampoline(&self) -> Option<usize>;
+}
+
+/// Available hook backends
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum HookBackendKind {
+    /// interceptor-rs `replace`, restoring saved bytes on removal
+    #[default]
+    Interceptor,
+    /// ilhook routine that replaces the target and returns to its caller
+    IlhookRetn,
+    /// ilhook routine that runs first and jumps back into the target
+    IlhookJmpBack,
+    /// MinHook detour that replaces the target and returns to its caller
+    #[cfg(feature = "minhook")]
+    MinHook,
+}
+
+impl HookBackendKind {
+    /// Create a fresh backend of this kind
+    pub fn create(self) -> Box<dyn HookBackend> {
+        match self {
+            Self::Interceptor => Box::new(InterceptorBackend::new()),
+            Self::IlhookRetn | Self::IlhookJmpBack => Box::new(IlhookBackend::new(self)),
+            #[cfg(feature = "minhook")]
+            Self::MinHook => Box::new(crate::hooks::minhook::MinHookBackend::new()),
+        }
+    }
+
+    /// Every backend compiled into this build
+    pub fn all() -> &'static [Self] {
+        &[
+            Self::Interceptor,
+            Self::IlhookRetn,
+            Self::IlhookJmpBack,
+            #[cfg(feature = "minhook")]
+            Self::MinHook,
+        ]
+    }
+
+    /// Name used in configuration
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Interceptor => "interceptor",
+            Self::IlhookRetn => "ilhook-retn",
+            Self::IlhookJmpBack => "ilhook-jmpback",
+            #[cfg(feature = "minhook")]
+            Self::MinHook => "minhook",
+        }
+    }
+
+    /// Check if this backend can install a routine that replaces the target
+    pub fn can_replace(self) -> bool {
+        self != Self::IlhookJmpBack
+    }
+}
+
+impl fmt::Display for HookBackendKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for HookBackendKind {
+    type Err = AppError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Self::all()
+            .iter()
+            .copied()
+            .find(|kind| kind.as_str().eq_ignore_ascii_case(s))
+            .ok_or_else(|| AppError::InvalidConfig {
+                field: "hook_backend".to_string(),
+                reason: format!("unknown hook backend '{}'", s),
+            })
+    }
+}
+
+pub(super) fn unsupported(kind: HookBackendKind, routine: &BackendRoutine) -> AppError {
+    AppError::HookFailed {
+        message: format!(
+            "The {} backend cannot install a {} routine",
+            kind,
+            routine.kind_name()
+        ),
+    }
+}
+
+/// interceptor-rs backend
+///
+/// interceptor-rs has no unhook API, so the bytes overwritten by `apply` are
+/// saved and written back by `remove`. The trampoline stays allocated but
+/// is no longer reachable.
+///
+/// interceptor-rs cannot take a thread callback either; `apply_gated`
+/// installs the same ilhook jmp-to-ret hook `replace` does and keeps its
+/// hook point until `remove` unhooks it.
+pub struct InterceptorBackend {
+    interceptor: Interceptor,
+    gated_hook: Option<HookPoint>,
+    target_address: Option<usize>,
+    original_bytes: Option<Vec<u8>>,
+}
+
+impl InterceptorBackend {
+    pub fn new() -> Self {
+        Self {
+            interceptor: Interceptor::new(),
+            gated_hook: None,
+            target_address: None,
+            original_bytes: None,
+        }
+    }
+
+    /// Save the original bytes, then install the hook with `install`
+    fn apply_with(
+        &mut self,
+        target_address: usize,
+        routine: BackendRoutine,
+        install: impl FnOnce(&mut Self, HookReplacement) -> Result<()>,
+    ) -> Result<()> {
+        let BackendRoutine::Replace(replacement) = routine else {
+            return Err(unsupported(self.kind(), &routine));
+        };
+
+        // Save the bytes the hook overwrites so remove() can put them back
+        let original =
+            unsafe { MemoryAccess::read_bytes(target_address as *const u8, HOOK_BACKUP_LEN)? };
+
+        install(self, replacement)?;
+
+        self.target_address = Some(target_address);
+        self.original_bytes = Some(original);
+        Ok(())
+    }
+}
+
+impl Default for InterceptorBackend {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl HookBackend for InterceptorBackend {
+    fn kind(&self) -> HookBackendKind {
+        HookBackendKind::Interceptor
+    }
+
+    fn apply(&mut self, target_address: usize, routine: BackendRoutine) -> Result<()> {
+        self.apply_with(target_address, routine, |backend, replacement| {
+            backend
+                .interceptor
+                .replace(target_address, replacement, None)
+                .map_err(|e| AppError::HookFailed {
+                    message: format!("{:?}", e),
+                })
+        })
+    }
+
+    fn apply_gated(
+        &mut self,
+        target_address: usize,
+        routine: BackendRoutine,
+        gate: Arc<dyn WriteGate>,
+    ) -> Result<()> {
+        if self.gated_hook.is_some() {
+            return Err(AppError::HookFailed {
+                message: "interceptor backend is already hooked".to_string(),
+            });
+        }
+
+        self.apply_with(target_address, routine, |backend, replacement| {
+            let hooker = Hooker::new(
+                target_address,
+                HookType::JmpToRet(replacement),
+                CallbackOption::Some(Box::new(GateCallback(gate))),
+                0,
+                HookFlags::empty(),
+            );
+            let hook_point = unsafe { hooker.hook() }.map_err(|e| AppError::HookFailed {
+                message: format!("{:?}", e),
+            })?;
+            backend.gated_hook = Some(hook_point);
+            Ok(())
+        })
+    }
+
+    fn remove(&mut self) -> Result<()> {
+        let (Some(target_address), Some(original)) =
+            (self.target_address, self.original_bytes.as_ref())
+        else {
+            return Err(AppError::HookFailed {
+                message: "No original bytes saved for the hooked function".to_string(),
+            });
+        };
+
+        // ilhook restores the bytes of a gated hook itself
+        if let Some(hook_point) = self.gated_hook.take() {
+            self.target_address = None;
+            self.original_bytes = None;
+            return unsafe { hook_point.unhook() }.map_err(|e| AppError::HookFailed {
+                message: format!("{:?}", e),
+            });
+        }
+
+        unsafe { MemoryAccess::write_bytes_patched(target_address as *mut u8, original)? };
+        self.target_address = None;
+        self.original_bytes = None;
+        Ok(())
+    }
+
+    fn detach(&mut self) -> Result<()> {
+        let (Some(target_address), Some(original)) =
+            (self.target_address, self.original_bytes.as_ref())
+        else {
+            return Err(AppError::HookFailed {
+                message: "No original bytes saved for the hooked function".to_string(),
+            });
+        };
+
+        unsafe { MemoryAccess::write_bytes_patched(target_address as *mut u8, original)? };
+        // Leak a gated hook point like interceptor-rs leaks its trampoline,
+        // the calling routine may be returning through it
+        let _ = self.gated_hook.take().map(ManuallyDrop::new);
+        self.target_address = None;
+        self.original_bytes = None;
+        Ok(())
+    }
+
+    fn trampoline(&self) -> Option<usize> {
+        None
+    }
+}
+
+/// ilhook backend in retn or jmp-back mode
+///
+/// ilhook relocates the overwritten instructions into its own trampoline
+/// and restores them itself on unhook. The overwritten bytes are also saved
+/// here so `detach` can restore them without freeing the trampoline.
+pub struct IlhookBackend {
+    kind: HookBackendKind,
+    hook_point: Option<HookPoint>,
+    target_address: Option<usize>,
+    original_bytes: Option<Vec<u8>>,
+}
+
+impl IlhookBackend {
+    /// Create a backend for `HookBackendKind::IlhookRetn` or `IlhookJmpBack`
+    pub fn new(kind: HookBackendKind) -> Self {
+        debug_assert!(kind != HookBackendKind::Interceptor);
+        Self {
+            kind,
+            hook_point: None,
+            target_address: None,
+            original_bytes: None,
+        }
+    }
+
+    /// Install the hook, running `callback` around the patch write
+    fn hook(
+        &mut self,
+        target_address: usize,
+        routine: BackendRoutine,
+        callback: CallbackOption,
+    ) -> Result<()> {
+        if self.hook_point.is_some() {
+            return Err(AppError::HookFailed {
+                message: "ilhook backend is already hooked".to_string(),
+            });
+        }
+
+        let (hook_type, user_data) = match (self.kind, routine) {
+            (HookBackendKind::IlhookRetn, BackendRoutine::Replace(replacement)) => {
+                (HookType::JmpToRet(replacement), 0)
+            }
+            (HookBackendKind::IlhookJmpBack, BackendRoutine::Observe(observer, user_data)) => {
+                (HookType::JmpBack(observer), user_data)
+            }
+            _ => return Err(unsupported(self.kind, &routine)),
+        };
+
+        let original =
+            unsafe { MemoryAccess::read_bytes(target_address as *const u8, HOOK_BACKUP_LEN)? };
+
+        let hooker = Hooker::new(
+            target_address,
+            hook_type,
+            callback,
+            user_data,
+            HookFlags::empty(),
+        );
+        let hook_point = unsafe { hooker.hook() }.map_err(|e| AppError::HookFailed {
+            message: format!("{:?}", e),
+        })?;
+
+        self.hook_point = Some(hook_point);
+        self.target_address = Some(target_address);
+        self.original_bytes = Some(original);
+        Ok(())
+    }
+}
+
+impl HookBackend for IlhookBackend {
+    fn kind(&self) -> HookBackendKind {
+        self.kind
+    }
+
+    fn apply(&mut self, target_address: usize, routine: BackendRoutine) -> Result<()> {
+        self.hook(target_address, routine, CallbackOption::None)
+    }
+
+    fn apply_gated(
+        &mut self,
+        target_address: usize,
+        routine: BackendRoutine,
+        gate: Arc<dyn WriteGate>,
+    ) -> Result<()> {
+        self.hook(
+            target_address,
+            routine,
+            CallbackOption::Some(Box::new(GateCallback(gate))),
+        )
+    }
+
+    fn remove(&mut self) -> Result<()> {
+        let Some(hook_point) = self.hook_point.take() else {
+            return Err(AppError::HookFailed {
+                message: "ilhook backend is not hooked".to_string(),
+            });
+        };
+
+        self.target_address = None;
+        self.original_bytes = None;
+        unsafe { hook_point.unhook() }.map_err(|e| AppError::HookFailed {
+            message: format!("{:?}", e),
+        })
+    }
+
+    fn detach(&mut self) -> Result<()> {
+        let (Some(target_address), Some(original)) =
+            (self.target_address, self.original_bytes.as_ref())
+        else {
+            return Err(AppError::HookFailed {
+                message: "ilhook backend is not hooked".to_string(),
+            });
+        };
+
+        unsafe { MemoryAccess::write_bytes_patched(target_address as *mut u8, original)? };
+        // Leak the hook point: unhooking would free the trampoline the
+        // calling routine may be returning through
+        let _ = self.hook_point.take().map(ManuallyDrop::new);
+        self.target_address = None;
+        self.original_bytes = None;
+        Ok(())
+    }
+
+    fn trampoline(&self) -> Option<usize> {
+        self.hook_point
+            .as_ref()
+            .map(|hook_point| hook_point.get_trampoline())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backend_kind_round_trips_through_names() {
+        for &kind in HookBackendKind::all() {
+            assert_eq!(kind.to_string().parse::<HookBackendKind>().unwrap(), kind);
+            assert_eq!(kind.create().kind(), kind);
+        }
+        assert!("detours".parse::<HookBackendKind>().is_err());
+        assert_eq!(
+            "minhook".parse::<HookBackendKind>().is_ok(),
+            cfg!(feature = "minhook")
+        );
+    }
+
+    #[test]
+    fn test_backends_reject_mismatched_routines() {
+        unsafe extern "win64" fn observer(_: *mut ilhook::x64::Registers, _: usize) {}
+
+        let mut interceptor = InterceptorBackend::new();
+        assert!(
+            interceptor
+                .apply(0x1000, BackendRoutine::Observe(observer, 0))
+                .is_err()
+        );
+
+        let mut retn = IlhookBackend::new(HookBackendKind::IlhookRetn);
+        assert!(
+            retn.apply(0x1000, BackendRoutine::Observe(observer, 0))
+                .is_err()
+        );
+        assert!(retn.trampoline().is_none());
+    }
+
+    #[test]
+    fn test_remove_before_apply_fails() {
+        assert!(InterceptorBackend::new().remove().is_err());
+        assert!(InterceptorBackend::new().detach().is_err());
+        assert!(
+            IlhookBackend::new(HookBackendKind::IlhookRetn)
+                .detach()
+                .is_err()
+        );
+        assert!(
+            IlhookBackend::new(HookBackendKind::IlhookJmpBack)
+                .remove()
+                .is_err()
+        );
+    }
+}
@@ -0,0 +1,231 @@
+//! Ordered callbacks attached to a hook point
+//!
+//! Each call runs every pre-call inspector, then the decision functions
+//! until one returns a value, then every post-call observer with the
+//! result. When no decision claims the call, the hook's own behaviour runs.
+
+use std::sync::RwLock;
+
+use ilhook::x64::Registers;
+
+use crate::error::{AppError, Result};
+
+/// One intercepted call, as seen by callbacks
+pub struct HookCall<'a> {
+    /// Register state at entry; pre-call inspectors may change arguments
+    pub registers: &'a mut Registers,
+    /// Address of the original function, 0 if the backend has none
+    pub original: usize,
+    /// Name the hook resolved for this call, e.g. the pak being verified
+    pub name: Option<&'a str>,
+}
+
+/// Runs before the call is decided and may edit its arguments
+pub type PreCallback = Box<dyn Fn(&mut HookCall<'_>) + Send + Sync>;
+
+/// Returns the value to hand back to the caller, or `None` to pass
+pub type DecisionCallback = Box<dyn Fn(&HookCall<'_>) -> Option<usize> + Send + Sync>;
+
+/// Runs after the call with the value returned to the caller
+pub type PostCallback = Box<dyn Fn(&HookCall<'_>, usize) + Send + Sync>;
+
+/// Named callbacks in registration order
+struct Stage<T> {
+    callbacks: RwLock<Vec<(String, T)>>,
+}
+
+impl<T> Stage<T> {
+    fn new() -> Self {
+        Self {
+            callbacks: RwLock::new(Vec::new()),
+        }
+    }
+
+    fn add(&self, name: &str, callback: T) -> Result<()> {
+        let mut callbacks = self.callbacks.write().unwrap();
+        if callbacks.iter().any(|(existing, _)| existing == name) {
+            return Err(AppError::HookFailed {
+                message: format!("A callback named '{}' is already registered", name),
+            });
+        }
+        callbacks.push((name.to_string(), callback));
+        Ok(())
+    }
+
+    fn remove(&self, name: &str) -> bool {
+        let mut callbacks = self.callbacks.write().unwrap();
+        let before = callbacks.len();
+        callbacks.retain(|(existing, _)| existing != name);
+        callbacks.len() != before
+    }
+
+    fn len(&self) -> usize {
+        self.callbacks.read().unwrap().len()
+    }
+}
+
+/// Pre-call, decision and post-call callbacks of one hook
+pub struct CallbackChain {
+    pre: Stage<PreCallback>,
+    decisions: Stage<DecisionCallback>,
+    post: Stage<PostCallback>,
+}
+
+impl CallbackChain {
+    pub fn new() -> Self {
+        Self {
+            pre: Stage::new(),
+            decisions: Stage::new(),
+            post: Stage::new(),
+        }
+    }
+
+    /// Add a pre-call inspector after the existing ones
+    pub fn add_pre(
+        &self,
+        name: &str,
+        callback: impl Fn(&mut HookCall<'_>) + Send + Sync + 'static,
+    ) -> Result<()> {
+        self.pre.add(name, Box::new(callback))
+    }
+
+    /// Add a decision function after the existing ones
+    pub fn add_decision(
+        &self,
+        name: &str,
+        callback: impl Fn(&HookCall<'_>) -> Option<usize> + Send + Sync + 'static,
+    ) -> Result<()> {
+        self.decisions.add(name, Box::new(callback))
+    }
+
+    /// Add a post-call observer after the existing ones
+    pub fn add_post(
+        &self,
+        name: &str,
+        callback: impl Fn(&HookCall<'_>, usize) + Send + Sync + 'static,
+    ) -> Result<()> {
+        self.post.add(name, Box::new(callback))
+    }
+
+    /// Remove every callback registered under `name`, returning whether any was
+    pub fn remove(&self, name: &str) -> bool {
+        // Non-short-circuiting so a name used in several stages goes everywhere
+        self.pre.remove(name) | self.decisions.remove(name) | self.post.remove(name)
+    }
+
+    /// Number of registered callbacks across all stages
+    pub fn len(&self) -> usize {
+        self.pre.len() + self.decisions.len() + self.post.len()
+    }
+
+    /// Check if no callbacks are registered
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Run the chain for one call, falling back to `default` when no
+    /// decision function claims it
+    pub fn run(
+        &self,
+        call: &mut HookCall<'_>,
+        default: impl FnOnce(&mut HookCall<'_>) -> usize,
+    ) -> usize {
+        for (_, callback) in self.pre.callbacks.read().unwrap().iter() {
+            callback(call);
+        }
+
+        let decided = self
+            .decisions
+            .callbacks
+            .read()
+            .unwrap()
+            .iter()
+            .find_map(|(_, callback)| callback(call));
+        let result = decided.unwrap_or_else(|| default(call));
+
+        for (_, callback) in self.post.callbacks.read().unwrap().iter() {
+            callback(call, result);
+        }
+        result
+    }
+}
+
+impl Default for CallbackChain {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    fn registers() -> Registers {
+        unsafe { std::mem::zeroed() }
+    }
+
+    #[test]
+    fn test_empty_chain_runs_default() {
+        let chain = CallbackChain::new();
+        let mut registers = registers();
+        let mut call = HookCall {
+            registers: &mut registers,
+            original: 0,
+            name: None,
+        };
+        assert!(chain.is_empty());
+        assert_eq!(chain.run(&mut call, |_| 7), 7);
+    }
+
+    #[test]
+    fn test_first_decision_wins_and_post_sees_result() {
+        let chain = CallbackChain::new();
+        let observed = Arc::new(AtomicUsize::new(0));
+
+        chain
+            .add_pre("bump_rcx", |call| call.registers.rcx += 1)
+            .unwrap();
+        chain
+            .add_decision("mods_only", |call| {
+                call.name.filter(|name| name.ends_with("_P.pak")).map(|_| 1)
+            })
+            .unwrap();
+        chain
+            .add_decision("from_rcx", |call| Some(call.registers.rcx as usize))
+            .unwrap();
+        let sink = Arc::clone(&observed);
+        chain
+            .add_post("record", move |_, result| {
+                sink.store(result, Ordering::SeqCst)
+            })
+            .unwrap();
+
+        let mut registers = registers();
+        let mut call = HookCall {
+            registers: &mut registers,
+            original: 0,
+            name: Some("pakchunk0.pak"),
+        };
+        assert_eq!(chain.run(&mut call, |_| 0), 1);
+        assert_eq!(observed.load(Ordering::SeqCst), 1);
+
+        call.name = Some("MyMod_P.pak");
+        assert_eq!(chain.run(&mut call, |_| 0), 1);
+        assert_eq!(call.registers.rcx, 2);
+    }
+
+    #[test]
+    fn test_duplicate_names_rejected_and_remove_spans_stages() {
+        let chain = CallbackChain::new();
+        chain.add_pre("plugin", |_| {}).unwrap();
+        chain.add_post("plugin", |_, _| {}).unwrap();
+        assert!(chain.add_pre("plugin", |_| {}).is_err());
+        assert_eq!(chain.len(), 2);
+
+        assert!(chain.remove("plugin"));
+        assert!(chain.is_empty());
+        assert!(!chain.remove("plugin"));
+    }
+}
@@ -0,0 +1,337 @@
+//! Guard page hooks
+//!
+//! Marks the page holding the target `PAGE_GUARD` and catches the resulting
+//! exception in a vectored exception handler. The guard is one-shot, so
+//! every fault on the page single-steps one instruction and re-arms it.
+//!
+//! Every instruction executed on the guarded page costs two exceptions.
+//! Use this only for targets on cold pages when neither inline patching nor
+//! a hardware breakpoint is an option.
+
+use std::cell::Cell;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU32, AtomicUsize, Ordering};
+
+use winapi::shared::minwindef::DWORD;
+use winapi::um::errhandlingapi::{AddVectoredExceptionHandler, RemoveVectoredExceptionHandler};
+use winapi::um::memoryapi::{VirtualProtect, VirtualQuery};
+use winapi::um::winnt::{
+    EXCEPTION_POINTERS, LONG, MEM_COMMIT, MEMORY_BASIC_INFORMATION, PAGE_GUARD,
+    STATUS_GUARD_PAGE_VIOLATION, STATUS_SINGLE_STEP,
+};
+
+use crate::constants::constants::memory::PAGE_SIZE;
+use crate::error::{AppError, Result};
+use crate::hooks::HookState;
+use crate::hooks::hwbp::{
+    BreakpointHandler, EXCEPTION_CONTINUE_EXECUTION, EXCEPTION_CONTINUE_SEARCH,
+};
+use crate::logger::Logger;
+use crate::memory::describe_address;
+
+/// Number of guard page hooks that can be active at once
+pub const GUARD_HOOK_SLOTS: usize = 8;
+
+/// `EFLAGS.TF`, raises a single-step exception after the next instruction
+const TRAP_FLAG: DWORD = 1 << 8;
+
+/// Target address per slot, 0 when free
+static SLOT_TARGETS: [AtomicUsize; GUARD_HOOK_SLOTS] =
+    [const { AtomicUsize::new(0) }; GUARD_HOOK_SLOTS];
+
+/// `BreakpointHandler` per slot, stored as an address for lock-free access
+static SLOT_HANDLERS: [AtomicUsize; GUARD_HOOK_SLOTS] =
+    [const { AtomicUsize::new(0) }; GUARD_HOOK_SLOTS];
+
+/// Protection of each slot's page before the guard was added
+static SLOT_PROTECT: [AtomicU32; GUARD_HOOK_SLOTS] =
+    [const { AtomicU32::new(0) }; GUARD_HOOK_SLOTS];
+
+/// Serializes claiming and releasing slots so a target is hooked at most once
+static SLOT_LOCK: Mutex<()> = Mutex::new(());
+
+/// Handle returned by `AddVectoredExceptionHandler`, `None` when not installed
+static VEH_HANDLE: Mutex<Option<usize>> = Mutex::new(None);
+
+thread_local! {
+    /// Guarded page this thread is single-stepping past, 0 when none
+    static PENDING_REARM: Cell<usize> = const { Cell::new(0) };
+}
+
+/// Hook on a function via a guard page and vectored exception handler
+///
+/// The guard is dropped from the page when the last hook on it is removed.
+pub struct GuardPageHook {
+    index: usize,
+    target: usize,
+    state: Mutex<HookState>,
+}
+
+impl GuardPageHook {
+    /// Guard the page holding `target` and run `handler` when it executes
+    pub fn apply(target: usize, handler: BreakpointHandler) -> Result<Self> {
+        if target == 0 {
+            return Err(AppError::MemoryAccessViolation { address: 0 });
+        }
+
+        install_exception_handler()?;
+        let index = claim_slot(target, handler)?;
+        let page = page_of(target);
+
+        Logger::hook(&format!(
+            "Guarding page {:#x} for hook at {}",
+            page,
+            describe_address(target)
+        ));
+
+        // A second hook on the page keeps the protection saved by the first,
+        // since the page already carries the guard
+        let protect = match shared_page_protect(index, page) {
+            Some(protect) => Ok(protect),
+            None => current_protect(page),
+        };
+        if let Err(e) = protect.and_then(|protect| {
+            SLOT_PROTECT[index].store(protect, Ordering::Release);
+            guard_page(page, protect)
+        }) {
+            release_slot(index);
+            return Err(e);
+        }
+
+        Logger::success(&format!(
+            "Guard page hook applied to {}",
+            describe_address(target)
+        ));
+        Ok(Self {
+            index,
+            target,
+            state: Mutex::new(HookState::Applied),
+        })
+    }
+
+    /// Free the slot and restore the page unless another hook still guards it
+    pub fn remove(&self) -> Result<()> {
+        let mut state = self.state.lock().unwrap();
+        if *state != HookState::Applied {
+            return Err(AppError::HookFailed {
+                message: format!("Cannot remove guard page hook in state: {:?}", *state),
+            });
+        }
+
+        let page = page_of(self.target);
+        let protect = SLOT_PROTECT[self.index].load(Ordering::Acquire);
+        release_slot(self.index);
+
+        if shared_page_protect(self.index, page).is_none() {
+            let mut ignored: DWORD = 0;
+            if unsafe { VirtualProtect(page as _, PAGE_SIZE, protect, &mut ignored) } == 0 {
+                return Err(AppError::ProtectionChangeFailed {
+                    address: page,
+                    source: std::io::Error::last_os_error(),
+                });
+            }
+        }
+
+        *state = HookState::Removed;
+        Logger::success(&format!(
+            "Guard page hook removed from {}",
+            describe_address(self.target)
+        ));
+        Ok(())
+    }
+
+    /// Address the hook intercepts
+    pub fn target(&self) -> usize {
+        self.target
+    }
+
+    /// Get the current hook state
+    pub fn state(&self) -> HookState {
+        *self.state.lock().unwrap()
+    }
+
+    /// Check if the hook is currently active
+    pub fn is_active(&self) -> bool {
+        self.state() == HookState::Applied
+    }
+}
+
+impl Drop for GuardPageHook {
+    fn drop(&mut self) {
+        if self.is_active()
+            && let Err(e) = self.remove()
+        {
+            Logger::error(&format!("Failed to remove guard page hook on drop: {}", e));
+        }
+    }
+}
+
+fn page_of(address: usize) -> usize {
+    address & !(PAGE_SIZE - 1)
+}
+
+/// Protection recorded by another active hook on `page`, if any
+fn shared_page_protect(index: usize, page: usize) -> Option<DWORD> {
+    SLOT_TARGETS
+        .iter()
+        .enumerate()
+        .find(|&(other, slot)| {
+            let target = slot.load(Ordering::Acquire);
+            other != index && target != 0 && page_of(target) == page
+        })
+        .map(|(other, _)| SLOT_PROTECT[other].load(Ordering::Acquire))
+}
+
+/// Protection of a committed page, without any guard flag
+fn current_protect(page: usize) -> Result<DWORD> {
+    let mut info: MEMORY_BASIC_INFORMATION = unsafe { std::mem::zeroed() };
+    let written = unsafe {
+        VirtualQuery(
+            page as *const _,
+            &mut info,
+            std::mem::size_of::<MEMORY_BASIC_INFORMATION>(),
+        )
+    };
+    if written == 0 || info.State != MEM_COMMIT {
+        return Err(AppError::MemoryAccessViolation { address: page });
+    }
+    Ok(info.Protect & !PAGE_GUARD)
+}
+
+fn guard_page(page: usize, protect: DWORD) -> Result<()> {
+    let mut ignored: DWORD = 0;
+    if unsafe { VirtualProtect(page as _, PAGE_SIZE, protect | PAGE_GUARD, &mut ignored) } == 0 {
+        return Err(AppError::ProtectionChangeFailed {
+            address: page,
+            source: std::io::Error::last_os_error(),
+        });
+    }
+    Ok(())
+}
+
+/// Reserve a free slot for `target`, rejecting a target that is already hooked
+fn claim_slot(target: usize, handler: BreakpointHandler) -> Result<usize> {
+    let _guard = SLOT_LOCK.lock().unwrap();
+    if SLOT_TARGETS
+        .iter()
+        .any(|slot| slot.load(Ordering::Acquire) == target)
+    {
+        return Err(AppError::HookConflict {
+            address: target,
+            owner: "guard page hook".to_string(),
+        });
+    }
+
+    for (index, slot) in SLOT_TARGETS.iter().enumerate() {
+        // A hit between taking the slot and publishing the handler just
+        // runs the original code
+        if slot
+            .compare_exchange(0, target, Ordering::AcqRel, Ordering::Acquire)
+            .is_ok()
+        {
+            SLOT_HANDLERS[index].store(handler as usize, Ordering::Release);
+            return Ok(index);
+        }
+    }
+
+    Err(AppError::HookFailed {
+        message: "All guard page hook slots are in use".to_string(),
+    })
+}
+
+fn release_slot(index: usize) {
+    let _guard = SLOT_LOCK.lock().unwrap();
+    SLOT_TARGETS[index].store(0, Ordering::Release);
+}
+
+fn install_exception_handler() -> Result<()> {
+    let mut installed = VEH_HANDLE.lock().unwrap();
+    if installed.is_some() {
+        return Ok(());
+    }
+
+    let handle = unsafe { AddVectoredExceptionHandler(1, Some(guard_exception_handler)) };
+    if handle.is_null() {
+        return Err(AppError::HookFailed {
+            message: "AddVectoredExceptionHandler failed".to_string(),
+        });
+    }
+    *installed = Some(handle as usize);
+    Ok(())
+}
+
+/// Unregister the exception handler, before unloading the module that
+/// installed guard page hooks
+pub fn remove_exception_handler() {
+    if let Some(handle) = VEH_HANDLE.lock().unwrap().take() {
+        unsafe { RemoveVectoredExceptionHandler(handle as _) };
+    }
+}
+
+/// Dispatch guard page faults on hooked pages and re-arm them after one step
+unsafe extern "system" fn guard_exception_handler(info: *mut EXCEPTION_POINTERS) -> LONG {
+    let (record, context) = unsafe { (&*(*info).ExceptionRecord, &mut *(*info).ContextRecord) };
+
+    match record.ExceptionCode {
+        STATUS_GUARD_PAGE_VIOLATION => {
+            // The faulting data address; equal to Rip for execution faults
+            let page = page_of(record.ExceptionInformation[1]);
+            let hooked_page = SLOT_TARGETS.iter().any(|slot| {
+                let target = slot.load(Ordering::Acquire);
+                target != 0 && page_of(target) == page
+            });
+            if !hooked_page {
+                return EXCEPTION_CONTINUE_SEARCH;
+            }
+
+            let rip = context.Rip as usize;
+            if let Some(hit) = SLOT_TARGETS
+                .iter()
+                .position(|slot| slot.load(Ordering::Acquire) == rip)
+            {
+                let handler = SLOT_HANDLERS[hit].load(Ordering::Acquire);
+                if handler != 0 {
+                    let handler: BreakpointHandler = unsafe { std::mem::transmute(handler) };
+                    handler(context);
+                }
+            }
+
+            PENDING_REARM.with(|pending| pending.set(page));
+            context.EFlags |= TRAP_FLAG;
+            EXCEPTION_CONTINUE_EXECUTION
+        }
+        STATUS_SINGLE_STEP => {
+            let page = PENDING_REARM.with(|pending| pending.replace(0));
+            if page == 0 {
+                return EXCEPTION_CONTINUE_SEARCH;
+            }
+
+            // Only re-arm if a hook on the page survived the step
+            if let Some(protect) = shared_page_protect(GUARD_HOOK_SLOTS, page) {
+                let _ = guard_page(page, protect);
+            }
+            EXCEPTION_CONTINUE_EXECUTION
+        }
+        _ => EXCEPTION_CONTINUE_SEARCH,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_page_of_aligns_down() {
+        assert_eq!(page_of(0x1_4000_1234), 0x1_4000_1000);
+        assert_eq!(page_of(0x1000), 0x1000);
+    }
+
+    #[test]
+    fn test_apply_rejects_null_target() {
+        fn noop(_: &mut winapi::um::winnt::CONTEXT) {}
+        assert!(matches!(
+            GuardPageHook::apply(0, noop),
+            Err(AppError::MemoryAccessViolation { address: 0 })
+        ));
+    }
+}
@@ -0,0 +1,152 @@
+//! Bounded history of recent hook invocations
+//!
+//! Keeps the last few calls so the pak that preceded a crash can be read
+//! back from the log. Recording never waits: an entry is dropped if another
+//! thread holds the buffer.
+
+use std::collections::VecDeque;
+use std::fmt;
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+use winapi::um::processthreadsapi::GetCurrentThreadId;
+
+use crate::logger::Logger;
+
+/// One recorded hook invocation
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvocationRecord {
+    pub timestamp: SystemTime,
+    /// Pak name read from the arguments, if it could be read
+    pub pak_name: Option<String>,
+    pub thread_id: u32,
+    /// Value the hook returned to the game
+    pub result: usize,
+}
+
+impl fmt::Display for InvocationRecord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let since_epoch = self
+            .timestamp
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default();
+        write!(
+            f,
+            "[{}.{:03}] thread {} '{}' -> {:#x}",
+            since_epoch.as_secs(),
+            since_epoch.subsec_millis(),
+            self.thread_id,
+            self.pak_name.as_deref().unwrap_or("<unreadable>"),
+            self.result
+        )
+    }
+}
+
+/// Ring buffer of the most recent invocations of one hook
+#[derive(Debug)]
+pub struct InvocationHistory {
+    entries: Mutex<VecDeque<InvocationRecord>>,
+    capacity: usize,
+}
+
+impl InvocationHistory {
+    /// Create an empty history keeping at most `capacity` entries
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            entries: Mutex::new(VecDeque::with_capacity(capacity)),
+            capacity,
+        }
+    }
+
+    /// Record a call made on the current thread
+    pub fn record(&self, pak_name: Option<&str>, result: usize) {
+        self.push(InvocationRecord {
+            timestamp: SystemTime::now(),
+            pak_name: pak_name.map(str::to_string),
+            thread_id: unsafe { GetCurrentThreadId() },
+            result,
+        });
+    }
+
+    /// Append a record, evicting the oldest one when full
+    pub fn push(&self, record: InvocationRecord) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        let Ok(mut entries) = self.entries.try_lock() else {
+            return;
+        };
+        if entries.len() == self.capacity {
+            entries.pop_front();
+        }
+        entries.push_back(record);
+    }
+
+    /// Copy of the recorded entries, oldest first
+    pub fn recent(&self) -> Vec<InvocationRecord> {
+        self.entries.lock().unwrap().iter().cloned().collect()
+    }
+
+    /// Maximum number of entries kept
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Log every recorded entry, oldest first
+    pub fn dump(&self, hook_name: &str) {
+        let recent = self.recent();
+        Logger::hook(&format!(
+            "Last {} invocations of '{}':",
+            recent.len(),
+            hook_name
+        ));
+        for record in &recent {
+            Logger::hook(&format!("  {}", record));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(name: &str) -> InvocationRecord {
+        InvocationRecord {
+            timestamp: SystemTime::UNIX_EPOCH,
+            pak_name: Some(name.to_string()),
+            thread_id: 7,
+            result: 1,
+        }
+    }
+
+    #[test]
+    fn test_history_evicts_oldest_entries() {
+        let history = InvocationHistory::new(2);
+        history.push(record("a.pak"));
+        history.push(record("b.pak"));
+        history.push(record("c.pak"));
+
+        let names: Vec<_> = history
+            .recent()
+            .into_iter()
+            .filter_map(|record| record.pak_name)
+            .collect();
+        assert_eq!(names, ["b.pak", "c.pak"]);
+    }
+
+    #[test]
+    fn test_zero_capacity_history_stays_empty() {
+        let history = InvocationHistory::new(0);
+        history.push(record("a.pak"));
+        assert!(history.recent().is_empty());
+    }
+
+    #[test]
+    fn test_record_display() {
+        assert_eq!(
+            record("a.pak").to_string(),
+            "[0.000] thread 7 'a.pak' -> 0x1"
+        );
+    }
+}
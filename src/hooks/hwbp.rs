@@ -0,0 +1,390 @@
+//! Hardware breakpoint hooks
+//!
+//! Arms one of the DR0-DR3 debug registers on every thread and handles the
+//! resulting single-step exception in a vectored exception handler. No byte
+//! of the target is written, so the hook survives code page hashing.
+//!
+//! Debug registers are per thread: threads created after `apply` are not
+//! covered, and the calling thread cannot change its own context, so the
+//! hook must be applied from a thread that never runs the target.
+
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use winapi::shared::minwindef::DWORD;
+use winapi::um::errhandlingapi::{AddVectoredExceptionHandler, RemoveVectoredExceptionHandler};
+use winapi::um::processthreadsapi::{
+    GetCurrentProcessId, GetCurrentThreadId, GetThreadContext, OpenThread, ResumeThread,
+    SetThreadContext, SuspendThread,
+};
+use winapi::um::tlhelp32::{
+    CreateToolhelp32Snapshot, TH32CS_SNAPTHREAD, THREADENTRY32, Thread32First, Thread32Next,
+};
+use winapi::um::winnt::{
+    CONTEXT, CONTEXT_DEBUG_REGISTERS, EXCEPTION_POINTERS, LONG, STATUS_SINGLE_STEP,
+    THREAD_GET_CONTEXT, THREAD_SET_CONTEXT, THREAD_SUSPEND_RESUME,
+};
+
+use crate::error::{AppError, Result};
+use crate::hooks::HookState;
+use crate::logger::Logger;
+use crate::memory::describe_address;
+use crate::safety::OwnedHandle;
+
+/// Number of hardware breakpoint slots (DR0-DR3)
+pub const HW_BREAKPOINT_SLOTS: usize = 4;
+
+/// Handler run when a hardware breakpoint is hit
+///
+/// The handler may rewrite the thread context, e.g. set `Rax` and return to
+/// the caller by popping the return address into `Rip`. If `Rip` is left at
+/// the target, execution resumes at the original function.
+pub type BreakpointHandler = fn(&mut CONTEXT);
+
+/// `EFLAGS.RF`, suppresses the breakpoint for the next instruction
+const RESUME_FLAG: DWORD = 1 << 16;
+
+pub(super) const EXCEPTION_CONTINUE_EXECUTION: LONG = -1;
+pub(super) const EXCEPTION_CONTINUE_SEARCH: LONG = 0;
+
+/// Target address per slot, 0 when free
+static SLOT_TARGETS: [AtomicUsize; HW_BREAKPOINT_SLOTS] =
+    [const { AtomicUsize::new(0) }; HW_BREAKPOINT_SLOTS];
+
+/// `BreakpointHandler` per slot, stored as an address for lock-free access
+static SLOT_HANDLERS: [AtomicUsize; HW_BREAKPOINT_SLOTS] =
+    [const { AtomicUsize::new(0) }; HW_BREAKPOINT_SLOTS];
+
+/// Serializes claiming and releasing slots so a target is armed at most once
+static SLOT_LOCK: Mutex<()> = Mutex::new(());
+
+/// Handle returned by `AddVectoredExceptionHandler`, `None` when not installed
+static VEH_HANDLE: Mutex<Option<usize>> = Mutex::new(None);
+
+/// `CONTEXT` with the 16-byte alignment `GetThreadContext` requires
+#[repr(C, align(16))]
+pub(super) struct AlignedContext(pub(super) CONTEXT);
+
+/// Hook on a function via a hardware execute breakpoint
+///
+/// The debug register is cleared when the hook is removed or dropped.
+pub struct HwBpHook {
+    index: usize,
+    target: usize,
+    state: Mutex<HookState>,
+}
+
+impl HwBpHook {
+    /// Arm a free debug register on `target` in every other thread
+    pub fn apply(target: usize, handler: BreakpointHandler) -> Result<Self> {
+        if target == 0 {
+            return Err(AppError::MemoryAccessViolation { address: 0 });
+        }
+
+        install_exception_handler()?;
+        let index = claim_slot(target, handler)?;
+
+        Logger::hook(&format!(
+            "Arming hardware breakpoint DR{} at {}",
+            index,
+            describe_address(target)
+        ));
+        match set_breakpoint_all_threads(index, Some(target)) {
+            Ok(update) => {
+                Logger::success(&format!(
+                    "Hardware breakpoint DR{} armed on {} threads",
+                    index, update.updated
+                ));
+                Ok(Self {
+                    index,
+                    target,
+                    state: Mutex::new(HookState::Applied),
+                })
+            }
+            Err(e) => {
+                // The walk fails before touching any thread, so nothing is armed
+                release_slot(index);
+                Logger::error(&format!("Failed to arm DR{}: {}", index, e));
+                Err(e)
+            }
+        }
+    }
+
+    /// Clear the debug register in every other thread and free the slot
+    ///
+    /// If any thread could not be cleared the hook stays applied, keeping
+    /// the slot and its handler for the breakpoints still armed, and the
+    /// remove can be retried.
+    pub fn remove(&self) -> Result<()> {
+        let mut state = self.state.lock().unwrap();
+        if *state != HookState::Applied {
+            return Err(AppError::HookFailed {
+                message: format!("Cannot remove hardware breakpoint in state: {:?}", *state),
+            });
+        }
+
+        let update = set_breakpoint_all_threads(self.index, None)?;
+        if update.failed > 0 {
+            return Err(AppError::HookFailed {
+                message: format!(
+                    "DR{} is still armed on {} threads, keeping the slot",
+                    self.index, update.failed
+                ),
+            });
+        }
+        release_slot(self.index);
+        *state = HookState::Removed;
+
+        Logger::success(&format!(
+            "Hardware breakpoint DR{} removed from {}",
+            self.index,
+            describe_address(self.target)
+        ));
+        Ok(())
+    }
+
+    /// Debug register index (0-3) used by this hook
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// Address the breakpoint is armed on
+    pub fn target(&self) -> usize {
+        self.target
+    }
+
+    /// Get the current hook state
+    pub fn state(&self) -> HookState {
+        *self.state.lock().unwrap()
+    }
+
+    /// Check if the hook is currently active
+    pub fn is_active(&self) -> bool {
+        self.state() == HookState::Applied
+    }
+}
+
+impl Drop for HwBpHook {
+    fn drop(&mut self) {
+        if self.is_active()
+            && let Err(e) = self.remove()
+        {
+            Logger::error(&format!("Failed to clear DR{} on drop: {}", self.index, e));
+        }
+    }
+}
+
+/// Reserve a free slot for `target`, rejecting a target that is already armed
+fn claim_slot(target: usize, handler: BreakpointHandler) -> Result<usize> {
+    let _guard = SLOT_LOCK.lock().unwrap();
+    if let Some(owner) = SLOT_TARGETS
+        .iter()
+        .position(|slot| slot.load(Ordering::Acquire) == target)
+    {
+        return Err(AppError::HookConflict {
+            address: target,
+            owner: format!("DR{}", owner),
+        });
+    }
+
+    for (index, slot) in SLOT_TARGETS.iter().enumerate() {
+        // No thread has the register armed yet, so the handler can be
+        // published after the slot is taken
+        if slot
+            .compare_exchange(0, target, Ordering::AcqRel, Ordering::Acquire)
+            .is_ok()
+        {
+            SLOT_HANDLERS[index].store(handler as usize, Ordering::Release);
+            return Ok(index);
+        }
+    }
+
+    Err(AppError::HookFailed {
+        message: "All hardware breakpoint slots are in use".to_string(),
+    })
+}
+
+fn release_slot(index: usize) {
+    let _guard = SLOT_LOCK.lock().unwrap();
+    SLOT_TARGETS[index].store(0, Ordering::Release);
+}
+
+/// Set or clear the local execute breakpoint for `index` in a DR7 value
+fn dr7_with(dr7: u64, index: usize, enabled: bool) -> u64 {
+    // Local enable bit, then the 2-bit condition and 2-bit length fields;
+    // condition 00 (execute) and length 00 (1 byte) are what we want
+    let enable = 1u64 << (index * 2);
+    let condition_and_len = 0b1111u64 << (16 + index * 4);
+
+    let cleared = dr7 & !enable & !condition_and_len;
+    if enabled { cleared | enable } else { cleared }
+}
+
+fn set_debug_register(context: &mut CONTEXT, index: usize, address: usize) {
+    let address = address as u64;
+    match index {
+        0 => context.Dr0 = address,
+        1 => context.Dr1 = address,
+        2 => context.Dr2 = address,
+        _ => context.Dr3 = address,
+    }
+}
+
+/// Outcome of updating a debug register across threads
+struct ThreadUpdate {
+    updated: usize,
+    failed: usize,
+}
+
+/// Update debug register `index` in every thread except the current one
+///
+/// Threads that exit or refuse access during the walk are skipped with a
+/// warning and counted in `failed`.
+fn set_breakpoint_all_threads(index: usize, address: Option<usize>) -> Result<ThreadUpdate> {
+    let snapshot = unsafe { OwnedHandle::from_raw(CreateToolhelp32Snapshot(TH32CS_SNAPTHREAD, 0)) }
+        .ok_or_else(|| AppError::last_error("CreateToolhelp32Snapshot"))?;
+
+    let process_id = unsafe { GetCurrentProcessId() };
+    let current_thread = unsafe { GetCurrentThreadId() };
+    let mut entry: THREADENTRY32 = unsafe { std::mem::zeroed() };
+    entry.dwSize = std::mem::size_of::<THREADENTRY32>() as DWORD;
+
+    let mut update = ThreadUpdate {
+        updated: 0,
+        failed: 0,
+    };
+    let mut more = unsafe { Thread32First(snapshot.as_raw(), &mut entry) } != 0;
+    while more {
+        if entry.th32OwnerProcessID == process_id && entry.th32ThreadID != current_thread {
+            match set_thread_breakpoint(entry.th32ThreadID, index, address) {
+                Ok(()) => update.updated += 1,
+                Err(e) => {
+                    update.failed += 1;
+                    Logger::warning(&format!(
+                        "Skipping thread {} for DR{}: {}",
+                        entry.th32ThreadID, index, e
+                    ));
+                }
+            }
+        }
+        more = unsafe { Thread32Next(snapshot.as_raw(), &mut entry) } != 0;
+    }
+
+    Ok(update)
+}
+
+fn set_thread_breakpoint(thread_id: DWORD, index: usize, address: Option<usize>) -> Result<()> {
+    let access = THREAD_GET_CONTEXT | THREAD_SET_CONTEXT | THREAD_SUSPEND_RESUME;
+    let handle = unsafe { OwnedHandle::from_raw(OpenThread(access, 0, thread_id)) }
+        .ok_or_else(|| AppError::last_error("OpenThread"))?;
+    let thread = handle.as_raw();
+
+    unsafe {
+        SuspendThread(thread);
+
+        let mut context: AlignedContext = std::mem::zeroed();
+        context.0.ContextFlags = CONTEXT_DEBUG_REGISTERS;
+
+        let result = if GetThreadContext(thread, &mut context.0) == 0 {
+            Err(AppError::last_error("GetThreadContext"))
+        } else {
+            set_debug_register(&mut context.0, index, address.unwrap_or(0));
+            context.0.Dr7 = dr7_with(context.0.Dr7, index, address.is_some());
+            if SetThreadContext(thread, &context.0) == 0 {
+                Err(AppError::last_error("SetThreadContext"))
+            } else {
+                Ok(())
+            }
+        };
+
+        ResumeThread(thread);
+        result
+    }
+}
+
+fn install_exception_handler() -> Result<()> {
+    let mut installed = VEH_HANDLE.lock().unwrap();
+    if installed.is_some() {
+        return Ok(());
+    }
+
+    let handle = unsafe { AddVectoredExceptionHandler(1, Some(breakpoint_exception_handler)) };
+    if handle.is_null() {
+        return Err(AppError::HookFailed {
+            message: "AddVectoredExceptionHandler failed".to_string(),
+        });
+    }
+    *installed = Some(handle as usize);
+    Ok(())
+}
+
+/// Unregister the exception handler, before unloading the module that
+/// installed hardware breakpoint hooks
+pub fn remove_exception_handler() {
+    if let Some(handle) = VEH_HANDLE.lock().unwrap().take() {
+        unsafe { RemoveVectoredExceptionHandler(handle as _) };
+    }
+}
+
+/// Dispatch single-step exceptions raised by our breakpoints
+unsafe extern "system" fn breakpoint_exception_handler(info: *mut EXCEPTION_POINTERS) -> LONG {
+    let (record, context) = unsafe { (&*(*info).ExceptionRecord, &mut *(*info).ContextRecord) };
+    if record.ExceptionCode != STATUS_SINGLE_STEP {
+        return EXCEPTION_CONTINUE_SEARCH;
+    }
+
+    let address = record.ExceptionAddress as usize;
+    let Some(index) = SLOT_TARGETS
+        .iter()
+        .position(|slot| slot.load(Ordering::Acquire) == address)
+    else {
+        return EXCEPTION_CONTINUE_SEARCH;
+    };
+
+    let handler = SLOT_HANDLERS[index].load(Ordering::Acquire);
+    if handler != 0 {
+        let handler: BreakpointHandler = unsafe { std::mem::transmute(handler) };
+        handler(context);
+    }
+
+    // Step over the breakpoint if the handler let the target run
+    if context.Rip as usize == address {
+        context.EFlags |= RESUME_FLAG;
+    }
+    EXCEPTION_CONTINUE_EXECUTION
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dr7_sets_and_clears_local_enable() {
+        let armed = dr7_with(0, 2, true);
+        assert_eq!(armed, 1 << 4);
+
+        // Existing condition bits for the slot are reset to execute
+        let stale = (0b0111 << 24) | (1 << 4) | 1;
+        assert_eq!(dr7_with(stale, 2, false), 1);
+        assert_eq!(dr7_with(stale, 2, true), (1 << 4) | 1);
+    }
+
+    #[test]
+    fn test_set_debug_register_by_index() {
+        let mut context: CONTEXT = unsafe { std::mem::zeroed() };
+        set_debug_register(&mut context, 1, 0x1400);
+        set_debug_register(&mut context, 3, 0x2800);
+        assert_eq!(context.Dr1, 0x1400);
+        assert_eq!(context.Dr3, 0x2800);
+        assert_eq!(context.Dr0, 0);
+    }
+
+    #[test]
+    fn test_apply_rejects_null_target() {
+        fn noop(_: &mut CONTEXT) {}
+        assert!(matches!(
+            HwBpHook::apply(0, noop),
+            Err(AppError::MemoryAccessViolation { address: 0 })
+        ));
+    }
+}
@@ -0,0 +1,226 @@
+//! Import address table hooks
+//!
+//! Redirects one imported function of a module by rewriting its IAT slot.
+//! Nothing in the module's code changes, so the hook is invisible to
+//! integrity checks that hash executable sections.
+
+use std::sync::Mutex;
+
+use winapi::shared::minwindef::HMODULE;
+
+use crate::error::{AppError, Result};
+use crate::hooks::HookState;
+use crate::logger::Logger;
+use crate::memory::ModuleScanner;
+use crate::safety::MemoryAccess;
+
+/// Hook on a single import-address-table slot
+///
+/// The slot is restored when the hook is removed or dropped.
+pub struct IatHook {
+    slot: *mut usize,
+    dll: String,
+    function: String,
+    state: Mutex<HookState>,
+    /// Imported address and the replacement written over it
+    patch: Mutex<Option<(usize, usize)>>,
+}
+
+impl IatHook {
+    /// Resolve the IAT slot `module` uses for `dll!function`
+    pub fn new(module: HMODULE, dll: &str, function: &str) -> Result<Self> {
+        let slot = ModuleScanner::with_caching(false).find_import_slot(module, dll, function)?;
+        Ok(Self::with_slot(slot, dll, function))
+    }
+
+    /// Wrap an already resolved IAT slot
+    pub(crate) fn with_slot(slot: *mut usize, dll: &str, function: &str) -> Self {
+        Self {
+            slot,
+            dll: dll.to_string(),
+            function: function.to_string(),
+            state: Mutex::new(HookState::Uninitialized),
+            patch: Mutex::new(None),
+        }
+    }
+
+    /// Point the slot at `replacement`, saving the imported address
+    pub fn apply(&self, replacement: usize) -> Result<()> {
+        let mut state = self.state.lock().unwrap();
+        if *state == HookState::Applied {
+            return Err(AppError::HookFailed {
+                message: format!("IAT hook on {} is already applied", self.name()),
+            });
+        }
+
+        Logger::hook(&format!(
+            "Patching IAT slot {:p} for {} -> {:#x}",
+            self.slot,
+            self.name(),
+            replacement
+        ));
+
+        match unsafe { MemoryAccess::swap_pointer(self.slot, replacement) } {
+            Ok(original) => {
+                *self.patch.lock().unwrap() = Some((original, replacement));
+                *state = HookState::Applied;
+                Logger::success(&format!(
+                    "IAT hook applied to {} (original {:#x})",
+                    self.name(),
+                    original
+                ));
+                Ok(())
+            }
+            Err(e) => {
+                *state = HookState::Failed;
+                Logger::error(&format!(
+                    "Failed to patch IAT slot for {}: {}",
+                    self.name(),
+                    e
+                ));
+                Err(e)
+            }
+        }
+    }
+
+    /// Write the imported address back into the slot
+    pub fn remove(&self) -> Result<()> {
+        let mut state = self.state.lock().unwrap();
+        if *state != HookState::Applied {
+            return Err(AppError::HookFailed {
+                message: format!("Cannot remove IAT hook in state: {:?}", *state),
+            });
+        }
+
+        let Some((original, replacement)) = *self.patch.lock().unwrap() else {
+            return Err(AppError::HookFailed {
+                message: format!("No original address saved for {}", self.name()),
+            });
+        };
+
+        let replaced = unsafe { MemoryAccess::swap_pointer(self.slot, original)? };
+        if replaced != replacement {
+            Logger::warning(&format!(
+                "IAT slot for {} was changed by someone else before removal",
+                self.name()
+            ));
+        }
+
+        *self.patch.lock().unwrap() = None;
+        *state = HookState::Removed;
+        Logger::success(&format!("IAT hook removed from {}", self.name()));
+        Ok(())
+    }
+
+    /// Address of the real import, for calling through from the replacement
+    pub fn original(&self) -> Option<usize> {
+        self.patch.lock().unwrap().map(|(original, _)| original)
+    }
+
+    /// Address of the patched IAT slot
+    pub fn slot(&self) -> *mut usize {
+        self.slot
+    }
+
+    /// Get the current hook state
+    pub fn state(&self) -> HookState {
+        *self.state.lock().unwrap()
+    }
+
+    /// Check if the hook is currently active
+    pub fn is_active(&self) -> bool {
+        self.state() == HookState::Applied
+    }
+
+    /// `dll!function` name of the hooked import
+    pub fn name(&self) -> String {
+        format!("{}!{}", self.dll, self.function)
+    }
+}
+
+impl Drop for IatHook {
+    fn drop(&mut self) {
+        if self.is_active()
+            && let Err(e) = self.remove()
+        {
+            Logger::error(&format!("Failed to restore IAT slot on drop: {}", e));
+        }
+    }
+}
+
+// The slot pointer is only written through MemoryAccess::swap_pointer
+unsafe impl Send for IatHook {}
+unsafe impl Sync for IatHook {}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use winapi::shared::minwindef::DWORD;
+    use winapi::um::processthreadsapi::GetCurrentThreadId;
+
+    use super::*;
+    use crate::memory::module_for_address;
+
+    #[test]
+    fn test_new_iat_hook_is_uninitialized() {
+        let mut slot = 0x1234usize;
+        let hook = IatHook::with_slot(&mut slot, "KERNEL32.dll", "CreateFileW");
+        assert_eq!(hook.state(), HookState::Uninitialized);
+        assert!(hook.original().is_none());
+        assert_eq!(hook.name(), "KERNEL32.dll!CreateFileW");
+    }
+
+    #[test]
+    fn test_remove_requires_applied_iat_hook() {
+        let mut slot = 0x1234usize;
+        let hook = IatHook::with_slot(&mut slot, "KERNEL32.dll", "CreateFileW");
+        assert!(hook.remove().is_err());
+        assert_eq!(slot, 0x1234);
+    }
+
+    #[test]
+    fn test_apply_rejects_null_slot() {
+        let hook = IatHook::with_slot(std::ptr::null_mut(), "KERNEL32.dll", "CreateFileW");
+        assert!(matches!(
+            hook.apply(0x5678),
+            Err(AppError::MemoryAccessViolation { address: 0 })
+        ));
+        assert_eq!(hook.state(), HookState::Failed);
+    }
+
+    #[test]
+    fn test_iat_hook_round_trip_on_a_real_import() {
+        static ORIGINAL: AtomicUsize = AtomicUsize::new(0);
+        static CALLS: AtomicUsize = AtomicUsize::new(0);
+
+        // Forwards to the real import, so other test threads still get
+        // their own id while the slot is patched
+        unsafe extern "system" fn counted_thread_id() -> DWORD {
+            CALLS.fetch_add(1, Ordering::SeqCst);
+            let original: unsafe extern "system" fn() -> DWORD =
+                unsafe { std::mem::transmute(ORIGINAL.load(Ordering::SeqCst)) };
+            unsafe { original() }
+        }
+
+        let exe =
+            module_for_address(test_iat_hook_round_trip_on_a_real_import as fn() as usize).unwrap();
+        let hook = IatHook::new(exe.base as HMODULE, "KERNEL32.dll", "GetCurrentThreadId").unwrap();
+        let imported = unsafe { *hook.slot() };
+        ORIGINAL.store(imported, Ordering::SeqCst);
+        let thread_id = unsafe { GetCurrentThreadId() };
+
+        let replacement = counted_thread_id as unsafe extern "system" fn() -> DWORD as usize;
+        hook.apply(replacement).unwrap();
+        assert_eq!(unsafe { *hook.slot() }, replacement);
+        assert_eq!(hook.original(), Some(imported));
+        let calls = CALLS.load(Ordering::SeqCst);
+        assert_eq!(unsafe { GetCurrentThreadId() }, thread_id);
+        assert!(CALLS.load(Ordering::SeqCst) > calls);
+
+        hook.remove().unwrap();
+        assert_eq!(unsafe { *hook.slot() }, imported);
+        assert_eq!(hook.state(), HookState::Removed);
+        assert!(hook.original().is_none());
+    }
+}
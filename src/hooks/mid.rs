@@ -0,0 +1,184 @@
+//! Mid-function instrumentation hooks
+//!
+//! Runs a closure at any instruction boundary, inside a function or at its
+//! start, with the live registers, then continues with the displaced
+//! instructions. Nothing is replaced, so the instrumented code behaves as
+//! before apart from whatever the closure changes in the registers.
+
+use std::sync::Mutex;
+
+use ilhook::x64::Registers;
+
+use crate::error::{AppError, Result};
+use crate::hooks::HookState;
+use crate::hooks::backend::{BackendRoutine, HookBackend, HookBackendKind, IlhookBackend};
+use crate::logger::Logger;
+use crate::memory::describe_address;
+
+/// Closure run at the instrumented address
+pub type MidHookCallback = dyn Fn(&mut Registers) + Send + Sync;
+
+/// Jmp-back hook at an arbitrary instruction
+///
+/// The hook is detached when dropped.
+pub struct MidFunctionHook {
+    address: usize,
+    backend: Mutex<IlhookBackend>,
+    state: Mutex<HookState>,
+    // Boxed twice so the user data handed to ilhook is a thin pointer that
+    // stays valid for as long as the hook exists
+    callback: Box<Box<MidHookCallback>>,
+}
+
+impl MidFunctionHook {
+    /// Instrument the instruction at `address`
+    ///
+    /// `address` must be the start of an instruction, and the instructions
+    /// displaced by the jump must not be branch targets.
+    pub fn attach(
+        address: usize,
+        callback: impl Fn(&mut Registers) + Send + Sync + 'static,
+    ) -> Result<Self> {
+        if address == 0 {
+            return Err(AppError::MemoryAccessViolation { address: 0 });
+        }
+
+        let hook = Self {
+            address,
+            backend: Mutex::new(IlhookBackend::new(HookBackendKind::IlhookJmpBack)),
+            state: Mutex::new(HookState::Uninitialized),
+            callback: Box::new(Box::new(callback)),
+        };
+
+        let user_data = &*hook.callback as *const Box<MidHookCallback> as usize;
+        let result = hook
+            .backend
+            .lock()
+            .unwrap()
+            .apply(address, BackendRoutine::Observe(dispatch, user_data));
+
+        let mut state = hook.state.lock().unwrap();
+        match result {
+            Ok(()) => {
+                *state = HookState::Applied;
+                Logger::hook(&format!(
+                    "Mid-function hook attached at {}",
+                    describe_address(address)
+                ));
+            }
+            Err(e) => {
+                *state = HookState::Failed;
+                Logger::error(&format!(
+                    "Failed to attach mid-function hook at {}: {}",
+                    describe_address(address),
+                    e
+                ));
+                return Err(e);
+            }
+        }
+        drop(state);
+
+        Ok(hook)
+    }
+
+    /// Remove the instrumentation
+    pub fn detach(&self) -> Result<()> {
+        let mut state = self.state.lock().unwrap();
+        if *state != HookState::Applied {
+            return Err(AppError::HookFailed {
+                message: format!("Cannot detach mid-function hook in state: {:?}", *state),
+            });
+        }
+
+        self.backend.lock().unwrap().remove()?;
+        *state = HookState::Removed;
+        Logger::hook(&format!(
+            "Mid-function hook detached from {}",
+            describe_address(self.address)
+        ));
+        Ok(())
+    }
+
+    /// Instrumented address
+    pub fn address(&self) -> usize {
+        self.address
+    }
+
+    /// Get the current hook state
+    pub fn state(&self) -> HookState {
+        *self.state.lock().unwrap()
+    }
+
+    /// Check if the hook is currently active
+    pub fn is_active(&self) -> bool {
+        self.state() == HookState::Applied
+    }
+}
+
+impl Drop for MidFunctionHook {
+    fn drop(&mut self) {
+        if self.is_active()
+            && let Err(e) = self.detach()
+        {
+            // The callback is freed with the hook, so the patch must not outlive it
+            Logger::error(&format!(
+                "Failed to detach mid-function hook at {}: {}; leaking its callback",
+                describe_address(self.address),
+                e
+            ));
+            std::mem::forget(std::mem::replace(
+                &mut self.callback,
+                Box::new(Box::new(|_: &mut Registers| {})),
+            ));
+        }
+    }
+}
+
+// The callback is Send + Sync and the backend is only touched under its lock
+unsafe impl Send for MidFunctionHook {}
+unsafe impl Sync for MidFunctionHook {}
+
+/// Forward an ilhook jmp-back call to the hook's closure
+unsafe extern "win64" fn dispatch(registers: *mut Registers, user_data: usize) {
+    let callback = unsafe { &*(user_data as *const Box<MidHookCallback>) };
+    let _ = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        callback(unsafe { &mut *registers })
+    }));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicU64, Ordering};
+
+    #[test]
+    fn test_attach_rejects_null_address() {
+        assert!(matches!(
+            MidFunctionHook::attach(0, |_| {}),
+            Err(AppError::MemoryAccessViolation { address: 0 })
+        ));
+    }
+
+    #[test]
+    fn test_dispatch_runs_callback_with_registers() {
+        let seen = Arc::new(AtomicU64::new(0));
+        let sink = Arc::clone(&seen);
+        let callback: Box<Box<MidHookCallback>> = Box::new(Box::new(move |registers| {
+            sink.store(registers.rdx, Ordering::SeqCst);
+            registers.rax = 1;
+        }));
+
+        let mut registers: Registers = unsafe { std::mem::zeroed() };
+        registers.rdx = 0x1234;
+        unsafe {
+            dispatch(
+                &mut registers,
+                &*callback as *const Box<MidHookCallback> as usize,
+            )
+        };
+
+        assert_eq!(seen.load(Ordering::SeqCst), 0x1234);
+        assert_eq!(registers.rax, 1);
+    }
+}
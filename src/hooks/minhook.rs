@@ -0,0 +1,261 @@
+//! MinHook backend, behind the `minhook` feature
+//!
+//! MinHook detours have the target's own signature, while hook routines take
+//! a register context, so each hook is bound to one of [`MINHOOK_SLOTS`]
+//! detours that rebuild a [`Registers`] from their arguments. Only rcx, rdx,
+//! r8, r9 and the next four stack arguments are captured; xmm registers and
+//! the return address read as zero.
+//!
+//! Unlike interceptor-rs, MinHook can unhook and free its trampoline, and
+//! can disable a hook while keeping the trampoline for `detach`.
+
+use std::ffi::c_void;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use ilhook::x64::Registers;
+use minhook::MinHook;
+
+use crate::error::{AppError, Result};
+use crate::hooks::HookReplacement;
+use crate::hooks::backend::{BackendRoutine, HookBackend, HookBackendKind, WriteGate, unsupported};
+
+/// Number of functions that can be hooked through MinHook at once
+pub const MINHOOK_SLOTS: usize = 8;
+
+/// Detour signature: four register and four stack arguments
+type Detour =
+    unsafe extern "win64" fn(usize, usize, usize, usize, usize, usize, usize, usize) -> usize;
+
+/// Routine and trampoline bound to one detour; 0 marks a free slot
+struct Slot {
+    routine: AtomicUsize,
+    trampoline: AtomicUsize,
+}
+
+#[allow(clippy::declare_interior_mutable_const)]
+const FREE_SLOT: Slot = Slot {
+    routine: AtomicUsize::new(0),
+    trampoline: AtomicUsize::new(0),
+};
+
+static SLOTS: [Slot; MINHOOK_SLOTS] = [FREE_SLOT; MINHOOK_SLOTS];
+
+const DETOURS: [Detour; MINHOOK_SLOTS] = [
+    detour::<0>,
+    detour::<1>,
+    detour::<2>,
+    detour::<3>,
+    detour::<4>,
+    detour::<5>,
+    detour::<6>,
+    detour::<7>,
+];
+
+/// Call the routine bound to slot `SLOT` with a rebuilt register context
+unsafe extern "win64" fn detour<const SLOT: usize>(
+    rcx: usize,
+    rdx: usize,
+    r8: usize,
+    r9: usize,
+    arg4: usize,
+    arg5: usize,
+    arg6: usize,
+    arg7: usize,
+) -> usize {
+    let slot = &SLOTS[SLOT];
+    let routine: HookReplacement =
+        unsafe { std::mem::transmute(slot.routine.load(Ordering::Acquire)) };
+
+    // Stack laid out as at the function entry: return address, shadow
+    // space, then the stack arguments
+    let stack: [usize; 9] = [0, 0, 0, 0, 0, arg4, arg5, arg6, arg7];
+    let mut registers: Registers = unsafe { std::mem::zeroed() };
+    registers.rcx = rcx as u64;
+    registers.rdx = rdx as u64;
+    registers.r8 = r8 as u64;
+    registers.r9 = r9 as u64;
+    registers.rsp = stack.as_ptr() as u64;
+
+    unsafe { routine(&mut registers, slot.trampoline.load(Ordering::Acquire), 0) }
+}
+
+/// Claim a free slot for `routine`
+fn claim_slot(routine: HookReplacement) -> Option<usize> {
+    SLOTS.iter().position(|slot| {
+        slot.routine
+            .compare_exchange(0, routine as usize, Ordering::AcqRel, Ordering::Relaxed)
+            .is_ok()
+    })
+}
+
+fn release_slot(index: usize) {
+    SLOTS[index].trampoline.store(0, Ordering::Release);
+    SLOTS[index].routine.store(0, Ordering::Release);
+}
+
+fn minhook_error(action: &str, status: minhook::MH_STATUS) -> AppError {
+    AppError::HookFailed {
+        message: format!("MinHook failed to {}: {:?}", action, status),
+    }
+}
+
+/// MinHook backend for replacement routines
+pub struct MinHookBackend {
+    target_address: Option<usize>,
+    slot: Option<usize>,
+    trampoline: Option<usize>,
+}
+
+impl MinHookBackend {
+    pub fn new() -> Self {
+        Self {
+            target_address: None,
+            slot: None,
+            trampoline: None,
+        }
+    }
+
+    /// Create the hook, then enable it with the write bracketed by `gate`
+    fn hook(
+        &mut self,
+        target_address: usize,
+        routine: BackendRoutine,
+        gate: Option<&dyn WriteGate>,
+    ) -> Result<()> {
+        let BackendRoutine::Replace(replacement) = routine else {
+            return Err(unsupported(self.kind(), &routine));
+        };
+        if self.target_address.is_some() {
+            return Err(AppError::HookFailed {
+                message: "MinHook backend is already hooked".to_string(),
+            });
+        }
+
+        let slot = claim_slot(replacement).ok_or_else(|| AppError::HookFailed {
+            message: format!("All {} MinHook detours are in use", MINHOOK_SLOTS),
+        })?;
+        let target = target_address as *mut c_void;
+
+        let trampoline = match unsafe { MinHook::create_hook(target, DETOURS[slot] as *mut c_void) }
+        {
+            Ok(trampoline) => trampoline as usize,
+            Err(status) => {
+                release_slot(slot);
+                return Err(minhook_error("create the hook", status));
+            }
+        };
+        // The detour can run as soon as the hook is enabled
+        SLOTS[slot].trampoline.store(trampoline, Ordering::Release);
+
+        let enabled = match gate {
+            Some(gate) if !gate.before_write() => Err(AppError::HookFailed {
+                message: "The hook's write gate refused the patch".to_string(),
+            }),
+            Some(gate) => {
+                let enabled = unsafe { MinHook::enable_hook(target) };
+                gate.after_write();
+                enabled.map_err(|status| minhook_error("enable the hook", status))
+            }
+            None => unsafe { MinHook::enable_hook(target) }
+                .map_err(|status| minhook_error("enable the hook", status)),
+        };
+        if let Err(e) = enabled {
+            let _ = unsafe { MinHook::remove_hook(target) };
+            release_slot(slot);
+            return Err(e);
+        }
+
+        self.target_address = Some(target_address);
+        self.slot = Some(slot);
+        self.trampoline = Some(trampoline);
+        Ok(())
+    }
+}
+
+impl Default for MinHookBackend {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl HookBackend for MinHookBackend {
+    fn kind(&self) -> HookBackendKind {
+        HookBackendKind::MinHook
+    }
+
+    fn apply(&mut self, target_address: usize, routine: BackendRoutine) -> Result<()> {
+        self.hook(target_address, routine, None)
+    }
+
+    fn apply_gated(
+        &mut self,
+        target_address: usize,
+        routine: BackendRoutine,
+        gate: Arc<dyn WriteGate>,
+    ) -> Result<()> {
+        self.hook(target_address, routine, Some(gate.as_ref()))
+    }
+
+    fn remove(&mut self) -> Result<()> {
+        let (Some(target_address), Some(slot)) = (self.target_address, self.slot) else {
+            return Err(AppError::HookFailed {
+                message: "MinHook backend is not hooked".to_string(),
+            });
+        };
+
+        unsafe { MinHook::remove_hook(target_address as *mut c_void) }
+            .map_err(|status| minhook_error("remove the hook", status))?;
+        release_slot(slot);
+        self.target_address = None;
+        self.slot = None;
+        self.trampoline = None;
+        Ok(())
+    }
+
+    fn detach(&mut self) -> Result<()> {
+        let Some(target_address) = self.target_address else {
+            return Err(AppError::HookFailed {
+                message: "MinHook backend is not hooked".to_string(),
+            });
+        };
+
+        // Disabling keeps the trampoline and the slot for calls still inside
+        unsafe { MinHook::disable_hook(target_address as *mut c_void) }
+            .map_err(|status| minhook_error("disable the hook", status))?;
+        self.target_address = None;
+        self.slot = None;
+        self.trampoline = None;
+        Ok(())
+    }
+
+    fn trampoline(&self) -> Option<usize> {
+        self.trampoline
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    unsafe extern "win64" fn echo_rdx(reg: *mut Registers, original: usize, _: usize) -> usize {
+        unsafe { (*reg).rdx as usize + original }
+    }
+
+    #[test]
+    fn test_detour_rebuilds_register_context() {
+        let slot = claim_slot(echo_rdx).unwrap();
+        SLOTS[slot].trampoline.store(0x100, Ordering::Release);
+
+        let result = unsafe { DETOURS[slot](1, 2, 3, 4, 5, 6, 7, 8) };
+        release_slot(slot);
+        assert_eq!(result, 0x102);
+    }
+
+    #[test]
+    fn test_remove_before_apply_fails() {
+        assert!(MinHookBackend::new().remove().is_err());
+        assert!(MinHookBackend::new().detach().is_err());
+        assert!(MinHookBackend::new().trampoline().is_none());
+    }
+}
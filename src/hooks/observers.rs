@@ -0,0 +1,137 @@
+//! Listeners notified when a hook changes state
+//!
+//! External tooling (an IPC server, a status file writer) can react to a
+//! hook becoming applied, failing or being removed instead of polling
+//! [`HookManager::statuses`](crate::hooks::HookManager::statuses).
+//! Listeners run synchronously on the thread making the change, after the
+//! new state is visible, so they may query the hook but should return fast.
+
+use std::sync::RwLock;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::hooks::HookState;
+
+/// One state transition of a hook
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StateChange<'a> {
+    /// Name the hook is registered under, `None` outside a manager
+    pub name: Option<&'a str>,
+    pub from: HookState,
+    pub to: HookState,
+    pub target_address: Option<usize>,
+}
+
+/// Callback run for every state transition
+pub type StateListener = Box<dyn Fn(&StateChange<'_>) + Send + Sync>;
+
+/// Handle for removing a registered listener
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ListenerId(u64);
+
+/// Registered state listeners in registration order
+pub struct StateObservers {
+    listeners: RwLock<Vec<(ListenerId, StateListener)>>,
+    next_id: AtomicU64,
+}
+
+impl StateObservers {
+    pub fn new() -> Self {
+        Self {
+            listeners: RwLock::new(Vec::new()),
+            next_id: AtomicU64::new(0),
+        }
+    }
+
+    /// Add a listener after the existing ones
+    pub fn subscribe(
+        &self,
+        listener: impl Fn(&StateChange<'_>) + Send + Sync + 'static,
+    ) -> ListenerId {
+        let id = ListenerId(self.next_id.fetch_add(1, Ordering::Relaxed));
+        self.listeners
+            .write()
+            .unwrap()
+            .push((id, Box::new(listener)));
+        id
+    }
+
+    /// Remove a listener, returning whether it was registered
+    pub fn unsubscribe(&self, id: ListenerId) -> bool {
+        let mut listeners = self.listeners.write().unwrap();
+        let before = listeners.len();
+        listeners.retain(|(existing, _)| *existing != id);
+        listeners.len() != before
+    }
+
+    /// Number of registered listeners
+    pub fn len(&self) -> usize {
+        self.listeners.read().unwrap().len()
+    }
+
+    /// Check if no listeners are registered
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Run every listener for `change`; unchanged states are not reported
+    pub fn notify(&self, change: &StateChange<'_>) {
+        if change.from == change.to {
+            return;
+        }
+        for (_, listener) in self.listeners.read().unwrap().iter() {
+            listener(change);
+        }
+    }
+}
+
+impl Default for StateObservers {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    fn change(from: HookState, to: HookState) -> StateChange<'static> {
+        StateChange {
+            name: Some("pak_check"),
+            from,
+            to,
+            target_address: Some(0x1000),
+        }
+    }
+
+    #[test]
+    fn test_listeners_see_transitions_in_order() {
+        let observers = StateObservers::new();
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&seen);
+        observers.subscribe(move |change| sink.lock().unwrap().push((change.from, change.to)));
+
+        observers.notify(&change(HookState::Uninitialized, HookState::Applied));
+        observers.notify(&change(HookState::Applied, HookState::Applied));
+        observers.notify(&change(HookState::Applied, HookState::Removed));
+
+        assert_eq!(
+            *seen.lock().unwrap(),
+            [
+                (HookState::Uninitialized, HookState::Applied),
+                (HookState::Applied, HookState::Removed)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_unsubscribe_removes_only_that_listener() {
+        let observers = StateObservers::new();
+        let first = observers.subscribe(|_| {});
+        observers.subscribe(|_| {});
+
+        assert!(observers.unsubscribe(first));
+        assert!(!observers.unsubscribe(first));
+        assert_eq!(observers.len(), 1);
+    }
+}
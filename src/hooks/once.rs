@@ -0,0 +1,73 @@
+//! One-shot hook mode
+//!
+//! For reconnaissance, such as capturing a structure pointer, a hook only
+//! needs to see a single call. An armed hook lets exactly one call through
+//! its routine, which then restores the original bytes; calls racing in
+//! before the restore lands should go straight to the original function.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Whether a hook removes itself after its first call
+#[derive(Debug, Default)]
+pub struct OneShot {
+    armed: AtomicBool,
+    fired: AtomicBool,
+}
+
+impl OneShot {
+    /// Create a disarmed setting; the hook stays until removed
+    pub const fn new() -> Self {
+        Self {
+            armed: AtomicBool::new(false),
+            fired: AtomicBool::new(false),
+        }
+    }
+
+    /// Switch one-shot mode on or off
+    pub fn arm(&self, armed: bool) {
+        self.armed.store(armed, Ordering::Release);
+    }
+
+    /// Check if one-shot mode is on
+    pub fn is_armed(&self) -> bool {
+        self.armed.load(Ordering::Acquire)
+    }
+
+    /// Check if the single call has been taken
+    pub fn has_fired(&self) -> bool {
+        self.fired.load(Ordering::Acquire)
+    }
+
+    /// Claim the single call
+    ///
+    /// Returns `true` for exactly one caller while armed; that caller runs
+    /// the hook body and then removes the hook. Always `false` when disarmed.
+    pub fn fire(&self) -> bool {
+        self.is_armed()
+            && self
+                .fired
+                .compare_exchange(false, true, Ordering::AcqRel, Ordering::Acquire)
+                .is_ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_disarmed_never_fires() {
+        let once = OneShot::new();
+        assert!(!once.fire());
+        assert!(!once.has_fired());
+    }
+
+    #[test]
+    fn test_armed_fires_exactly_once() {
+        let once = OneShot::new();
+        once.arm(true);
+        assert!(once.fire());
+        assert!(!once.fire());
+        assert!(once.has_fired());
+    }
+}
@@ -0,0 +1,104 @@
+//! Declarative hook definitions
+//!
+//! Each hook the DLL can install is described by a [`HookSpec`]: which
+//! signature finds its target, what its routine does with each call and
+//! whether it is installed at all. The specs come from the configuration,
+//! see [`Config::hook_specs`](crate::config::Config::hook_specs).
+
+use std::fmt;
+
+use crate::constants::constants::{PAK_CHECK_HOOK_NAME, SIG_CHECK_HOOK_NAME};
+
+/// Hooks that can be configured, in the order they are installed
+pub const CONFIGURABLE_HOOKS: &[&str] = &[PAK_CHECK_HOOK_NAME, SIG_CHECK_HOOK_NAME];
+
+/// What a hook routine does with each call
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HookAction {
+    /// Return the value without running the original check, as far as
+    /// filters and callbacks allow
+    Spoof(usize),
+    /// Run the original check and only log its result; callbacks cannot
+    /// change it
+    Observe,
+    /// Run the original check, leaving registered callbacks free to decide
+    CallOriginal,
+}
+
+impl HookAction {
+    /// Action for a spoof setting, where `None` runs the original check
+    pub fn from_spoof(value: Option<usize>) -> Self {
+        value.map_or(Self::CallOriginal, Self::Spoof)
+    }
+
+    /// Name used in configuration
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Spoof(_) => "spoof",
+            Self::Observe => "observe",
+            Self::CallOriginal => "call-original",
+        }
+    }
+
+    /// Value the routine returns, `None` when it runs the original check
+    pub fn spoof_value(self) -> Option<usize> {
+        match self {
+            Self::Spoof(value) => Some(value),
+            Self::Observe | Self::CallOriginal => None,
+        }
+    }
+
+    /// Whether the routine only logs the original result
+    pub fn is_observe(self) -> bool {
+        self == Self::Observe
+    }
+}
+
+impl fmt::Display for HookAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Spoof(value) => write!(f, "spoof {:#x}", value),
+            other => f.write_str(other.as_str()),
+        }
+    }
+}
+
+/// One configured hook
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HookSpec {
+    /// Name the hook is registered under, one of [`CONFIGURABLE_HOOKS`]
+    pub name: String,
+    /// Named signature locating the target, or `None` for the hook's own
+    /// pattern keys
+    pub signature: Option<String>,
+    pub action: HookAction,
+    pub enabled: bool,
+}
+
+impl HookSpec {
+    /// An enabled hook taking `action`, located by its own pattern keys
+    pub fn new(name: &str, action: HookAction) -> Self {
+        Self {
+            name: name.to_string(),
+            signature: None,
+            action,
+            enabled: true,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_action_spoof_values() {
+        assert_eq!(HookAction::from_spoof(Some(2)), HookAction::Spoof(2));
+        assert_eq!(HookAction::from_spoof(None), HookAction::CallOriginal);
+        assert_eq!(HookAction::Spoof(2).spoof_value(), Some(2));
+        assert_eq!(HookAction::Observe.spoof_value(), None);
+        assert!(HookAction::Observe.is_observe());
+        assert_eq!(HookAction::Spoof(1).to_string(), "spoof 0x1");
+        assert_eq!(HookAction::CallOriginal.to_string(), "call-original");
+    }
+}
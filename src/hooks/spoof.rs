@@ -0,0 +1,79 @@
+//! Per-hook spoofed return value
+//!
+//! Functions differ in how they report success (`1`, `0`, any non-null
+//! pointer), so the value a hook fakes is set per hook rather than fixed.
+//! It can be changed while the hook is live; routines read it atomically.
+
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+use crate::constants::constants::BYPASS_SUCCESS;
+
+/// Whether a hook fakes its result, and with which value
+#[derive(Debug)]
+pub struct SpoofSetting {
+    enabled: AtomicBool,
+    value: AtomicUsize,
+}
+
+impl SpoofSetting {
+    /// Spoof every call with `value`
+    pub const fn new(value: usize) -> Self {
+        Self {
+            enabled: AtomicBool::new(true),
+            value: AtomicUsize::new(value),
+        }
+    }
+
+    /// Let every call through to the original function
+    pub const fn disabled() -> Self {
+        Self {
+            enabled: AtomicBool::new(false),
+            value: AtomicUsize::new(BYPASS_SUCCESS),
+        }
+    }
+
+    /// Value to return, or `None` to run the original function
+    pub fn value(&self) -> Option<usize> {
+        self.enabled
+            .load(Ordering::Acquire)
+            .then(|| self.value.load(Ordering::Relaxed))
+    }
+
+    /// Change the spoofed value, `None` to stop spoofing
+    pub fn set(&self, value: Option<usize>) {
+        if let Some(value) = value {
+            self.value.store(value, Ordering::Relaxed);
+        }
+        self.enabled.store(value.is_some(), Ordering::Release);
+    }
+}
+
+impl Default for SpoofSetting {
+    fn default() -> Self {
+        Self::new(BYPASS_SUCCESS)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_spoofs_bypass_success() {
+        assert_eq!(SpoofSetting::default().value(), Some(BYPASS_SUCCESS));
+        assert_eq!(SpoofSetting::disabled().value(), None);
+    }
+
+    #[test]
+    fn test_set_switches_value_and_mode() {
+        let spoof = SpoofSetting::default();
+        spoof.set(Some(0));
+        assert_eq!(spoof.value(), Some(0));
+
+        spoof.set(None);
+        assert_eq!(spoof.value(), None);
+
+        spoof.set(Some(0x1400_0000));
+        assert_eq!(spoof.value(), Some(0x1400_0000));
+    }
+}
@@ -0,0 +1,114 @@
+//! Per-hook invocation statistics
+//!
+//! Recorded from inside hook routines on game threads, so the hot path only
+//! touches atomics; the last pak name is dropped rather than waited for when
+//! another thread is updating it.
+
+use std::fmt;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Counters updated every time a hook routine runs
+#[derive(Debug, Default)]
+pub struct HookStats {
+    calls: AtomicU64,
+    /// Milliseconds since the Unix epoch of the last call, 0 if never called
+    last_call_ms: AtomicU64,
+    last_pak: Mutex<Option<String>>,
+}
+
+impl HookStats {
+    pub const fn new() -> Self {
+        Self {
+            calls: AtomicU64::new(0),
+            last_call_ms: AtomicU64::new(0),
+            last_pak: Mutex::new(None),
+        }
+    }
+
+    /// Record one invocation, with the pak name if it could be read
+    pub fn record(&self, pak_name: Option<&str>) {
+        self.calls.fetch_add(1, Ordering::Relaxed);
+
+        let now_ms = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_millis() as u64);
+        self.last_call_ms.store(now_ms, Ordering::Relaxed);
+
+        if let Some(name) = pak_name
+            && let Ok(mut last_pak) = self.last_pak.try_lock()
+        {
+            *last_pak = Some(name.to_string());
+        }
+    }
+
+    /// Number of recorded invocations
+    pub fn calls(&self) -> u64 {
+        self.calls.load(Ordering::Relaxed)
+    }
+
+    /// Copy the current values
+    pub fn snapshot(&self) -> HookStatsSnapshot {
+        let last_call_ms = self.last_call_ms.load(Ordering::Relaxed);
+        HookStatsSnapshot {
+            calls: self.calls(),
+            last_call: (last_call_ms != 0)
+                .then(|| UNIX_EPOCH + Duration::from_millis(last_call_ms)),
+            last_pak: self.last_pak.lock().unwrap().clone(),
+        }
+    }
+}
+
+/// Point-in-time copy of [`HookStats`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HookStatsSnapshot {
+    pub calls: u64,
+    pub last_call: Option<SystemTime>,
+    pub last_pak: Option<String>,
+}
+
+impl fmt::Display for HookStatsSnapshot {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} calls", self.calls)?;
+
+        if let Some(last_call) = self.last_call {
+            let ago = SystemTime::now()
+                .duration_since(last_call)
+                .unwrap_or_default();
+            write!(f, ", last {:.1}s ago", ago.as_secs_f64())?;
+        }
+        if let Some(last_pak) = &self.last_pak {
+            write!(f, ", last pak '{}'", last_pak)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_new_stats_are_empty() {
+        let snapshot = HookStats::new().snapshot();
+        assert_eq!(snapshot.calls, 0);
+        assert!(snapshot.last_call.is_none());
+        assert_eq!(snapshot.to_string(), "0 calls");
+    }
+
+    #[test]
+    fn test_record_counts_calls_and_keeps_last_name() {
+        let stats = HookStats::new();
+        stats.record(Some("pakchunk0-WindowsNoEditor.pak"));
+        stats.record(None);
+
+        let snapshot = stats.snapshot();
+        assert_eq!(snapshot.calls, 2);
+        assert!(snapshot.last_call.is_some());
+        assert_eq!(
+            snapshot.last_pak.as_deref(),
+            Some("pakchunk0-WindowsNoEditor.pak")
+        );
+    }
+}
@@ -0,0 +1,450 @@
+//! Atomic application of several hooks
+//!
+//! Patching hooks one at a time while game threads run can catch a thread
+//! executing the very bytes being rewritten. A transaction stages hooks and
+//! commits them in two phases:
+//!
+//! 1. Each backend allocates its trampoline and writes its patch as usual,
+//!    but through a [`WriteGate`] that suspends the other threads just for
+//!    that write, copies the patch out and puts the original bytes back.
+//! 2. All other threads are suspended, checked to be outside every patch
+//!    window, and the captured patches are written in one go.
+//!
+//! Only plain byte copies and page protection changes run while threads are
+//! suspended: a suspended thread holding the heap or the console lock cannot
+//! stall the commit, as nothing allocates or logs until they are resumed.
+//! If one hook fails, the hooks prepared so far are removed again.
+
+use std::mem;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+use winapi::shared::minwindef::DWORD;
+use winapi::um::processthreadsapi::{
+    GetCurrentProcessId, GetCurrentThreadId, GetThreadContext, OpenThread, ResumeThread,
+    SuspendThread,
+};
+use winapi::um::tlhelp32::{
+    CreateToolhelp32Snapshot, TH32CS_SNAPTHREAD, THREADENTRY32, Thread32First, Thread32Next,
+};
+use winapi::um::winnt::{
+    CONTEXT_CONTROL, THREAD_GET_CONTEXT, THREAD_QUERY_INFORMATION, THREAD_SUSPEND_RESUME,
+};
+
+use crate::constants::constants::memory::{
+    HOOK_BACKUP_LEN, TRANSACTION_RETRY_DELAY_MS, TRANSACTION_SUSPEND_ATTEMPTS,
+};
+use crate::error::{AppError, Result};
+use crate::hooks::hwbp::AlignedContext;
+use crate::hooks::{FunctionHook, HookRoutine, WriteGate};
+use crate::logger::Logger;
+use crate::safety::{MemoryAccess, OwnedHandle};
+
+/// One hook waiting for the transaction to commit
+struct StagedHook<'a> {
+    target: usize,
+    apply: Box<dyn Fn(Arc<dyn WriteGate>) -> Result<()> + 'a>,
+    remove: Box<dyn Fn() -> Result<()> + 'a>,
+}
+
+/// Patch of one prepared hook, ready to be written during the commit
+struct CapturedPatch {
+    target: usize,
+    original: Vec<u8>,
+    patch: Vec<u8>,
+}
+
+/// Set of hooks applied together while other threads are suspended
+///
+/// Nothing is patched until [`commit`](Self::commit); dropping an
+/// uncommitted transaction discards it.
+#[derive(Default)]
+pub struct HookTransaction<'a> {
+    staged: Vec<StagedHook<'a>>,
+}
+
+impl<'a> HookTransaction<'a> {
+    /// Create an empty transaction
+    pub fn new() -> Self {
+        Self { staged: Vec::new() }
+    }
+
+    /// Stage `hook` to be applied over `target_address` on commit
+    pub fn stage<F: HookRoutine>(
+        &mut self,
+        hook: &'a FunctionHook<F>,
+        target_address: usize,
+        routine: F,
+    ) -> &mut Self {
+        self.staged.push(StagedHook {
+            target: target_address,
+            apply: Box::new(move |gate| hook.apply_gated(target_address, routine, gate)),
+            remove: Box::new(move || hook.remove()),
+        });
+        self
+    }
+
+    /// Number of staged hooks
+    pub fn len(&self) -> usize {
+        self.staged.len()
+    }
+
+    /// Check if nothing is staged
+    pub fn is_empty(&self) -> bool {
+        self.staged.is_empty()
+    }
+
+    /// Apply every staged hook with all other threads suspended
+    ///
+    /// Either all hooks end up applied or, on the first failure, the ones
+    /// prepared so far are removed again and the error is returned. Returns
+    /// the number of hooks applied.
+    pub fn commit(self) -> Result<usize> {
+        if self.staged.is_empty() {
+            return Ok(0);
+        }
+
+        // Phase 1: let every backend allocate and build its patch
+        let mut prepared: Vec<&StagedHook<'_>> = Vec::with_capacity(self.staged.len());
+        let mut patches = Vec::with_capacity(self.staged.len());
+        for staged in &self.staged {
+            let gate = Arc::new(CaptureGate::new(staged.target));
+            if let Err(e) = (staged.apply)(gate.clone()) {
+                let cause = gate.take_error().unwrap_or(e);
+                return Err(roll_back(&prepared, hook_failure(staged.target, cause)));
+            }
+            prepared.push(staged);
+            match gate.take_patch() {
+                Ok(patch) => patches.push(patch),
+                Err(e) => return Err(roll_back(&prepared, hook_failure(staged.target, e))),
+            }
+        }
+
+        // Phase 2: write the patches with every other thread suspended
+        let windows: Vec<(usize, usize)> = patches
+            .iter()
+            .map(|patch| (patch.target, patch.target + patch.patch.len()))
+            .collect();
+        let threads = match suspend_outside(&windows) {
+            Ok(threads) => threads,
+            Err(e) => return Err(roll_back(&prepared, e.to_string())),
+        };
+        let suspended = threads.len();
+        let written = write_patches(&patches);
+        drop(threads);
+
+        if let Err((target, e)) = written {
+            return Err(roll_back(&prepared, hook_failure(target, e)));
+        }
+
+        Logger::success(&format!(
+            "Transaction applied {} hooks with {} threads suspended",
+            patches.len(),
+            suspended
+        ));
+        Ok(patches.len())
+    }
+}
+
+/// Remove the `prepared` hooks again, returning the commit's error
+fn roll_back(prepared: &[&StagedHook<'_>], failure: String) -> AppError {
+    for done in prepared.iter().rev() {
+        if let Err(remove_error) = (done.remove)() {
+            Logger::error(&format!(
+                "Rollback of hook at {:#x} failed: {}",
+                done.target, remove_error
+            ));
+        }
+    }
+    AppError::HookFailed {
+        message: format!("Transaction rolled back: {}", failure),
+    }
+}
+
+/// Rollback reason naming the hook at `target`
+fn hook_failure(target: usize, cause: AppError) -> String {
+    format!("hook at {:#x} failed: {}", target, cause)
+}
+
+/// Write every captured patch; on failure, restore the ones already written
+///
+/// Runs while other threads are suspended, so it neither allocates nor logs.
+fn write_patches(patches: &[CapturedPatch]) -> std::result::Result<(), (usize, AppError)> {
+    for (index, patch) in patches.iter().enumerate() {
+        if let Err(e) =
+            unsafe { MemoryAccess::write_bytes_patched(patch.target as *mut u8, &patch.patch) }
+        {
+            for done in patches[..index].iter().rev() {
+                let _ = unsafe {
+                    MemoryAccess::write_bytes_patched(done.target as *mut u8, &done.original)
+                };
+            }
+            return Err((patch.target, e));
+        }
+    }
+    Ok(())
+}
+
+/// Write gate that takes a backend's patch back out right after it is written
+///
+/// Other threads are suspended only between the two callbacks, and the
+/// buffers the patch is copied into are allocated up front. The gate acts
+/// once; later writes, such as the backend restoring the target on removal,
+/// pass through untouched.
+struct CaptureGate {
+    target: usize,
+    used: AtomicBool,
+    original: Mutex<Vec<u8>>,
+    patch: Mutex<Vec<u8>>,
+    threads: Mutex<Option<SuspendedThreads>>,
+    error: Mutex<Option<AppError>>,
+}
+
+impl CaptureGate {
+    fn new(target: usize) -> Self {
+        Self {
+            target,
+            used: AtomicBool::new(false),
+            original: Mutex::new(Vec::new()),
+            patch: Mutex::new(Vec::with_capacity(HOOK_BACKUP_LEN)),
+            threads: Mutex::new(None),
+            error: Mutex::new(None),
+        }
+    }
+
+    /// Error raised by one of the callbacks, if any
+    fn take_error(&self) -> Option<AppError> {
+        self.error.lock().unwrap().take()
+    }
+
+    /// The captured patch and the original bytes it replaces
+    fn take_patch(&self) -> Result<CapturedPatch> {
+        if let Some(e) = self.take_error() {
+            return Err(e);
+        }
+        let patch = mem::take(&mut *self.patch.lock().unwrap());
+        if patch.is_empty() {
+            return Err(AppError::HookFailed {
+                message: "The backend wrote its patch without running the write gate".to_string(),
+            });
+        }
+        Ok(CapturedPatch {
+            target: self.target,
+            original: mem::take(&mut *self.original.lock().unwrap()),
+            patch,
+        })
+    }
+
+    fn fail(&self, error: AppError) {
+        *self.error.lock().unwrap() = Some(error);
+    }
+}
+
+impl WriteGate for CaptureGate {
+    fn before_write(&self) -> bool {
+        if self.used.swap(true, Ordering::AcqRel) {
+            return true;
+        }
+
+        match unsafe { MemoryAccess::read_bytes(self.target as *const u8, HOOK_BACKUP_LEN) } {
+            Ok(original) => *self.original.lock().unwrap() = original,
+            Err(e) => {
+                self.fail(e);
+                return false;
+            }
+        }
+        match suspend_outside(&[(self.target, self.target + HOOK_BACKUP_LEN)]) {
+            Ok(threads) => {
+                *self.threads.lock().unwrap() = Some(threads);
+                true
+            }
+            Err(e) => {
+                self.fail(e);
+                false
+            }
+        }
+    }
+
+    fn after_write(&self) {
+        let Some(threads) = self.threads.lock().unwrap().take() else {
+            return;
+        };
+
+        // The patch buffer has its capacity already; nothing allocates here
+        self.patch.lock().unwrap().extend_from_slice(unsafe {
+            std::slice::from_raw_parts(self.target as *const u8, HOOK_BACKUP_LEN)
+        });
+        let original = self.original.lock().unwrap();
+        let restored =
+            unsafe { MemoryAccess::write_bytes_patched(self.target as *mut u8, &original) };
+        drop(original);
+        drop(threads);
+
+        if let Err(e) = restored {
+            self.fail(e);
+        }
+    }
+}
+
+/// Suspend all other threads, retrying while any of them sits in a window
+fn suspend_outside(windows: &[(usize, usize)]) -> Result<SuspendedThreads> {
+    let mut blocking = None;
+    for _ in 0..TRANSACTION_SUSPEND_ATTEMPTS {
+        let threads = SuspendedThreads::suspend_all()?;
+        match threads.thread_inside(windows) {
+            None => return Ok(threads),
+            Some(found) => blocking = Some(found),
+        }
+        drop(threads);
+        std::thread::sleep(std::time::Duration::from_millis(TRANSACTION_RETRY_DELAY_MS));
+    }
+
+    let (thread_id, rip) = blocking.unwrap_or_default();
+    Err(AppError::HookFailed {
+        message: format!(
+            "Thread {} kept executing inside a patch window (rip {:#x})",
+            thread_id, rip
+        ),
+    })
+}
+
+/// Check if `address` falls into any half-open `[start, end)` window
+fn inside_any(windows: &[(usize, usize)], address: usize) -> bool {
+    windows
+        .iter()
+        .any(|&(start, end)| (start..end).contains(&address))
+}
+
+/// Every other thread of the process, suspended until dropped
+pub(crate) struct SuspendedThreads {
+    threads: Vec<(DWORD, OwnedHandle)>,
+}
+
+impl SuspendedThreads {
+    /// Suspend every thread of the process except the current one
+    ///
+    /// Threads that exit or refuse access during the walk are skipped.
+    /// Nothing is allocated once the first thread is suspended.
+    pub(crate) fn suspend_all() -> Result<Self> {
+        let snapshot =
+            unsafe { OwnedHandle::from_raw(CreateToolhelp32Snapshot(TH32CS_SNAPTHREAD, 0)) }
+                .ok_or_else(|| AppError::last_error("CreateToolhelp32Snapshot"))?;
+
+        let process_id = unsafe { GetCurrentProcessId() };
+        let current_thread = unsafe { GetCurrentThreadId() };
+        let mut entry: THREADENTRY32 = unsafe { std::mem::zeroed() };
+        entry.dwSize = std::mem::size_of::<THREADENTRY32>() as DWORD;
+
+        // Open every thread first: the vector must not grow once threads
+        // are suspended, as one of them may hold the heap lock
+        let access = THREAD_SUSPEND_RESUME | THREAD_GET_CONTEXT | THREAD_QUERY_INFORMATION;
+        let mut threads = Vec::new();
+        let mut more = unsafe { Thread32First(snapshot.as_raw(), &mut entry) } != 0;
+        while more {
+            if entry.th32OwnerProcessID == process_id && entry.th32ThreadID != current_thread {
+                let thread =
+                    unsafe { OwnedHandle::from_raw(OpenThread(access, 0, entry.th32ThreadID)) };
+                if let Some(thread) = thread {
+                    threads.push((entry.th32ThreadID, thread));
+                }
+            }
+            more = unsafe { Thread32Next(snapshot.as_raw(), &mut entry) } != 0;
+        }
+        drop(snapshot);
+
+        threads.retain(|(_, thread)| unsafe { SuspendThread(thread.as_raw()) } != DWORD::MAX);
+        Ok(Self { threads })
+    }
+
+    /// Number of suspended threads
+    pub(crate) fn len(&self) -> usize {
+        self.threads.len()
+    }
+
+    /// First thread whose instruction pointer lies in one of `windows`
+    ///
+    /// Reading the context also waits for the suspension to take effect,
+    /// as `SuspendThread` alone is asynchronous.
+    fn thread_inside(&self, windows: &[(usize, usize)]) -> Option<(DWORD, usize)> {
+        self.threads.iter().find_map(|(thread_id, thread)| {
+            let mut context: AlignedContext = unsafe { std::mem::zeroed() };
+            context.0.ContextFlags = CONTEXT_CONTROL;
+            if unsafe { GetThreadContext(thread.as_raw(), &mut context.0) } == 0 {
+                return None;
+            }
+            let rip = context.0.Rip as usize;
+            inside_any(windows, rip).then_some((*thread_id, rip))
+        })
+    }
+}
+
+impl Drop for SuspendedThreads {
+    fn drop(&mut self) {
+        for (_, thread) in &self.threads {
+            unsafe { ResumeThread(thread.as_raw()) };
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hooks::{HookState, PakFileHook};
+    use ilhook::x64::Registers;
+
+    unsafe extern "win64" fn replacement(_: *mut Registers, _: usize, _: usize) -> usize {
+        0
+    }
+
+    #[test]
+    fn test_empty_transaction_commits_nothing() {
+        let transaction = HookTransaction::new();
+        assert!(transaction.is_empty());
+        assert_eq!(transaction.commit().unwrap(), 0);
+    }
+
+    #[test]
+    fn test_failed_preparation_rolls_back_before_suspending() {
+        // Preparing the hook over address 0 fails reading its original
+        // bytes, before its write gate would suspend any thread, and the
+        // second hook is never attempted
+        let unreadable = PakFileHook::new();
+        let untouched = PakFileHook::new();
+        let mut transaction = HookTransaction::new();
+        transaction
+            .stage(&unreadable, 0, replacement)
+            .stage(&untouched, 0x1000, replacement);
+        assert_eq!(transaction.len(), 2);
+
+        assert!(matches!(
+            transaction.commit(),
+            Err(AppError::HookFailed { .. })
+        ));
+        assert_eq!(unreadable.state(), HookState::Failed);
+        assert_eq!(untouched.state(), HookState::Uninitialized);
+    }
+
+    #[test]
+    fn test_capture_gate_requires_a_gated_write() {
+        let gate = CaptureGate::new(0x1000);
+        assert!(matches!(
+            gate.take_patch(),
+            Err(AppError::HookFailed { .. })
+        ));
+
+        // Once used, the gate lets later writes through without suspending
+        gate.used.store(true, Ordering::Release);
+        assert!(gate.before_write());
+        gate.after_write();
+        assert!(gate.take_error().is_none());
+    }
+
+    #[test]
+    fn test_inside_any_uses_half_open_windows() {
+        let windows = [(0x1000, 0x1020), (0x2000, 0x2020)];
+        assert!(inside_any(&windows, 0x1000));
+        assert!(inside_any(&windows, 0x201f));
+        assert!(!inside_any(&windows, 0x1020));
+        assert!(!inside_any(&windows, 0x0fff));
+    }
+}
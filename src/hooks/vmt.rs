@@ -0,0 +1,237 @@
+//! Virtual method table hooks
+//!
+//! Swaps entries of a C++ vtable, such as the virtual methods of
+//! `FPakPlatformFile`. Vtables live in read-only data, so no code page is
+//! ever written.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use crate::error::{AppError, Result};
+use crate::logger::Logger;
+use crate::safety::MemoryAccess;
+
+/// Hook on entries of one vtable, shared by every object of the class
+///
+/// All replaced entries are restored when the hook is dropped.
+pub struct VmtHook {
+    vtable: *mut usize,
+    /// Original and replacement function per hooked index
+    patched: Mutex<HashMap<usize, (usize, usize)>>,
+}
+
+impl VmtHook {
+    /// Hook the vtable of a live object
+    ///
+    /// # Safety
+    ///
+    /// `object` must point to an object whose first field is its vtable pointer.
+    pub unsafe fn from_object(object: *const usize) -> Result<Self> {
+        let vtable = unsafe { MemoryAccess::deref_pointer_safe(object)? };
+        if vtable == 0 {
+            return Err(AppError::MemoryAccessViolation {
+                address: object as usize,
+            });
+        }
+        Ok(Self::from_vtable(vtable as *mut usize))
+    }
+
+    /// Hook a vtable located by address
+    pub fn from_vtable(vtable: *mut usize) -> Self {
+        Self {
+            vtable,
+            patched: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Replace entry `index` with `replacement`, returning the original function
+    ///
+    /// The current entry must point to executable memory, which catches an
+    /// index past the end of the table.
+    pub fn hook(&self, index: usize, replacement: usize) -> Result<usize> {
+        let mut patched = self.patched.lock().unwrap();
+        if patched.contains_key(&index) {
+            return Err(AppError::HookFailed {
+                message: format!("Vtable entry {} is already hooked", index),
+            });
+        }
+
+        let slot = self.slot(index)?;
+        let current = unsafe { MemoryAccess::read_volatile_safe(slot)? };
+        if !MemoryAccess::is_executable(current as *const u8) {
+            return Err(AppError::HookFailed {
+                message: format!(
+                    "Vtable entry {} at {:p} does not point to code ({:#x})",
+                    index, slot, current
+                ),
+            });
+        }
+
+        let original = unsafe { MemoryAccess::swap_pointer(slot, replacement)? };
+        patched.insert(index, (original, replacement));
+
+        Logger::hook(&format!(
+            "Vtable {:p} entry {}: {:#x} -> {:#x}",
+            self.vtable, index, original, replacement
+        ));
+        Ok(original)
+    }
+
+    /// Restore entry `index` to its original function
+    pub fn unhook(&self, index: usize) -> Result<()> {
+        let mut patched = self.patched.lock().unwrap();
+        let Some(&(original, replacement)) = patched.get(&index) else {
+            return Err(AppError::HookFailed {
+                message: format!("Vtable entry {} is not hooked", index),
+            });
+        };
+
+        let replaced = unsafe { MemoryAccess::swap_pointer(self.slot(index)?, original)? };
+        if replaced != replacement {
+            Logger::warning(&format!(
+                "Vtable {:p} entry {} was changed by someone else before removal",
+                self.vtable, index
+            ));
+        }
+
+        patched.remove(&index);
+        Logger::hook(&format!(
+            "Vtable {:p} entry {} restored",
+            self.vtable, index
+        ));
+        Ok(())
+    }
+
+    /// Restore every hooked entry, reporting the first failure
+    pub fn unhook_all(&self) -> Result<()> {
+        let mut indices: Vec<usize> = self.patched.lock().unwrap().keys().copied().collect();
+        indices.sort_unstable();
+
+        let mut first_error = None;
+        for index in indices {
+            if let Err(e) = self.unhook(index) {
+                Logger::error(&format!("Failed to restore vtable entry {}: {}", index, e));
+                first_error.get_or_insert(e);
+            }
+        }
+        first_error.map_or(Ok(()), Err)
+    }
+
+    /// Original function of a hooked entry, for calling through
+    pub fn original(&self, index: usize) -> Option<usize> {
+        self.patched
+            .lock()
+            .unwrap()
+            .get(&index)
+            .map(|&(original, _)| original)
+    }
+
+    /// Check if entry `index` is currently hooked
+    pub fn is_hooked(&self, index: usize) -> bool {
+        self.patched.lock().unwrap().contains_key(&index)
+    }
+
+    /// Address of the hooked vtable
+    pub fn vtable(&self) -> *mut usize {
+        self.vtable
+    }
+
+    fn slot(&self, index: usize) -> Result<*mut usize> {
+        if self.vtable.is_null() {
+            return Err(AppError::MemoryAccessViolation { address: 0 });
+        }
+        Ok(self.vtable.wrapping_add(index))
+    }
+}
+
+impl Drop for VmtHook {
+    fn drop(&mut self) {
+        if let Err(e) = self.unhook_all() {
+            Logger::error(&format!("Failed to restore vtable on drop: {}", e));
+        }
+    }
+}
+
+// Entries are only written through MemoryAccess::swap_pointer
+unsafe impl Send for VmtHook {}
+unsafe impl Sync for VmtHook {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_object_reads_vtable_pointer() {
+        let mut vtable = [0usize; 4];
+        let object = [vtable.as_mut_ptr() as usize];
+
+        let hook = unsafe { VmtHook::from_object(object.as_ptr()) }.unwrap();
+        assert_eq!(hook.vtable(), vtable.as_mut_ptr());
+        assert!(!hook.is_hooked(0));
+    }
+
+    #[test]
+    fn test_from_object_rejects_null_vtable() {
+        let object = [0usize];
+        assert!(unsafe { VmtHook::from_object(object.as_ptr()) }.is_err());
+        assert!(unsafe { VmtHook::from_object(std::ptr::null()) }.is_err());
+    }
+
+    #[test]
+    fn test_unhook_requires_hooked_entry() {
+        let mut vtable = [0usize; 4];
+        let hook = VmtHook::from_vtable(vtable.as_mut_ptr());
+        assert!(hook.unhook(1).is_err());
+        assert!(hook.original(1).is_none());
+        assert!(hook.unhook_all().is_ok());
+    }
+
+    #[test]
+    fn test_hook_rejects_entry_without_code() {
+        let mut vtable = [0usize; 4];
+        let hook = VmtHook::from_vtable(vtable.as_mut_ptr());
+        assert!(hook.hook(2, 0x1000).is_err());
+        assert_eq!(vtable[2], 0);
+    }
+
+    trait Probe {
+        fn value(&self) -> u32;
+    }
+
+    struct Real(u32);
+
+    impl Probe for Real {
+        fn value(&self) -> u32 {
+            self.0
+        }
+    }
+
+    fn replaced_value(_: &Real) -> u32 {
+        2
+    }
+
+    #[test]
+    fn test_hook_and_unhook_a_real_vtable() {
+        let real = Real(1);
+        let object: &dyn Probe = &real;
+        let (_, vtable): (*const Real, *mut usize) = unsafe { std::mem::transmute(object) };
+
+        // Drop glue, size and alignment come before the methods
+        const VALUE: usize = 3;
+        let entries = unsafe { std::slice::from_raw_parts(vtable, VALUE + 1) };
+        assert_eq!(entries[1..VALUE], [size_of::<Real>(), align_of::<Real>()]);
+        let method = entries[VALUE];
+
+        let hook = VmtHook::from_vtable(vtable);
+        let replacement = replaced_value as fn(&Real) -> u32 as usize;
+        assert_eq!(hook.hook(VALUE, replacement).unwrap(), method);
+        assert_eq!(unsafe { vtable.add(VALUE).read_volatile() }, replacement);
+        assert_eq!(hook.original(VALUE), Some(method));
+        assert_eq!(std::hint::black_box(object).value(), 2);
+
+        hook.unhook(VALUE).unwrap();
+        assert_eq!(unsafe { vtable.add(VALUE).read_volatile() }, method);
+        assert!(!hook.is_hooked(VALUE));
+        assert_eq!(std::hint::black_box(object).value(), 1);
+    }
+}
@@ -0,0 +1,150 @@
+//! Function-key hotkeys handled while the game runs
+//!
+//! A background thread polls the keyboard with `GetAsyncKeyState`, since the
+//! DLL owns no window to receive key messages. Presses only count while a
+//! window of the game process, its console included, has the focus, so
+//! pressing the key in another program does nothing.
+
+use std::fmt;
+use std::str::FromStr;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use winapi::um::processthreadsapi::GetCurrentProcessId;
+use winapi::um::winuser::{GetAsyncKeyState, GetForegroundWindow, GetWindowThreadProcessId, VK_F1};
+
+use crate::error::{AppError, Result};
+use crate::shutdown;
+
+/// How often the keyboard is polled
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// A function key, F1 to F24
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Hotkey(u8);
+
+impl Hotkey {
+    pub const F9: Self = Self(9);
+    pub const F10: Self = Self(10);
+
+    /// Function key `number`, if there is one
+    pub fn function(number: u8) -> Option<Self> {
+        (1..=24).contains(&number).then_some(Self(number))
+    }
+
+    /// Windows virtual-key code of the key
+    pub fn virtual_key(self) -> i32 {
+        VK_F1 + i32::from(self.0) - 1
+    }
+
+    /// Whether the key is held down right now
+    fn is_down(self) -> bool {
+        // The most significant bit is set while the key is down
+        let state = unsafe { GetAsyncKeyState(self.virtual_key()) };
+        state < 0
+    }
+}
+
+impl fmt::Display for Hotkey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "F{}", self.0)
+    }
+}
+
+impl FromStr for Hotkey {
+    type Err = AppError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        s.strip_prefix(['F', 'f'])
+            .and_then(|number| number.parse().ok())
+            .and_then(Self::function)
+            .ok_or_else(|| AppError::InvalidConfig {
+                field: "hotkey".to_string(),
+                reason: format!("'{}' is not a function key F1 to F24", s),
+            })
+    }
+}
+
+/// A key and what pressing it does
+pub struct Binding {
+    key: Hotkey,
+    action: Box<dyn Fn() + Send>,
+    was_down: bool,
+}
+
+impl Binding {
+    pub fn new(key: Hotkey, action: impl Fn() + Send + 'static) -> Self {
+        Self {
+            key,
+            action: Box::new(action),
+            was_down: false,
+        }
+    }
+
+    /// Record the key's state, reporting whether it was just pressed
+    fn pressed(&mut self, down: bool) -> bool {
+        let pressed = down && !self.was_down;
+        self.was_down = down;
+        pressed
+    }
+}
+
+/// Start a thread running the action of each binding whenever its key is
+/// pressed; the thread runs until a shutdown is requested
+pub fn spawn(mut bindings: Vec<Binding>) -> Result<JoinHandle<()>> {
+    thread::Builder::new()
+        .name("wuwa-sig-hotkey".to_string())
+        .spawn(move || {
+            while !shutdown::is_requested() {
+                thread::sleep(POLL_INTERVAL);
+                let focused = game_has_focus();
+                for binding in &mut bindings {
+                    let down = focused && binding.key.is_down();
+                    if binding.pressed(down) {
+                        (binding.action)();
+                    }
+                }
+            }
+        })
+        .map_err(|source| AppError::HotkeyThreadFailed { source })
+}
+
+/// Whether the foreground window belongs to this process
+fn game_has_focus() -> bool {
+    let mut process_id = 0;
+    unsafe {
+        let window = GetForegroundWindow();
+        if window.is_null() {
+            return false;
+        }
+        GetWindowThreadProcessId(window, &mut process_id);
+        process_id == GetCurrentProcessId()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hotkey_names() {
+        let key: Hotkey = "F9".parse().unwrap();
+        assert_eq!(key, Hotkey::F9);
+        assert_eq!(key.to_string(), "F9");
+        assert_eq!(key.virtual_key(), 0x78);
+        assert_eq!("f24".parse::<Hotkey>().unwrap().virtual_key(), 0x87);
+        for name in ["F0", "F25", "G1", "9", ""] {
+            assert!(name.parse::<Hotkey>().is_err(), "{}", name);
+        }
+    }
+
+    #[test]
+    fn test_binding_fires_once_per_press() {
+        let mut binding = Binding::new(Hotkey::F9, || {});
+        let presses: Vec<bool> = [false, true, true, false, true]
+            .into_iter()
+            .map(|down| binding.pressed(down))
+            .collect();
+        assert_eq!(presses, [false, true, false, false, true]);
+    }
+}
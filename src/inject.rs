@@ -0,0 +1,236 @@
+//! Mounting of paks from an extra directory
+//!
+//! The game only mounts paks it enumerates itself, so user paks dropped in
+//! a folder it does not scan are never loaded. With an extra pak directory
+//! configured, the first mount seen by the [`mount`](crate::mount) hook
+//! captures the engine's pak platform file. A worker thread then waits until
+//! the game has mounted nothing for [`INJECT_SETTLE_MS`], so its own mount
+//! phase is over, and mounts every `.pak` in the directory through the same
+//! routine. Paks the game already mounted under the same file name are
+//! skipped.
+//!
+//! This depends on the mount hook, so it needs the mount signature too.
+
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use crate::constants::constants::INJECT_SETTLE_MS;
+use crate::error::{AppError, Result};
+use crate::logger::Logger;
+use crate::safety::StringConverter;
+
+/// `FPakPlatformFile::Mount(this, pak, order, mount_point, load_index)`
+type MountFn = unsafe extern "win64" fn(usize, *const u16, u32, *const u16, bool) -> bool;
+
+/// Where extra paks come from and the order they are mounted with
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InjectSettings {
+    pub directory: PathBuf,
+    pub order: u32,
+}
+
+/// Settings of the injection, set before the mount hook is installed
+static SETTINGS: OnceLock<InjectSettings> = OnceLock::new();
+
+/// File names of paks mounted so far, lowercased
+static MOUNTED: Mutex<Option<HashSet<String>>> = Mutex::new(None);
+
+/// When the last mount was seen
+static LAST_MOUNT: Mutex<Option<Instant>> = Mutex::new(None);
+
+/// Whether the injection worker has been started
+static STARTED: AtomicBool = AtomicBool::new(false);
+
+/// Mount every pak in `directory` once the game starts mounting
+pub fn configure(directory: &str, order: u32) -> Result<()> {
+    SETTINGS
+        .set(InjectSettings {
+            directory: PathBuf::from(directory),
+            order,
+        })
+        .map_err(|_| AppError::InvalidConfig {
+            field: "extra_pak_dir".to_string(),
+            reason: "Pak injection is already configured".to_string(),
+        })
+}
+
+/// Current injection settings, if configured
+pub fn settings() -> Option<&'static InjectSettings> {
+    SETTINGS.get()
+}
+
+/// `.pak` files in `directory`, sorted by name
+pub fn pak_files(directory: &Path) -> Result<Vec<PathBuf>> {
+    let entries = std::fs::read_dir(directory).map_err(|e| AppError::InvalidConfig {
+        field: "extra_pak_dir".to_string(),
+        reason: format!("Cannot read '{}': {}", directory.display(), e),
+    })?;
+
+    let mut paks: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.is_file() && is_pak(path))
+        .collect();
+    paks.sort();
+    Ok(paks)
+}
+
+fn is_pak(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("pak"))
+}
+
+/// Lowercased file name of a pak path, as used to detect duplicates
+fn pak_key(path: &str) -> String {
+    let name = path.rsplit(['/', '\\']).next().unwrap_or(path);
+    name.to_lowercase()
+}
+
+/// Record a mount seen by the mount hook, starting the injection on the first
+///
+/// `routine` is the hooked mount routine and `platform_file` its `this`.
+pub(crate) fn observe_mount(routine: usize, platform_file: usize, pak: &str) {
+    MOUNTED
+        .lock()
+        .unwrap()
+        .get_or_insert_with(HashSet::new)
+        .insert(pak_key(pak));
+    *LAST_MOUNT.lock().unwrap() = Some(Instant::now());
+
+    let Some(settings) = SETTINGS.get() else {
+        return;
+    };
+    if platform_file == 0 || STARTED.swap(true, Ordering::AcqRel) {
+        return;
+    }
+
+    // Mounting from inside the hooked call would re-enter it on this thread
+    std::thread::spawn(move || inject_all(settings, routine, platform_file));
+}
+
+/// Time still to wait for the game's mounts to settle, if any
+fn settle_remaining(last_mount: Option<Instant>, settle: Duration) -> Option<Duration> {
+    let quiet = last_mount.map_or(settle, |at| at.elapsed());
+    settle
+        .checked_sub(quiet)
+        .filter(|remaining| !remaining.is_zero())
+}
+
+/// Block until the game has not mounted a pak for [`INJECT_SETTLE_MS`]
+fn wait_for_game_mounts() {
+    let settle = Duration::from_millis(INJECT_SETTLE_MS);
+    while let Some(remaining) = settle_remaining(*LAST_MOUNT.lock().unwrap(), settle) {
+        std::thread::sleep(remaining);
+    }
+}
+
+/// Mount every pak of the extra directory not mounted yet
+fn inject_all(settings: &InjectSettings, routine: usize, platform_file: usize) {
+    // Injecting at the first mount would miss every pak the game mounts
+    // after it, and mount those twice
+    wait_for_game_mounts();
+
+    let paks = match pak_files(&settings.directory) {
+        Ok(paks) => paks,
+        Err(e) => {
+            Logger::warning(&format!("Pak injection skipped: {}", e));
+            return;
+        }
+    };
+
+    let mount: MountFn = unsafe { std::mem::transmute(routine) };
+    let mut injected = 0;
+    for pak in paks {
+        let path = pak.to_string_lossy().into_owned();
+        let already_mounted = MOUNTED
+            .lock()
+            .unwrap()
+            .as_ref()
+            .is_some_and(|mounted| mounted.contains(&pak_key(&path)));
+        if already_mounted {
+            Logger::info(&format!("Pak '{}' is already mounted by the game", path));
+            continue;
+        }
+
+        let wide_path = StringConverter::to_wide_null(&path);
+        if unsafe {
+            mount(
+                platform_file,
+                wide_path.as_ptr(),
+                settings.order,
+                std::ptr::null(),
+                true,
+            )
+        } {
+            injected += 1;
+        } else {
+            Logger::warning(&format!("Engine refused to mount '{}'", path));
+        }
+    }
+
+    Logger::success(&format!(
+        "Mounted {} extra paks from '{}' at order {}",
+        injected,
+        settings.directory.display(),
+        settings.order
+    ));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pak_files_lists_only_paks_sorted() {
+        let directory =
+            std::env::temp_dir().join(format!("wuwa-sig-inject-{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        for name in ["b_P.pak", "a_P.PAK", "readme.txt", "a_P.sig"] {
+            std::fs::write(directory.join(name), b"").unwrap();
+        }
+
+        let names: Vec<String> = pak_files(&directory)
+            .unwrap()
+            .iter()
+            .map(|path| path.file_name().unwrap().to_string_lossy().into_owned())
+            .collect();
+        std::fs::remove_dir_all(&directory).unwrap();
+
+        assert_eq!(names, ["a_P.PAK", "b_P.pak"]);
+    }
+
+    #[test]
+    fn test_pak_files_reports_missing_directory() {
+        assert!(matches!(
+            pak_files(Path::new("/definitely/not/a/pak/dir")),
+            Err(AppError::InvalidConfig { .. })
+        ));
+    }
+
+    #[test]
+    fn test_settle_waits_out_recent_mounts() {
+        let settle = Duration::from_secs(60);
+        assert_eq!(settle_remaining(None, settle), None);
+
+        let remaining = settle_remaining(Some(Instant::now()), settle).unwrap();
+        assert!(remaining > Duration::from_secs(59) && remaining <= settle);
+
+        let mounted = Instant::now();
+        std::thread::sleep(Duration::from_millis(5));
+        assert_eq!(
+            settle_remaining(Some(mounted), Duration::from_millis(1)),
+            None
+        );
+    }
+
+    #[test]
+    fn test_pak_key_ignores_directory_and_case() {
+        assert_eq!(
+            pak_key("../../../Client/Content/Paks/~mods/MyMod_P.pak"),
+            "mymod_p.pak"
+        );
+        assert_eq!(pak_key("C:\\Mods\\MYMOD_P.PAK"), "mymod_p.pak");
+    }
+}
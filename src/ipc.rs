@@ -0,0 +1,436 @@
+//! Status and control commands on a named pipe
+//!
+//! A background thread serves [`COMMAND_PIPE_NAME`] for tools outside the
+//! game, such as a control UI or a launcher. The pipe is in message mode:
+//! every message a client writes is one command and is answered with one
+//! message. A client may send several commands over one connection, or use
+//! `CallNamedPipe` for a single one. Remote clients are rejected, and the
+//! pipe's default security only lets the game's user and administrators in.
+//!
+//! | Command         | Reply                                                  |
+//! |-----------------|--------------------------------------------------------|
+//! | `status`        | Startup phase, target RVA, error code and build        |
+//! | `hooks`         | Every registered hook with its state and target        |
+//! | `toggle <hook>` | Removes the hook if applied, applies it again if not   |
+//! | `level <level>` | Sets the log level, named as for `log_level`           |
+//! | `stats`         | Invocation statistics of every hook                    |
+//! | `unload`        | Removes the hooks and unloads the DLL                  |
+//!
+//! Replies are UTF-8 text, one item per line. A command that fails is
+//! answered with `error: ` and the reason; a message longer than
+//! [`MAX_COMMAND_LEN`] closes the connection.
+
+use std::fmt::Write as _;
+use std::ptr;
+use std::str::FromStr;
+use std::thread::{self, JoinHandle};
+
+use winapi::shared::minwindef::{BOOL, DWORD, FALSE, TRUE};
+use winapi::shared::winerror::{
+    ERROR_BROKEN_PIPE, ERROR_IO_PENDING, ERROR_MORE_DATA, ERROR_PIPE_CONNECTED, WAIT_TIMEOUT,
+};
+use winapi::um::errhandlingapi::GetLastError;
+use winapi::um::fileapi::{ReadFile, WriteFile};
+use winapi::um::ioapiset::{CancelIoEx, GetOverlappedResult};
+use winapi::um::minwinbase::OVERLAPPED;
+use winapi::um::namedpipeapi::{ConnectNamedPipe, CreateNamedPipeW, DisconnectNamedPipe};
+use winapi::um::synchapi::{CreateEventW, WaitForSingleObject};
+use winapi::um::winbase::{
+    FILE_FLAG_FIRST_PIPE_INSTANCE, FILE_FLAG_OVERLAPPED, PIPE_ACCESS_DUPLEX, PIPE_READMODE_MESSAGE,
+    PIPE_REJECT_REMOTE_CLIENTS, PIPE_TYPE_MESSAGE, PIPE_WAIT, WAIT_OBJECT_0,
+};
+
+use crate::build_info::BuildInfo;
+use crate::constants::constants::{COMMAND_PIPE_NAME, MAX_COMMAND_LEN};
+use crate::error::{AppError, Result};
+use crate::hooks::global_hook_manager;
+use crate::logger::{self, LogLevel, Logger};
+use crate::memory::describe_address;
+use crate::safety::{OwnedHandle, StringConverter};
+use crate::{shutdown, startup};
+
+/// How often pending pipe I/O checks for a shutdown request, in milliseconds
+const POLL_INTERVAL_MS: DWORD = 50;
+
+/// A command a client can send
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    Status,
+    Hooks,
+    Toggle(String),
+    Level(LogLevel),
+    Stats,
+    Unload,
+}
+
+impl FromStr for Command {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, String> {
+        let mut words = s.split_whitespace();
+        let name = words.next().ok_or("empty command")?.to_ascii_lowercase();
+        let argument = words.next();
+        if words.next().is_some() {
+            return Err(format!("too many arguments for '{}'", name));
+        }
+
+        let command = match (name.as_str(), argument) {
+            ("status", None) => Self::Status,
+            ("hooks", None) => Self::Hooks,
+            ("toggle", Some(hook)) => Self::Toggle(hook.to_string()),
+            ("level", Some(level)) => Self::Level(level.parse().map_err(|_| {
+                let names: Vec<&str> = LogLevel::ALL.iter().map(|level| level.as_str()).collect();
+                format!(
+                    "unknown log level '{}', use one of {}",
+                    level,
+                    names.join(", ")
+                )
+            })?),
+            ("stats", None) => Self::Stats,
+            ("unload", None) => Self::Unload,
+            ("toggle" | "level", None) => return Err(format!("'{}' needs an argument", name)),
+            ("status" | "hooks" | "stats" | "unload", Some(_)) => {
+                return Err(format!("'{}' takes no argument", name));
+            }
+            _ => return Err(format!("unknown command '{}'", name)),
+        };
+        Ok(command)
+    }
+}
+
+impl Command {
+    /// Carry out the command, returning the reply text
+    pub fn execute(&self) -> std::result::Result<String, String> {
+        let mut reply = String::new();
+        match self {
+            Self::Status => {
+                let status = startup::status();
+                let _ = writeln!(reply, "phase: {}", status.phase());
+                let _ = match status.target() {
+                    Some(rva) => writeln!(reply, "target: {}", rva),
+                    None => writeln!(reply, "target: none"),
+                };
+                let _ = writeln!(reply, "error_code: {}", status.error_code().unwrap_or(0));
+                let _ = writeln!(reply, "build: {}", BuildInfo::CURRENT);
+            }
+            Self::Hooks => {
+                for (name, info) in global_hook_manager().statuses() {
+                    let _ = writeln!(
+                        reply,
+                        "{}: {:?}{}",
+                        name,
+                        info.state,
+                        info.target_address.map_or(String::new(), |address| format!(
+                            " at {}",
+                            describe_address(address)
+                        ))
+                    );
+                }
+                if reply.is_empty() {
+                    reply.push_str("no hooks registered\n");
+                }
+            }
+            Self::Toggle(name) => {
+                let manager = global_hook_manager();
+                let info = manager
+                    .status(name)
+                    .ok_or_else(|| format!("no hook named '{}'", name))?;
+                let (result, done) = if info.is_active {
+                    (manager.remove(name), "removed")
+                } else {
+                    // Suspends the other threads around the patch write
+                    (manager.apply_together(&[name]).map(|_| ()), "applied")
+                };
+                result.map_err(|e| e.to_string())?;
+                Logger::info(&format!("Hook '{}' {} over the command pipe", name, done));
+                let _ = writeln!(reply, "{}: {}", name, done);
+            }
+            Self::Level(level) => {
+                logger::set_global_min_level(*level);
+                Logger::info(&format!(
+                    "Log level set to {} over the command pipe",
+                    level.as_str()
+                ));
+                let _ = writeln!(reply, "log level: {}", level.as_str());
+            }
+            Self::Stats => {
+                let manager = global_hook_manager();
+                for (name, _) in manager.statuses() {
+                    if let Some(stats) = manager.stats(&name) {
+                        let _ = writeln!(reply, "{}: {}", name, stats.snapshot());
+                    }
+                }
+                if reply.is_empty() {
+                    reply.push_str("no hooks registered\n");
+                }
+            }
+            Self::Unload => {
+                Logger::info("Unload requested over the command pipe");
+                shutdown::request(true);
+                reply.push_str("unloading\n");
+            }
+        }
+        Ok(reply)
+    }
+}
+
+/// Reply to one message from a client
+fn respond(message: &[u8]) -> String {
+    std::str::from_utf8(message)
+        .map_err(|_| "commands must be UTF-8".to_string())
+        .and_then(|text| text.parse::<Command>())
+        .and_then(|command| command.execute())
+        .unwrap_or_else(|reason| format!("error: {}\n", reason))
+}
+
+/// How a pending pipe operation ended
+enum Completion {
+    /// Finished, moving this many bytes
+    Done(DWORD),
+    /// Failed with this Win32 error code
+    Failed(DWORD),
+    /// Cancelled for a shutdown request
+    Cancelled,
+}
+
+/// Server end of the command pipe and the event its overlapped I/O signals
+struct CommandPipe {
+    name: String,
+    handle: OwnedHandle,
+    event: OwnedHandle,
+}
+
+impl CommandPipe {
+    /// Create the only instance of the pipe named `name`
+    fn create(name: &str) -> Result<Self> {
+        let wide_name = StringConverter::to_wide_null(name);
+        let handle = unsafe {
+            OwnedHandle::from_raw(CreateNamedPipeW(
+                wide_name.as_ptr(),
+                PIPE_ACCESS_DUPLEX | FILE_FLAG_OVERLAPPED | FILE_FLAG_FIRST_PIPE_INSTANCE,
+                PIPE_TYPE_MESSAGE | PIPE_READMODE_MESSAGE | PIPE_WAIT | PIPE_REJECT_REMOTE_CLIENTS,
+                1,
+                4096,
+                MAX_COMMAND_LEN as DWORD,
+                0,
+                ptr::null_mut(),
+            ))
+        };
+        let event = unsafe {
+            OwnedHandle::from_raw(CreateEventW(ptr::null_mut(), TRUE, FALSE, ptr::null()))
+        };
+        match (handle, event) {
+            (Some(handle), Some(event)) => Ok(Self {
+                name: name.to_string(),
+                handle,
+                event,
+            }),
+            _ => Err(Self::error(name, unsafe { GetLastError() })),
+        }
+    }
+
+    fn error(name: &str, code: DWORD) -> AppError {
+        AppError::CommandPipeFailed {
+            name: name.to_string(),
+            source: std::io::Error::from_raw_os_error(code as i32),
+        }
+    }
+
+    fn overlapped(&self) -> OVERLAPPED {
+        let mut overlapped: OVERLAPPED = unsafe { std::mem::zeroed() };
+        overlapped.hEvent = self.event.as_raw();
+        overlapped
+    }
+
+    /// Wait for the operation on `overlapped` that returned `started`,
+    /// cancelling it when a shutdown is requested
+    fn finish(&self, overlapped: &mut OVERLAPPED, started: BOOL) -> Completion {
+        if started == FALSE {
+            let code = unsafe { GetLastError() };
+            if code != ERROR_IO_PENDING {
+                return Completion::Failed(code);
+            }
+            loop {
+                match unsafe { WaitForSingleObject(self.event.as_raw(), POLL_INTERVAL_MS) } {
+                    WAIT_OBJECT_0 => break,
+                    WAIT_TIMEOUT if shutdown::is_requested() => {
+                        // The buffers must outlive the I/O, so wait for the cancel
+                        let mut transferred = 0;
+                        unsafe {
+                            CancelIoEx(self.handle.as_raw(), overlapped);
+                            GetOverlappedResult(
+                                self.handle.as_raw(),
+                                overlapped,
+                                &mut transferred,
+                                TRUE,
+                            );
+                        }
+                        return Completion::Cancelled;
+                    }
+                    WAIT_TIMEOUT => {}
+                    _ => return Completion::Failed(unsafe { GetLastError() }),
+                }
+            }
+        }
+
+        let mut transferred = 0;
+        let finished = unsafe {
+            GetOverlappedResult(self.handle.as_raw(), overlapped, &mut transferred, FALSE)
+        };
+        if finished == FALSE {
+            return Completion::Failed(unsafe { GetLastError() });
+        }
+        Completion::Done(transferred)
+    }
+
+    /// Wait for a client, returning `false` on a shutdown request
+    fn connect(&self) -> Result<bool> {
+        let mut overlapped = self.overlapped();
+        let started = unsafe { ConnectNamedPipe(self.handle.as_raw(), &mut overlapped) };
+        if started == FALSE && unsafe { GetLastError() } == ERROR_PIPE_CONNECTED {
+            // The client connected between creating and connecting the pipe
+            return Ok(true);
+        }
+        match self.finish(&mut overlapped, started) {
+            Completion::Done(_) => Ok(true),
+            Completion::Cancelled => Ok(false),
+            Completion::Failed(code) => Err(Self::error(&self.name, code)),
+        }
+    }
+
+    /// Read the next message into `buffer`, returning its length or `None`
+    /// once the client is gone or a shutdown is requested
+    fn read(&self, buffer: &mut [u8]) -> Result<Option<usize>> {
+        let mut overlapped = self.overlapped();
+        let started = unsafe {
+            ReadFile(
+                self.handle.as_raw(),
+                buffer.as_mut_ptr().cast(),
+                buffer.len() as DWORD,
+                ptr::null_mut(),
+                &mut overlapped,
+            )
+        };
+        match self.finish(&mut overlapped, started) {
+            Completion::Done(read) => Ok(Some(read as usize)),
+            Completion::Cancelled | Completion::Failed(ERROR_BROKEN_PIPE) => Ok(None),
+            Completion::Failed(ERROR_MORE_DATA) => Err(AppError::CommandPipeFailed {
+                name: self.name.clone(),
+                source: std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    format!("command longer than {} bytes", buffer.len()),
+                ),
+            }),
+            Completion::Failed(code) => Err(Self::error(&self.name, code)),
+        }
+    }
+
+    /// Send one message, returning `false` once the client is gone or a
+    /// shutdown is requested
+    fn write(&self, message: &[u8]) -> Result<bool> {
+        let mut overlapped = self.overlapped();
+        let started = unsafe {
+            WriteFile(
+                self.handle.as_raw(),
+                message.as_ptr().cast(),
+                message.len() as DWORD,
+                ptr::null_mut(),
+                &mut overlapped,
+            )
+        };
+        match self.finish(&mut overlapped, started) {
+            Completion::Done(_) => Ok(true),
+            Completion::Cancelled | Completion::Failed(ERROR_BROKEN_PIPE) => Ok(false),
+            Completion::Failed(code) => Err(Self::error(&self.name, code)),
+        }
+    }
+
+    /// Answer the connected client until it disconnects
+    fn serve_client(&self) -> Result<()> {
+        let mut buffer = [0u8; MAX_COMMAND_LEN];
+        while let Some(len) = self.read(&mut buffer)? {
+            let reply = respond(&buffer[..len]);
+            if !self.write(reply.as_bytes())? {
+                break;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Create the command pipe and start a thread answering its clients; the
+/// thread runs until a shutdown is requested
+///
+/// Fails when another process, such as a second game client, already
+/// serves the pipe.
+pub fn spawn() -> Result<JoinHandle<()>> {
+    let pipe = CommandPipe::create(COMMAND_PIPE_NAME)?;
+    thread::Builder::new()
+        .name("wuwa-sig-ipc".to_string())
+        .spawn(move || {
+            while !shutdown::is_requested() {
+                match pipe.connect() {
+                    Ok(true) => {}
+                    Ok(false) => break,
+                    Err(e) => {
+                        Logger::warning(&format!("Command pipe stopped: {}", e));
+                        break;
+                    }
+                }
+                if let Err(e) = pipe.serve_client() {
+                    Logger::warning(&format!("Command pipe client dropped: {}", e));
+                }
+                unsafe { DisconnectNamedPipe(pipe.handle.as_raw()) };
+            }
+        })
+        .map_err(|source| AppError::CommandPipeFailed {
+            name: COMMAND_PIPE_NAME.to_string(),
+            source,
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_commands() {
+        assert_eq!("status".parse(), Ok(Command::Status));
+        assert_eq!(" HOOKS \r\n".parse(), Ok(Command::Hooks));
+        assert_eq!(
+            "toggle pak_file_check".parse(),
+            Ok(Command::Toggle("pak_file_check".to_string()))
+        );
+        assert_eq!("level hook".parse(), Ok(Command::Level(LogLevel::Hook)));
+        assert_eq!("stats".parse(), Ok(Command::Stats));
+        assert_eq!("unload".parse(), Ok(Command::Unload));
+
+        for invalid in [
+            "",
+            "toggle",
+            "level loud",
+            "status now",
+            "toggle a b",
+            "reload",
+        ] {
+            assert!(invalid.parse::<Command>().is_err(), "{:?}", invalid);
+        }
+    }
+
+    #[test]
+    fn test_failures_reply_with_error() {
+        assert_eq!(respond(b"fly"), "error: unknown command 'fly'\n");
+        assert_eq!(respond(&[0xFF]), "error: commands must be UTF-8\n");
+        assert_eq!(
+            respond(b"toggle no_such_hook"),
+            "error: no hook named 'no_such_hook'\n"
+        );
+    }
+
+    #[test]
+    fn test_status_reply_names_the_build() {
+        let reply = respond(b"status");
+        assert!(reply.starts_with("phase: "), "{}", reply);
+        assert!(reply.contains(&format!("build: {}", BuildInfo::CURRENT)));
+    }
+}
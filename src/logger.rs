@@ -1,8 +1,39 @@
-use crossterm::style::{Color, Print, ResetColor, SetForegroundColor};
-use crossterm::{execute, queue};
-use std::io::{self, Write, stdout};
-use std::sync::{Arc, Mutex};
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::io::Write;
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use winapi::shared::minwindef::DWORD;
+use winapi::um::processthreadsapi::GetCurrentThreadId;
+
+use crate::constants::constants::RECENT_LOG_CAPACITY;
+use crate::error::{AppError, Result};
+
+pub mod category;
+pub mod clock;
+pub mod crash;
+pub mod facade;
+pub mod limit;
+pub mod panics;
+pub mod sinks;
+pub mod theme;
+pub mod worker;
+
+pub use category::{LogCategory, LogFilters};
+pub use clock::{CivilTime, mark_attach, since_attach};
+pub use crash::{install_crash_handler, log_exit, uninstall_crash_handler};
+pub use limit::{BurstLimiter, LogEveryN, LogOnce};
+pub use panics::install_panic_hook;
+pub use sinks::{
+    CallbackSink, ConsoleSink, DebuggerSink, LogRecord, LogSink, RingBufferSink, WriterSink,
+};
+pub use theme::ColorTheme;
+use worker::LogQueue;
+pub use worker::{LogWorker, OverflowPolicy};
+
+/// Sinks of a logger, shared with its worker thread
+type SinkList = RwLock<Vec<Arc<dyn LogSink>>>;
 
 /// Log levels for structured logging
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
@@ -16,13 +47,112 @@ pub enum LogLevel {
     Bypass = 6,
 }
 
+impl LogLevel {
+    /// Every level, from the least to the most verbose
+    pub const ALL: [Self; 7] = [
+        Self::Error,
+        Self::Warning,
+        Self::Info,
+        Self::Success,
+        Self::Scan,
+        Self::Hook,
+        Self::Bypass,
+    ];
+
+    /// Name used in configuration
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Error => "error",
+            Self::Warning => "warning",
+            Self::Info => "info",
+            Self::Success => "success",
+            Self::Scan => "scan",
+            Self::Hook => "hook",
+            Self::Bypass => "bypass",
+        }
+    }
+
+    /// The next more verbose level, wrapping from bypass back to error
+    pub fn next(self) -> Self {
+        match self {
+            Self::Error => Self::Warning,
+            Self::Warning => Self::Info,
+            Self::Info => Self::Success,
+            Self::Success => Self::Scan,
+            Self::Scan => Self::Hook,
+            Self::Hook => Self::Bypass,
+            Self::Bypass => Self::Error,
+        }
+    }
+}
+
+impl FromStr for LogLevel {
+    type Err = AppError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|level| level.as_str().eq_ignore_ascii_case(s))
+            .ok_or_else(|| AppError::InvalidConfig {
+                field: "log_level".to_string(),
+                reason: format!("unknown log level '{}'", s),
+            })
+    }
+}
+
+/// Layout of each logged line
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LogFormat {
+    /// `[LEVEL] message`, optionally colored
+    #[default]
+    Text,
+    /// One JSON object per line with `timestamp` (Unix milliseconds),
+    /// `elapsed_ms` (since the DLL was attached), `level`, `thread` (the
+    /// Windows thread id), `message` and, when given, `fields`; never colored
+    Json,
+}
+
+impl LogFormat {
+    /// Name used in configuration
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Text => "text",
+            Self::Json => "json",
+        }
+    }
+}
+
+impl FromStr for LogFormat {
+    type Err = AppError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        [Self::Text, Self::Json]
+            .into_iter()
+            .find(|format| format.as_str().eq_ignore_ascii_case(s))
+            .ok_or_else(|| AppError::InvalidConfig {
+                field: "log_format".to_string(),
+                reason: format!("unknown log format '{}'", s),
+            })
+    }
+}
+
 /// Configuration for the logger
 #[derive(Debug, Clone)]
 pub struct LoggerConfig {
     pub min_level: LogLevel,
     pub show_timestamps: bool,
     pub show_thread_ids: bool,
+    /// Color console lines by level; the console sink still leaves colors
+    /// out for redirected output and under `NO_COLOR`
     pub colored_output: bool,
+    /// Color of each level
+    pub theme: ColorTheme,
+    pub format: LogFormat,
+    /// Log a run of identical messages once, followed by how often it
+    /// repeated
+    pub collapse_repeats: bool,
+    /// Categories logged at their own level instead of `min_level`
+    pub filters: LogFilters,
 }
 
 impl Default for LoggerConfig {
@@ -31,34 +161,63 @@ impl Default for LoggerConfig {
             min_level: LogLevel::Info,
             show_timestamps: false,
             show_thread_ids: false,
-            colored_output: false,
+            colored_output: true,
+            theme: ColorTheme::default(),
+            format: LogFormat::Text,
+            collapse_repeats: true,
+            filters: LogFilters::default(),
         }
     }
 }
 
+/// The last message logged and how often it has repeated since
+struct Repeats {
+    level: LogLevel,
+    message: String,
+    count: u64,
+}
+
+/// Whether a message continues a run of identical ones
+enum Collapse {
+    /// Same as the last message; only counted
+    Repeat,
+    /// A new message, after a run that repeated `count` times at `level`
+    New(Option<(LogLevel, u64)>),
+}
+
 /// High-performance structured logger with thread safety
+///
+/// Each record is formatted once and written to every sink in the list,
+/// which starts with the console. The last lines are also kept in memory for
+/// [`Logger::recent`].
 pub struct Logger {
     config: Arc<Mutex<LoggerConfig>>,
-    stdout: Arc<Mutex<std::io::Stdout>>,
-    stderr: Arc<Mutex<std::io::Stderr>>,
+    sinks: Arc<SinkList>,
+    ring: Arc<RingBufferSink>,
+    /// Thread formatting and writing records, `None` to do it on the
+    /// calling thread
+    worker: Mutex<Option<LogWorker>>,
+    last: Mutex<Option<Repeats>>,
 }
 
 impl Logger {
     /// Create a new logger with default configuration
     pub fn new() -> Self {
-        Self {
-            config: Arc::new(Mutex::new(LoggerConfig::default())),
-            stdout: Arc::new(Mutex::new(stdout())),
-            stderr: Arc::new(Mutex::new(io::stderr())),
-        }
+        Self::with_config(LoggerConfig::default())
     }
 
     /// Create a new logger with custom configuration
     pub fn with_config(config: LoggerConfig) -> Self {
+        let ring = Arc::new(RingBufferSink::new(RECENT_LOG_CAPACITY));
         Self {
             config: Arc::new(Mutex::new(config)),
-            stdout: Arc::new(Mutex::new(stdout())),
-            stderr: Arc::new(Mutex::new(io::stderr())),
+            sinks: Arc::new(RwLock::new(vec![
+                Arc::new(ConsoleSink::new()),
+                Arc::clone(&ring) as Arc<dyn LogSink>,
+            ])),
+            ring,
+            worker: Mutex::new(None),
+            last: Mutex::new(None),
         }
     }
 
@@ -69,118 +228,252 @@ impl Logger {
         }
     }
 
-    /// Log a message with the specified level
-    pub fn log(&self, level: LogLevel, msg: &str) {
-        let config = match self.config.lock() {
-            Ok(config) => config.clone(),
-            Err(_) => return, // If we can't get the config, skip logging
-        };
+    /// Change only the minimum level logged
+    pub fn set_min_level(&self, level: LogLevel) {
+        if let Ok(mut current_config) = self.config.lock() {
+            current_config.min_level = level;
+        }
+    }
 
-        if level > config.min_level {
-            return;
+    /// Most verbose level logged in categories without a level of their own
+    pub fn min_level(&self) -> LogLevel {
+        self.config
+            .lock()
+            .map_or(LogLevel::Info, |config| config.min_level)
+    }
+
+    /// Give `category` its own most verbose level, or `None` to have it
+    /// follow the minimum level again
+    pub fn set_category_level(&self, category: LogCategory, level: Option<LogLevel>) {
+        if let Ok(mut current_config) = self.config.lock() {
+            current_config.filters.set(category, level);
         }
+    }
 
-        let formatted_msg = self.format_message(level, msg, &config);
+    /// Replace every category level
+    pub fn set_filters(&self, filters: LogFilters) {
+        if let Ok(mut current_config) = self.config.lock() {
+            current_config.filters = filters;
+        }
+    }
 
-        match level {
-            LogLevel::Error => self.log_to_stderr(&formatted_msg, &config),
-            _ => self.log_to_stdout(&formatted_msg, level, &config),
+    /// Most verbose level logged in `category`
+    pub fn threshold(&self, category: LogCategory) -> LogLevel {
+        self.config.lock().map_or(LogLevel::Info, |config| {
+            config.filters.threshold(category, config.min_level)
+        })
+    }
+
+    /// Turn console colors on or off and choose them
+    pub fn set_colors(&self, enabled: bool, theme: ColorTheme) {
+        if let Ok(mut current_config) = self.config.lock() {
+            current_config.colored_output = enabled;
+            current_config.theme = theme;
         }
     }
 
-    /// Format a log message with timestamp and level information
-    fn format_message(&self, level: LogLevel, msg: &str, config: &LoggerConfig) -> String {
-        let mut formatted = String::new();
+    /// Change only the layout of logged lines
+    pub fn set_format(&self, format: LogFormat) {
+        if let Ok(mut current_config) = self.config.lock() {
+            current_config.format = format;
+        }
+    }
 
-        if config.show_timestamps {
-            if let Ok(duration) = SystemTime::now().duration_since(UNIX_EPOCH) {
-                let timestamp = duration.as_secs();
-                formatted.push_str(&format!("[{}] ", timestamp));
-            }
+    /// Turn the time columns of text lines on or off
+    pub fn set_timestamps(&self, show: bool) {
+        if let Ok(mut current_config) = self.config.lock() {
+            current_config.show_timestamps = show;
         }
+    }
 
-        if config.show_thread_ids {
-            let thread_id = std::thread::current().id();
-            formatted.push_str(&format!("[T{:?}] ", thread_id));
+    /// Write every later record to `sink` as well
+    pub fn add_sink(&self, sink: Arc<dyn LogSink>) {
+        if let Ok(mut sinks) = self.sinks.write() {
+            sinks.push(sink);
         }
+    }
 
-        let level_str = match level {
-            LogLevel::Error => "ERROR",
-            LogLevel::Warning => "WARNING",
-            LogLevel::Info => "INFO",
-            LogLevel::Success => "SUCCESS",
-            LogLevel::Scan => "SCAN",
-            LogLevel::Hook => "HOOK",
-            LogLevel::Bypass => "BYPASS",
-        };
+    /// Replace the list of sinks; the in-memory ring of recent lines is
+    /// always kept
+    pub fn set_sinks(&self, sinks: Vec<Arc<dyn LogSink>>) {
+        if let Ok(mut current) = self.sinks.write() {
+            *current = sinks;
+            current.push(Arc::clone(&self.ring) as Arc<dyn LogSink>);
+        }
+    }
 
-        formatted.push_str(&format!("[{}] {}", level_str, msg));
-        formatted
+    /// Up to `count` of the most recently written lines, oldest first
+    pub fn recent(&self, count: usize) -> Vec<String> {
+        self.ring.recent(count)
     }
 
-    /// Log to stdout with optional coloring
-    fn log_to_stdout(&self, msg: &str, level: LogLevel, config: &LoggerConfig) {
-        let mut stdout_guard = match self.stdout.lock() {
-            Ok(guard) => guard,
-            Err(_) => {
-                // If we can't get the lock, just print to stdout directly
-                println!("{}", msg);
-                return;
+    /// Write every line kept in memory to the file at `path`, replacing it
+    pub fn dump_recent(&self, path: &Path) -> Result<()> {
+        self.flush();
+        let export = |path: &Path| -> std::io::Result<()> {
+            let mut file = std::fs::File::create(path)?;
+            for line in self.recent(RECENT_LOG_CAPACITY) {
+                writeln!(file, "{}", line)?;
             }
+            file.flush()
         };
+        export(path).map_err(|source| AppError::LogExportFailed {
+            path: path.display().to_string(),
+            source,
+        })
+    }
+
+    /// Hand formatting and writing to a worker thread, queueing up to
+    /// `capacity` records; `policy` decides what happens when it is full
+    pub fn start_worker(&self, capacity: usize, policy: OverflowPolicy) -> Result<()> {
+        let worker = LogWorker::spawn(Arc::clone(&self.sinks), capacity, policy)?;
+        if let Ok(mut current) = self.worker.lock() {
+            *current = Some(worker);
+        }
+        Ok(())
+    }
 
-        if config.colored_output {
-            let color = self.get_color_for_level(level);
-            let _ = execute!(
-                *stdout_guard,
-                SetForegroundColor(color),
-                Print(msg),
-                Print("\n"),
-                ResetColor
+    /// Write the queued records and go back to logging on the calling thread
+    pub fn stop_worker(&self) {
+        let worker = self.worker.lock().ok().and_then(|mut worker| worker.take());
+        drop(worker);
+    }
+
+    /// Write queued records, then flush every sink
+    ///
+    /// A pending count of repeated messages is logged first. On the writer
+    /// thread, which cannot wait for itself, only the sinks are flushed.
+    pub fn flush(&self) {
+        let repeated = self
+            .last
+            .lock()
+            .ok()
+            .and_then(|mut last| last.take())
+            .filter(|last| last.count > 0);
+        if let (Some(repeated), Ok(config)) = (repeated, self.config.lock()) {
+            let message = repeated_message(repeated.count);
+            self.dispatch(
+                Entry::capture(repeated.level, &message, &[]),
+                config.clone(),
             );
-        } else {
-            let _ = writeln!(*stdout_guard, "{}", msg);
         }
+
+        if let Some(queue) = self.queue()
+            && !queue.is_current()
+        {
+            queue.flush();
+            return;
+        }
+        flush_sinks(&self.sinks);
     }
 
-    /// Log to stderr with optional coloring
-    fn log_to_stderr(&self, msg: &str, config: &LoggerConfig) {
-        let mut stderr_guard = match self.stderr.lock() {
-            Ok(guard) => guard,
-            Err(_) => {
-                // If we can't get the lock, just print to stderr directly
-                eprintln!("{}", msg);
-                return;
-            }
+    /// Write one last record straight to the sinks and flush them, for when
+    /// the process is about to go away
+    ///
+    /// With `drain` the queued records are written first. Without it the
+    /// writer thread is bypassed, as at process exit it may already be gone.
+    /// Locks are only tried, since the caller may have crashed holding one:
+    /// a sink whose lock is held misses the record.
+    pub fn write_final(&self, level: LogLevel, msg: &str, drain: bool) {
+        if drain {
+            self.flush();
+        }
+        let config = self
+            .config
+            .try_lock()
+            .map_or_else(|_| LoggerConfig::default(), |config| config.clone());
+        Entry::capture(level, msg, &[]).write_final(&config, &self.sinks);
+    }
+
+    /// Log a message with the specified level
+    pub fn log(&self, level: LogLevel, msg: &str) {
+        self.log_fields(level, msg, &[]);
+    }
+
+    /// Log a message with named values, kept apart from the message so
+    /// JSON consumers need not parse it
+    ///
+    /// Text lines leave the fields out; the message should already name
+    /// them for a reader.
+    pub fn log_fields(&self, level: LogLevel, msg: &str, fields: &[(&str, &str)]) {
+        self.log_in(LogCategory::for_level(level), level, msg, fields);
+    }
+
+    /// Log a message in `category`, filtered by that category's level
+    /// rather than the one its level implies
+    pub fn log_in(
+        &self,
+        category: LogCategory,
+        level: LogLevel,
+        msg: &str,
+        fields: &[(&str, &str)],
+    ) {
+        let config = match self.config.lock() {
+            Ok(config) => config.clone(),
+            Err(_) => return, // If we can't get the config, skip logging
         };
 
-        if config.colored_output {
-            let _ = queue!(
-                *stderr_guard,
-                SetForegroundColor(Color::Red),
-                Print(msg),
-                Print("\n"),
-                ResetColor
-            );
-            let _ = stderr_guard.flush();
-        } else {
-            let _ = writeln!(*stderr_guard, "{}", msg);
+        if level > config.filters.threshold(category, config.min_level) {
+            return;
+        }
+
+        if config.collapse_repeats {
+            match self.collapse(level, msg) {
+                Collapse::Repeat => return,
+                Collapse::New(Some((level, count))) => {
+                    let message = repeated_message(count);
+                    self.dispatch(Entry::capture(level, &message, &[]), config.clone());
+                }
+                Collapse::New(None) => {}
+            }
+        }
+
+        self.dispatch(Entry::capture(level, msg, fields), config);
+    }
+
+    /// Count `msg` if it repeats the last message, otherwise make it the
+    /// last one
+    fn collapse(&self, level: LogLevel, msg: &str) -> Collapse {
+        let Ok(mut last) = self.last.lock() else {
+            return Collapse::New(None);
+        };
+        if let Some(last) = last.as_mut()
+            && last.level == level
+            && last.message == msg
+        {
+            last.count += 1;
+            return Collapse::Repeat;
         }
+
+        let previous = last.replace(Repeats {
+            level,
+            message: msg.to_string(),
+            count: 0,
+        });
+        Collapse::New(
+            previous
+                .filter(|previous| previous.count > 0)
+                .map(|previous| (previous.level, previous.count)),
+        )
     }
 
-    /// Get the appropriate color for a log level
-    fn get_color_for_level(&self, level: LogLevel) -> Color {
-        match level {
-            LogLevel::Error => Color::Red,
-            LogLevel::Warning => Color::Yellow,
-            LogLevel::Info => Color::Cyan,
-            LogLevel::Success => Color::Green,
-            LogLevel::Scan => Color::Yellow,
-            LogLevel::Hook => Color::Magenta,
-            LogLevel::Bypass => Color::Green,
+    /// Hand an entry to the worker, or write it here without one
+    fn dispatch(&self, entry: Entry, config: LoggerConfig) {
+        match self.queue() {
+            Some(queue) => queue.submit(entry, config),
+            None => entry.write(&config, &self.sinks),
         }
     }
 
+    /// The worker's queue, taken out of the lock so a full queue never
+    /// blocks `stop_worker` or other loggers
+    fn queue(&self) -> Option<LogQueue> {
+        self.worker
+            .lock()
+            .ok()
+            .and_then(|worker| worker.as_ref().and_then(LogWorker::queue))
+    }
+
     // Convenience methods for different log levels
     pub fn info_instance(&self, msg: &str) {
         self.log(LogLevel::Info, msg);
@@ -211,6 +504,168 @@ impl Logger {
     }
 }
 
+/// Message closing a run of identical messages
+fn repeated_message(count: u64) -> String {
+    format!("(last message repeated {} more time(s))", count)
+}
+
+/// A message captured on the thread that logged it, formatted when written
+struct Entry {
+    level: LogLevel,
+    message: String,
+    fields: Vec<(String, String)>,
+    time: SystemTime,
+    /// Time since the DLL was attached
+    elapsed: Duration,
+    /// Windows id of the logging thread, as debuggers show it
+    thread_id: DWORD,
+}
+
+impl Entry {
+    fn capture(level: LogLevel, message: &str, fields: &[(&str, &str)]) -> Self {
+        Self {
+            level,
+            message: message.to_string(),
+            fields: fields
+                .iter()
+                .map(|(key, value)| (key.to_string(), value.to_string()))
+                .collect(),
+            time: SystemTime::now(),
+            elapsed: clock::since_attach(),
+            thread_id: unsafe { GetCurrentThreadId() },
+        }
+    }
+
+    /// Format the entry as `config` asks and write it to every sink
+    fn write(&self, config: &LoggerConfig, sinks: &SinkList) {
+        self.with_record(config, |record| {
+            if let Ok(sinks) = sinks.read() {
+                for sink in sinks.iter() {
+                    sink.write(record);
+                }
+            }
+        });
+    }
+
+    /// Like [`write`](Self::write), without blocking on any lock, see
+    /// [`LogSink::write_final`]
+    fn write_final(&self, config: &LoggerConfig, sinks: &SinkList) {
+        self.with_record(config, |record| {
+            if let Ok(sinks) = sinks.try_read() {
+                for sink in sinks.iter() {
+                    sink.write_final(record);
+                }
+            }
+        });
+    }
+
+    /// Format the entry as `config` asks and pass the record to `write`
+    fn with_record(&self, config: &LoggerConfig, write: impl FnOnce(&LogRecord<'_>)) {
+        let line = match config.format {
+            LogFormat::Text => format_text(self, config),
+            LogFormat::Json => format_json(self),
+        };
+        let fields: Vec<(&str, &str)> = self
+            .fields
+            .iter()
+            .map(|(key, value)| (key.as_str(), value.as_str()))
+            .collect();
+        let record = LogRecord {
+            level: self.level,
+            message: &self.message,
+            fields: &fields,
+            line: &line,
+            // Escape codes would break the one-object-per-line output
+            colored: config.colored_output && config.format == LogFormat::Text,
+            color: config.theme.color(self.level),
+        };
+        write(&record);
+    }
+}
+
+/// Flush every sink in the list
+fn flush_sinks(sinks: &SinkList) {
+    if let Ok(sinks) = sinks.read() {
+        sinks.iter().for_each(|sink| sink.flush());
+    }
+}
+
+/// Format a log message with timestamp and level information
+fn format_text(entry: &Entry, config: &LoggerConfig) -> String {
+    let mut formatted = String::new();
+
+    if config.show_timestamps {
+        formatted.push_str(&format!(
+            "[{} {}] ",
+            CivilTime::local(entry.time),
+            clock::format_elapsed(entry.elapsed)
+        ));
+    }
+
+    if config.show_thread_ids {
+        formatted.push_str(&format!("[T{}] ", entry.thread_id));
+    }
+
+    let level_str = match entry.level {
+        LogLevel::Error => "ERROR",
+        LogLevel::Warning => "WARNING",
+        LogLevel::Info => "INFO",
+        LogLevel::Success => "SUCCESS",
+        LogLevel::Scan => "SCAN",
+        LogLevel::Hook => "HOOK",
+        LogLevel::Bypass => "BYPASS",
+    };
+
+    formatted.push_str(&format!("[{}] {}", level_str, entry.message));
+    formatted
+}
+
+/// Format one log line as a JSON object
+fn format_json(entry: &Entry) -> String {
+    let timestamp = entry
+        .time
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_millis());
+
+    let mut line = format!(
+        "{{\"timestamp\":{},\"elapsed_ms\":{},\"level\":{},\"thread\":{},\"message\":{}",
+        timestamp,
+        entry.elapsed.as_millis(),
+        json_string(entry.level.as_str()),
+        entry.thread_id,
+        json_string(&entry.message)
+    );
+    if !entry.fields.is_empty() {
+        let members: Vec<String> = entry
+            .fields
+            .iter()
+            .map(|(key, value)| format!("{}:{}", json_string(key), json_string(value)))
+            .collect();
+        line.push_str(&format!(",\"fields\":{{{}}}", members.join(",")));
+    }
+    line.push('}');
+    line
+}
+
+/// Quote `value` as a JSON string
+fn json_string(value: &str) -> String {
+    let mut quoted = String::with_capacity(value.len() + 2);
+    quoted.push('"');
+    for ch in value.chars() {
+        match ch {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            ch if u32::from(ch) < 0x20 => quoted.push_str(&format!("\\u{:04x}", u32::from(ch))),
+            ch => quoted.push(ch),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
 // Global logger instance
 static GLOBAL_LOGGER: std::sync::OnceLock<Logger> = std::sync::OnceLock::new();
 
@@ -224,6 +679,94 @@ pub fn init_global_logger_with_config(config: LoggerConfig) {
     GLOBAL_LOGGER.set(Logger::with_config(config)).ok();
 }
 
+/// Change the minimum level of the global logger
+pub fn set_global_min_level(level: LogLevel) {
+    get_global_logger().set_min_level(level);
+    sync_log_max_level();
+}
+
+/// Switch the global logger to the next more verbose level, wrapping from
+/// the most verbose back to errors only, and announce it at that level
+pub fn cycle_global_level() -> LogLevel {
+    let level = get_global_logger().min_level().next();
+    set_global_min_level(level);
+    let message = format!("Log level is now {}", level.as_str());
+    get_global_logger().log_in(LogCategory::General, level, &message, &[]);
+    level
+}
+
+/// Change every category level of the global logger
+pub fn set_global_filters(filters: LogFilters) {
+    get_global_logger().set_filters(filters);
+    sync_log_max_level();
+}
+
+/// Change the level of one category of the global logger, see
+/// [`Logger::set_category_level`]
+pub fn set_global_category_level(category: LogCategory, level: Option<LogLevel>) {
+    get_global_logger().set_category_level(category, level);
+    sync_log_max_level();
+}
+
+/// Let the `log` crate skip records its backend would discard anyway
+fn sync_log_max_level() {
+    let level = get_global_logger().threshold(LogCategory::General);
+    log::set_max_level(facade::level_filter(level));
+}
+
+/// Change the line layout of the global logger
+pub fn set_global_format(format: LogFormat) {
+    get_global_logger().set_format(format);
+}
+
+/// Turn the time columns of the global logger's text lines on or off
+pub fn set_global_timestamps(show: bool) {
+    get_global_logger().set_timestamps(show);
+}
+
+/// Turn the global logger's console colors on or off and choose them
+pub fn set_global_colors(enabled: bool, theme: ColorTheme) {
+    get_global_logger().set_colors(enabled, theme);
+}
+
+/// Replace the sinks of the global logger, see [`Logger::set_sinks`]
+pub fn set_global_sinks(sinks: Vec<Arc<dyn LogSink>>) {
+    get_global_logger().set_sinks(sinks);
+}
+
+/// Write every later record of the global logger to `sink` as well
+pub fn add_global_sink(sink: Arc<dyn LogSink>) {
+    get_global_logger().add_sink(sink);
+}
+
+/// The recent lines of the global logger, see [`Logger::recent`]
+pub fn global_recent(count: usize) -> Vec<String> {
+    get_global_logger().recent(count)
+}
+
+/// Write the recent lines of the global logger to `path`, see
+/// [`Logger::dump_recent`]
+pub fn dump_global_recent(path: &Path) -> Result<()> {
+    get_global_logger().dump_recent(path)
+}
+
+/// Move formatting and writing of the global logger to a worker thread,
+/// see [`Logger::start_worker`]
+pub fn start_global_worker(capacity: usize, policy: OverflowPolicy) -> Result<()> {
+    get_global_logger().start_worker(capacity, policy)
+}
+
+/// Write the queued records of the global logger and stop its worker
+/// thread, see [`Logger::stop_worker`]
+pub fn stop_global_worker() {
+    get_global_logger().stop_worker();
+}
+
+/// Get the global logger instance, if something initialized it
+fn initialized_global_logger() -> Option<&'static Logger> {
+    GLOBAL_LOGGER.get()
+}
+
 /// Get the global logger instance
 fn get_global_logger() -> &'static Logger {
     GLOBAL_LOGGER.get_or_init(|| Logger::new())
@@ -258,6 +801,17 @@ impl Logger {
     pub fn bypass(msg: &str) {
         get_global_logger().log(LogLevel::Bypass, msg);
     }
+
+    /// Log through the global logger with named values, see
+    /// [`Logger::log_fields`]
+    pub fn event(level: LogLevel, msg: &str, fields: &[(&str, &str)]) {
+        get_global_logger().log_fields(level, msg, fields);
+    }
+
+    /// Log to the global logger in `category`, see [`Logger::log_in`]
+    pub fn event_in(category: LogCategory, level: LogLevel, msg: &str, fields: &[(&str, &str)]) {
+        get_global_logger().log_in(category, level, msg, fields);
+    }
 }
 
 #[cfg(test)]
@@ -271,6 +825,27 @@ mod tests {
         assert!(LogLevel::Scan < LogLevel::Hook);
     }
 
+    #[test]
+    fn test_log_level_round_trips_through_names() {
+        for level in [LogLevel::Error, LogLevel::Info, LogLevel::Bypass] {
+            assert_eq!(level.as_str().parse::<LogLevel>().unwrap(), level);
+        }
+        assert_eq!("WARNING".parse::<LogLevel>().unwrap(), LogLevel::Warning);
+        assert!("verbose".parse::<LogLevel>().is_err());
+    }
+
+    #[test]
+    fn test_log_level_next_visits_every_level() {
+        let mut level = LogLevel::Error;
+        let mut seen = vec![level];
+        while level.next() != LogLevel::Error {
+            level = level.next();
+            seen.push(level);
+        }
+        assert_eq!(seen.len(), 7);
+        assert!(seen.windows(2).all(|pair| pair[0] < pair[1]));
+    }
+
     #[test]
     fn test_logger_config_default() {
         let config = LoggerConfig::default();
@@ -294,6 +869,10 @@ mod tests {
             show_timestamps: false,
             show_thread_ids: true,
             colored_output: false,
+            theme: ColorTheme::default(),
+            format: LogFormat::Text,
+            collapse_repeats: true,
+            filters: LogFilters::default(),
         };
         let _logger = Logger::with_config(config);
         // Test that we can create a logger with custom config
@@ -326,6 +905,10 @@ mod tests {
             show_timestamps: false,
             show_thread_ids: false,
             colored_output: false,
+            theme: ColorTheme::default(),
+            format: LogFormat::Text,
+            collapse_repeats: true,
+            filters: LogFilters::default(),
         };
         let logger = Logger::with_config(config);
 
@@ -337,4 +920,150 @@ mod tests {
         logger.warning_instance("This should appear");
         logger.error_instance("This should appear");
     }
+
+    #[test]
+    fn test_log_format_round_trips_through_names() {
+        for format in [LogFormat::Text, LogFormat::Json] {
+            assert_eq!(format.as_str().parse::<LogFormat>().unwrap(), format);
+        }
+        assert!("xml".parse::<LogFormat>().is_err());
+    }
+
+    #[test]
+    fn test_json_line_escapes_and_carries_fields() {
+        let line = format_json(&Entry::capture(
+            LogLevel::Warning,
+            "pak \"a\"\\b\n",
+            &[("pak", "pakchunk0.pak"), ("result", "0x1")],
+        ));
+        assert!(line.starts_with("{\"timestamp\":"));
+        assert!(line.contains(r#""level":"warning""#));
+        let thread = format!(r#""thread":{},"#, unsafe { GetCurrentThreadId() });
+        assert!(line.contains(&thread));
+        assert!(line.contains(r#""message":"pak \"a\"\\b\n""#));
+        assert!(line.ends_with(r#""fields":{"pak":"pakchunk0.pak","result":"0x1"}}"#));
+        assert!(!line.contains('\n'));
+
+        assert!(!format_json(&Entry::capture(LogLevel::Info, "x", &[])).contains("fields"));
+        assert_eq!(json_string("\u{1}"), r#""\u0001""#);
+    }
+
+    #[test]
+    fn test_identical_messages_collapse_into_a_count() {
+        let logger = Logger::new();
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let lines = Arc::clone(&seen);
+        logger.set_sinks(vec![Arc::new(CallbackSink::new(
+            move |record: &LogRecord<'_>| lines.lock().unwrap().push(record.line.to_string()),
+        ))]);
+
+        for _ in 0..3 {
+            logger.info_instance("same");
+        }
+        logger.warning_instance("same");
+        logger.warning_instance("same");
+        logger.flush();
+        logger.info_instance("other");
+
+        assert_eq!(
+            *seen.lock().unwrap(),
+            [
+                "[INFO] same",
+                "[INFO] (last message repeated 2 more time(s))",
+                "[WARNING] same",
+                "[WARNING] (last message repeated 1 more time(s))",
+                "[INFO] other",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_recent_lines_survive_sink_changes() {
+        let logger = Logger::new();
+        logger.set_sinks(Vec::new());
+        logger.info_instance("first");
+        logger.warning_instance("second");
+        assert_eq!(logger.recent(1), ["[WARNING] second"]);
+
+        let path = std::env::temp_dir().join(format!("wuwa-sig-recent-{}.log", std::process::id()));
+        logger.dump_recent(&path).unwrap();
+        let text = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(text, "[INFO] first\n[WARNING] second\n");
+    }
+
+    #[test]
+    fn test_every_sink_receives_filtered_records() {
+        let logger = Logger::with_config(LoggerConfig {
+            min_level: LogLevel::Warning,
+            colored_output: true,
+            ..LoggerConfig::default()
+        });
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let sink = |seen: &Arc<Mutex<Vec<(String, bool)>>>| {
+            let seen = Arc::clone(seen);
+            Arc::new(CallbackSink::new(move |record: &LogRecord<'_>| {
+                seen.lock()
+                    .unwrap()
+                    .push((record.line.to_string(), record.colored));
+            })) as Arc<dyn LogSink>
+        };
+        logger.set_sinks(vec![sink(&seen), sink(&seen)]);
+
+        logger.info_instance("dropped");
+        logger.warning_instance("kept");
+        logger.set_format(LogFormat::Json);
+        logger.log_fields(LogLevel::Error, "json", &[("pak", "a.pak")]);
+
+        let seen = seen.lock().unwrap();
+        assert_eq!(seen.len(), 4);
+        assert_eq!(seen[0], ("[WARNING] kept".to_string(), true));
+        assert_eq!(seen[0], seen[1]);
+        assert!(seen[2].0.contains(r#""fields":{"pak":"a.pak"}"#));
+        assert!(!seen[2].1);
+    }
+
+    #[test]
+    fn test_category_levels_replace_the_minimum() {
+        let logger = Logger::new();
+        logger.set_min_level(LogLevel::Bypass);
+        logger.set_category_level(LogCategory::Scan, Some(LogLevel::Warning));
+        logger.set_category_level(LogCategory::Config, Some(LogLevel::Error));
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let lines = Arc::clone(&seen);
+        logger.set_sinks(vec![Arc::new(CallbackSink::new(
+            move |record: &LogRecord<'_>| {
+                lines.lock().unwrap().push(record.line.to_string());
+            },
+        ))]);
+
+        logger.scan_instance("scan detail");
+        logger.log_in(LogCategory::Scan, LogLevel::Warning, "scan warning", &[]);
+        logger.hook_instance("hook detail");
+        logger.log_in(LogCategory::Config, LogLevel::Info, "reloaded", &[]);
+        assert_eq!(logger.threshold(LogCategory::Pak), LogLevel::Bypass);
+
+        assert_eq!(
+            *seen.lock().unwrap(),
+            ["[WARNING] scan warning", "[HOOK] hook detail"]
+        );
+    }
+
+    #[test]
+    fn test_final_record_skips_filters_and_drains_the_worker() {
+        let logger = Logger::new();
+        logger.set_min_level(LogLevel::Error);
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let lines = Arc::clone(&seen);
+        logger.set_sinks(vec![Arc::new(CallbackSink::new(
+            move |record: &LogRecord<'_>| {
+                lines.lock().unwrap().push(record.line.to_string());
+            },
+        ))]);
+        logger.start_worker(16, OverflowPolicy::Block).unwrap();
+
+        logger.error_instance("queued");
+        logger.write_final(LogLevel::Info, "exiting", true);
+        assert_eq!(*seen.lock().unwrap(), ["[ERROR] queued", "[INFO] exiting"]);
+    }
 }
@@ -0,0 +1,155 @@
+//! Per-category log levels
+//!
+//! The scan, hook and bypass levels rank below info by detail, not by what
+//! they concern, so one `min_level` cannot quiet the scanner while keeping
+//! the hook lines. Each message therefore also belongs to a
+//! [`LogCategory`], and [`LogFilters`] can give a category a level of its
+//! own in place of the logger's minimum.
+
+use std::str::FromStr;
+
+use super::LogLevel;
+use crate::error::{AppError, Result};
+
+/// Part of the DLL a message comes from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LogCategory {
+    /// Anything not covered below, including records of the `log` crate
+    General,
+    /// Module and signature scanning
+    Scan,
+    /// Installing and removing hooks
+    Hook,
+    /// Verdicts on individual paks
+    Pak,
+    /// Loading, reloading and updating the configuration
+    Config,
+}
+
+impl LogCategory {
+    pub const ALL: [Self; 5] = [
+        Self::General,
+        Self::Scan,
+        Self::Hook,
+        Self::Pak,
+        Self::Config,
+    ];
+
+    /// Name used in configuration
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::General => "general",
+            Self::Scan => "scan",
+            Self::Hook => "hook",
+            Self::Pak => "pak",
+            Self::Config => "config",
+        }
+    }
+
+    /// Category of a message logged without one, following its level
+    pub fn for_level(level: LogLevel) -> Self {
+        match level {
+            LogLevel::Scan => Self::Scan,
+            LogLevel::Hook => Self::Hook,
+            LogLevel::Bypass => Self::Pak,
+            LogLevel::Error | LogLevel::Warning | LogLevel::Info | LogLevel::Success => {
+                Self::General
+            }
+        }
+    }
+
+    fn index(self) -> usize {
+        self as usize
+    }
+}
+
+impl FromStr for LogCategory {
+    type Err = AppError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|category| category.as_str().eq_ignore_ascii_case(s))
+            .ok_or_else(|| AppError::InvalidConfig {
+                field: "log_filters".to_string(),
+                reason: format!("unknown log category '{}'", s),
+            })
+    }
+}
+
+/// Levels of the categories that do not follow the logger's minimum
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LogFilters {
+    levels: [Option<LogLevel>; LogCategory::ALL.len()],
+}
+
+impl LogFilters {
+    /// Give `category` its own most verbose level, or `None` to have it
+    /// follow the logger's minimum again
+    pub fn set(&mut self, category: LogCategory, level: Option<LogLevel>) {
+        self.levels[category.index()] = level;
+    }
+
+    /// The level set for `category`, if any
+    pub fn get(&self, category: LogCategory) -> Option<LogLevel> {
+        self.levels[category.index()]
+    }
+
+    /// Most verbose level logged in `category` when the logger's minimum is
+    /// `min_level`
+    pub fn threshold(&self, category: LogCategory, min_level: LogLevel) -> LogLevel {
+        self.get(category).unwrap_or(min_level)
+    }
+
+    /// Categories with a level of their own
+    pub fn iter(&self) -> impl Iterator<Item = (LogCategory, LogLevel)> + '_ {
+        LogCategory::ALL
+            .into_iter()
+            .filter_map(|category| self.get(category).map(|level| (category, level)))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.levels.iter().all(Option::is_none)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_category_round_trips_through_names() {
+        for category in LogCategory::ALL {
+            assert_eq!(category.as_str().parse::<LogCategory>().unwrap(), category);
+        }
+        assert!("render".parse::<LogCategory>().is_err());
+    }
+
+    #[test]
+    fn test_filters_override_the_minimum_per_category() {
+        let mut filters = LogFilters::default();
+        assert!(filters.is_empty());
+        filters.set(LogCategory::Scan, Some(LogLevel::Warning));
+        filters.set(LogCategory::Hook, Some(LogLevel::Hook));
+
+        assert_eq!(
+            filters.threshold(LogCategory::Scan, LogLevel::Bypass),
+            LogLevel::Warning
+        );
+        assert_eq!(
+            filters.threshold(LogCategory::Pak, LogLevel::Info),
+            LogLevel::Info
+        );
+        assert_eq!(
+            filters.iter().collect::<Vec<_>>(),
+            [
+                (LogCategory::Scan, LogLevel::Warning),
+                (LogCategory::Hook, LogLevel::Hook)
+            ]
+        );
+
+        filters.set(LogCategory::Scan, None);
+        filters.set(LogCategory::Hook, None);
+        assert!(filters.is_empty());
+    }
+}
@@ -0,0 +1,162 @@
+//! Timestamps of log lines
+//!
+//! Each line carries the local wall-clock time, to line it up with the game's
+//! own logs, and the time since the DLL was attached, which keeps counting
+//! steadily when the system clock is adjusted.
+
+use std::sync::OnceLock;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use winapi::shared::minwindef::FILETIME;
+use winapi::um::minwinbase::SYSTEMTIME;
+use winapi::um::timezoneapi::{FileTimeToSystemTime, SystemTimeToTzSpecificLocalTime};
+
+/// 100-nanosecond intervals between 1601-01-01 and the Unix epoch
+const FILETIME_UNIX_EPOCH: u64 = 116_444_736_000_000_000;
+
+static ATTACHED_AT: OnceLock<Instant> = OnceLock::new();
+
+/// Start measuring elapsed times from now; later calls change nothing
+///
+/// Called when the DLL is attached. Without it elapsed times count from the
+/// first logged line.
+pub fn mark_attach() {
+    ATTACHED_AT.get_or_init(Instant::now);
+}
+
+/// Time since [`mark_attach`]
+pub fn since_attach() -> Duration {
+    ATTACHED_AT.get_or_init(Instant::now).elapsed()
+}
+
+/// Date and time broken into fields
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CivilTime {
+    pub year: u16,
+    pub month: u16,
+    pub day: u16,
+    pub hour: u16,
+    pub minute: u16,
+    pub second: u16,
+    pub millis: u16,
+    /// Whether this is UTC because the local time zone was unavailable
+    pub utc: bool,
+}
+
+impl CivilTime {
+    /// `time` in the local time zone, or in UTC if Windows cannot convert it
+    pub fn local(time: SystemTime) -> Self {
+        local_time(time).unwrap_or_else(|| Self::utc(time))
+    }
+
+    /// `time` in UTC
+    pub fn utc(time: SystemTime) -> Self {
+        let since_epoch = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+        let secs = since_epoch.as_secs();
+        let (year, month, day) = civil_from_days((secs / 86_400) as i64);
+        let secs_of_day = secs % 86_400;
+
+        Self {
+            year: year as u16,
+            month,
+            day,
+            hour: (secs_of_day / 3600) as u16,
+            minute: (secs_of_day / 60 % 60) as u16,
+            second: (secs_of_day % 60) as u16,
+            millis: since_epoch.subsec_millis() as u16,
+            utc: true,
+        }
+    }
+}
+
+impl std::fmt::Display for CivilTime {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{:04}-{:02}-{:02} {:02}:{:02}:{:02}.{:03}",
+            self.year, self.month, self.day, self.hour, self.minute, self.second, self.millis
+        )?;
+        if self.utc {
+            f.write_str("Z")?;
+        }
+        Ok(())
+    }
+}
+
+/// Convert through the Windows time zone settings
+fn local_time(time: SystemTime) -> Option<CivilTime> {
+    let since_epoch = time.duration_since(UNIX_EPOCH).ok()?;
+    let intervals = FILETIME_UNIX_EPOCH + (since_epoch.as_nanos() / 100) as u64;
+    let file_time = FILETIME {
+        dwLowDateTime: intervals as u32,
+        dwHighDateTime: (intervals >> 32) as u32,
+    };
+
+    let mut utc: SYSTEMTIME = unsafe { std::mem::zeroed() };
+    let mut local: SYSTEMTIME = unsafe { std::mem::zeroed() };
+    unsafe {
+        if FileTimeToSystemTime(&file_time, &mut utc) == 0
+            || SystemTimeToTzSpecificLocalTime(std::ptr::null(), &utc, &mut local) == 0
+        {
+            return None;
+        }
+    }
+
+    Some(CivilTime {
+        year: local.wYear,
+        month: local.wMonth,
+        day: local.wDay,
+        hour: local.wHour,
+        minute: local.wMinute,
+        second: local.wSecond,
+        millis: local.wMilliseconds,
+        utc: false,
+    })
+}
+
+/// Year, month and day of the day `days` after 1970-01-01
+fn civil_from_days(days: i64) -> (i64, u16, u16) {
+    // Howard Hinnant's algorithm, counting in 400-year eras from March 0000
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * shifted_month + 2) / 5 + 1) as u16;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    } as u16;
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+/// Elapsed time as `+12.345s`
+pub fn format_elapsed(elapsed: Duration) -> String {
+    format!("+{}.{:03}s", elapsed.as_secs(), elapsed.subsec_millis())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_utc_time_fields() {
+        let time = UNIX_EPOCH + Duration::from_millis(1_709_210_096_789);
+        let civil = CivilTime::utc(time);
+        assert_eq!(civil.to_string(), "2024-02-29 12:34:56.789Z");
+        assert_eq!(
+            CivilTime::utc(UNIX_EPOCH).to_string(),
+            "1970-01-01 00:00:00.000Z"
+        );
+    }
+
+    #[test]
+    fn test_elapsed_format() {
+        assert_eq!(format_elapsed(Duration::from_millis(12_345)), "+12.345s");
+        assert_eq!(format_elapsed(Duration::from_millis(7)), "+0.007s");
+    }
+}
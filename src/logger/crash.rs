@@ -0,0 +1,92 @@
+//! Logging of crashes and of the process going away
+//!
+//! When the game crashes, or the DLL is unloaded, records still queued for
+//! the writer thread would be lost and the log would simply stop. An
+//! unhandled-exception filter and the DLL detach notification therefore
+//! write a final record straight to the sinks and flush them.
+//!
+//! Both run in delicate places: the filter on a thread that just faulted,
+//! detach under the loader lock. Neither waits on the writer thread, and
+//! sink locks are only tried: at process exit, the threads already killed
+//! may hold them for good, and a sink they hold misses the final record.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use winapi::shared::ntdef::LONG;
+use winapi::um::errhandlingapi::SetUnhandledExceptionFilter;
+use winapi::um::winnt::EXCEPTION_POINTERS;
+use winapi::vc::excpt::EXCEPTION_CONTINUE_SEARCH;
+
+use super::{LogLevel, get_global_logger, initialized_global_logger};
+use crate::memory::describe_address;
+
+type ExceptionFilter = unsafe extern "system" fn(*mut EXCEPTION_POINTERS) -> LONG;
+
+/// Filter installed before ours, stored as an address; zero when none
+static PREVIOUS_FILTER: AtomicUsize = AtomicUsize::new(0);
+
+/// Log unhandled exceptions before the process dies
+///
+/// Any filter installed earlier, such as the game's crash reporter, still
+/// runs afterwards.
+pub fn install_crash_handler() {
+    let filter: ExceptionFilter = log_unhandled_exception;
+    let previous = unsafe { SetUnhandledExceptionFilter(Some(filter)) };
+    if let Some(previous) = previous
+        && previous as usize != filter as usize
+    {
+        PREVIOUS_FILTER.store(previous as usize, Ordering::Release);
+    }
+}
+
+/// Put back the filter that was installed before ours
+///
+/// Must run before the DLL unloads, or the process would call into freed
+/// code when it next crashes.
+pub fn uninstall_crash_handler() {
+    let previous = match PREVIOUS_FILTER.swap(0, Ordering::AcqRel) {
+        0 => None,
+        previous => Some(unsafe { std::mem::transmute::<usize, ExceptionFilter>(previous) }),
+    };
+    unsafe { SetUnhandledExceptionFilter(previous) };
+}
+
+unsafe extern "system" fn log_unhandled_exception(info: *mut EXCEPTION_POINTERS) -> LONG {
+    let record = unsafe { info.as_ref().and_then(|info| info.ExceptionRecord.as_ref()) };
+    let msg = match record {
+        Some(record) => format!(
+            "Unhandled exception {:#010x} at {} in thread '{}', the process is crashing",
+            record.ExceptionCode,
+            describe_address(record.ExceptionAddress as usize),
+            std::thread::current().name().unwrap_or("<unnamed>")
+        ),
+        None => "Unhandled exception, the process is crashing".to_string(),
+    };
+    get_global_logger().write_final(LogLevel::Error, &msg, true);
+
+    match PREVIOUS_FILTER.load(Ordering::Acquire) {
+        0 => EXCEPTION_CONTINUE_SEARCH,
+        previous => {
+            let previous: ExceptionFilter = unsafe { std::mem::transmute(previous) };
+            unsafe { previous(info) }
+        }
+    }
+}
+
+/// Record that the DLL is going away and flush the sinks
+///
+/// Called on DLL detach; `process_terminating` tells a process exit from
+/// the DLL being unloaded while the game keeps running. Nothing is logged
+/// if the logger was never initialized: creating one under the loader lock
+/// is no better than blocking on a dead thread's lock.
+pub fn log_exit(process_terminating: bool) {
+    let msg = if process_terminating {
+        "Process exiting, closing the log"
+    } else {
+        "DLL unloading, closing the log"
+    };
+    if let Some(logger) = initialized_global_logger() {
+        // Waiting for the writer thread under the loader lock could hang
+        logger.write_final(LogLevel::Info, msg, false);
+    }
+}
@@ -0,0 +1,103 @@
+//! Backend for the `log` crate facade
+//!
+//! Dependencies log through the `log` macros. Once
+//! [`Logger::install_as_log_backend`] has run, their records reach the sinks
+//! of the global logger, prefixed with and carrying as the `target` field the
+//! module that emitted them.
+
+use log::{Level, LevelFilter, Log, Metadata, Record};
+
+use super::{LogCategory, LogLevel, Logger, get_global_logger};
+use crate::error::{AppError, Result};
+
+/// The one backend handed to `log::set_logger`
+static BACKEND: LogBackend = LogBackend;
+
+struct LogBackend;
+
+impl Log for LogBackend {
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        level_for(metadata.level()) <= get_global_logger().threshold(LogCategory::General)
+    }
+
+    fn log(&self, record: &Record<'_>) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        let message = format!("{}: {}", record.target(), record.args());
+        get_global_logger().log_fields(
+            level_for(record.level()),
+            &message,
+            &[("target", record.target())],
+        );
+    }
+
+    fn flush(&self) {
+        get_global_logger().flush();
+    }
+}
+
+/// Our level for a `log` level; debug and trace map to the scan and bypass
+/// detail levels
+pub fn level_for(level: Level) -> LogLevel {
+    match level {
+        Level::Error => LogLevel::Error,
+        Level::Warn => LogLevel::Warning,
+        Level::Info => LogLevel::Info,
+        Level::Debug => LogLevel::Scan,
+        Level::Trace => LogLevel::Bypass,
+    }
+}
+
+/// Most verbose `log` level that can pass `level`
+pub fn level_filter(level: LogLevel) -> LevelFilter {
+    match level {
+        LogLevel::Error => LevelFilter::Error,
+        LogLevel::Warning => LevelFilter::Warn,
+        LogLevel::Info | LogLevel::Success => LevelFilter::Info,
+        LogLevel::Scan | LogLevel::Hook => LevelFilter::Debug,
+        LogLevel::Bypass => LevelFilter::Trace,
+    }
+}
+
+impl Logger {
+    /// Route records of the `log` crate to the global logger
+    ///
+    /// Fails when a backend, this one included, is already installed.
+    pub fn install_as_log_backend() -> Result<()> {
+        log::set_logger(&BACKEND).map_err(|_| AppError::LogBackendInstalled)?;
+        log::set_max_level(level_filter(
+            get_global_logger().threshold(LogCategory::General),
+        ));
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_levels_map_both_ways() {
+        for level in [
+            Level::Error,
+            Level::Warn,
+            Level::Info,
+            Level::Debug,
+            Level::Trace,
+        ] {
+            assert_eq!(level_filter(level_for(level)), level.to_level_filter());
+        }
+        assert_eq!(level_filter(LogLevel::Hook), LevelFilter::Debug);
+    }
+
+    #[test]
+    fn test_backend_installs_once() {
+        let _ = Logger::install_as_log_backend();
+        log::info!("record from the log facade");
+        assert!(matches!(
+            Logger::install_as_log_backend(),
+            Err(AppError::LogBackendInstalled)
+        ));
+    }
+}
@@ -0,0 +1,163 @@
+//! Rate limits for messages logged from hot paths
+//!
+//! Hooks run for every pak the game checks, and logging each call buries
+//! everything else. These helpers decide per call site whether a message is
+//! logged: only the first time, every n-th time, or up to a burst per time
+//! window with a count of what was held back.
+
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+/// Lets a message through the first time only
+#[derive(Debug, Default)]
+pub struct LogOnce {
+    done: AtomicBool,
+}
+
+impl LogOnce {
+    pub const fn new() -> Self {
+        Self {
+            done: AtomicBool::new(false),
+        }
+    }
+
+    /// Whether this is the first call
+    pub fn first(&self) -> bool {
+        !self.done.swap(true, Ordering::Relaxed)
+    }
+}
+
+/// Lets every n-th message through, starting with the first
+#[derive(Debug)]
+pub struct LogEveryN {
+    every: u64,
+    seen: AtomicU64,
+}
+
+impl LogEveryN {
+    /// Let one message in `every` through; 0 and 1 let all through
+    pub const fn new(every: u64) -> Self {
+        Self {
+            every,
+            seen: AtomicU64::new(0),
+        }
+    }
+
+    /// The number of calls so far if this one should be logged
+    pub fn tick(&self) -> Option<u64> {
+        let seen = self.seen.fetch_add(1, Ordering::Relaxed);
+        seen.is_multiple_of(self.every.max(1)).then_some(seen + 1)
+    }
+}
+
+/// Lets up to `burst` messages through per window and counts the rest
+#[derive(Debug)]
+pub struct BurstLimiter {
+    burst: AtomicU32,
+    window: Duration,
+    state: Mutex<BurstWindow>,
+}
+
+#[derive(Debug)]
+struct BurstWindow {
+    start: Option<Instant>,
+    admitted: u32,
+    suppressed: u64,
+}
+
+impl BurstLimiter {
+    /// Allow `burst` messages per `window`; a burst of 0 allows all
+    pub const fn new(burst: u32, window: Duration) -> Self {
+        Self {
+            burst: AtomicU32::new(burst),
+            window,
+            state: Mutex::new(BurstWindow {
+                start: None,
+                admitted: 0,
+                suppressed: 0,
+            }),
+        }
+    }
+
+    /// Change the number of messages allowed per window
+    pub fn set_burst(&self, burst: u32) {
+        self.burst.store(burst, Ordering::Relaxed);
+    }
+
+    /// Whether to log this message: `None` to hold it back, otherwise how
+    /// many were held back since the last report
+    pub fn admit(&self) -> Option<u64> {
+        self.admit_at(Instant::now())
+    }
+
+    fn admit_at(&self, now: Instant) -> Option<u64> {
+        let burst = self.burst.load(Ordering::Relaxed);
+        let Ok(mut state) = self.state.lock() else {
+            return Some(0);
+        };
+        if burst == 0 {
+            return Some(std::mem::take(&mut state.suppressed));
+        }
+
+        if state
+            .start
+            .is_none_or(|start| now.duration_since(start) >= self.window)
+        {
+            state.start = Some(now);
+            state.admitted = 0;
+        }
+        if state.admitted < burst {
+            state.admitted += 1;
+            Some(std::mem::take(&mut state.suppressed))
+        } else {
+            state.suppressed += 1;
+            None
+        }
+    }
+
+    /// Messages held back since the last report, resetting the count
+    pub fn take_suppressed(&self) -> u64 {
+        self.state
+            .lock()
+            .map_or(0, |mut state| std::mem::take(&mut state.suppressed))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_log_once_and_every_n() {
+        let once = LogOnce::new();
+        assert!(once.first());
+        assert!(!once.first());
+
+        let every = LogEveryN::new(3);
+        let logged: Vec<Option<u64>> = (0..7).map(|_| every.tick()).collect();
+        assert_eq!(logged, [Some(1), None, None, Some(4), None, None, Some(7)]);
+        assert_eq!(LogEveryN::new(0).tick(), Some(1));
+    }
+
+    #[test]
+    fn test_burst_limiter_counts_held_back_messages() {
+        let limiter = BurstLimiter::new(2, Duration::from_secs(1));
+        let start = Instant::now();
+
+        assert_eq!(limiter.admit_at(start), Some(0));
+        assert_eq!(limiter.admit_at(start), Some(0));
+        assert_eq!(limiter.admit_at(start), None);
+        assert_eq!(limiter.admit_at(start + Duration::from_millis(500)), None);
+
+        let next_window = start + Duration::from_secs(1);
+        assert_eq!(limiter.admit_at(next_window), Some(2));
+        assert_eq!(limiter.admit_at(next_window), Some(0));
+        assert_eq!(limiter.admit_at(next_window), None);
+        assert_eq!(limiter.take_suppressed(), 1);
+        assert_eq!(limiter.take_suppressed(), 0);
+
+        limiter.set_burst(0);
+        assert_eq!(limiter.admit_at(next_window), Some(0));
+    }
+}
@@ -0,0 +1,65 @@
+//! Logging of panics
+//!
+//! The DLL has no console of its own to print a panic to once the game
+//! closes, so the hook installed here writes the message, where it happened
+//! and a backtrace to the log sinks, and flushes them. Code running inside
+//! the game's threads still has to catch the unwind itself, see
+//! [`std::panic::catch_unwind`].
+
+use std::any::Any;
+use std::backtrace::Backtrace;
+use std::panic::PanicHookInfo;
+
+use super::{LogLevel, get_global_logger};
+
+/// Route every panic to the global logger
+pub fn install_panic_hook() {
+    std::panic::set_hook(Box::new(log_panic));
+}
+
+fn log_panic(info: &PanicHookInfo<'_>) {
+    let thread = std::thread::current();
+    let location = info
+        .location()
+        .map_or_else(|| "an unknown location".to_string(), ToString::to_string);
+    let logger = get_global_logger();
+    logger.log(
+        LogLevel::Error,
+        &format!(
+            "Panic in thread '{}' at {}: {}",
+            thread.name().unwrap_or("<unnamed>"),
+            location,
+            payload_text(info.payload())
+        ),
+    );
+
+    let backtrace = Backtrace::force_capture().to_string();
+    for line in backtrace.lines() {
+        logger.log(LogLevel::Error, &format!("  {}", line));
+    }
+    logger.flush();
+}
+
+/// The message a panic was raised with
+pub fn payload_text(payload: &(dyn Any + Send)) -> &str {
+    payload
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+        .unwrap_or("non-string panic payload")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_payload_text_reads_both_string_kinds() {
+        let literal: Box<dyn Any + Send> = Box::new("literal");
+        let formatted: Box<dyn Any + Send> = Box::new(format!("formatted {}", 1));
+        let other: Box<dyn Any + Send> = Box::new(7);
+        assert_eq!(payload_text(literal.as_ref()), "literal");
+        assert_eq!(payload_text(formatted.as_ref()), "formatted 1");
+        assert_eq!(payload_text(other.as_ref()), "non-string panic payload");
+    }
+}
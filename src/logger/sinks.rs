@@ -0,0 +1,409 @@
+//! Destinations for log records
+//!
+//! A [`Logger`](super::Logger) formats each record once and hands it to every
+//! sink in its list, so console, file, pipe, debugger and callback output
+//! compose freely.
+
+use crossterm::execute;
+use crossterm::style::{Color, ResetColor, SetForegroundColor};
+use std::collections::VecDeque;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::path::Path;
+use std::sync::Mutex;
+
+use winapi::shared::minwindef::DWORD;
+use winapi::um::consoleapi::{GetConsoleMode, WriteConsoleW};
+use winapi::um::debugapi::OutputDebugStringW;
+use winapi::um::handleapi::INVALID_HANDLE_VALUE;
+use winapi::um::processenv::GetStdHandle;
+use winapi::um::winbase::{STD_ERROR_HANDLE, STD_OUTPUT_HANDLE};
+use winapi::um::winnt::HANDLE;
+
+use super::LogLevel;
+use super::theme::no_color_requested;
+use crate::safety::StringConverter;
+
+/// One logged message as sinks receive it
+#[derive(Debug, Clone, Copy)]
+pub struct LogRecord<'a> {
+    pub level: LogLevel,
+    pub message: &'a str,
+    /// Named values logged with the message
+    pub fields: &'a [(&'a str, &'a str)],
+    /// The record in the logger's format, without a line ending
+    pub line: &'a str,
+    /// Whether sinks able to color should do so
+    pub colored: bool,
+    /// Color of the record's level in the logger's theme
+    pub color: Color,
+}
+
+/// Destination for log records
+pub trait LogSink: Send + Sync {
+    /// Write one record; errors are swallowed since there is nowhere left to
+    /// report them
+    fn write(&self, record: &LogRecord<'_>);
+
+    /// Push buffered output to its destination
+    fn flush(&self) {}
+
+    /// Write and flush one last record without blocking, for when other
+    /// threads may have died holding the sink's locks; a sink whose lock is
+    /// held skips the record
+    fn write_final(&self, record: &LogRecord<'_>) {
+        self.write(record);
+        self.flush();
+    }
+}
+
+/// The attached console: errors to stderr, everything else to stdout
+///
+/// Lines reach a console as UTF-16 through `WriteConsoleW`, so pak names in
+/// any script display whatever the console code page. Redirected output is
+/// written as UTF-8 and never colored.
+#[derive(Debug)]
+pub struct ConsoleSink {
+    /// Cleared by `NO_COLOR`
+    colors: bool,
+}
+
+impl Default for ConsoleSink {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ConsoleSink {
+    pub fn new() -> Self {
+        Self {
+            colors: !no_color_requested(),
+        }
+    }
+}
+
+impl LogSink for ConsoleSink {
+    fn write(&self, record: &LogRecord<'_>) {
+        let _ = match record.level {
+            LogLevel::Error => write_line(
+                &mut io::stderr().lock(),
+                console_handle(STD_ERROR_HANDLE),
+                record,
+                self.colors,
+            ),
+            _ => write_line(
+                &mut io::stdout().lock(),
+                console_handle(STD_OUTPUT_HANDLE),
+                record,
+                self.colors,
+            ),
+        };
+    }
+
+    fn flush(&self) {
+        let _ = io::stdout().flush();
+        let _ = io::stderr().flush();
+    }
+
+    fn write_final(&self, record: &LogRecord<'_>) {
+        // The standard streams' locks cannot be tried; a console is written
+        // to directly, and a redirected stream is skipped
+        let std_handle = match record.level {
+            LogLevel::Error => STD_ERROR_HANDLE,
+            _ => STD_OUTPUT_HANDLE,
+        };
+        if let Some(console) = console_handle(std_handle) {
+            write_console_wide(console, &format!("{}\n", record.line));
+        }
+    }
+}
+
+/// Write the record's line to `out`, or to `console` when the stream is
+/// one, colored when `colors` allows and the record asks for it
+///
+/// A line the console refuses is written to `out` instead.
+fn write_line(
+    out: &mut impl Write,
+    console: Option<HANDLE>,
+    record: &LogRecord<'_>,
+    colors: bool,
+) -> io::Result<()> {
+    let line = format!("{}\n", record.line);
+    let Some(console) = console else {
+        return out.write_all(line.as_bytes());
+    };
+
+    let colored = colors && record.colored;
+    if colored {
+        execute!(out, SetForegroundColor(record.color))?;
+    }
+    if !write_console_wide(console, &line) {
+        out.write_all(line.as_bytes())?;
+    }
+    if colored {
+        execute!(out, ResetColor)?;
+    }
+    Ok(())
+}
+
+/// The handle behind `std_handle` if it is a console rather than a file or
+/// pipe
+fn console_handle(std_handle: DWORD) -> Option<HANDLE> {
+    let handle = unsafe { GetStdHandle(std_handle) };
+    let mut mode = 0;
+    let is_console = !handle.is_null()
+        && handle != INVALID_HANDLE_VALUE
+        && unsafe { GetConsoleMode(handle, &mut mode) } != 0;
+    is_console.then_some(handle)
+}
+
+/// Write `text` as UTF-16 to the console `handle`, returning whether any of
+/// it was written
+fn write_console_wide(handle: HANDLE, text: &str) -> bool {
+    let wide: Vec<u16> = text.encode_utf16().collect();
+    let mut remaining = wide.as_slice();
+    while !remaining.is_empty() {
+        let mut written = 0;
+        let ok = unsafe {
+            WriteConsoleW(
+                handle,
+                remaining.as_ptr().cast(),
+                remaining.len() as DWORD,
+                &mut written,
+                std::ptr::null_mut(),
+            )
+        };
+        if ok == 0 || written == 0 {
+            break;
+        }
+        remaining = &remaining[(written as usize).min(remaining.len())..];
+    }
+    remaining.len() < wide.len()
+}
+
+/// Any writer, such as a log file or the client end of a named pipe,
+/// receiving one uncolored line per record
+#[derive(Debug)]
+pub struct WriterSink<W: Write + Send> {
+    writer: Mutex<W>,
+}
+
+impl<W: Write + Send> WriterSink<W> {
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer: Mutex::new(writer),
+        }
+    }
+}
+
+impl WriterSink<File> {
+    /// Append to the file at `path`, creating it if needed
+    pub fn file(path: impl AsRef<Path>) -> io::Result<Self> {
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map(Self::new)
+    }
+
+    /// Connect to the named pipe `\\.\pipe\<name>`, which must already be
+    /// listening
+    pub fn pipe(name: &str) -> io::Result<Self> {
+        OpenOptions::new()
+            .write(true)
+            .open(format!(r"\\.\pipe\{}", name))
+            .map(Self::new)
+    }
+}
+
+impl<W: Write + Send> LogSink for WriterSink<W> {
+    fn write(&self, record: &LogRecord<'_>) {
+        if let Ok(mut writer) = self.writer.lock() {
+            let _ = writeln!(writer, "{}", record.line);
+        }
+    }
+
+    fn flush(&self) {
+        if let Ok(mut writer) = self.writer.lock() {
+            let _ = writer.flush();
+        }
+    }
+
+    fn write_final(&self, record: &LogRecord<'_>) {
+        if let Ok(mut writer) = self.writer.try_lock() {
+            let _ = writeln!(writer, "{}", record.line);
+            let _ = writer.flush();
+        }
+    }
+}
+
+/// The debugger's output window, through `OutputDebugStringW`
+#[derive(Debug, Default)]
+pub struct DebuggerSink;
+
+impl DebuggerSink {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl LogSink for DebuggerSink {
+    fn write(&self, record: &LogRecord<'_>) {
+        let text = StringConverter::to_wide_null(&format!("{}\n", record.line));
+        unsafe { OutputDebugStringW(text.as_ptr()) };
+    }
+}
+
+/// The last lines logged, kept in memory for post-mortem retrieval
+#[derive(Debug)]
+pub struct RingBufferSink {
+    capacity: usize,
+    lines: Mutex<VecDeque<String>>,
+}
+
+impl RingBufferSink {
+    /// Keep the last `capacity` lines
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            lines: Mutex::new(VecDeque::with_capacity(capacity)),
+        }
+    }
+
+    /// Up to `count` of the most recent lines, oldest first
+    pub fn recent(&self, count: usize) -> Vec<String> {
+        self.lines.lock().map_or_else(
+            |_| Vec::new(),
+            |lines| {
+                let skip = lines.len().saturating_sub(count);
+                lines.iter().skip(skip).cloned().collect()
+            },
+        )
+    }
+}
+
+impl RingBufferSink {
+    fn push(&self, lines: &mut VecDeque<String>, line: &str) {
+        if lines.len() == self.capacity {
+            lines.pop_front();
+        }
+        lines.push_back(line.to_string());
+    }
+}
+
+impl LogSink for RingBufferSink {
+    fn write(&self, record: &LogRecord<'_>) {
+        if self.capacity == 0 {
+            return;
+        }
+        if let Ok(mut lines) = self.lines.lock() {
+            self.push(&mut lines, record.line);
+        }
+    }
+
+    fn write_final(&self, record: &LogRecord<'_>) {
+        if self.capacity == 0 {
+            return;
+        }
+        if let Ok(mut lines) = self.lines.try_lock() {
+            self.push(&mut lines, record.line);
+        }
+    }
+}
+
+/// A function called with every record
+pub struct CallbackSink {
+    callback: Box<dyn Fn(&LogRecord<'_>) + Send + Sync>,
+}
+
+impl CallbackSink {
+    pub fn new(callback: impl Fn(&LogRecord<'_>) + Send + Sync + 'static) -> Self {
+        Self {
+            callback: Box::new(callback),
+        }
+    }
+}
+
+impl LogSink for CallbackSink {
+    fn write(&self, record: &LogRecord<'_>) {
+        (self.callback)(record);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_redirected_console_output_is_plain_utf8() {
+        let mut out = Vec::new();
+        let line = "[BYPASS] Verifying pak: 'pakchunk1-鸣潮.pak' -> OK";
+        // Not a console, so the line goes to the stream uncolored
+        write_line(&mut out, None, &record(line), true).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), format!("{}\n", line));
+    }
+
+    #[test]
+    fn test_refused_console_write_falls_back_to_the_stream() {
+        let mut out = Vec::new();
+        let line = "[INFO] Console detached";
+        write_line(&mut out, Some(INVALID_HANDLE_VALUE), &record(line), false).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), format!("{}\n", line));
+    }
+
+    #[test]
+    fn test_final_write_skips_a_held_sink() {
+        let sink = RingBufferSink::new(4);
+        {
+            let _held = sink.lines.lock().unwrap();
+            sink.write_final(&record("lost"));
+        }
+        sink.write_final(&record("kept"));
+        assert_eq!(sink.recent(4), ["kept"]);
+    }
+
+    fn record<'a>(line: &'a str) -> LogRecord<'a> {
+        LogRecord {
+            level: LogLevel::Info,
+            message: line,
+            fields: &[],
+            line,
+            colored: true,
+            color: Color::Cyan,
+        }
+    }
+
+    #[test]
+    fn test_writer_sink_writes_plain_lines() {
+        let sink = WriterSink::new(Vec::new());
+        sink.write(&record("[INFO] one"));
+        sink.write(&record("[INFO] two"));
+
+        let written = sink.writer.into_inner().unwrap();
+        assert_eq!(written, b"[INFO] one\n[INFO] two\n");
+    }
+
+    #[test]
+    fn test_ring_buffer_keeps_the_last_lines() {
+        let ring = RingBufferSink::new(3);
+        for line in ["1", "2", "3", "4"] {
+            ring.write(&record(line));
+        }
+        assert_eq!(ring.recent(10), ["2", "3", "4"]);
+        assert_eq!(ring.recent(2), ["3", "4"]);
+        assert!(RingBufferSink::new(0).recent(1).is_empty());
+    }
+
+    #[test]
+    fn test_file_sink_appends() {
+        let path = std::env::temp_dir().join(format!("wuwa-sig-sink-{}.log", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        for line in ["first", "second"] {
+            WriterSink::file(&path).unwrap().write(&record(line));
+        }
+        let text = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(text, "first\nsecond\n");
+    }
+}
@@ -0,0 +1,137 @@
+//! Console colors of the log levels
+//!
+//! Colors only reach a real console: the console sink leaves them out when
+//! its output is redirected to a file or pipe, and whenever the `NO_COLOR`
+//! environment variable is set to anything non-empty.
+
+use crossterm::style::Color;
+
+use super::LogLevel;
+use crate::error::{AppError, Result};
+
+/// Names accepted for colors, besides `#rrggbb`
+const COLOR_NAMES: &[(&str, Color)] = &[
+    ("black", Color::Black),
+    ("dark_grey", Color::DarkGrey),
+    ("red", Color::Red),
+    ("dark_red", Color::DarkRed),
+    ("green", Color::Green),
+    ("dark_green", Color::DarkGreen),
+    ("yellow", Color::Yellow),
+    ("dark_yellow", Color::DarkYellow),
+    ("blue", Color::Blue),
+    ("dark_blue", Color::DarkBlue),
+    ("magenta", Color::Magenta),
+    ("dark_magenta", Color::DarkMagenta),
+    ("cyan", Color::Cyan),
+    ("dark_cyan", Color::DarkCyan),
+    ("white", Color::White),
+    ("grey", Color::Grey),
+];
+
+/// Color of each log level
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ColorTheme {
+    colors: [Color; 7],
+}
+
+impl Default for ColorTheme {
+    fn default() -> Self {
+        Self {
+            colors: [
+                Color::Red,     // Error
+                Color::Yellow,  // Warning
+                Color::Cyan,    // Info
+                Color::Green,   // Success
+                Color::Yellow,  // Scan
+                Color::Magenta, // Hook
+                Color::Green,   // Bypass
+            ],
+        }
+    }
+}
+
+impl ColorTheme {
+    pub fn color(&self, level: LogLevel) -> Color {
+        self.colors[level as usize]
+    }
+
+    pub fn set(&mut self, level: LogLevel, color: Color) {
+        self.colors[level as usize] = color;
+    }
+
+    /// Levels whose color differs from the default theme
+    pub fn changes(&self) -> impl Iterator<Item = (LogLevel, Color)> + '_ {
+        let default = Self::default();
+        LogLevel::ALL
+            .into_iter()
+            .filter(move |level| self.color(*level) != default.color(*level))
+            .map(|level| (level, self.color(level)))
+    }
+}
+
+/// A color by name, such as `dark_cyan`, or as `#rrggbb`
+pub fn parse_color(name: &str) -> Result<Color> {
+    let invalid = || AppError::InvalidConfig {
+        field: "log_theme".to_string(),
+        reason: format!("unknown color '{}'", name),
+    };
+    if let Some(hex) = name.strip_prefix('#') {
+        let value = u32::from_str_radix(hex, 16).map_err(|_| invalid())?;
+        if hex.len() != 6 {
+            return Err(invalid());
+        }
+        let [_, r, g, b] = value.to_be_bytes();
+        return Ok(Color::Rgb { r, g, b });
+    }
+    COLOR_NAMES
+        .iter()
+        .find(|(known, _)| known.eq_ignore_ascii_case(name))
+        .map(|(_, color)| *color)
+        .ok_or_else(invalid)
+}
+
+/// Name of a color as [`parse_color`] reads it
+pub fn color_name(color: Color) -> String {
+    match color {
+        Color::Rgb { r, g, b } => format!("#{:02x}{:02x}{:02x}", r, g, b),
+        _ => COLOR_NAMES
+            .iter()
+            .find(|(_, known)| *known == color)
+            .map_or_else(|| "white".to_string(), |(name, _)| name.to_string()),
+    }
+}
+
+/// Whether the `NO_COLOR` convention asks for plain output
+pub fn no_color_requested() -> bool {
+    std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_colors_round_trip_through_names() {
+        for name in ["dark_cyan", "red", "#ff8000"] {
+            assert_eq!(color_name(parse_color(name).unwrap()), name);
+        }
+        assert_eq!(parse_color("Magenta").unwrap(), Color::Magenta);
+        for name in ["purple", "#ff80", "#gg0000", ""] {
+            assert!(parse_color(name).is_err(), "{}", name);
+        }
+    }
+
+    #[test]
+    fn test_theme_reports_changed_levels() {
+        let mut theme = ColorTheme::default();
+        assert_eq!(theme.color(LogLevel::Hook), Color::Magenta);
+        assert_eq!(theme.changes().count(), 0);
+
+        theme.set(LogLevel::Scan, Color::DarkGrey);
+        assert_eq!(
+            theme.changes().collect::<Vec<_>>(),
+            [(LogLevel::Scan, Color::DarkGrey)]
+        );
+    }
+}
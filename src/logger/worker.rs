@@ -0,0 +1,268 @@
+//! Formatting and writing of log records on a dedicated thread
+//!
+//! Hooks log from inside the game's loading path, where a slow console write
+//! stalls the game. With a worker the calling thread only captures the record
+//! and queues it; the worker formats it and writes it to the sinks.
+
+use std::str::FromStr;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver, SyncSender, TrySendError};
+use std::thread::{self, JoinHandle, ThreadId};
+
+use super::{Entry, LogLevel, LoggerConfig, SinkList, flush_sinks};
+use crate::error::{AppError, Result};
+
+/// What happens to a record logged while the queue is full
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OverflowPolicy {
+    /// Discard it; the worker reports how many were lost
+    #[default]
+    Drop,
+    /// Wait for room, stalling the logging thread
+    Block,
+}
+
+impl OverflowPolicy {
+    /// Name used in configuration
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Drop => "drop",
+            Self::Block => "block",
+        }
+    }
+}
+
+impl FromStr for OverflowPolicy {
+    type Err = AppError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        [Self::Drop, Self::Block]
+            .into_iter()
+            .find(|policy| policy.as_str().eq_ignore_ascii_case(s))
+            .ok_or_else(|| AppError::InvalidConfig {
+                field: "log_overflow".to_string(),
+                reason: format!("unknown overflow policy '{}'", s),
+            })
+    }
+}
+
+/// Work queued for the worker thread
+enum Job {
+    Write(Entry, LoggerConfig),
+    /// Flush the sinks, then signal the sender
+    Flush(mpsc::Sender<()>),
+}
+
+/// Thread writing the records of one logger
+///
+/// Dropping the worker writes everything still queued before returning.
+pub struct LogWorker {
+    sender: Option<SyncSender<Job>>,
+    policy: OverflowPolicy,
+    dropped: Arc<AtomicU64>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl LogWorker {
+    /// Start a worker writing to `sinks`, queueing up to `capacity` records
+    pub(super) fn spawn(
+        sinks: Arc<SinkList>,
+        capacity: usize,
+        policy: OverflowPolicy,
+    ) -> Result<Self> {
+        let (sender, receiver) = mpsc::sync_channel(capacity);
+        let dropped = Arc::new(AtomicU64::new(0));
+        let thread = {
+            let dropped = Arc::clone(&dropped);
+            thread::Builder::new()
+                .name("wuwa-sig-log".to_string())
+                .spawn(move || run(&receiver, &sinks, &dropped))
+                .map_err(|source| AppError::LogWorkerFailed { source })?
+        };
+
+        Ok(Self {
+            sender: Some(sender),
+            policy,
+            dropped,
+            thread: Some(thread),
+        })
+    }
+
+    /// Sending end of the queue, to submit records without holding on to
+    /// the worker; `None` once it is shutting down
+    pub(super) fn queue(&self) -> Option<LogQueue> {
+        self.sender.as_ref().map(|sender| LogQueue {
+            sender: sender.clone(),
+            policy: self.policy,
+            dropped: Arc::clone(&self.dropped),
+            thread: self.thread.as_ref().map(|thread| thread.thread().id()),
+        })
+    }
+
+    /// Wait until everything queued so far is written and the sinks flushed
+    pub fn flush(&self) {
+        if let Some(queue) = self.queue() {
+            queue.flush();
+        }
+    }
+
+    /// Whether this is called on the worker thread itself
+    pub fn is_current(&self) -> bool {
+        self.thread
+            .as_ref()
+            .is_some_and(|thread| thread.thread().id() == thread::current().id())
+    }
+
+    /// Records discarded since the worker last reported losses
+    pub fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+}
+
+/// Sending end of a worker's queue
+///
+/// A clone keeps the worker thread running until it is dropped, so callers
+/// take one out of the logger's lock and let it go right after sending.
+#[derive(Clone)]
+pub(super) struct LogQueue {
+    sender: SyncSender<Job>,
+    policy: OverflowPolicy,
+    dropped: Arc<AtomicU64>,
+    thread: Option<ThreadId>,
+}
+
+impl LogQueue {
+    /// Queue an entry, applying the overflow policy when the queue is full
+    pub(super) fn submit(&self, entry: Entry, config: LoggerConfig) {
+        let job = Job::Write(entry, config);
+        match self.policy {
+            OverflowPolicy::Block => {
+                let _ = self.sender.send(job);
+            }
+            OverflowPolicy::Drop => {
+                if let Err(TrySendError::Full(_)) = self.sender.try_send(job) {
+                    self.dropped.fetch_add(1, Ordering::Relaxed);
+                }
+            }
+        }
+    }
+
+    /// Wait until everything queued so far is written and the sinks flushed
+    pub(super) fn flush(&self) {
+        let (done, wait) = mpsc::channel();
+        if self.sender.send(Job::Flush(done)).is_ok() {
+            let _ = wait.recv();
+        }
+    }
+
+    /// Whether this is called on the worker thread itself
+    pub(super) fn is_current(&self) -> bool {
+        self.thread == Some(thread::current().id())
+    }
+}
+
+impl Drop for LogWorker {
+    fn drop(&mut self) {
+        // Closing the queue ends the thread once it is drained
+        self.sender = None;
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// Body of the worker thread, returning once every sender is gone
+fn run(jobs: &Receiver<Job>, sinks: &SinkList, dropped: &AtomicU64) {
+    for job in jobs {
+        match job {
+            Job::Write(entry, config) => {
+                let lost = dropped.swap(0, Ordering::Relaxed);
+                if lost > 0 {
+                    let message = format!("{} log record(s) dropped, the queue was full", lost);
+                    Entry::capture(LogLevel::Warning, &message, &[]).write(&config, sinks);
+                }
+                entry.write(&config, sinks);
+            }
+            Job::Flush(done) => {
+                flush_sinks(sinks);
+                let _ = done.send(());
+            }
+        }
+    }
+    flush_sinks(sinks);
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Mutex, RwLock};
+
+    use super::*;
+    use crate::logger::{CallbackSink, LogRecord, LogSink};
+
+    fn collecting_sinks() -> (Arc<SinkList>, Arc<Mutex<Vec<String>>>) {
+        let lines = Arc::new(Mutex::new(Vec::new()));
+        let seen = Arc::clone(&lines);
+        let sink: Arc<dyn LogSink> = Arc::new(CallbackSink::new(move |record: &LogRecord<'_>| {
+            seen.lock().unwrap().push(record.line.to_string());
+        }));
+        (Arc::new(RwLock::new(vec![sink])), lines)
+    }
+
+    #[test]
+    fn test_overflow_policy_round_trips_through_names() {
+        for policy in [OverflowPolicy::Drop, OverflowPolicy::Block] {
+            assert_eq!(policy.as_str().parse::<OverflowPolicy>().unwrap(), policy);
+        }
+        assert!("spill".parse::<OverflowPolicy>().is_err());
+    }
+
+    #[test]
+    fn test_worker_writes_in_order_and_drains_on_drop() {
+        let (sinks, lines) = collecting_sinks();
+        let worker = LogWorker::spawn(sinks, 4, OverflowPolicy::Block).unwrap();
+        let queue = worker.queue().unwrap();
+        for i in 0..10 {
+            let entry = Entry::capture(LogLevel::Info, &i.to_string(), &[]);
+            queue.submit(entry, LoggerConfig::default());
+        }
+        drop(queue);
+        drop(worker);
+
+        let expected: Vec<String> = (0..10).map(|i| format!("[INFO] {}", i)).collect();
+        assert_eq!(*lines.lock().unwrap(), expected);
+    }
+
+    #[test]
+    fn test_dropped_records_are_reported() {
+        let (sinks, lines) = collecting_sinks();
+        let (sender, receiver) = mpsc::sync_channel(1);
+        // No thread takes from the queue, so the second record finds it full
+        let worker = LogWorker {
+            sender: Some(sender),
+            policy: OverflowPolicy::Drop,
+            dropped: Arc::new(AtomicU64::new(0)),
+            thread: None,
+        };
+        let queue = worker.queue().unwrap();
+        for message in ["kept", "lost"] {
+            let entry = Entry::capture(LogLevel::Info, message, &[]);
+            queue.submit(entry, LoggerConfig::default());
+        }
+        drop(queue);
+        assert_eq!(worker.dropped(), 1);
+
+        let dropped = Arc::clone(&worker.dropped);
+        drop(worker);
+        run(&receiver, &sinks, &dropped);
+
+        assert_eq!(
+            *lines.lock().unwrap(),
+            [
+                "[WARNING] 1 log record(s) dropped, the queue was full",
+                "[INFO] kept"
+            ]
+        );
+        assert_eq!(dropped.load(Ordering::Relaxed), 0);
+    }
+}
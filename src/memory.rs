@@ -1,35 +1,121 @@
 use std::collections::HashMap;
-use std::ffi::CStr;
 use std::ptr;
 use std::sync::{Arc, RwLock};
+use std::thread;
+use std::time::{Duration, Instant};
 
 use winapi::ctypes::c_void;
-use winapi::shared::minwindef::{DWORD, HMODULE};
+use winapi::shared::minwindef::{DWORD, HMODULE, MAX_PATH};
+use winapi::um::libloaderapi::GetModuleHandleW;
 use winapi::um::processthreadsapi::GetCurrentProcess;
-use winapi::um::psapi::{EnumProcessModules, GetModuleBaseNameA, GetModuleInformation, MODULEINFO};
+use winapi::um::psapi::{EnumProcessModules, GetModuleBaseNameW, GetModuleInformation, MODULEINFO};
 
+use crate::constants::constants::memory::{INITIAL_MODULE_CAPACITY, MODULE_POLL_INTERVAL_MS};
 use crate::error::{AppError, Result};
+use crate::logger::Logger;
+use crate::safety::{MemoryAccess, StringConverter};
+
+pub mod address;
+pub(crate) mod dll_notify;
+pub mod hash;
+pub mod image_file;
+pub mod map;
+pub mod pe;
+pub mod peb;
+pub mod version;
+
+pub use address::{ModuleAddress, Rva, describe_address, module_for_address};
+use dll_notify::DllLoadNotification;
+use hash::FileHash;
+pub use image_file::MappedImage;
+pub use map::{MemoryRegion, dump_memory_map, memory_regions};
+use pe::{ExportTarget, ImportedModule, PeImage};
+use version::ModuleVersion;
 
 /// Module scanner with caching for improved performance
 pub struct ModuleScanner {
     module_cache: Arc<RwLock<HashMap<String, HMODULE>>>,
+    caching_enabled: bool,
+    revalidate: bool,
 }
 
 impl ModuleScanner {
     pub fn new() -> Self {
+        Self::with_caching(true)
+    }
+
+    /// Create a scanner, optionally bypassing the module cache entirely
+    pub fn with_caching(caching_enabled: bool) -> Self {
         Self {
             module_cache: Arc::new(RwLock::new(HashMap::new())),
+            caching_enabled,
+            revalidate: false,
         }
     }
 
+    /// Re-check cached handles against the loader before returning them
+    ///
+    /// Catches modules that were unloaded or reloaded at a different base
+    /// since they were cached, at the cost of one extra lookup per call.
+    pub fn with_revalidation(mut self, revalidate: bool) -> Self {
+        self.revalidate = revalidate;
+        self
+    }
+
+    /// Check if lookups are served from the module cache
+    pub fn is_caching_enabled(&self) -> bool {
+        self.caching_enabled
+    }
+
+    /// Drop the cached handle for a module, returning whether one was cached
+    pub fn invalidate(&self, name: &str) -> bool {
+        let mut cache = self.module_cache.write().unwrap();
+        cache.remove(name).is_some()
+    }
+
+    /// Drop all cached module handles
+    pub fn clear(&self) {
+        let mut cache = self.module_cache.write().unwrap();
+        cache.clear();
+    }
+
+    /// Check if a cached handle still refers to the loaded module `name`
+    fn is_cached_handle_current(&self, name: &str, module: HMODULE) -> bool {
+        if self.get_module_info(module).is_err() {
+            return false;
+        }
+
+        let wide_name = StringConverter::to_wide_null(name);
+        let current = unsafe { GetModuleHandleW(wide_name.as_ptr()) };
+        if !current.is_null() {
+            return current == module;
+        }
+
+        peb::find_loaded_module(name).is_some_and(|loaded| loaded.base == module)
+    }
+
     /// Find a module by name with caching
     pub fn find_module(&self, name: &str) -> Result<HMODULE> {
+        if !self.caching_enabled {
+            return self.find_module_impl(name);
+        }
+
         // Check cache first
-        {
+        let cached = {
             let cache = self.module_cache.read().unwrap();
-            if let Some(&module) = cache.get(name) {
+            cache.get(name).copied()
+        };
+
+        if let Some(module) = cached {
+            if !self.revalidate || self.is_cached_handle_current(name, module) {
                 return Ok(module);
             }
+
+            Logger::warning(&format!(
+                "Cached handle {:?} for module '{}' is stale, searching again",
+                module, name
+            ));
+            self.invalidate(name);
         }
 
         // Module not in cache, search for it
@@ -44,37 +130,155 @@ impl ModuleScanner {
         Ok(module)
     }
 
-    /// Internal implementation of module finding
-    fn find_module_impl(&self, name: &str) -> Result<HMODULE> {
-        unsafe {
-            let h_process = GetCurrentProcess();
-            let mut modules: [HMODULE; 1024] = [ptr::null_mut(); 1024];
-            let mut cb_needed: DWORD = 0;
+    /// Wait until a module is loaded, up to `timeout`
+    ///
+    /// Wakes up on DLL load notifications when `LdrRegisterDllNotification` is
+    /// available and polls every `MODULE_POLL_INTERVAL_MS` otherwise, so the
+    /// DLL can be injected before the target module is mapped.
+    pub fn wait_for_module(&self, name: &str, timeout: Duration) -> Result<HMODULE> {
+        self.wait_for_any_module(&[name], timeout)?;
+        self.find_module(name)
+    }
 
-            if EnumProcessModules(
-                h_process,
-                modules.as_mut_ptr(),
-                std::mem::size_of_val(&modules) as DWORD,
-                &mut cb_needed,
-            ) == 0
-            {
+    /// Wait until at least one of `names` is loaded, up to `timeout`
+    ///
+    /// Returns every name that is loaded by then, keeping their order. See
+    /// [`ModuleScanner::wait_for_module`] for how the wait works.
+    pub fn wait_for_any_module<'n>(
+        &self,
+        names: &[&'n str],
+        timeout: Duration,
+    ) -> Result<Vec<&'n str>> {
+        let start = Instant::now();
+        let notification = DllLoadNotification::register();
+        let mut announced = false;
+        let listed = names.join("', '");
+
+        loop {
+            let generation = dll_notify::load_generation();
+            let loaded: Vec<&str> = names
+                .iter()
+                .copied()
+                .filter(|name| Self::is_module_loaded(name))
+                .collect();
+            if !loaded.is_empty() {
+                if announced {
+                    Logger::info(&format!(
+                        "Module '{}' loaded after {:?}",
+                        loaded.join("', '"),
+                        start.elapsed()
+                    ));
+                }
+                return Ok(loaded);
+            }
+
+            let elapsed = start.elapsed();
+            if elapsed >= timeout {
+                Logger::error(&format!(
+                    "Module '{}' did not load within {:?}",
+                    listed, timeout
+                ));
                 return Err(AppError::ModuleNotFound {
-                    name: name.to_string(),
+                    name: names.join(", "),
                 });
             }
 
-            let count = (cb_needed as usize) / std::mem::size_of::<HMODULE>();
-            for &mod_handle in &modules[..count] {
-                if let Some(module_name) = self.get_module_name(h_process, mod_handle)? {
-                    if module_name.eq_ignore_ascii_case(name) {
-                        return Ok(mod_handle);
+            if !announced {
+                match notification {
+                    Some(_) => Logger::info(&format!(
+                        "Waiting for module '{}' (DLL load notifications)...",
+                        listed
+                    )),
+                    None => Logger::info(&format!("Waiting for module '{}' (polling)...", listed)),
+                }
+                announced = true;
+            }
+
+            let interval = (timeout - elapsed).min(Duration::from_millis(MODULE_POLL_INTERVAL_MS));
+            match &notification {
+                Some(notification) => {
+                    notification.wait_since(generation, interval);
+                }
+                None => thread::sleep(interval),
+            }
+        }
+    }
+
+    /// Cheap presence check used while waiting for a module to load
+    fn is_module_loaded(name: &str) -> bool {
+        let wide_name = StringConverter::to_wide_null(name);
+        let handle = unsafe { GetModuleHandleW(wide_name.as_ptr()) };
+        !handle.is_null() || peb::find_loaded_module(name).is_some()
+    }
+
+    /// Internal implementation of module finding
+    ///
+    /// Falls back to walking the PEB loader list if `EnumProcessModules`
+    /// fails or does not report the module.
+    fn find_module_impl(&self, name: &str) -> Result<HMODULE> {
+        match self.enumerate_modules() {
+            Ok(modules) => {
+                let h_process = unsafe { GetCurrentProcess() };
+                for mod_handle in modules {
+                    if let Some(module_name) = self.get_module_name(h_process, mod_handle)? {
+                        if StringConverter::names_equal(&module_name, name) {
+                            return Ok(mod_handle);
+                        }
                     }
                 }
+
+                Logger::warning(&format!(
+                    "Module '{}' not listed by EnumProcessModules, walking the PEB loader list",
+                    name
+                ));
             }
+            Err(e) => {
+                Logger::warning(&format!(
+                    "EnumProcessModules failed ({}), walking the PEB loader list",
+                    e
+                ));
+            }
+        }
 
-            Err(AppError::ModuleNotFound {
+        peb::find_loaded_module(name)
+            .map(|module| module.base)
+            .ok_or_else(|| AppError::ModuleNotFound {
                 name: name.to_string(),
             })
+    }
+
+    /// Enumerate the handles of every module loaded in the current process
+    ///
+    /// The buffer starts at `INITIAL_MODULE_CAPACITY` entries and grows until
+    /// `EnumProcessModules` reports that every handle fit.
+    pub fn enumerate_modules(&self) -> Result<Vec<HMODULE>> {
+        let h_process = unsafe { GetCurrentProcess() };
+        let mut modules: Vec<HMODULE> = vec![ptr::null_mut(); INITIAL_MODULE_CAPACITY];
+
+        loop {
+            let mut cb_needed: DWORD = 0;
+            if unsafe {
+                EnumProcessModules(
+                    h_process,
+                    modules.as_mut_ptr(),
+                    std::mem::size_of_val(modules.as_slice()) as DWORD,
+                    &mut cb_needed,
+                )
+            } == 0
+            {
+                return Err(AppError::ModuleInfoFailed {
+                    source: std::io::Error::last_os_error(),
+                });
+            }
+
+            let count = (cb_needed as usize) / std::mem::size_of::<HMODULE>();
+            if count <= modules.len() {
+                modules.truncate(count);
+                return Ok(modules);
+            }
+
+            // More modules than slots: grow to the reported size and retry
+            modules.resize(count, ptr::null_mut());
         }
     }
 
@@ -103,44 +307,227 @@ impl ModuleScanner {
         }
     }
 
+    /// Resolve an exported function by parsing the module's export directory
+    ///
+    /// Unlike `GetProcAddress` this never calls into the loader, so hooks a
+    /// protector places on it do not see or redirect the lookup.
+    pub fn find_export(&self, module: HMODULE, name: &str) -> Result<*mut u8> {
+        let info = self.get_module_info(module)?;
+        let pe = unsafe { PeImage::from_module(&info)? };
+        Self::resolve_export(&info, name.to_string(), pe.find_export(name)?)
+    }
+
+    /// Resolve an exported function by the [`pe::export_hash`] of its name
+    pub fn find_export_by_hash(&self, module: HMODULE, hash: u32) -> Result<*mut u8> {
+        let info = self.get_module_info(module)?;
+        let pe = unsafe { PeImage::from_module(&info)? };
+        Self::resolve_export(
+            &info,
+            format!("#{:08x}", hash),
+            pe.find_export_by_hash(hash)?,
+        )
+    }
+
+    /// Read the file and product version from the module's version resource
+    pub fn get_version_info(&self, module: HMODULE) -> Result<ModuleVersion> {
+        version::module_version(module)
+    }
+
+    /// SHA-256 of the file on disk the module was loaded from
+    pub fn hash_module_file(&self, module: HMODULE) -> Result<FileHash> {
+        let path = hash::module_path(module)?;
+        hash::hash_file(&path)
+    }
+
+    /// List the DLLs and functions a module imports
+    pub fn imports(&self, module: HMODULE) -> Result<Vec<ImportedModule>> {
+        let info = self.get_module_info(module)?;
+        let pe = unsafe { PeImage::from_module(&info)? };
+        pe.imports()
+    }
+
+    /// Find the IAT slot through which `module` calls `dll!function`
+    ///
+    /// The slot holds the resolved address the module's calls jump through,
+    /// which is what an IAT hook overwrites.
+    pub fn find_import_slot(
+        &self,
+        module: HMODULE,
+        dll: &str,
+        function: &str,
+    ) -> Result<*mut usize> {
+        let info = self.get_module_info(module)?;
+        let pe = unsafe { PeImage::from_module(&info)? };
+        match pe.find_import(dll, function)? {
+            Some(rva) => Ok((info.lpBaseOfDll as *mut u8).wrapping_add(rva as usize) as *mut usize),
+            None => Err(AppError::ImportNotFound {
+                module: dll.to_string(),
+                name: function.to_string(),
+            }),
+        }
+    }
+
+    fn resolve_export(
+        info: &MODULEINFO,
+        name: String,
+        target: Option<ExportTarget>,
+    ) -> Result<*mut u8> {
+        match target {
+            Some(ExportTarget::Rva(rva)) => {
+                Ok((info.lpBaseOfDll as *mut u8).wrapping_add(rva as usize))
+            }
+            Some(ExportTarget::Forwarder(forwarder)) => {
+                Err(AppError::ExportForwarded { name, forwarder })
+            }
+            None => Err(AppError::ExportNotFound { name }),
+        }
+    }
+
     fn get_module_name(
         &self,
         h_process: *mut c_void,
         mod_handle: HMODULE,
     ) -> Result<Option<String>> {
-        unsafe {
-            let mut mod_name = [0u8; 256];
-            if GetModuleBaseNameA(
+        let mut mod_name = [0u16; MAX_PATH];
+        let len = unsafe {
+            GetModuleBaseNameW(
                 h_process,
                 mod_handle,
-                mod_name.as_mut_ptr() as *mut i8,
-                256 as DWORD,
-            ) == 0
-            {
-                return Ok(None);
-            }
+                mod_name.as_mut_ptr(),
+                MAX_PATH as DWORD,
+            )
+        } as usize;
 
-            if let Ok(s) = CStr::from_ptr(mod_name.as_ptr() as *const i8).to_str() {
-                Ok(Some(s.to_string()))
-            } else {
-                Ok(None)
-            }
+        if len == 0 {
+            return Ok(None);
         }
+
+        Ok(Some(String::from_utf16_lossy(
+            &mod_name[..len.min(MAX_PATH)],
+        )))
     }
 }
 
+/// Bytes that dominate x86-64 machine code, most common first
+///
+/// Used to rank anchor candidates for wildcard scans; bytes not listed here
+/// are considered rare.
+const COMMON_CODE_BYTES: [u8; 24] = [
+    0x00, 0xFF, 0xCC, 0x48, 0x8B, 0x89, 0x24, 0x0F, 0x4C, 0x44, 0xE8, 0x85, 0xC0, 0x01, 0x83, 0x8D,
+    0x4D, 0x45, 0x08, 0x10, 0x74, 0x20, 0xC3, 0x90,
+];
+
+/// Estimated frequency rank of a byte in code (higher means more common)
+fn byte_frequency_rank(byte: u8) -> usize {
+    COMMON_CODE_BYTES
+        .iter()
+        .position(|&b| b == byte)
+        .map_or(0, |pos| COMMON_CODE_BYTES.len() - pos)
+}
+
+/// Result of a signature uniqueness self-test
+#[derive(Debug, Clone)]
+pub struct UniquenessReport {
+    /// Addresses of every match found in the module
+    pub matches: Vec<usize>,
+    /// Matches that do not lie in executable memory
+    pub non_executable: Vec<usize>,
+}
+
+impl UniquenessReport {
+    /// Number of matches found in the module
+    pub fn match_count(&self) -> usize {
+        self.matches.len()
+    }
+
+    /// Check if the signature matched exactly once
+    pub fn is_unique(&self) -> bool {
+        self.matches.len() == 1
+    }
+
+    /// Check if the signature matched exactly once inside executable memory
+    pub fn is_clean(&self) -> bool {
+        self.is_unique() && self.non_executable.is_empty()
+    }
+}
+
+/// Suggested replacement for a signature that drifted after a game update
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SignatureSuggestion {
+    /// Offset of the closest match from the module base
+    pub rva: Rva,
+    /// Bytes currently found at the closest match
+    pub pattern: Vec<u8>,
+    /// Original mask with every changed byte turned into a wildcard
+    pub mask: String,
+    /// Number of exact bytes that no longer match
+    pub changed: usize,
+}
+
+impl SignatureSuggestion {
+    /// Diff the old signature against the bytes now found in memory
+    pub fn diff(rva: Rva, old_pattern: &[u8], old_mask: &str, new_bytes: &[u8]) -> Self {
+        let mut changed = 0;
+        let mask = old_pattern
+            .iter()
+            .zip(new_bytes)
+            .zip(old_mask.bytes())
+            .map(|((old, new), m)| {
+                if m == b'x' && old != new {
+                    changed += 1;
+                    '?'
+                } else {
+                    m as char
+                }
+            })
+            .collect();
+
+        Self {
+            rva,
+            pattern: new_bytes.to_vec(),
+            mask,
+            changed,
+        }
+    }
+
+    /// Format the pattern as a Rust array literal ready to paste into `constants`
+    pub fn pattern_literal(&self) -> String {
+        let bytes: Vec<String> = self
+            .pattern
+            .iter()
+            .map(|b| format!("0x{:02X}", b))
+            .collect();
+        format!("[{}]", bytes.join(", "))
+    }
+}
+
+/// Key of a cached scan: region base and size, pattern and mask
+type ScanKey = (usize, usize, Vec<u8>, String);
+
 /// High-performance pattern scanner with optimized algorithms
 pub struct PatternScanner {
-    cache: HashMap<(usize, usize), *mut u8>,
+    cache: HashMap<ScanKey, *mut u8>,
+    caching_enabled: bool,
 }
 
 impl PatternScanner {
     pub fn new() -> Self {
+        Self::with_caching(true)
+    }
+
+    /// Create a scanner, optionally rescanning on every call
+    pub fn with_caching(caching_enabled: bool) -> Self {
         Self {
             cache: HashMap::new(),
+            caching_enabled,
         }
     }
 
+    /// Check if scan results are served from the cache
+    pub fn is_caching_enabled(&self) -> bool {
+        self.caching_enabled
+    }
+
     /// Scan for a pattern with caching and optimized algorithms
     pub fn scan(
         &mut self,
@@ -149,7 +536,11 @@ impl PatternScanner {
         pattern: &[u8],
         mask: &str,
     ) -> Result<*mut u8> {
-        let cache_key = (base as usize, size);
+        if !self.caching_enabled {
+            return self.scan_impl(base, size, pattern, mask);
+        }
+
+        let cache_key = (base as usize, size, pattern.to_vec(), mask.to_string());
 
         if let Some(&cached_result) = self.cache.get(&cache_key) {
             if !cached_result.is_null() {
@@ -200,7 +591,7 @@ impl PatternScanner {
     fn scan_single_byte(&self, base: *mut u8, size: usize, byte: u8) -> Result<*mut u8> {
         unsafe {
             let slice = std::slice::from_raw_parts(base, size);
-            if let Some(pos) = slice.iter().position(|&b| b == byte) {
+            if let Some(pos) = memchr::memchr(byte, slice) {
                 Ok(base.add(pos))
             } else {
                 Err(AppError::PatternNotFound { size })
@@ -230,7 +621,11 @@ impl PatternScanner {
         }
     }
 
-    /// Scan patterns with wildcards using brute force
+    /// Scan patterns with wildcards using a rarity-anchored search
+    ///
+    /// The rarest exact byte of the pattern is used as an anchor: memchr jumps
+    /// between its occurrences and the full pattern is only verified around
+    /// each candidate, instead of at every offset of the module.
     fn scan_with_wildcards(
         &self,
         base: *mut u8,
@@ -238,13 +633,195 @@ impl PatternScanner {
         pattern: &[u8],
         mask: &str,
     ) -> Result<*mut u8> {
-        for i in 0..=size.saturating_sub(pattern.len()) {
-            if self.matches_pattern(base, i, pattern, mask) {
-                return Ok(unsafe { base.add(i) });
-            }
+        if pattern.len() > size {
+            return Err(AppError::PatternNotFound { size });
+        }
+
+        // A mask made only of wildcards matches at the very first offset
+        let Some(anchor) = Self::select_anchor(pattern, mask) else {
+            return Ok(base);
+        };
+
+        match self
+            .anchored_matches(base, size, pattern, mask, anchor)
+            .next()
+        {
+            Some(start) => Ok(unsafe { base.add(start) }),
+            None => Err(AppError::PatternNotFound { size }),
         }
+    }
+
+    /// Iterate over the start offsets of every match around an anchor byte
+    ///
+    /// The caller must ensure the pattern is not longer than the scanned region.
+    fn anchored_matches(
+        &self,
+        base: *mut u8,
+        size: usize,
+        pattern: &[u8],
+        mask: &str,
+        anchor: usize,
+    ) -> impl Iterator<Item = usize> {
+        let last_start = size - pattern.len();
+        let slice = unsafe { std::slice::from_raw_parts(base, size) };
+
+        // The anchor byte of a match starting at `start` sits at `start + anchor`,
+        // so searching from `anchor` makes every hit position a start offset.
+        let haystack = &slice[anchor..=last_start + anchor];
+        memchr::memchr_iter(pattern[anchor], haystack)
+            .filter(move |&start| self.matches_pattern(base, start, pattern, mask))
+    }
+
+    /// Find the offsets of every match of a pattern
+    fn find_all(
+        &self,
+        base: *mut u8,
+        size: usize,
+        pattern: &[u8],
+        mask: &str,
+    ) -> Result<Vec<usize>> {
+        if pattern.len() != mask.len() || pattern.is_empty() {
+            return Err(AppError::PatternNotFound { size });
+        }
+
+        if pattern.len() > size {
+            return Ok(Vec::new());
+        }
+
+        match Self::select_anchor(pattern, mask) {
+            Some(anchor) => Ok(self
+                .anchored_matches(base, size, pattern, mask, anchor)
+                .collect()),
+            None => Ok((0..=size - pattern.len()).collect()),
+        }
+    }
 
-        Err(AppError::PatternNotFound { size })
+    /// Find the offsets of every match of a pattern in `image`
+    ///
+    /// The same search as [`PatternScanner::verify_unique`], for images that
+    /// are not loaded as modules, such as one mapped from disk.
+    pub fn find_matches(&self, image: &[u8], pattern: &[u8], mask: &str) -> Result<Vec<usize>> {
+        self.find_all(image.as_ptr() as *mut u8, image.len(), pattern, mask)
+    }
+
+    /// Search near a hinted RVA for a drifted copy of the signature
+    ///
+    /// Meant to be used after an exact scan failed: every offset within
+    /// `window` bytes of the hint is compared against the exact bytes of the
+    /// pattern, and the closest candidate differing in at most a quarter of
+    /// them is diffed into a suggested replacement signature.
+    pub fn suggest_signature(
+        &self,
+        module: &MODULEINFO,
+        pattern: &[u8],
+        mask: &str,
+        hint_rva: Rva,
+        window: usize,
+    ) -> Option<SignatureSuggestion> {
+        let base = module.lpBaseOfDll as *const u8;
+        let size = module.SizeOfImage as usize;
+
+        if pattern.len() != mask.len() || pattern.is_empty() || pattern.len() > size {
+            return None;
+        }
+
+        let exact_bytes = mask.bytes().filter(|&m| m == b'x').count();
+        let max_changed = exact_bytes / 4;
+        if max_changed == 0 {
+            return None;
+        }
+
+        let slice = unsafe { std::slice::from_raw_parts(base, size) };
+        let hint = hint_rva.as_usize();
+        let first = hint.saturating_sub(window);
+        let last = hint.saturating_add(window).min(size - pattern.len());
+
+        let (rva, changed) = (first..=last)
+            .map(|offset| {
+                let candidate = &slice[offset..offset + pattern.len()];
+                let changed = pattern
+                    .iter()
+                    .zip(candidate)
+                    .zip(mask.bytes())
+                    .filter(|&((old, new), m)| m == b'x' && old != new)
+                    .count();
+                (offset, changed)
+            })
+            .min_by_key(|&(offset, changed)| (changed, offset.abs_diff(hint)))?;
+
+        if changed > max_changed {
+            return None;
+        }
+
+        let new_bytes = &slice[rva..rva + pattern.len()];
+        // Offsets within `SizeOfImage` fit in 32 bits
+        Some(SignatureSuggestion::diff(
+            Rva(rva as u32),
+            pattern,
+            mask,
+            new_bytes,
+        ))
+    }
+
+    /// Count every match of a signature in a module and flag suspicious results
+    ///
+    /// Warns if the signature matches more than once or if a match lies outside
+    /// executable memory, both of which usually mean the pattern is stale.
+    pub fn verify_unique(
+        &self,
+        module: &MODULEINFO,
+        pattern: &[u8],
+        mask: &str,
+    ) -> Result<UniquenessReport> {
+        let base = module.lpBaseOfDll as *mut u8;
+        let size = module.SizeOfImage as usize;
+
+        let matches: Vec<usize> = self
+            .find_all(base, size, pattern, mask)?
+            .into_iter()
+            .map(|offset| base as usize + offset)
+            .collect();
+        let non_executable = matches
+            .iter()
+            .copied()
+            .filter(|&address| !MemoryAccess::is_executable(address as *const u8))
+            .collect();
+
+        let report = UniquenessReport {
+            matches,
+            non_executable,
+        };
+
+        match report.match_count() {
+            0 => Logger::warning("Signature has no matches in the module"),
+            1 => Logger::scan("Signature is unique in the module"),
+            count => Logger::warning(&format!(
+                "Signature is not unique: {} matches found, the first one will be used",
+                count
+            )),
+        }
+
+        for address in &report.non_executable {
+            Logger::warning(&format!(
+                "Signature match at {:#x} is not in executable memory",
+                address
+            ));
+        }
+
+        Ok(report)
+    }
+
+    /// Pick the index of the rarest exact byte in the pattern
+    ///
+    /// Returns `None` if the mask contains no exact bytes.
+    fn select_anchor(pattern: &[u8], mask: &str) -> Option<usize> {
+        pattern
+            .iter()
+            .zip(mask.bytes())
+            .enumerate()
+            .filter(|&(_, (_, m))| m == b'x')
+            .min_by_key(|&(_, (&b, _))| byte_frequency_rank(b))
+            .map(|(i, _)| i)
     }
 
     /// Check if pattern matches at given offset
@@ -308,6 +885,152 @@ mod tests {
         assert!(scanner.matches_pattern(buffer.as_mut_ptr(), 0, &pattern, mask));
     }
 
+    #[test]
+    fn test_module_cache_invalidate_and_clear() {
+        let scanner = ModuleScanner::new();
+        {
+            let mut cache = scanner.module_cache.write().unwrap();
+            cache.insert("a.dll".to_string(), 0x1000 as HMODULE);
+            cache.insert("b.dll".to_string(), 0x2000 as HMODULE);
+        }
+
+        assert!(scanner.invalidate("a.dll"));
+        assert!(!scanner.invalidate("a.dll"));
+        assert_eq!(scanner.module_cache.read().unwrap().len(), 1);
+
+        scanner.clear();
+        assert!(scanner.module_cache.read().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_pattern_scanner_without_caching_rescans() {
+        let mut scanner = PatternScanner::with_caching(false);
+        let mut buffer = vec![0x90, 0x55, 0x53, 0x90];
+        let base = buffer.as_mut_ptr();
+
+        let first = scanner
+            .scan(base, buffer.len(), &[0x55, 0x53], "xx")
+            .unwrap();
+        assert_eq!(first, unsafe { base.add(1) });
+        assert_eq!(scanner.cache_stats(), (0, 0));
+
+        buffer[1] = 0x90;
+        buffer[2] = 0x55;
+        buffer[3] = 0x53;
+        let second = scanner
+            .scan(base, buffer.len(), &[0x55, 0x53], "xx")
+            .unwrap();
+        assert_eq!(second, unsafe { base.add(2) });
+    }
+
+    #[test]
+    fn test_select_anchor_prefers_rare_byte() {
+        let pattern = [0x48, 0x8B, 0x00, 0x9A, 0xE8];
+        assert_eq!(PatternScanner::select_anchor(&pattern, "xx?xx"), Some(3));
+        assert_eq!(PatternScanner::select_anchor(&pattern, "???"), None);
+    }
+
+    #[test]
+    fn test_wildcard_scan_leading_wildcards() {
+        let mut scanner = PatternScanner::new();
+        let mut buffer = vec![0x48, 0x8B, 0x9A, 0x48, 0x8B, 0x05, 0x9A, 0x00];
+        let pattern = [0x00, 0x8B, 0x05, 0x9A];
+        let mask = "?xxx";
+
+        let result = scanner.scan(buffer.as_mut_ptr(), buffer.len(), &pattern, mask);
+        assert_eq!(result.unwrap(), unsafe { buffer.as_mut_ptr().add(3) });
+    }
+
+    #[test]
+    fn test_wildcard_scan_match_at_end() {
+        let mut scanner = PatternScanner::new();
+        let mut buffer = vec![0x9A, 0x00, 0x00, 0x11, 0x42, 0x9A];
+        let pattern = [0x11, 0x00, 0x9A];
+        let mask = "x?x";
+
+        let result = scanner.scan(buffer.as_mut_ptr(), buffer.len(), &pattern, mask);
+        assert_eq!(result.unwrap(), unsafe { buffer.as_mut_ptr().add(3) });
+    }
+
+    #[test]
+    fn test_wildcard_scan_not_found() {
+        let mut scanner = PatternScanner::new();
+        let mut buffer = vec![0x9A, 0x00, 0x9A, 0x00];
+        let pattern = [0x9A, 0x00, 0x11];
+        let mask = "x?x";
+
+        let result = scanner.scan(buffer.as_mut_ptr(), buffer.len(), &pattern, mask);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_find_all_counts_every_match() {
+        let scanner = PatternScanner::new();
+        let mut buffer = vec![0x9A, 0x01, 0x9A, 0x02, 0x9A, 0x01];
+        let pattern = [0x9A, 0x01];
+
+        let exact = scanner.find_all(buffer.as_mut_ptr(), buffer.len(), &pattern, "xx");
+        assert_eq!(exact.unwrap(), vec![0, 4]);
+
+        let wildcard = scanner.find_all(buffer.as_mut_ptr(), buffer.len(), &pattern, "x?");
+        assert_eq!(wildcard.unwrap(), vec![0, 2, 4]);
+    }
+
+    #[test]
+    fn test_uniqueness_report() {
+        let report = UniquenessReport {
+            matches: vec![0x1000],
+            non_executable: vec![],
+        };
+        assert!(report.is_unique());
+        assert!(report.is_clean());
+
+        let report = UniquenessReport {
+            matches: vec![0x1000, 0x2000],
+            non_executable: vec![0x2000],
+        };
+        assert_eq!(report.match_count(), 2);
+        assert!(!report.is_unique());
+        assert!(!report.is_clean());
+    }
+
+    #[test]
+    fn test_signature_suggestion_diff() {
+        let old = [0x49, 0x81, 0xC3, 0x9A, 0x0B];
+        let new = [0x49, 0x81, 0xC3, 0x9B, 0x0C];
+        let suggestion = SignatureSuggestion::diff(Rva(0x10), &old, "xxx?x", &new);
+
+        assert_eq!(suggestion.mask, "xxx??");
+        assert_eq!(suggestion.changed, 1);
+        assert_eq!(
+            suggestion.pattern_literal(),
+            "[0x49, 0x81, 0xC3, 0x9B, 0x0C]"
+        );
+    }
+
+    #[test]
+    fn test_suggest_signature_near_hint() {
+        let scanner = PatternScanner::new();
+        let mut buffer = vec![0u8; 64];
+        buffer[40..48].copy_from_slice(&[0x49, 0x81, 0xC3, 0x9A, 0x0B, 0xFB, 0xFE, 0x48]);
+        let pattern = [0x49, 0x81, 0xC3, 0x9A, 0x0B, 0xFB, 0xFF, 0x48];
+
+        let module = MODULEINFO {
+            lpBaseOfDll: buffer.as_mut_ptr() as *mut c_void,
+            SizeOfImage: buffer.len() as DWORD,
+            EntryPoint: ptr::null_mut(),
+        };
+
+        let suggestion = scanner
+            .suggest_signature(&module, &pattern, "xxxxxxxx", Rva(32), 16)
+            .unwrap();
+        assert_eq!(suggestion.rva, Rva(40));
+        assert_eq!(suggestion.mask, "xxxxxx?x");
+
+        let too_far = scanner.suggest_signature(&module, &pattern, "xxxxxxxx", Rva(0), 8);
+        assert!(too_far.is_none());
+    }
+
     #[test]
     fn test_single_byte_pattern() {
         let mut scanner = PatternScanner::new();
@@ -365,6 +1088,22 @@ mod tests {
         assert!(non_null > 0);
     }
 
+    #[test]
+    fn test_cache_keeps_patterns_apart() {
+        let mut scanner = PatternScanner::new();
+        let mut buffer = vec![0x55, 0x53, 0x56, 0x41, 0x54];
+        let base = buffer.as_mut_ptr();
+
+        let first = scanner
+            .scan(base, buffer.len(), &[0x55, 0x53], "xx")
+            .unwrap();
+        let second = scanner
+            .scan(base, buffer.len(), &[0x41, 0x54], "xx")
+            .unwrap();
+        assert_eq!(first, base);
+        assert_eq!(second, base.wrapping_add(3));
+    }
+
     #[test]
     fn test_clear_cache() {
         let mut scanner = PatternScanner::new();
@@ -0,0 +1,195 @@
+//! Mapping of absolute addresses back to the module that contains them
+//!
+//! Module bases move between runs because of ASLR, so an absolute address
+//! in a log line cannot be compared with one from another run.
+//! `Client-Win64-Shipping.exe+0x123456` can, and points straight at the
+//! place to look in a disassembler. Offsets kept across runs, like the
+//! signature hint in the configuration, are stored as an [`Rva`] for the
+//! same reason.
+
+use std::fmt;
+use std::ptr;
+
+use winapi::shared::minwindef::{DWORD, HMODULE, MAX_PATH};
+use winapi::um::libloaderapi::{
+    GET_MODULE_HANDLE_EX_FLAG_FROM_ADDRESS, GET_MODULE_HANDLE_EX_FLAG_UNCHANGED_REFCOUNT,
+    GetModuleHandleExW,
+};
+use winapi::um::processthreadsapi::GetCurrentProcess;
+use winapi::um::psapi::{GetModuleBaseNameW, GetModuleInformation, MODULEINFO};
+
+/// Offset of an address from the base of its module
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct Rva(pub u32);
+
+impl Rva {
+    /// Offset of `address` from `base`, if it is one a PE image can have
+    pub fn from_address(address: usize, base: usize) -> Option<Self> {
+        address
+            .checked_sub(base)
+            .and_then(|offset| u32::try_from(offset).ok())
+            .map(Self)
+    }
+
+    /// Absolute address of this offset in the module loaded at `base`
+    pub fn to_address(self, base: usize) -> usize {
+        base.wrapping_add(self.as_usize())
+    }
+
+    pub fn as_usize(self) -> usize {
+        self.0 as usize
+    }
+}
+
+impl From<u32> for Rva {
+    fn from(offset: u32) -> Self {
+        Self(offset)
+    }
+}
+
+impl TryFrom<i64> for Rva {
+    type Error = std::num::TryFromIntError;
+
+    fn try_from(offset: i64) -> std::result::Result<Self, Self::Error> {
+        u32::try_from(offset).map(Self)
+    }
+}
+
+impl fmt::Display for Rva {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:#x}", self.0)
+    }
+}
+
+impl fmt::LowerHex for Rva {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::LowerHex::fmt(&self.0, f)
+    }
+}
+
+/// An address expressed as an offset into its module
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModuleAddress {
+    /// File name of the module, e.g. `Client-Win64-Shipping.exe`
+    pub module: String,
+    pub base: usize,
+    pub rva: Rva,
+}
+
+impl fmt::Display for ModuleAddress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}+{}", self.module, self.rva)
+    }
+}
+
+/// Find the loaded module containing `address`
+///
+/// Returns `None` for addresses outside every module, such as heap memory
+/// or code a protector allocated privately.
+pub fn module_for_address(address: usize) -> Option<ModuleAddress> {
+    let mut module: HMODULE = ptr::null_mut();
+    let found = unsafe {
+        GetModuleHandleExW(
+            GET_MODULE_HANDLE_EX_FLAG_FROM_ADDRESS | GET_MODULE_HANDLE_EX_FLAG_UNCHANGED_REFCOUNT,
+            address as *const u16,
+            &mut module,
+        )
+    };
+    if found == 0 || module.is_null() {
+        return None;
+    }
+
+    let mut info = MODULEINFO {
+        lpBaseOfDll: ptr::null_mut(),
+        SizeOfImage: 0,
+        EntryPoint: ptr::null_mut(),
+    };
+    let mut name = [0u16; MAX_PATH];
+    let len = unsafe {
+        if GetModuleInformation(
+            GetCurrentProcess(),
+            module,
+            &mut info,
+            size_of::<MODULEINFO>() as DWORD,
+        ) == 0
+        {
+            return None;
+        }
+        GetModuleBaseNameW(
+            GetCurrentProcess(),
+            module,
+            name.as_mut_ptr(),
+            MAX_PATH as DWORD,
+        ) as usize
+    };
+
+    let base = info.lpBaseOfDll as usize;
+    let rva = Rva::from_address(address, base).filter(|rva| rva.0 < info.SizeOfImage)?;
+    Some(ModuleAddress {
+        module: String::from_utf16_lossy(&name[..len.min(MAX_PATH)]),
+        base,
+        rva,
+    })
+}
+
+/// `module+0xrva` for addresses inside a module, the plain address otherwise
+pub fn describe_address(address: usize) -> String {
+    module_for_address(address).map_or_else(|| format!("{:#x}", address), |owner| owner.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_module_address_display() {
+        let address = ModuleAddress {
+            module: "Client-Win64-Shipping.exe".to_string(),
+            base: 0x7FF6_0000_0000,
+            rva: Rva(0x123456),
+        };
+        assert_eq!(address.to_string(), "Client-Win64-Shipping.exe+0x123456");
+    }
+
+    #[test]
+    fn test_rva_round_trips_through_a_base() {
+        let base = 0x7FF6_0000_0000;
+        let rva = Rva::from_address(base + 0x1400, base).unwrap();
+        assert_eq!(rva, Rva(0x1400));
+        assert_eq!(rva.to_address(base), base + 0x1400);
+        assert_eq!(format!("{} {:#x}", rva, rva), "0x1400 0x1400");
+
+        assert_eq!(Rva::from_address(base - 1, base), None);
+        assert_eq!(Rva::from_address(base + (1 << 32), base), None);
+        assert!(Rva::try_from(-1i64).is_err());
+    }
+
+    #[test]
+    fn test_addresses_outside_modules_print_plainly() {
+        let heap = Box::new(0u64);
+        let address = &*heap as *const u64 as usize;
+        assert_eq!(module_for_address(address), None);
+        assert_eq!(describe_address(address), format!("{:#x}", address));
+    }
+
+    #[test]
+    fn test_code_maps_to_the_test_executable() {
+        let address = test_code_maps_to_the_test_executable as fn() as usize;
+        let owner = module_for_address(address).unwrap();
+
+        let exe = std::env::current_exe().unwrap();
+        let exe_name = exe.file_name().unwrap().to_string_lossy();
+        assert!(owner.module.eq_ignore_ascii_case(&exe_name));
+        assert_eq!(owner.rva.to_address(owner.base), address);
+
+        let info = crate::memory::ModuleScanner::new()
+            .get_module_info(owner.base as HMODULE)
+            .unwrap();
+        assert_eq!(info.lpBaseOfDll as usize, owner.base);
+        assert!(owner.rva.0 < info.SizeOfImage);
+        assert_eq!(
+            describe_address(address),
+            format!("{}+{:#x}", owner.module, owner.rva)
+        );
+    }
+}
@@ -0,0 +1,118 @@
+//! DLL load notifications via `LdrRegisterDllNotification`
+//!
+//! The loader invokes the callback while holding the loader lock, so it only
+//! bumps a generation counter and wakes up waiters.
+
+use std::ffi::{CStr, c_void};
+use std::ptr;
+use std::sync::{Condvar, Mutex};
+use std::time::Duration;
+
+use winapi::um::libloaderapi::{GetModuleHandleA, GetProcAddress};
+
+/// Notification reason reported when a DLL has been loaded
+const LDR_DLL_NOTIFICATION_REASON_LOADED: u32 = 1;
+
+type LdrDllNotificationFunction =
+    unsafe extern "system" fn(reason: u32, data: *const c_void, context: *mut c_void);
+
+type LdrRegisterDllNotification = unsafe extern "system" fn(
+    flags: u32,
+    notification_function: LdrDllNotificationFunction,
+    context: *mut c_void,
+    cookie: *mut *mut c_void,
+) -> i32;
+
+type LdrUnregisterDllNotification = unsafe extern "system" fn(cookie: *mut c_void) -> i32;
+
+// Number of DLL loads observed since the first registration
+static LOAD_GENERATION: Mutex<u64> = Mutex::new(0);
+static LOAD_SIGNAL: Condvar = Condvar::new();
+
+unsafe extern "system" fn on_dll_notification(
+    reason: u32,
+    _data: *const c_void,
+    _context: *mut c_void,
+) {
+    if reason == LDR_DLL_NOTIFICATION_REASON_LOADED {
+        if let Ok(mut generation) = LOAD_GENERATION.lock() {
+            *generation += 1;
+        }
+        LOAD_SIGNAL.notify_all();
+    }
+}
+
+/// Get the current DLL load generation
+pub fn load_generation() -> u64 {
+    LOAD_GENERATION
+        .lock()
+        .map(|generation| *generation)
+        .unwrap_or(0)
+}
+
+/// Resolve an export of ntdll.dll
+fn ntdll_export(name: &CStr) -> Option<*mut c_void> {
+    unsafe {
+        let ntdll = GetModuleHandleA(c"ntdll.dll".as_ptr());
+        if ntdll.is_null() {
+            return None;
+        }
+
+        let address = GetProcAddress(ntdll, name.as_ptr());
+        if address.is_null() {
+            None
+        } else {
+            Some(address as *mut c_void)
+        }
+    }
+}
+
+/// Registration of the DLL load callback, removed on drop
+pub struct DllLoadNotification {
+    cookie: *mut c_void,
+    unregister: LdrUnregisterDllNotification,
+}
+
+impl DllLoadNotification {
+    /// Register for DLL load notifications, if the loader supports them
+    pub fn register() -> Option<Self> {
+        let register = ntdll_export(c"LdrRegisterDllNotification")?;
+        let unregister = ntdll_export(c"LdrUnregisterDllNotification")?;
+
+        unsafe {
+            let register: LdrRegisterDllNotification = std::mem::transmute(register);
+            let unregister: LdrUnregisterDllNotification = std::mem::transmute(unregister);
+
+            let mut cookie = ptr::null_mut();
+            let status = register(0, on_dll_notification, ptr::null_mut(), &mut cookie);
+            if status < 0 || cookie.is_null() {
+                return None;
+            }
+
+            Some(Self { cookie, unregister })
+        }
+    }
+
+    /// Wait until a DLL loads after `generation` was read, up to `timeout`
+    ///
+    /// Returns `true` if a load was observed.
+    pub fn wait_since(&self, generation: u64, timeout: Duration) -> bool {
+        let Ok(guard) = LOAD_GENERATION.lock() else {
+            std::thread::sleep(timeout);
+            return false;
+        };
+
+        match LOAD_SIGNAL.wait_timeout_while(guard, timeout, |current| *current == generation) {
+            Ok((_, result)) => !result.timed_out(),
+            Err(_) => false,
+        }
+    }
+}
+
+impl Drop for DllLoadNotification {
+    fn drop(&mut self) {
+        unsafe {
+            (self.unregister)(self.cookie);
+        }
+    }
+}
@@ -0,0 +1,104 @@
+//! SHA-256 of the file backing a loaded module
+//!
+//! Tells apart builds that share a module name (CN, global, beta) and keys
+//! anything cached per build.
+
+use std::fmt;
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use sha2::{Digest, Sha256};
+use winapi::shared::minwindef::{DWORD, HMODULE, MAX_PATH};
+use winapi::um::libloaderapi::GetModuleFileNameW;
+
+use crate::error::{AppError, Result};
+
+/// Longest path `GetModuleFileNameW` can return with long path support
+const MAX_LONG_PATH: usize = 32_768;
+
+/// Read size used while hashing
+const HASH_CHUNK_SIZE: usize = 64 * 1024;
+
+/// SHA-256 digest of a module file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct FileHash(pub [u8; 32]);
+
+impl fmt::Display for FileHash {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.iter().try_for_each(|byte| write!(f, "{:02x}", byte))
+    }
+}
+
+/// Hash a file on disk
+pub fn hash_file(path: &Path) -> Result<FileHash> {
+    let read_error = |source| AppError::ModuleFileRead {
+        path: path.display().to_string(),
+        source,
+    };
+
+    let mut file = File::open(path).map_err(read_error)?;
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0u8; HASH_CHUNK_SIZE];
+    loop {
+        let read = file.read(&mut buffer).map_err(read_error)?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+
+    Ok(FileHash(hasher.finalize().into()))
+}
+
+/// Full path of the file a loaded module was mapped from
+pub(crate) fn module_path(module: HMODULE) -> Result<PathBuf> {
+    let mut capacity = MAX_PATH;
+    loop {
+        let mut buffer = vec![0u16; capacity];
+        let len =
+            unsafe { GetModuleFileNameW(module, buffer.as_mut_ptr(), capacity as DWORD) } as usize;
+
+        if len == 0 {
+            return Err(AppError::ModuleInfoFailed {
+                source: std::io::Error::last_os_error(),
+            });
+        }
+
+        // A full buffer means the path was truncated
+        if len < capacity || capacity >= MAX_LONG_PATH {
+            let path =
+                String::from_utf16(&buffer[..len]).map_err(|e| AppError::StringConversion {
+                    details: format!("Module path is not valid UTF-16: {}", e),
+                })?;
+            return Ok(PathBuf::from(path));
+        }
+
+        capacity = (capacity * 4).min(MAX_LONG_PATH);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hash_file_matches_known_digest() {
+        let path = std::env::temp_dir().join(format!("wuwa-sig-hash-{}", std::process::id()));
+        std::fs::write(&path, b"abc").unwrap();
+
+        let hash = hash_file(&path).unwrap();
+        std::fs::remove_file(&path).ok();
+
+        assert_eq!(
+            hash.to_string(),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+    }
+
+    #[test]
+    fn test_hash_missing_file_fails() {
+        let error = hash_file(Path::new("definitely/not/here.exe")).unwrap_err();
+        assert!(matches!(error, AppError::ModuleFileRead { .. }));
+    }
+}
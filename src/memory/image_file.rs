@@ -0,0 +1,105 @@
+//! PE images mapped from disk
+//!
+//! Maps an executable as an image section, the way the loader would but
+//! without running or relocating it, so offsets into the mapping are RVAs
+//! and signatures can be checked against a game build without launching it.
+
+use std::path::{Path, PathBuf};
+use std::ptr;
+
+use winapi::shared::minwindef::LPVOID;
+use winapi::um::fileapi::{CreateFileW, OPEN_EXISTING};
+use winapi::um::memoryapi::{CreateFileMappingW, FILE_MAP_READ, MapViewOfFile, UnmapViewOfFile};
+use winapi::um::winnt::{FILE_SHARE_READ, GENERIC_READ, PAGE_READONLY, SEC_IMAGE};
+
+use crate::constants::constants::memory::PAGE_SIZE;
+use crate::error::{AppError, Result};
+use crate::memory::pe::PeImage;
+use crate::safety::{OwnedHandle, StringConverter};
+
+/// Read-only view of an executable mapped with its sections at their RVAs
+#[derive(Debug)]
+pub struct MappedImage {
+    path: PathBuf,
+    view: *const u8,
+    size: usize,
+}
+
+impl MappedImage {
+    /// Map the executable at `path`
+    ///
+    /// Fails unless the file is a PE image Windows can map, such as the game
+    /// executable or one of its DLLs.
+    pub fn open(path: &Path) -> Result<Self> {
+        let failed = || AppError::ModuleFileRead {
+            path: path.display().to_string(),
+            source: std::io::Error::last_os_error(),
+        };
+        let wide_path = StringConverter::to_wide_null(&path.to_string_lossy());
+
+        let file = unsafe {
+            OwnedHandle::from_raw(CreateFileW(
+                wide_path.as_ptr(),
+                GENERIC_READ,
+                FILE_SHARE_READ,
+                ptr::null_mut(),
+                OPEN_EXISTING,
+                0,
+                ptr::null_mut(),
+            ))
+        }
+        .ok_or_else(failed)?;
+        let mapping = unsafe {
+            OwnedHandle::from_raw(CreateFileMappingW(
+                file.as_raw(),
+                ptr::null_mut(),
+                PAGE_READONLY | SEC_IMAGE,
+                0,
+                0,
+                ptr::null(),
+            ))
+        }
+        .ok_or_else(failed)?;
+
+        // The view keeps the section and file alive once the handles close
+        let view = unsafe { MapViewOfFile(mapping.as_raw(), FILE_MAP_READ, 0, 0, 0) };
+        if view.is_null() {
+            return Err(failed());
+        }
+        let mut image = Self {
+            path: path.to_path_buf(),
+            view: view as *const u8,
+            size: 0,
+        };
+
+        // The headers fill at least the first page
+        let headers = unsafe { std::slice::from_raw_parts(image.view, PAGE_SIZE) };
+        image.size = PeImage::parse(headers)?.size_of_image() as usize;
+        Ok(image)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Address the image is mapped at; not its preferred base
+    pub fn base(&self) -> usize {
+        self.view as usize
+    }
+
+    /// The whole image, `SizeOfImage` bytes from its base
+    pub fn bytes(&self) -> &[u8] {
+        unsafe { std::slice::from_raw_parts(self.view, self.size) }
+    }
+
+    /// Parse the headers of the image
+    pub fn pe(&self) -> Result<PeImage<'_>> {
+        PeImage::parse(self.bytes())
+    }
+}
+
+impl Drop for MappedImage {
+    fn drop(&mut self) {
+        unsafe { UnmapViewOfFile(self.view as LPVOID) };
+    }
+}
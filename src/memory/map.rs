@@ -0,0 +1,198 @@
+//! Address space enumeration via `VirtualQuery`
+//!
+//! Shows what a protector has done to the process when scans fail:
+//! unmapped or re-protected code, guard pages, and private executable
+//! memory outside any module.
+
+use winapi::um::memoryapi::VirtualQuery;
+use winapi::um::winnt::{
+    MEM_COMMIT, MEM_FREE, MEM_IMAGE, MEM_MAPPED, MEM_PRIVATE, MEM_RESERVE,
+    MEMORY_BASIC_INFORMATION, PAGE_EXECUTE, PAGE_EXECUTE_READ, PAGE_EXECUTE_READWRITE,
+    PAGE_EXECUTE_WRITECOPY, PAGE_GUARD, PAGE_NOACCESS, PAGE_NOCACHE, PAGE_READONLY, PAGE_READWRITE,
+    PAGE_WRITECOMBINE, PAGE_WRITECOPY,
+};
+
+use crate::logger::Logger;
+use crate::memory::peb::{self, LoadedModule};
+
+/// One contiguous region with uniform state and protection
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MemoryRegion {
+    pub base: usize,
+    pub size: usize,
+    /// `MEM_COMMIT` or `MEM_RESERVE`
+    pub state: u32,
+    /// `PAGE_*` protection of committed pages, 0 for reserved ones
+    pub protect: u32,
+    /// `MEM_IMAGE`, `MEM_MAPPED` or `MEM_PRIVATE`
+    pub kind: u32,
+    /// Name of the loaded module containing the region, if any
+    pub module: Option<String>,
+}
+
+impl MemoryRegion {
+    /// Address one past the end of the region
+    pub fn end(&self) -> usize {
+        self.base.saturating_add(self.size)
+    }
+
+    /// Check if the region's pages can be executed
+    pub fn is_executable(&self) -> bool {
+        self.state == MEM_COMMIT
+            && self.protect
+                & (PAGE_EXECUTE
+                    | PAGE_EXECUTE_READ
+                    | PAGE_EXECUTE_READWRITE
+                    | PAGE_EXECUTE_WRITECOPY)
+                != 0
+    }
+}
+
+/// Enumerate every allocated (non-free) region in the process
+pub fn memory_regions() -> Vec<MemoryRegion> {
+    let modules = peb::loaded_modules();
+    let mut regions = Vec::new();
+    let mut address: usize = 0;
+
+    loop {
+        let mut info: MEMORY_BASIC_INFORMATION = unsafe { std::mem::zeroed() };
+        let written = unsafe {
+            VirtualQuery(
+                address as *const _,
+                &mut info,
+                std::mem::size_of::<MEMORY_BASIC_INFORMATION>(),
+            )
+        };
+        if written == 0 || info.RegionSize == 0 {
+            break;
+        }
+
+        let base = info.BaseAddress as usize;
+        if info.State != MEM_FREE {
+            regions.push(MemoryRegion {
+                base,
+                size: info.RegionSize,
+                state: info.State,
+                protect: info.Protect,
+                kind: info.Type,
+                module: owning_module(&modules, base),
+            });
+        }
+
+        match base.checked_add(info.RegionSize) {
+            Some(next) if next > address => address = next,
+            _ => break,
+        }
+    }
+
+    regions
+}
+
+/// Log every allocated region with its protection and owning module
+pub fn dump_memory_map() {
+    let regions = memory_regions();
+    Logger::scan(&format!("Memory map: {} regions", regions.len()));
+
+    for region in &regions {
+        Logger::scan(&format!(
+            "{:#018x}-{:#018x} {:>10} {:<7} {:<7} {:<5} {}",
+            region.base,
+            region.end(),
+            region.size,
+            state_name(region.state),
+            kind_name(region.kind),
+            protection_name(region.protect),
+            region.module.as_deref().unwrap_or("")
+        ));
+    }
+
+    let private_exec = regions
+        .iter()
+        .filter(|region| region.is_executable() && region.module.is_none())
+        .count();
+    if private_exec > 0 {
+        Logger::warning(&format!(
+            "{} executable regions lie outside any loaded module",
+            private_exec
+        ));
+    }
+}
+
+fn owning_module(modules: &[LoadedModule], address: usize) -> Option<String> {
+    modules
+        .iter()
+        .find(|module| {
+            let base = module.base as usize;
+            address >= base && address < base + module.size as usize
+        })
+        .map(|module| module.name.clone())
+}
+
+/// Short form of a `PAGE_*` protection, e.g. `RX` or `RW+G`
+pub fn protection_name(protect: u32) -> String {
+    let base = match protect & 0xFF {
+        0 => "-",
+        PAGE_NOACCESS => "NA",
+        PAGE_READONLY => "R",
+        PAGE_READWRITE => "RW",
+        PAGE_WRITECOPY => "WC",
+        PAGE_EXECUTE => "X",
+        PAGE_EXECUTE_READ => "RX",
+        PAGE_EXECUTE_READWRITE => "RWX",
+        PAGE_EXECUTE_WRITECOPY => "XWC",
+        _ => "?",
+    };
+
+    let mut name = base.to_string();
+    if protect & PAGE_GUARD != 0 {
+        name.push_str("+G");
+    }
+    if protect & PAGE_NOCACHE != 0 {
+        name.push_str("+NC");
+    }
+    if protect & PAGE_WRITECOMBINE != 0 {
+        name.push_str("+WCB");
+    }
+    name
+}
+
+fn state_name(state: u32) -> &'static str {
+    match state {
+        MEM_COMMIT => "commit",
+        MEM_RESERVE => "reserve",
+        _ => "free",
+    }
+}
+
+fn kind_name(kind: u32) -> &'static str {
+    match kind {
+        MEM_IMAGE => "image",
+        MEM_MAPPED => "mapped",
+        MEM_PRIVATE => "private",
+        _ => "-",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_protection_names() {
+        assert_eq!(protection_name(PAGE_EXECUTE_READ), "RX");
+        assert_eq!(protection_name(PAGE_READWRITE | PAGE_GUARD), "RW+G");
+        assert_eq!(protection_name(0), "-");
+    }
+
+    #[test]
+    fn test_owning_module_by_range() {
+        let modules = vec![LoadedModule {
+            base: 0x1000 as _,
+            size: 0x2000,
+            name: "game.exe".to_string(),
+        }];
+        assert_eq!(owning_module(&modules, 0x2FFF).as_deref(), Some("game.exe"));
+        assert_eq!(owning_module(&modules, 0x3000), None);
+        assert_eq!(owning_module(&modules, 0), None);
+    }
+}
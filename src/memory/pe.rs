@@ -0,0 +1,648 @@
+//! PE header and section table parsing for loaded x64 modules
+//!
+//! Works on the mapped image, so all addresses are RVAs relative to the
+//! module base. Every read is bounds-checked against the image size.
+
+use winapi::um::psapi::MODULEINFO;
+use winapi::um::winnt::{
+    IMAGE_DIRECTORY_ENTRY_EXPORT, IMAGE_DIRECTORY_ENTRY_IMPORT, IMAGE_DOS_SIGNATURE,
+    IMAGE_NT_OPTIONAL_HDR64_MAGIC, IMAGE_NT_SIGNATURE, IMAGE_ORDINAL_FLAG64, IMAGE_SCN_MEM_EXECUTE,
+    IMAGE_SCN_MEM_READ, IMAGE_SCN_MEM_WRITE,
+};
+
+use crate::error::{AppError, Result};
+use crate::safety::StringConverter;
+
+/// Offset of `e_lfanew` in the DOS header
+const DOS_LFANEW_OFFSET: usize = 0x3C;
+
+/// Size of the NT signature plus `IMAGE_FILE_HEADER`
+const NT_FIXED_HEADER_SIZE: usize = 24;
+
+/// Size of one `IMAGE_SECTION_HEADER`
+const SECTION_HEADER_SIZE: usize = 40;
+
+/// Offset of `NumberOfRvaAndSizes` in `IMAGE_OPTIONAL_HEADER64`
+const OPTIONAL_RVA_COUNT_OFFSET: usize = 108;
+
+/// Offset of the data directory array in `IMAGE_OPTIONAL_HEADER64`
+const OPTIONAL_DATA_DIRECTORY_OFFSET: usize = 112;
+
+/// Size of one `IMAGE_IMPORT_DESCRIPTOR`
+const IMPORT_DESCRIPTOR_SIZE: usize = 20;
+
+/// Upper bound on import descriptors or thunks walked, guarding against
+/// corrupted or unterminated tables
+const MAX_IMPORT_ENTRIES: usize = 0x10000;
+
+/// FNV-1a offset basis used by [`export_hash`]
+const FNV_OFFSET_BASIS: u32 = 0x811C_9DC5;
+
+/// FNV-1a prime used by [`export_hash`]
+const FNV_PRIME: u32 = 0x0100_0193;
+
+/// Hash an export name for [`PeImage::find_export_by_hash`]
+///
+/// 32-bit FNV-1a over the raw name bytes. `const` so callers can embed
+/// hashes instead of plain export names.
+pub const fn export_hash(name: &str) -> u32 {
+    fnv1a(name.as_bytes())
+}
+
+const fn fnv1a(bytes: &[u8]) -> u32 {
+    let mut hash = FNV_OFFSET_BASIS;
+    let mut i = 0;
+    while i < bytes.len() {
+        hash ^= bytes[i] as u32;
+        hash = hash.wrapping_mul(FNV_PRIME);
+        i += 1;
+    }
+    hash
+}
+
+/// Where a named export resolves to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExportTarget {
+    /// RVA of the exported code or data in this image
+    Rva(u32),
+    /// Forwarded to another module, e.g. `NTDLL.RtlAllocateHeap`
+    Forwarder(String),
+}
+
+/// How an imported function is identified
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ImportName {
+    /// Imported by name
+    Name(String),
+    /// Imported by ordinal only
+    Ordinal(u16),
+}
+
+/// One function imported through the IAT
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImportedFunction {
+    pub name: ImportName,
+    /// RVA of the IAT slot the loader writes the resolved address into
+    pub iat_rva: u32,
+}
+
+/// One DLL listed in the import directory
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImportedModule {
+    /// DLL name as written in the image, e.g. `KERNEL32.dll`
+    pub name: String,
+    pub functions: Vec<ImportedFunction>,
+}
+
+impl ImportedModule {
+    /// Find an imported function by name
+    pub fn function(&self, name: &str) -> Option<&ImportedFunction> {
+        self.functions
+            .iter()
+            .find(|function| matches!(&function.name, ImportName::Name(n) if n == name))
+    }
+}
+
+/// Typed view of one entry in the section table
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SectionInfo {
+    /// Section name with trailing NULs removed, e.g. `.text`
+    pub name: String,
+    /// RVA of the section start
+    pub virtual_address: u32,
+    /// Size of the section once mapped
+    pub virtual_size: u32,
+    /// Size of the section in the file on disk
+    pub raw_size: u32,
+    /// `IMAGE_SCN_*` flags
+    pub characteristics: u32,
+}
+
+impl SectionInfo {
+    /// RVA one past the end of the mapped section
+    pub fn end(&self) -> u32 {
+        self.virtual_address
+            .saturating_add(self.virtual_size.max(self.raw_size))
+    }
+
+    /// Check if `rva` falls inside this section
+    pub fn contains_rva(&self, rva: u32) -> bool {
+        rva >= self.virtual_address && rva < self.end()
+    }
+
+    /// Check if the section is mapped executable
+    pub fn is_executable(&self) -> bool {
+        self.characteristics & IMAGE_SCN_MEM_EXECUTE != 0
+    }
+
+    /// Check if the section is mapped readable
+    pub fn is_readable(&self) -> bool {
+        self.characteristics & IMAGE_SCN_MEM_READ != 0
+    }
+
+    /// Check if the section is mapped writable
+    pub fn is_writable(&self) -> bool {
+        self.characteristics & IMAGE_SCN_MEM_WRITE != 0
+    }
+}
+
+/// Location of one optional-header data directory
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DataDirectory {
+    pub virtual_address: u32,
+    pub size: u32,
+}
+
+/// Parsed headers of a mapped PE32+ image
+pub struct PeImage<'a> {
+    image: &'a [u8],
+    nt_offset: usize,
+    sections: Vec<SectionInfo>,
+}
+
+impl<'a> PeImage<'a> {
+    /// Parse the headers of an image mapped into `image`
+    pub fn parse(image: &'a [u8]) -> Result<Self> {
+        if read_u16(image, 0)? != IMAGE_DOS_SIGNATURE {
+            return Err(invalid("missing MZ signature"));
+        }
+
+        let nt_offset = read_u32(image, DOS_LFANEW_OFFSET)? as usize;
+        if read_u32(image, nt_offset)? != IMAGE_NT_SIGNATURE {
+            return Err(invalid("missing PE signature"));
+        }
+
+        let optional_offset = nt_offset + NT_FIXED_HEADER_SIZE;
+        if read_u16(image, optional_offset)? != IMAGE_NT_OPTIONAL_HDR64_MAGIC {
+            return Err(invalid("not a PE32+ image"));
+        }
+
+        let section_count = read_u16(image, nt_offset + 6)? as usize;
+        let optional_size = read_u16(image, nt_offset + 20)? as usize;
+        let table_offset = optional_offset + optional_size;
+
+        let sections = (0..section_count)
+            .map(|i| parse_section(image, table_offset + i * SECTION_HEADER_SIZE))
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Self {
+            image,
+            nt_offset,
+            sections,
+        })
+    }
+
+    /// Parse the headers of a module loaded in this process
+    ///
+    /// # Safety
+    ///
+    /// `module` must describe a module that stays mapped for the lifetime of
+    /// the returned image.
+    pub unsafe fn from_module(module: &MODULEINFO) -> Result<PeImage<'static>> {
+        if module.lpBaseOfDll.is_null() {
+            return Err(AppError::MemoryAccessViolation { address: 0 });
+        }
+
+        let image = unsafe {
+            std::slice::from_raw_parts(module.lpBaseOfDll as *const u8, module.SizeOfImage as usize)
+        };
+        PeImage::parse(image)
+    }
+
+    /// The mapped image bytes, indexed by RVA
+    pub fn bytes(&self) -> &'a [u8] {
+        self.image
+    }
+
+    fn optional_offset(&self) -> usize {
+        self.nt_offset + NT_FIXED_HEADER_SIZE
+    }
+
+    /// Link time recorded in the file header
+    pub fn timestamp(&self) -> u32 {
+        read_u32(self.image, self.nt_offset + 8).unwrap_or(0)
+    }
+
+    /// RVA of the entry point
+    pub fn entry_point_rva(&self) -> u32 {
+        read_u32(self.image, self.optional_offset() + 16).unwrap_or(0)
+    }
+
+    /// Size of the image as declared by the optional header
+    pub fn size_of_image(&self) -> u32 {
+        read_u32(self.image, self.optional_offset() + 56).unwrap_or(0)
+    }
+
+    /// Get a data directory by `IMAGE_DIRECTORY_ENTRY_*` index
+    ///
+    /// Returns `None` for directories the image does not declare or leaves empty.
+    pub fn data_directory(&self, index: usize) -> Option<DataDirectory> {
+        let count = read_u32(
+            self.image,
+            self.optional_offset() + OPTIONAL_RVA_COUNT_OFFSET,
+        )
+        .ok()?;
+        if index >= count as usize {
+            return None;
+        }
+
+        let offset = self.optional_offset() + OPTIONAL_DATA_DIRECTORY_OFFSET + index * 8;
+        let directory = DataDirectory {
+            virtual_address: read_u32(self.image, offset).ok()?,
+            size: read_u32(self.image, offset + 4).ok()?,
+        };
+        (directory.virtual_address != 0).then_some(directory)
+    }
+
+    /// All sections in table order
+    pub fn sections(&self) -> &[SectionInfo] {
+        &self.sections
+    }
+
+    /// Find a section by exact name, e.g. `.text`
+    pub fn section(&self, name: &str) -> Option<&SectionInfo> {
+        self.sections.iter().find(|section| section.name == name)
+    }
+
+    /// Find the section containing `rva`
+    pub fn section_for_rva(&self, rva: u32) -> Option<&SectionInfo> {
+        self.sections
+            .iter()
+            .find(|section| section.contains_rva(rva))
+    }
+
+    /// Resolve an export by name from the export directory
+    pub fn find_export(&self, name: &str) -> Result<Option<ExportTarget>> {
+        self.find_export_where(|candidate| candidate == name.as_bytes())
+    }
+
+    /// Resolve an export by the [`export_hash`] of its name
+    pub fn find_export_by_hash(&self, hash: u32) -> Result<Option<ExportTarget>> {
+        self.find_export_where(|candidate| fnv1a(candidate) == hash)
+    }
+
+    /// Walk the export name table and resolve the first name accepted by `matches`
+    fn find_export_where(&self, matches: impl Fn(&[u8]) -> bool) -> Result<Option<ExportTarget>> {
+        let Some(directory) = self.data_directory(IMAGE_DIRECTORY_ENTRY_EXPORT as usize) else {
+            return Ok(None);
+        };
+
+        let export_dir = directory.virtual_address as usize;
+        let function_count = read_u32(self.image, export_dir + 20)?;
+        let name_count = read_u32(self.image, export_dir + 24)? as usize;
+        let functions = read_u32(self.image, export_dir + 28)? as usize;
+        let names = read_u32(self.image, export_dir + 32)? as usize;
+        let ordinals = read_u32(self.image, export_dir + 36)? as usize;
+
+        for i in 0..name_count {
+            let name_rva = read_u32(self.image, names + i * 4)? as usize;
+            if !matches(self.c_str_at(name_rva)?) {
+                continue;
+            }
+
+            let ordinal = read_u16(self.image, ordinals + i * 2)?;
+            if u32::from(ordinal) >= function_count {
+                return Err(invalid("export ordinal out of range"));
+            }
+
+            let rva = read_u32(self.image, functions + ordinal as usize * 4)?;
+            let in_directory = rva >= directory.virtual_address
+                && rva < directory.virtual_address.saturating_add(directory.size);
+            if in_directory {
+                let forwarder = self.c_str_at(rva as usize)?;
+                return Ok(Some(ExportTarget::Forwarder(
+                    String::from_utf8_lossy(forwarder).into_owned(),
+                )));
+            }
+
+            return Ok(Some(ExportTarget::Rva(rva)));
+        }
+
+        Ok(None)
+    }
+
+    /// Parse the import directory
+    ///
+    /// Names are read from the import lookup table, so this also works on a
+    /// loaded image whose IAT already holds resolved addresses.
+    pub fn imports(&self) -> Result<Vec<ImportedModule>> {
+        let Some(directory) = self.data_directory(IMAGE_DIRECTORY_ENTRY_IMPORT as usize) else {
+            return Ok(Vec::new());
+        };
+
+        let mut modules = Vec::new();
+        for i in 0..MAX_IMPORT_ENTRIES {
+            let descriptor = directory.virtual_address as usize + i * IMPORT_DESCRIPTOR_SIZE;
+            let lookup_table = read_u32(self.image, descriptor)?;
+            let name_rva = read_u32(self.image, descriptor + 12)?;
+            let iat = read_u32(self.image, descriptor + 16)?;
+            if name_rva == 0 && iat == 0 {
+                break;
+            }
+
+            // Images without a lookup table keep the names in the IAT itself
+            let thunks = if lookup_table != 0 { lookup_table } else { iat };
+            modules.push(ImportedModule {
+                name: String::from_utf8_lossy(self.c_str_at(name_rva as usize)?).into_owned(),
+                functions: self.imported_functions(thunks, iat)?,
+            });
+        }
+
+        Ok(modules)
+    }
+
+    /// Find the IAT slot RVA of `function` imported from `module`
+    ///
+    /// The module name is compared case-insensitively.
+    pub fn find_import(&self, module: &str, function: &str) -> Result<Option<u32>> {
+        Ok(self
+            .imports()?
+            .iter()
+            .filter(|imported| StringConverter::names_equal(&imported.name, module))
+            .find_map(|imported| imported.function(function).map(|f| f.iat_rva)))
+    }
+
+    fn imported_functions(&self, thunks: u32, iat: u32) -> Result<Vec<ImportedFunction>> {
+        let mut functions = Vec::new();
+        for i in 0..MAX_IMPORT_ENTRIES {
+            let thunk = read_u64(self.image, thunks as usize + i * 8)?;
+            if thunk == 0 {
+                break;
+            }
+
+            let name = if thunk & IMAGE_ORDINAL_FLAG64 != 0 {
+                ImportName::Ordinal(thunk as u16)
+            } else {
+                // IMAGE_IMPORT_BY_NAME: u16 hint followed by the name
+                let by_name = (thunk as u32) as usize + 2;
+                ImportName::Name(String::from_utf8_lossy(self.c_str_at(by_name)?).into_owned())
+            };
+
+            functions.push(ImportedFunction {
+                name,
+                iat_rva: iat + (i * 8) as u32,
+            });
+        }
+
+        Ok(functions)
+    }
+
+    /// Borrow the NUL-terminated string at `rva`, without the terminator
+    fn c_str_at(&self, rva: usize) -> Result<&'a [u8]> {
+        let tail = self
+            .image
+            .get(rva..)
+            .ok_or_else(|| invalid(&format!("string past end of image at {:#x}", rva)))?;
+        let len = memchr::memchr(0, tail).ok_or_else(|| invalid("unterminated string"))?;
+        Ok(&tail[..len])
+    }
+
+    /// Iterate over executable sections
+    pub fn executable_sections(&self) -> impl Iterator<Item = &SectionInfo> {
+        self.sections
+            .iter()
+            .filter(|section| section.is_executable())
+    }
+}
+
+fn invalid(reason: &str) -> AppError {
+    AppError::InvalidPeImage {
+        reason: reason.to_string(),
+    }
+}
+
+fn parse_section(image: &[u8], offset: usize) -> Result<SectionInfo> {
+    let raw_name = image
+        .get(offset..offset + 8)
+        .ok_or_else(|| invalid("section table truncated"))?;
+    let name_len = raw_name.iter().position(|&b| b == 0).unwrap_or(8);
+
+    Ok(SectionInfo {
+        name: String::from_utf8_lossy(&raw_name[..name_len]).into_owned(),
+        virtual_size: read_u32(image, offset + 8)?,
+        virtual_address: read_u32(image, offset + 12)?,
+        raw_size: read_u32(image, offset + 16)?,
+        characteristics: read_u32(image, offset + 36)?,
+    })
+}
+
+/// Read a little-endian `u16` at `offset`, bounds-checked
+pub(crate) fn read_u16(image: &[u8], offset: usize) -> Result<u16> {
+    image
+        .get(offset..offset.wrapping_add(2))
+        .map(|bytes| u16::from_le_bytes([bytes[0], bytes[1]]))
+        .ok_or_else(|| invalid(&format!("read past end of image at {:#x}", offset)))
+}
+
+/// Read a little-endian `u32` at `offset`, bounds-checked
+pub(crate) fn read_u32(image: &[u8], offset: usize) -> Result<u32> {
+    image
+        .get(offset..offset.wrapping_add(4))
+        .map(|bytes| u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+        .ok_or_else(|| invalid(&format!("read past end of image at {:#x}", offset)))
+}
+
+/// Read a little-endian `u64` at `offset`, bounds-checked
+pub(crate) fn read_u64(image: &[u8], offset: usize) -> Result<u64> {
+    image
+        .get(offset..offset.wrapping_add(8))
+        .map(|bytes| u64::from_le_bytes(bytes.try_into().unwrap()))
+        .ok_or_else(|| invalid(&format!("read past end of image at {:#x}", offset)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixture::{FIXTURE_NT_OFFSET, build_pe_image};
+
+    #[test]
+    fn test_parse_sections() {
+        let image = build_pe_image(&[
+            (
+                ".text",
+                0x1000,
+                0x1800,
+                IMAGE_SCN_MEM_EXECUTE | IMAGE_SCN_MEM_READ,
+            ),
+            (
+                ".data",
+                0x3000,
+                0x200,
+                IMAGE_SCN_MEM_READ | IMAGE_SCN_MEM_WRITE,
+            ),
+        ]);
+        let pe = PeImage::parse(&image).unwrap();
+
+        assert_eq!(pe.sections().len(), 2);
+        assert_eq!(pe.timestamp(), 0x6500_0000);
+        assert_eq!(pe.entry_point_rva(), 0x1010);
+        assert_eq!(pe.size_of_image(), 0x4000);
+
+        let text = pe.section(".text").unwrap();
+        assert!(text.is_executable() && !text.is_writable());
+        assert_eq!(pe.section_for_rva(0x2000).unwrap().name, ".text");
+        assert_eq!(pe.section_for_rva(0x3100).unwrap().name, ".data");
+        assert!(pe.section_for_rva(0x2900).is_none());
+        assert_eq!(pe.executable_sections().count(), 1);
+    }
+
+    #[test]
+    fn test_parse_rejects_bad_signatures() {
+        let mut image = build_pe_image(&[]);
+        image[0] = 0;
+        assert!(PeImage::parse(&image).is_err());
+
+        let mut image = build_pe_image(&[]);
+        image[FIXTURE_NT_OFFSET] = 0;
+        assert!(PeImage::parse(&image).is_err());
+
+        assert!(PeImage::parse(&[0x4D, 0x5A]).is_err());
+    }
+
+    /// Add an export directory at RVA 0x2000 with the given names and RVAs
+    fn add_exports(image: &mut [u8], exports: &[(&str, u32)]) {
+        let put = |image: &mut [u8], offset: usize, value: u32| {
+            image[offset..offset + 4].copy_from_slice(&value.to_le_bytes());
+        };
+
+        let dir = 0x2000;
+        let functions = dir + 0x40;
+        let names = functions + exports.len() * 4;
+        let ordinals = names + exports.len() * 4;
+        let mut strings = ordinals + exports.len() * 2;
+
+        put(image, dir + 20, exports.len() as u32);
+        put(image, dir + 24, exports.len() as u32);
+        put(image, dir + 28, functions as u32);
+        put(image, dir + 32, names as u32);
+        put(image, dir + 36, ordinals as u32);
+
+        for (i, &(name, rva)) in exports.iter().enumerate() {
+            put(image, functions + i * 4, rva);
+            put(image, names + i * 4, strings as u32);
+            image[ordinals + i * 2..ordinals + i * 2 + 2]
+                .copy_from_slice(&(i as u16).to_le_bytes());
+            image[strings..strings + name.len()].copy_from_slice(name.as_bytes());
+            strings += name.len() + 1;
+        }
+
+        let optional = FIXTURE_NT_OFFSET + NT_FIXED_HEADER_SIZE;
+        put(image, optional + OPTIONAL_DATA_DIRECTORY_OFFSET, dir as u32);
+        put(image, optional + OPTIONAL_DATA_DIRECTORY_OFFSET + 4, 0x400);
+    }
+
+    #[test]
+    fn test_find_export_by_name_and_hash() {
+        let mut image = build_pe_image(&[]);
+        add_exports(&mut image, &[("GetVersion", 0x1100), ("VerifyPak", 0x1200)]);
+        let pe = PeImage::parse(&image).unwrap();
+
+        assert_eq!(
+            pe.find_export("VerifyPak").unwrap(),
+            Some(ExportTarget::Rva(0x1200))
+        );
+        assert_eq!(
+            pe.find_export_by_hash(export_hash("GetVersion")).unwrap(),
+            Some(ExportTarget::Rva(0x1100))
+        );
+        assert_eq!(pe.find_export("Missing").unwrap(), None);
+    }
+
+    #[test]
+    fn test_find_export_forwarder() {
+        let mut image = build_pe_image(&[]);
+        add_exports(&mut image, &[("HeapAlloc", 0x2300)]);
+        image[0x2300..0x2315].copy_from_slice(b"NTDLL.RtlAllocateHeap");
+        let pe = PeImage::parse(&image).unwrap();
+
+        assert_eq!(
+            pe.find_export("HeapAlloc").unwrap(),
+            Some(ExportTarget::Forwarder("NTDLL.RtlAllocateHeap".to_string()))
+        );
+    }
+
+    /// Add an import directory at RVA 0x3000 importing `functions` from `dll`
+    fn add_imports(image: &mut [u8], dll: &str, functions: &[&str]) {
+        let put = |image: &mut [u8], offset: usize, value: u32| {
+            image[offset..offset + 4].copy_from_slice(&value.to_le_bytes());
+        };
+
+        let dir = 0x3000;
+        let lookup = dir + 0x40;
+        let iat = lookup + (functions.len() + 1) * 8;
+        let mut strings = iat + (functions.len() + 1) * 8;
+
+        put(image, dir, lookup as u32);
+        put(image, dir + 12, strings as u32);
+        put(image, dir + 16, iat as u32);
+        image[strings..strings + dll.len()].copy_from_slice(dll.as_bytes());
+        strings += dll.len() + 1;
+
+        for (i, name) in functions.iter().enumerate() {
+            // The loaded IAT holds resolved addresses, only the lookup table has names
+            image[lookup + i * 8..lookup + i * 8 + 8]
+                .copy_from_slice(&(strings as u64).to_le_bytes());
+            image[iat + i * 8..iat + i * 8 + 8].copy_from_slice(&0x7FF8_0000_1000u64.to_le_bytes());
+            image[strings + 2..strings + 2 + name.len()].copy_from_slice(name.as_bytes());
+            strings += name.len() + 3;
+        }
+
+        let optional = FIXTURE_NT_OFFSET + NT_FIXED_HEADER_SIZE;
+        put(
+            image,
+            optional + OPTIONAL_DATA_DIRECTORY_OFFSET + 8,
+            dir as u32,
+        );
+        put(image, optional + OPTIONAL_DATA_DIRECTORY_OFFSET + 12, 0x28);
+    }
+
+    #[test]
+    fn test_imports_list_functions_and_slots() {
+        let mut image = build_pe_image(&[]);
+        add_imports(&mut image, "KERNEL32.dll", &["CreateFileW", "ReadFile"]);
+        let pe = PeImage::parse(&image).unwrap();
+
+        let imports = pe.imports().unwrap();
+        assert_eq!(imports.len(), 1);
+        assert_eq!(imports[0].name, "KERNEL32.dll");
+        assert_eq!(
+            imports[0].functions[0].name,
+            ImportName::Name("CreateFileW".to_string())
+        );
+
+        let iat = 0x3000 + 0x40 + 3 * 8;
+        assert_eq!(
+            pe.find_import("kernel32.DLL", "ReadFile").unwrap(),
+            Some(iat as u32 + 8)
+        );
+        assert_eq!(pe.find_import("user32.dll", "ReadFile").unwrap(), None);
+    }
+
+    #[test]
+    fn test_imports_by_ordinal() {
+        let mut image = build_pe_image(&[]);
+        add_imports(&mut image, "WS2_32.dll", &["send"]);
+        let lookup = 0x3000 + 0x40;
+        image[lookup..lookup + 8].copy_from_slice(&(IMAGE_ORDINAL_FLAG64 | 19).to_le_bytes());
+        let pe = PeImage::parse(&image).unwrap();
+
+        assert_eq!(
+            pe.imports().unwrap()[0].functions[0].name,
+            ImportName::Ordinal(19)
+        );
+    }
+
+    #[test]
+    fn test_export_hash_is_fnv1a() {
+        assert_eq!(export_hash(""), FNV_OFFSET_BASIS);
+        assert_eq!(export_hash("a"), 0xE40C_292C);
+    }
+
+    #[test]
+    fn test_data_directory_empty_is_none() {
+        let image = build_pe_image(&[]);
+        let pe = PeImage::parse(&image).unwrap();
+        assert!(pe.data_directory(0).is_none());
+        assert!(pe.data_directory(16).is_none());
+    }
+}
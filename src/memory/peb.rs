@@ -0,0 +1,125 @@
+//! Module enumeration by walking the PEB loader lists directly
+//!
+//! Used as a fallback when `EnumProcessModules` fails or truncates its
+//! results, which happens under some protectors. The list is walked without
+//! taking the loader lock, so it is only a best-effort snapshot.
+
+use std::ffi::c_void;
+
+use winapi::shared::minwindef::HMODULE;
+
+use crate::safety::StringConverter;
+
+/// Offset of the `Ldr` field in the x64 PEB
+const PEB_LDR_OFFSET: usize = 0x18;
+
+/// Upper bound on list entries visited, guarding against corrupted links
+const MAX_LOADER_ENTRIES: usize = 4096;
+
+#[repr(C)]
+struct ListEntry {
+    flink: *const ListEntry,
+    blink: *const ListEntry,
+}
+
+#[repr(C)]
+struct UnicodeString {
+    length: u16,
+    maximum_length: u16,
+    buffer: *const u16,
+}
+
+#[repr(C)]
+struct PebLdrData {
+    length: u32,
+    initialized: u8,
+    ss_handle: *const c_void,
+    in_load_order_module_list: ListEntry,
+    in_memory_order_module_list: ListEntry,
+    in_initialization_order_module_list: ListEntry,
+}
+
+#[repr(C)]
+struct LdrDataTableEntry {
+    in_load_order_links: ListEntry,
+    in_memory_order_links: ListEntry,
+    in_initialization_order_links: ListEntry,
+    dll_base: *mut c_void,
+    entry_point: *const c_void,
+    size_of_image: u32,
+    full_dll_name: UnicodeString,
+    base_dll_name: UnicodeString,
+}
+
+/// A module found in the PEB loader list
+#[derive(Debug, Clone)]
+pub struct LoadedModule {
+    pub base: HMODULE,
+    pub size: u32,
+    pub name: String,
+}
+
+/// Read the PEB address of the current process from the TEB
+fn current_peb() -> *const u8 {
+    let peb: *const u8;
+    unsafe {
+        std::arch::asm!(
+            "mov {}, gs:[0x60]",
+            out(reg) peb,
+            options(nostack, readonly, preserves_flags)
+        );
+    }
+    peb
+}
+
+/// Convert a loader `UNICODE_STRING` into a Rust string
+unsafe fn unicode_string_to_string(value: &UnicodeString) -> String {
+    if value.buffer.is_null() || value.length == 0 {
+        return String::new();
+    }
+
+    let chars = unsafe { std::slice::from_raw_parts(value.buffer, (value.length / 2) as usize) };
+    String::from_utf16_lossy(chars)
+}
+
+/// List every module in load order by walking the PEB loader list
+pub fn loaded_modules() -> Vec<LoadedModule> {
+    let mut modules = Vec::new();
+
+    unsafe {
+        let peb = current_peb();
+        if peb.is_null() {
+            return modules;
+        }
+
+        let ldr = *(peb.add(PEB_LDR_OFFSET) as *const *const PebLdrData);
+        if ldr.is_null() {
+            return modules;
+        }
+
+        let head = &(*ldr).in_load_order_module_list as *const ListEntry;
+        let mut current = (*head).flink;
+
+        // in_load_order_links is the first field, so list entries are entry pointers
+        while !current.is_null() && current != head && modules.len() < MAX_LOADER_ENTRIES {
+            let entry = &*(current as *const LdrDataTableEntry);
+            if !entry.dll_base.is_null() {
+                modules.push(LoadedModule {
+                    base: entry.dll_base as HMODULE,
+                    size: entry.size_of_image,
+                    name: unicode_string_to_string(&entry.base_dll_name),
+                });
+            }
+            current = entry.in_load_order_links.flink;
+        }
+    }
+
+    modules
+}
+
+/// Find a loaded module by name by walking the PEB loader list
+pub fn find_loaded_module(name: &str) -> Option<LoadedModule> {
+    loaded_modules()
+        .into_iter()
+        .find(|module| StringConverter::names_equal(&module.name, name))
+}
@@ -0,0 +1,181 @@
+//! File and product version from a module's `VS_VERSION_INFO` resource
+
+use std::fmt;
+use std::str::FromStr;
+
+use winapi::shared::minwindef::HMODULE;
+use winapi::um::libloaderapi::{FindResourceW, LoadResource, LockResource, SizeofResource};
+use winapi::um::winnt::LPCWSTR;
+
+use crate::error::{AppError, Result};
+
+/// `RT_VERSION` resource type
+const RT_VERSION: u16 = 16;
+
+/// Resource id of `VS_VERSION_INFO`
+const VS_VERSION_INFO_ID: u16 = 1;
+
+/// `VS_FIXEDFILEINFO::dwSignature`
+const VS_FFI_SIGNATURE: u32 = 0xFEEF_04BD;
+
+/// Size of `VS_FIXEDFILEINFO` up to and including the product version
+const VS_FFI_VERSION_FIELDS_SIZE: usize = 24;
+
+/// Four-part version number, e.g. `2.5.0.1`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct VersionNumber(pub [u16; 4]);
+
+impl VersionNumber {
+    /// Build from the `*VersionMS`/`*VersionLS` pair of `VS_FIXEDFILEINFO`
+    pub fn from_ms_ls(ms: u32, ls: u32) -> Self {
+        Self([(ms >> 16) as u16, ms as u16, (ls >> 16) as u16, ls as u16])
+    }
+}
+
+impl FromStr for VersionNumber {
+    type Err = AppError;
+
+    /// Parse `major.minor.build.revision`; missing trailing parts are 0
+    fn from_str(s: &str) -> Result<Self> {
+        let invalid = || AppError::InvalidConfig {
+            field: "version".to_string(),
+            reason: format!("invalid version '{}'", s),
+        };
+
+        let mut parts = [0u16; 4];
+        for (index, part) in s.trim().split('.').enumerate() {
+            let slot = parts.get_mut(index).ok_or_else(invalid)?;
+            *slot = part.parse().map_err(|_| invalid())?;
+        }
+        Ok(Self(parts))
+    }
+}
+
+impl fmt::Display for VersionNumber {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let [major, minor, build, revision] = self.0;
+        write!(f, "{}.{}.{}.{}", major, minor, build, revision)
+    }
+}
+
+/// Version numbers declared by a module
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ModuleVersion {
+    pub file_version: VersionNumber,
+    pub product_version: VersionNumber,
+}
+
+impl fmt::Display for ModuleVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "file {} / product {}",
+            self.file_version, self.product_version
+        )
+    }
+}
+
+/// Find `VS_FIXEDFILEINFO` in a raw `VS_VERSION_INFO` block and read its versions
+///
+/// The structure is DWORD-aligned after the variable-length block header, so
+/// only aligned offsets are checked for the signature.
+pub fn parse_version_resource(resource: &[u8]) -> Option<ModuleVersion> {
+    let read = |offset: usize| -> Option<u32> {
+        resource
+            .get(offset..offset + 4)
+            .map(|bytes| u32::from_le_bytes(bytes.try_into().unwrap()))
+    };
+
+    let candidates = resource
+        .len()
+        .saturating_sub(VS_FFI_VERSION_FIELDS_SIZE - 1);
+    let start = (0..candidates)
+        .step_by(4)
+        .find(|&offset| read(offset) == Some(VS_FFI_SIGNATURE))?;
+
+    Some(ModuleVersion {
+        file_version: VersionNumber::from_ms_ls(read(start + 8)?, read(start + 12)?),
+        product_version: VersionNumber::from_ms_ls(read(start + 16)?, read(start + 20)?),
+    })
+}
+
+/// Read the version resource of a loaded module
+pub(crate) fn module_version(module: HMODULE) -> Result<ModuleVersion> {
+    let missing = |reason: &str| AppError::VersionInfoUnavailable {
+        reason: reason.to_string(),
+    };
+
+    let resource = unsafe {
+        let info = FindResourceW(
+            module,
+            VS_VERSION_INFO_ID as usize as LPCWSTR,
+            RT_VERSION as usize as LPCWSTR,
+        );
+        if info.is_null() {
+            return Err(missing("module has no VS_VERSION_INFO resource"));
+        }
+
+        let size = SizeofResource(module, info) as usize;
+        let data = LockResource(LoadResource(module, info));
+        if data.is_null() || size == 0 {
+            return Err(missing("version resource could not be loaded"));
+        }
+
+        // Resources of a loaded module stay mapped as long as the module does
+        std::slice::from_raw_parts(data as *const u8, size)
+    };
+
+    parse_version_resource(resource).ok_or_else(|| missing("VS_FIXEDFILEINFO not found"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_version_resource() {
+        // VS_VERSION_INFO header, "VS_VERSION_INFO" key, padding, then VS_FIXEDFILEINFO
+        let mut resource = vec![0u8; 0x28];
+        let fixed = [
+            VS_FFI_SIGNATURE,
+            0x0001_0000,
+            0x0002_0005,
+            0x0000_0001,
+            0x0002_0005,
+            0x0003_0000,
+        ];
+        for value in fixed {
+            resource.extend_from_slice(&value.to_le_bytes());
+        }
+        resource.extend_from_slice(&[0u8; 28]);
+
+        let version = parse_version_resource(&resource).unwrap();
+        assert_eq!(version.file_version, VersionNumber([2, 5, 0, 1]));
+        assert_eq!(version.product_version.to_string(), "2.5.3.0");
+    }
+
+    #[test]
+    fn test_parse_version_resource_without_signature() {
+        assert!(parse_version_resource(&[0u8; 64]).is_none());
+        assert!(parse_version_resource(&VS_FFI_SIGNATURE.to_le_bytes()).is_none());
+    }
+
+    #[test]
+    fn test_version_number_ordering() {
+        assert!(VersionNumber([2, 5, 0, 1]) > VersionNumber([2, 4, 9, 9]));
+    }
+
+    #[test]
+    fn test_version_number_parses_partial_versions() {
+        assert_eq!(
+            "2.5.0.1".parse::<VersionNumber>().unwrap(),
+            VersionNumber([2, 5, 0, 1])
+        );
+        assert_eq!(
+            "2.5".parse::<VersionNumber>().unwrap(),
+            VersionNumber([2, 5, 0, 0])
+        );
+        assert!("2.5.0.1.7".parse::<VersionNumber>().is_err());
+        assert!("2.x".parse::<VersionNumber>().is_err());
+    }
+}
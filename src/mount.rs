@@ -0,0 +1,200 @@
+//! Logging of pak mounts
+//!
+//! Observes the engine's pak mount routine (`FPakPlatformFile::Mount`) with
+//! a jmp-back hook at its entry and logs each pak with its mount order and
+//! mount point, in the sequence the engine mounts them. When two paks carry
+//! the same file the higher order wins, which is usually why an override
+//! pak seems to be ignored.
+//!
+//! The routine has no stable export, so it is found through the mount
+//! signature in [`Config`]; mount logging is off while none is configured.
+//! At entry its arguments are `this` in rcx, the pak path in rdx, the order
+//! in r8 and the mount point in r9 (null for the pak's default one).
+
+use std::fmt;
+use std::sync::OnceLock;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use winapi::um::psapi::MODULEINFO;
+
+use crate::config::Config;
+use crate::constants::constants::memory::MAX_PAK_PATH_CHARS;
+use crate::error::{AppError, Result};
+use crate::hooks::{ArgReader, MidFunctionHook};
+use crate::logger::Logger;
+use crate::memory::PatternScanner;
+use crate::safety::StringConverter;
+use crate::{bypass, inject};
+
+/// The installed mount hook, kept for the lifetime of the process
+static MOUNT_HOOK: OnceLock<MidFunctionHook> = OnceLock::new();
+
+/// Number of mounts seen so far
+static MOUNT_SEQUENCE: AtomicU64 = AtomicU64::new(0);
+
+/// One observed pak mount
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MountRecord {
+    /// Position in the sequence of mounts seen, starting at 1
+    pub sequence: u64,
+    pub pak: String,
+    /// Mount priority; higher orders override lower ones
+    pub order: u32,
+    /// Explicit mount point, `None` when the pak's default is used
+    pub mount_point: Option<String>,
+}
+
+impl MountRecord {
+    /// Read the mount arguments at the routine's entry
+    pub fn from_args(sequence: u64, args: &ArgReader<'_>) -> Result<Self> {
+        let pak = unsafe {
+            StringConverter::pcwstr_to_string_bounded(args.arg_ptr(1)?, MAX_PAK_PATH_CHARS)?
+        };
+        let mount_point = match args.arg_ptr::<u16>(3)? {
+            path if path.is_null() => None,
+            path => Some(unsafe {
+                StringConverter::pcwstr_to_string_bounded(path, MAX_PAK_PATH_CHARS)?
+            }),
+        };
+
+        Ok(Self {
+            sequence,
+            pak,
+            order: args.arg(2)? as u32,
+            mount_point,
+        })
+    }
+}
+
+impl fmt::Display for MountRecord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "#{} '{}' order {} at ",
+            self.sequence, self.pak, self.order
+        )?;
+        match &self.mount_point {
+            Some(mount_point) => write!(f, "'{}'", mount_point),
+            None => f.write_str("its default mount point"),
+        }
+    }
+}
+
+/// Find the mount routine in the game module through the configured signature
+///
+/// The routine start must lie inside the module.
+pub fn locate(config: &Config, module_info: &MODULEINFO) -> Result<usize> {
+    if config.mount_pattern.is_empty() {
+        return Err(AppError::InvalidConfig {
+            field: "mount_pattern".to_string(),
+            reason: "No mount signature configured".to_string(),
+        });
+    }
+
+    let signature = config.mount_signature();
+    signature.validate()?;
+
+    let mut scanner = PatternScanner::with_caching(config.cache_patterns);
+    let found = bypass::find_signature(&mut scanner, &signature, module_info)?;
+    bypass::function_start(found, &signature, module_info).map(|start| start as usize)
+}
+
+/// Start logging every pak mounted through the routine at `address`
+pub fn install(address: usize) -> Result<()> {
+    if MOUNT_HOOK.get().is_some() {
+        return Err(AppError::HookFailed {
+            message: "Mount logging is already installed".to_string(),
+        });
+    }
+
+    let hook = MidFunctionHook::attach(address, move |registers| {
+        let sequence = MOUNT_SEQUENCE.fetch_add(1, Ordering::Relaxed) + 1;
+        let args = ArgReader::from_registers(registers);
+        match MountRecord::from_args(sequence, &args) {
+            Ok(record) => {
+                Logger::info(&format!("Mount {}", record));
+                inject::observe_mount(address, args.arg(0).unwrap_or(0), &record.pak);
+            }
+            Err(e) => Logger::warning(&format!("Mount #{} unreadable: {}", sequence, e)),
+        }
+    })?;
+    let _ = MOUNT_HOOK.set(hook);
+    Ok(())
+}
+
+/// Stop logging pak mounts
+pub fn uninstall() -> Result<()> {
+    match MOUNT_HOOK.get() {
+        Some(hook) if hook.is_active() => hook.detach(),
+        _ => Ok(()),
+    }
+}
+
+/// Number of mounts seen since the hook was installed
+pub fn mount_count() -> u64 {
+    MOUNT_SEQUENCE.load(Ordering::Relaxed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::constants::memory::MAX_FUNCTION_OFFSET;
+    use ilhook::x64::Registers;
+
+    fn wide(value: &str) -> Vec<u16> {
+        StringConverter::to_wide_null(value)
+    }
+
+    #[test]
+    fn test_record_reads_mount_arguments() {
+        let pak = wide("../../../Client/Content/Paks/~mods/MyMod_P.pak");
+        let mount_point = wide("../../../Client/Content/");
+        let mut registers: Registers = unsafe { std::mem::zeroed() };
+        registers.rdx = pak.as_ptr() as u64;
+        registers.r8 = 4;
+        registers.r9 = mount_point.as_ptr() as u64;
+
+        let record = MountRecord::from_args(3, &ArgReader::from_registers(&registers)).unwrap();
+        assert_eq!(record.order, 4);
+        assert_eq!(
+            record.to_string(),
+            "#3 '../../../Client/Content/Paks/~mods/MyMod_P.pak' order 4 at '../../../Client/Content/'"
+        );
+    }
+
+    #[test]
+    fn test_record_without_mount_point_uses_default() {
+        let pak = wide("pakchunk0-WindowsNoEditor.pak");
+        let mut registers: Registers = unsafe { std::mem::zeroed() };
+        registers.rdx = pak.as_ptr() as u64;
+
+        let record = MountRecord::from_args(1, &ArgReader::from_registers(&registers)).unwrap();
+        assert!(record.mount_point.is_none());
+        assert!(record.to_string().ends_with("its default mount point"));
+    }
+
+    #[test]
+    fn test_locate_requires_a_signature() {
+        let config = Config::new("test.exe", &[0x48], "x");
+        let info: MODULEINFO = unsafe { std::mem::zeroed() };
+        assert!(matches!(
+            locate(&config, &info),
+            Err(AppError::InvalidConfig { .. })
+        ));
+    }
+
+    #[test]
+    fn test_locate_rejects_far_function_offsets() {
+        let mut config = Config::new("test.exe", &[0x48], "x");
+        config.mount_pattern = vec![0x48, 0x8B];
+        config.mount_mask = "xx".to_string();
+        config.mount_function_offset = MAX_FUNCTION_OFFSET as isize + 1;
+        assert!(config.validate().is_err());
+
+        let info: MODULEINFO = unsafe { std::mem::zeroed() };
+        assert!(matches!(
+            locate(&config, &info),
+            Err(AppError::InvalidConfig { .. })
+        ));
+    }
+}
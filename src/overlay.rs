@@ -0,0 +1,97 @@
+//! Live bypass status and statistics published through named shared memory
+//!
+//! Overlay tools (an OBS plugin, an external HUD, ...) and launchers can open
+//! the section named [`OVERLAY_SECTION_NAME`] read-only and read an
+//! [`OverlayBlock`] from offset 0 without any IPC round-trip. All multi-byte
+//! fields are little-endian and naturally aligned:
+//!
+//! | Offset | Size | Field               | Description                                   |
+//! |--------|------|---------------------|-----------------------------------------------|
+//! | 0x00   | 4    | `magic`             | `OVERLAY_MAGIC` (`"WWSG"`) once initialized   |
+//! | 0x04   | 4    | `version`           | Layout version, currently `OVERLAY_VERSION`   |
+//! | 0x08   | 4    | `process_id`        | Id of the game process owning the block       |
+//! | 0x0C   | 4    | `phase`             | Numeric `StartupPhase`                        |
+//! | 0x10   | 4    | `sequence`          | Odd while `last_pak_name` is being written    |
+//! | 0x14   | 4    | `last_pak_name_len` | Length of `last_pak_name` in UTF-16 units     |
+//! | 0x18   | 8    | `paks_verified`     | Verifications bypassed with a readable name   |
+//! | 0x20   | 8    | `pak_name_failures` | Verifications where the name was unreadable   |
+//! | 0x28   | 8    | `last_update_ms`    | Unix time in milliseconds of the last update  |
+//! | 0x30   | 520  | `last_pak_name`     | UTF-16 name of the last pak, not terminated   |
+//! | 0x238  | 4    | `target_rva`        | RVA of the hooked pak check; 0 until found    |
+//! | 0x23C  | 4    | `error_code`        | `AppError::code` of a failure; 0 otherwise    |
+//! | 0x240  | 8    | `heartbeat_ms`      | Unix time in milliseconds of the last check   |
+//!
+//! Readers should copy `last_pak_name` only while `sequence` is even and
+//! unchanged before and after the copy.
+//!
+//! Fields are only ever appended, so readers of an older `version` keep
+//! working. The watchdog refreshes `heartbeat_ms` every
+//! `watchdog_interval_ms`; a heartbeat several intervals old means the
+//! bypass stopped, as the block outlives an unloaded DLL.
+
+use std::sync::atomic::{AtomicU32, AtomicU64};
+
+#[cfg(feature = "dll")]
+mod section;
+
+#[cfg(feature = "dll")]
+pub(crate) use section::{SharedSection, global_overlay, init_global_overlay};
+
+/// Name of the shared-memory section holding the overlay block
+pub const OVERLAY_SECTION_NAME: &str = "Local\\WuwaSigOverlay";
+
+/// Magic value identifying an initialized overlay block ("WWSG")
+pub const OVERLAY_MAGIC: u32 = 0x4753_5757;
+
+/// Current overlay block layout version
+pub const OVERLAY_VERSION: u32 = 2;
+
+/// Capacity of the last pak name in UTF-16 code units
+pub const OVERLAY_NAME_CAPACITY: usize = 260;
+
+/// Shared-memory layout read by overlay tools
+#[repr(C)]
+pub struct OverlayBlock {
+    pub magic: u32,
+    pub version: u32,
+    pub process_id: u32,
+    pub phase: AtomicU32,
+    pub sequence: AtomicU32,
+    pub last_pak_name_len: AtomicU32,
+    pub paks_verified: AtomicU64,
+    pub pak_name_failures: AtomicU64,
+    pub last_update_ms: AtomicU64,
+    pub last_pak_name: [u16; OVERLAY_NAME_CAPACITY],
+    pub target_rva: AtomicU32,
+    pub error_code: AtomicU32,
+    pub heartbeat_ms: AtomicU64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::mem::{offset_of, size_of};
+
+    #[test]
+    fn test_overlay_layout_matches_documentation() {
+        assert_eq!(offset_of!(OverlayBlock, magic), 0x00);
+        assert_eq!(offset_of!(OverlayBlock, version), 0x04);
+        assert_eq!(offset_of!(OverlayBlock, process_id), 0x08);
+        assert_eq!(offset_of!(OverlayBlock, phase), 0x0C);
+        assert_eq!(offset_of!(OverlayBlock, sequence), 0x10);
+        assert_eq!(offset_of!(OverlayBlock, last_pak_name_len), 0x14);
+        assert_eq!(offset_of!(OverlayBlock, paks_verified), 0x18);
+        assert_eq!(offset_of!(OverlayBlock, pak_name_failures), 0x20);
+        assert_eq!(offset_of!(OverlayBlock, last_update_ms), 0x28);
+        assert_eq!(offset_of!(OverlayBlock, last_pak_name), 0x30);
+        assert_eq!(offset_of!(OverlayBlock, target_rva), 0x238);
+        assert_eq!(offset_of!(OverlayBlock, error_code), 0x23C);
+        assert_eq!(offset_of!(OverlayBlock, heartbeat_ms), 0x240);
+        assert_eq!(size_of::<OverlayBlock>(), 0x248);
+    }
+
+    #[test]
+    fn test_overlay_magic_spells_wwsg() {
+        assert_eq!(&OVERLAY_MAGIC.to_le_bytes(), b"WWSG");
+    }
+}
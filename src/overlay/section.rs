@@ -0,0 +1,198 @@
+//! The named section the DLL publishes the overlay block in
+
+use std::ptr;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use winapi::shared::minwindef::LPVOID;
+use winapi::um::handleapi::INVALID_HANDLE_VALUE;
+use winapi::um::memoryapi::{
+    CreateFileMappingW, FILE_MAP_ALL_ACCESS, MapViewOfFile, UnmapViewOfFile,
+};
+use winapi::um::processthreadsapi::GetCurrentProcessId;
+use winapi::um::winnt::PAGE_READWRITE;
+
+use super::{
+    OVERLAY_MAGIC, OVERLAY_NAME_CAPACITY, OVERLAY_SECTION_NAME, OVERLAY_VERSION, OverlayBlock,
+};
+use crate::error::{AppError, Result};
+use crate::safety::{OwnedHandle, StringConverter};
+use crate::startup::StartupStatus;
+
+/// Named shared-memory section holding one `T`, zeroed when created
+pub(crate) struct SharedSection<T> {
+    /// Closed on drop, after the view is unmapped
+    _mapping: OwnedHandle,
+    view: *mut T,
+}
+
+impl<T> SharedSection<T> {
+    /// Create or open the section `name` and zero its contents
+    pub(crate) fn create(name: &str) -> Result<Self> {
+        let size = std::mem::size_of::<T>();
+        let wide_name = StringConverter::to_wide_null(name);
+
+        let mapping = unsafe {
+            OwnedHandle::from_raw(CreateFileMappingW(
+                INVALID_HANDLE_VALUE,
+                ptr::null_mut(),
+                PAGE_READWRITE,
+                0,
+                size as u32,
+                wide_name.as_ptr(),
+            ))
+        }
+        .ok_or_else(|| AppError::SharedMemoryFailed {
+            name: name.to_string(),
+            source: std::io::Error::last_os_error(),
+        })?;
+
+        let view = unsafe { MapViewOfFile(mapping.as_raw(), FILE_MAP_ALL_ACCESS, 0, 0, size) };
+        if view.is_null() {
+            return Err(AppError::SharedMemoryFailed {
+                name: name.to_string(),
+                source: std::io::Error::last_os_error(),
+            });
+        }
+        unsafe { ptr::write_bytes(view as *mut u8, 0, size) };
+
+        Ok(Self {
+            _mapping: mapping,
+            view: view as *mut T,
+        })
+    }
+
+    /// The mapped view
+    pub(crate) fn as_ptr(&self) -> *mut T {
+        self.view
+    }
+}
+
+impl<T> Drop for SharedSection<T> {
+    fn drop(&mut self) {
+        unsafe { UnmapViewOfFile(self.view as LPVOID) };
+    }
+}
+
+/// Owner of the mapped overlay section
+pub(crate) struct SharedOverlay {
+    section: SharedSection<OverlayBlock>,
+    name_writer: Mutex<()>,
+}
+
+impl SharedOverlay {
+    /// Create the named section and initialize its header
+    pub fn create(name: &str) -> Result<Self> {
+        let section = SharedSection::<OverlayBlock>::create(name)?;
+        let block = section.as_ptr();
+        unsafe {
+            (*block).version = OVERLAY_VERSION;
+            (*block).process_id = GetCurrentProcessId();
+            // Publish the magic last so readers never see a half-written header
+            AtomicU32::from_ptr(ptr::addr_of_mut!((*block).magic))
+                .store(OVERLAY_MAGIC, Ordering::Release);
+        }
+
+        Ok(Self {
+            section,
+            name_writer: Mutex::new(()),
+        })
+    }
+
+    fn block(&self) -> &OverlayBlock {
+        unsafe { &*self.section.as_ptr() }
+    }
+
+    fn touch(&self) {
+        self.block()
+            .last_update_ms
+            .store(unix_time_ms(), Ordering::Relaxed);
+    }
+
+    /// Publish the phase, target and error code of `status`
+    pub fn set_status(&self, status: &StartupStatus) {
+        let block = self.block();
+        block.phase.store(status.phase() as u32, Ordering::Relaxed);
+        block
+            .target_rva
+            .store(status.target().map_or(0, |rva| rva.0), Ordering::Relaxed);
+        block
+            .error_code
+            .store(status.error_code().unwrap_or(0), Ordering::Relaxed);
+        self.touch();
+        self.heartbeat();
+    }
+
+    /// Show readers the bypass is still alive
+    pub fn heartbeat(&self) {
+        self.block()
+            .heartbeat_ms
+            .store(unix_time_ms(), Ordering::Relaxed);
+    }
+
+    /// Record a bypassed verification and the pak it was for
+    pub fn record_pak(&self, name: &str) {
+        self.block().paks_verified.fetch_add(1, Ordering::Relaxed);
+
+        // Never stall a game thread: skip the name if another thread is writing it
+        if let Ok(_guard) = self.name_writer.try_lock() {
+            let block = self.block();
+            block.sequence.fetch_add(1, Ordering::AcqRel);
+
+            let mut len = 0;
+            for unit in name.encode_utf16().take(OVERLAY_NAME_CAPACITY) {
+                unsafe {
+                    let slot = ptr::addr_of_mut!((*self.section.as_ptr()).last_pak_name[len]);
+                    ptr::write_volatile(slot, unit);
+                }
+                len += 1;
+            }
+            block.last_pak_name_len.store(len as u32, Ordering::Relaxed);
+
+            block.sequence.fetch_add(1, Ordering::AcqRel);
+        }
+
+        self.touch();
+    }
+
+    /// Record a verification whose pak name could not be read
+    pub fn record_name_failure(&self) {
+        self.block()
+            .pak_name_failures
+            .fetch_add(1, Ordering::Relaxed);
+        self.touch();
+    }
+}
+
+// The block is only written through atomics or under `name_writer`
+unsafe impl Send for SharedOverlay {}
+unsafe impl Sync for SharedOverlay {}
+
+/// Current Unix time in milliseconds
+fn unix_time_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+// Global overlay instance
+static GLOBAL_OVERLAY: OnceLock<SharedOverlay> = OnceLock::new();
+
+/// Create the global overlay section
+pub(crate) fn init_global_overlay() -> Result<()> {
+    if GLOBAL_OVERLAY.get().is_some() {
+        return Ok(());
+    }
+
+    let overlay = SharedOverlay::create(OVERLAY_SECTION_NAME)?;
+    overlay.set_status(crate::startup::status());
+    GLOBAL_OVERLAY.set(overlay).ok();
+    Ok(())
+}
+
+/// Get the global overlay, if it has been created
+pub(crate) fn global_overlay() -> Option<&'static SharedOverlay> {
+    GLOBAL_OVERLAY.get()
+}
@@ -0,0 +1,312 @@
+//! Named byte patches that can be reverted
+//!
+//! Besides hooking the pak check, small checks can be disabled by
+//! overwriting a few instructions, typically with NOPs. Each patch is
+//! located by a [`Signature`] and registered under a name in a
+//! [`PatchManager`], which keeps the bytes it replaced so the patch can be
+//! taken back later, one at a time or all together.
+
+use winapi::um::psapi::MODULEINFO;
+
+use crate::error::{AppError, Result};
+use crate::logger::Logger;
+use crate::memory::{PatternScanner, describe_address};
+use crate::safety::MemoryAccess;
+use crate::signatures::Signature;
+
+/// x86 single-byte NOP
+pub const NOP: u8 = 0x90;
+
+/// Bytes to write and where to find the place for them
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PatchSpec {
+    /// Name the patch is registered under
+    pub name: String,
+    /// Pattern whose match anchors the patch; its function offset is unused
+    pub signature: Signature,
+    /// Distance from the start of the match to the first patched byte
+    pub offset: isize,
+    pub bytes: Vec<u8>,
+}
+
+impl PatchSpec {
+    pub fn new(name: &str, signature: Signature, offset: isize, bytes: &[u8]) -> Self {
+        Self {
+            name: name.to_string(),
+            signature,
+            offset,
+            bytes: bytes.to_vec(),
+        }
+    }
+
+    /// A patch overwriting `len` bytes with NOPs
+    pub fn nop(name: &str, signature: Signature, offset: isize, len: usize) -> Self {
+        Self::new(name, signature, offset, &vec![NOP; len])
+    }
+
+    /// Scan `module` for the signature and return the address to patch
+    pub fn locate(&self, scanner: &mut PatternScanner, module: &MODULEINFO) -> Result<*mut u8> {
+        let base = module.lpBaseOfDll as usize;
+        let size = module.SizeOfImage as usize;
+        let found = scanner.scan(
+            base as *mut u8,
+            size,
+            &self.signature.pattern,
+            &self.signature.mask,
+        )?;
+
+        let address = (found as usize).wrapping_add_signed(self.offset);
+        let inside = address
+            .checked_sub(base)
+            .and_then(|rva| rva.checked_add(self.bytes.len()))
+            .is_some_and(|end| end <= size);
+        if !inside {
+            return Err(AppError::MemoryAccessViolation { address });
+        }
+        Ok(address as *mut u8)
+    }
+}
+
+/// A patch currently in place
+#[derive(Debug)]
+struct AppliedPatch {
+    name: String,
+    address: usize,
+    original: Vec<u8>,
+    patched: Vec<u8>,
+}
+
+/// Byte patches applied by name, with the bytes they replaced
+///
+/// Patches are not reverted on drop: code may still run through them.
+/// Call [`revert_all`](Self::revert_all) when unloading.
+#[derive(Debug, Default)]
+pub struct PatchManager {
+    applied: Vec<AppliedPatch>,
+}
+
+impl PatchManager {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Locate `spec` in `module` and apply it, returning the patched address
+    ///
+    /// # Safety
+    ///
+    /// No thread may be executing the patched bytes, and they must leave the
+    /// code consistent.
+    pub unsafe fn apply(
+        &mut self,
+        spec: &PatchSpec,
+        scanner: &mut PatternScanner,
+        module: &MODULEINFO,
+    ) -> Result<*mut u8> {
+        self.check_name(&spec.name)?;
+        let address = spec.locate(scanner, module)?;
+        unsafe { self.apply_at(&spec.name, address, &spec.bytes)? };
+        Ok(address)
+    }
+
+    /// Overwrite `bytes.len()` bytes at `address`, saving the original ones
+    /// under `name`
+    ///
+    /// # Safety
+    ///
+    /// Same as [`apply`](Self::apply).
+    pub unsafe fn apply_at(&mut self, name: &str, address: *mut u8, bytes: &[u8]) -> Result<()> {
+        self.check_free(name, address as usize, bytes.len())?;
+        let original = unsafe { MemoryAccess::read_bytes(address, bytes.len())? };
+        unsafe { MemoryAccess::write_bytes_patched(address, bytes)? };
+
+        Logger::hook(&format!(
+            "Patch '{}' applied at {} ({} bytes)",
+            name,
+            describe_address(address as usize),
+            bytes.len()
+        ));
+        self.applied.push(AppliedPatch {
+            name: name.to_string(),
+            address: address as usize,
+            original,
+            patched: bytes.to_vec(),
+        });
+        Ok(())
+    }
+
+    /// Put back the bytes `name` replaced
+    ///
+    /// # Safety
+    ///
+    /// No thread may be executing the patched bytes.
+    pub unsafe fn revert(&mut self, name: &str) -> Result<()> {
+        let index = self
+            .applied
+            .iter()
+            .position(|patch| patch.name == name)
+            .ok_or_else(|| AppError::PatchNotApplied {
+                name: name.to_string(),
+            })?;
+        unsafe { Self::restore(&self.applied[index])? };
+        self.applied.remove(index);
+        Ok(())
+    }
+
+    /// Revert every patch, newest first
+    ///
+    /// A patch that fails to revert stays registered; the others are still
+    /// reverted and the first error is returned.
+    ///
+    /// # Safety
+    ///
+    /// Same as [`revert`](Self::revert), for every patch.
+    pub unsafe fn revert_all(&mut self) -> Result<()> {
+        let mut first_error = None;
+        let mut index = self.applied.len();
+        while index > 0 {
+            index -= 1;
+            match unsafe { Self::restore(&self.applied[index]) } {
+                Ok(()) => {
+                    self.applied.remove(index);
+                }
+                Err(e) => {
+                    first_error.get_or_insert(e);
+                }
+            }
+        }
+        first_error.map_or(Ok(()), Err)
+    }
+
+    pub fn is_applied(&self, name: &str) -> bool {
+        self.applied.iter().any(|patch| patch.name == name)
+    }
+
+    /// Names of the applied patches, oldest first
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.applied.iter().map(|patch| patch.name.as_str())
+    }
+
+    fn check_name(&self, name: &str) -> Result<()> {
+        if self.is_applied(name) {
+            return Err(AppError::PatchConflict {
+                name: name.to_string(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Check `name` is unused and `len` bytes at `address` touch no applied
+    /// patch, whose saved original bytes a second patch would make stale
+    fn check_free(&self, name: &str, address: usize, len: usize) -> Result<()> {
+        self.check_name(name)?;
+        let end = address.saturating_add(len);
+        match self
+            .applied
+            .iter()
+            .find(|patch| address < patch.address + patch.patched.len() && patch.address < end)
+        {
+            Some(patch) => Err(AppError::PatchOverlap {
+                name: name.to_string(),
+                address,
+                owner: patch.name.clone(),
+            }),
+            None => Ok(()),
+        }
+    }
+
+    unsafe fn restore(patch: &AppliedPatch) -> Result<()> {
+        let address = patch.address as *mut u8;
+        let current = unsafe { MemoryAccess::read_bytes(address, patch.patched.len())? };
+        if current != patch.patched {
+            Logger::warning(&format!(
+                "Patch '{}' at {} was changed by someone else, restoring the original bytes anyway",
+                patch.name,
+                describe_address(patch.address)
+            ));
+        }
+        unsafe { MemoryAccess::write_bytes_patched(address, &patch.original)? };
+        Logger::hook(&format!(
+            "Patch '{}' reverted at {}",
+            patch.name,
+            describe_address(patch.address)
+        ));
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn module_of(code: &mut [u8]) -> MODULEINFO {
+        MODULEINFO {
+            lpBaseOfDll: code.as_mut_ptr().cast(),
+            SizeOfImage: code.len() as u32,
+            EntryPoint: std::ptr::null_mut(),
+        }
+    }
+
+    #[test]
+    fn test_patches_apply_and_revert_by_name() {
+        let mut code = [
+            0x48, 0x85, 0xC0, 0x74, 0x05, 0xE8, 0x11, 0x22, 0x33, 0x44, 0xC3,
+        ];
+        let module = module_of(&mut code);
+        let check = PatchSpec::nop("check", Signature::new("t", &[0x85, 0xC0], "xx", 0), 2, 2);
+        let call = PatchSpec::nop("call", Signature::new("c", &[0xE8], "x", 0), 0, 5);
+        let mut scanner = PatternScanner::new();
+        let mut patches = PatchManager::new();
+
+        unsafe {
+            patches.apply(&check, &mut scanner, &module).unwrap();
+            patches.apply(&call, &mut scanner, &module).unwrap();
+            assert!(matches!(
+                patches.apply(&check, &mut scanner, &module),
+                Err(AppError::PatchConflict { .. })
+            ));
+            assert_eq!(code[3..10], [NOP; 7]);
+            assert_eq!(patches.names().collect::<Vec<_>>(), ["check", "call"]);
+
+            patches.revert("check").unwrap();
+            assert_eq!(code[3..5], [0x74, 0x05]);
+            assert!(!patches.is_applied("check"));
+            assert!(patches.revert("check").is_err());
+
+            patches.revert_all().unwrap();
+        }
+        assert_eq!(code[5..10], [0xE8, 0x11, 0x22, 0x33, 0x44]);
+        assert_eq!(patches.names().count(), 0);
+    }
+
+    #[test]
+    fn test_overlapping_patches_are_rejected() {
+        let mut code = [0x90; 8];
+        let start = code.as_mut_ptr();
+        let mut patches = PatchManager::new();
+
+        unsafe {
+            patches
+                .apply_at("middle", start.add(2), &[0xCC; 3])
+                .unwrap();
+            for (offset, len) in [(0, 3), (4, 2), (3, 1), (1, 6)] {
+                assert!(matches!(
+                    patches.apply_at("other", start.add(offset), &vec![0xCC; len]),
+                    Err(AppError::PatchOverlap { ref owner, .. }) if owner == "middle"
+                ));
+            }
+            // Adjacent ranges share no byte
+            patches.apply_at("before", start, &[0xEB; 2]).unwrap();
+            patches.apply_at("after", start.add(5), &[0xEB; 3]).unwrap();
+            patches.revert_all().unwrap();
+        }
+        assert_eq!(code, [0x90; 8]);
+    }
+
+    #[test]
+    fn test_patch_must_fit_in_the_module() {
+        let mut code = [0x90, 0xC3];
+        let module = module_of(&mut code);
+        let spec = PatchSpec::nop("tail", Signature::new("r", &[0xC3], "x", 0), 0, 2);
+        assert!(spec.locate(&mut PatternScanner::new(), &module).is_err());
+    }
+}
@@ -0,0 +1,50 @@
+//! Stable public API for downstream tools
+//!
+//! Everything re-exported here follows semver: it only changes in a
+//! breaking way together with a major version bump. Items reachable only
+//! through the individual modules may change in any release.
+//!
+//! ```rust
+//! use wuwa_sig_rs::prelude::*;
+//!
+//! let config = Config::new("Client-Win64-Shipping.exe", &[0x49, 0x81], "xx");
+//! assert!(config.validate().is_ok());
+//! ```
+
+pub use crate::anticheat::{AntiCheatModule, AntiCheatVendor, is_ace_loaded};
+pub use crate::build_info::BuildInfo;
+pub use crate::config::{Config, ConfigBuilder};
+pub use crate::error::{AppError, Result};
+#[cfg(feature = "dll")]
+pub use crate::ffi::{WuwaSigBuildInfo, WuwaSigStatus};
+pub use crate::filter::{PakFilter, UnmatchedPakAction, glob_match};
+pub use crate::hooks::{
+    ArgReader, BackendRoutine, BreakpointHandler, CallbackChain, FunctionHook, GuardPageHook,
+    HookAction, HookBackend, HookBackendKind, HookCall, HookInfo, HookManager, HookReplacement,
+    HookRoutine, HookSpec, HookState, HookStats, HookStatsSnapshot, HookTransaction, HwBpHook,
+    IatHook, InvocationHistory, InvocationRecord, ListenerId, MidFunctionHook, OneShot,
+    PakFileHook, RegisterDump, SpoofSetting, StateChange, VmtHook, global_hook_manager,
+};
+pub use crate::hotkey::Hotkey;
+pub use crate::logger::{
+    BurstLimiter, CallbackSink, CivilTime, ColorTheme, ConsoleSink, DebuggerSink, LogCategory,
+    LogEveryN, LogFilters, LogFormat, LogLevel, LogOnce, LogRecord, LogSink, LogWorker, Logger,
+    LoggerConfig, OverflowPolicy, WriterSink,
+};
+pub use crate::memory::hash::FileHash;
+pub use crate::memory::pe::{
+    DataDirectory, ExportTarget, ImportName, ImportedFunction, ImportedModule, PeImage,
+    SectionInfo, export_hash,
+};
+pub use crate::memory::version::{ModuleVersion, VersionNumber};
+pub use crate::memory::{
+    MemoryRegion, ModuleAddress, ModuleScanner, PatternScanner, Rva, SignatureSuggestion,
+    UniquenessReport, describe_address, module_for_address,
+};
+pub use crate::overlay::{OVERLAY_MAGIC, OVERLAY_SECTION_NAME, OVERLAY_VERSION, OverlayBlock};
+pub use crate::patch::{PatchManager, PatchSpec};
+pub use crate::reload::{ConfigWatcher, ReloadOutcome, ReloadableSetting, apply_safe_changes};
+pub use crate::retry::{RetryPolicy, retry};
+pub use crate::signatures::{DEFAULT_SIGNATURE, Signature, SignatureDb, VersionRange};
+pub use crate::startup::{StartupIssue, StartupPhase, StartupStatus, current_phase, status};
+pub use crate::update::UpdateUrl;
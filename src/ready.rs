@@ -0,0 +1,147 @@
+//! Named events telling launchers when the bypass is in place
+//!
+//! A launcher that injects the DLL can wait on [`READY_EVENT_NAME`] and
+//! [`FAILED_EVENT_NAME`] before letting the game session start, instead of
+//! guessing how long initialization takes. Both are manual-reset events:
+//!
+//! - the ready event is set once the hooks are applied, and reset again
+//!   when they are removed on shutdown
+//! - the failed event is set when initialization fails; the reason is the
+//!   `error_code` of the [`LaunchStatusBlock`] at [`LAUNCH_STATUS_NAME`]
+//!
+//! Either side may create the events first. The DLL resets both when it
+//! starts, so a launcher keeping them open across game sessions never sees
+//! the outcome of an earlier one.
+//!
+//! The status block is created with the events, before the configuration
+//! is read, so the error code is there whatever `publish_overlay` says:
+//!
+//! | Offset | Size | Field        | Description                                |
+//! |--------|------|--------------|--------------------------------------------|
+//! | 0x00   | 4    | `magic`      | `LAUNCH_STATUS_MAGIC` (`"WWSL"`)           |
+//! | 0x04   | 4    | `process_id` | Id of the game process owning the block    |
+//! | 0x08   | 4    | `error_code` | `AppError::code` of a failure; 0 otherwise |
+
+use std::ptr;
+use std::sync::OnceLock;
+use std::sync::atomic::{AtomicU32, Ordering};
+
+use winapi::um::processthreadsapi::GetCurrentProcessId;
+use winapi::um::synchapi::{CreateEventW, ResetEvent, SetEvent};
+
+use crate::constants::constants::{FAILED_EVENT_NAME, LAUNCH_STATUS_NAME, READY_EVENT_NAME};
+use crate::error::{AppError, Result};
+use crate::overlay::SharedSection;
+use crate::safety::{OwnedHandle, StringConverter};
+
+/// Magic value identifying an initialized launch status block ("WWSL")
+pub const LAUNCH_STATUS_MAGIC: u32 = 0x4C53_5757;
+
+/// Shared-memory layout read by launchers after the failed event
+#[repr(C)]
+pub struct LaunchStatusBlock {
+    pub magic: u32,
+    pub process_id: u32,
+    pub error_code: AtomicU32,
+}
+
+/// The ready and failed events of this process, and the status block
+struct LaunchEvents {
+    ready: OwnedHandle,
+    failed: OwnedHandle,
+    status: SharedSection<LaunchStatusBlock>,
+}
+
+impl LaunchEvents {
+    fn status(&self) -> &LaunchStatusBlock {
+        unsafe { &*self.status.as_ptr() }
+    }
+}
+
+// The status block is only written through atomics after initialization
+unsafe impl Send for LaunchEvents {}
+unsafe impl Sync for LaunchEvents {}
+
+static EVENTS: OnceLock<LaunchEvents> = OnceLock::new();
+
+/// Create or open the manual-reset event `name`, cleared
+fn open_event(name: &str) -> Result<OwnedHandle> {
+    let wide_name = StringConverter::to_wide_null(name);
+    let event =
+        unsafe { OwnedHandle::from_raw(CreateEventW(ptr::null_mut(), 1, 0, wide_name.as_ptr())) }
+            .ok_or_else(|| AppError::last_error(&format!("CreateEventW('{}')", name)))?;
+    // An event opened rather than created may still be set from a previous run
+    unsafe { ResetEvent(event.as_raw()) };
+    Ok(event)
+}
+
+/// Create the launch status block with no error set
+fn create_status() -> Result<SharedSection<LaunchStatusBlock>> {
+    let section = SharedSection::<LaunchStatusBlock>::create(LAUNCH_STATUS_NAME)?;
+    let block = section.as_ptr();
+    unsafe {
+        (*block).process_id = GetCurrentProcessId();
+        // Publish the magic last so readers never see a half-written header
+        AtomicU32::from_ptr(ptr::addr_of_mut!((*block).magic))
+            .store(LAUNCH_STATUS_MAGIC, Ordering::Release);
+    }
+    Ok(section)
+}
+
+/// Create the ready and failed events, both cleared, and the status block;
+/// later calls change nothing
+pub(crate) fn init() -> Result<()> {
+    if EVENTS.get().is_some() {
+        return Ok(());
+    }
+    let events = LaunchEvents {
+        ready: open_event(READY_EVENT_NAME)?,
+        failed: open_event(FAILED_EVENT_NAME)?,
+        status: create_status()?,
+    };
+    EVENTS.set(events).ok();
+    Ok(())
+}
+
+/// Tell launchers the hooks are applied
+pub(crate) fn signal_ready() {
+    if let Some(events) = EVENTS.get() {
+        unsafe { SetEvent(events.ready.as_raw()) };
+    }
+}
+
+/// Tell launchers initialization failed with the error `error_code`
+pub(crate) fn signal_failed(error_code: u32) {
+    if let Some(events) = EVENTS.get() {
+        events
+            .status()
+            .error_code
+            .store(error_code, Ordering::Release);
+        unsafe {
+            ResetEvent(events.ready.as_raw());
+            SetEvent(events.failed.as_raw());
+        }
+    }
+}
+
+/// Tell launchers the hooks were removed
+pub(crate) fn withdraw() {
+    if let Some(events) = EVENTS.get() {
+        unsafe { ResetEvent(events.ready.as_raw()) };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::mem::{offset_of, size_of};
+
+    #[test]
+    fn test_launch_status_layout_matches_documentation() {
+        assert_eq!(offset_of!(LaunchStatusBlock, magic), 0x00);
+        assert_eq!(offset_of!(LaunchStatusBlock, process_id), 0x04);
+        assert_eq!(offset_of!(LaunchStatusBlock, error_code), 0x08);
+        assert_eq!(size_of::<LaunchStatusBlock>(), 0x0C);
+        assert_eq!(&LAUNCH_STATUS_MAGIC.to_le_bytes(), b"WWSL");
+    }
+}
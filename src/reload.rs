@@ -0,0 +1,191 @@
+//! Hot reload of the configuration file
+//!
+//! The maintenance loop polls the file's modification time every
+//! `watchdog_interval_ms`. A changed file is parsed and validated as at
+//! startup, but only settings that are safe to change while hooked are taken
+//! over: the log level and category filters, the pak allow and deny lists with the action for
+//! unmatched paks, and the watchdog interval itself. Any other change is
+//! reported and waits for the next launch.
+
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use crate::config::Config;
+use crate::error::Result;
+
+/// Setting taken over from a reloaded configuration
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReloadableSetting {
+    /// `log_level` and `log_filters`
+    LogLevel,
+    /// `pak_allow`, `pak_deny` and `unmatched_pak_action`
+    PakFilter,
+    WatchdogInterval,
+}
+
+/// What applying a reloaded configuration changed
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ReloadOutcome {
+    /// Settings that now have their new value
+    pub applied: Vec<ReloadableSetting>,
+    /// Whether the file also changed settings that need a restart
+    pub restart_required: bool,
+}
+
+/// Polls a configuration file for changes
+#[derive(Debug, Clone)]
+pub struct ConfigWatcher {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+}
+
+impl ConfigWatcher {
+    /// Watch `path`, treating its current contents as already loaded
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+        let modified = modified_time(&path);
+        Self { path, modified }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Load and validate the file if it changed since the last poll
+    ///
+    /// Returns `None` while the file is unchanged or cannot be read, such as
+    /// while an editor replaces it.
+    pub fn poll(&mut self) -> Option<Result<Config>> {
+        let modified = modified_time(&self.path)?;
+        if self.modified == Some(modified) {
+            return None;
+        }
+        self.modified = Some(modified);
+
+        Some(Config::load(Some(&self.path)).and_then(|config| config.validate().map(|()| config)))
+    }
+}
+
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
+
+/// Take the settings of `updated` that are safe to change at runtime into
+/// `current`
+pub fn apply_safe_changes(current: &mut Config, updated: &Config) -> ReloadOutcome {
+    let mut applied = Vec::new();
+
+    if current.log_level != updated.log_level || current.log_filters != updated.log_filters {
+        current.log_level = updated.log_level;
+        current.log_filters = updated.log_filters.clone();
+        applied.push(ReloadableSetting::LogLevel);
+    }
+
+    if current.pak_allow != updated.pak_allow
+        || current.pak_deny != updated.pak_deny
+        || current.unmatched_pak_action != updated.unmatched_pak_action
+    {
+        current.pak_allow = updated.pak_allow.clone();
+        current.pak_deny = updated.pak_deny.clone();
+        current.unmatched_pak_action = updated.unmatched_pak_action;
+        applied.push(ReloadableSetting::PakFilter);
+    }
+
+    if current.watchdog_interval_ms != updated.watchdog_interval_ms {
+        current.watchdog_interval_ms = updated.watchdog_interval_ms;
+        applied.push(ReloadableSetting::WatchdogInterval);
+    }
+
+    // Downloaded signatures never come from the file
+    let reloaded = Config {
+        downloaded_signatures: current.downloaded_signatures.clone(),
+        ..updated.clone()
+    };
+    ReloadOutcome {
+        applied,
+        // Whatever still differs could not be taken over
+        restart_required: *current != reloaded,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::File;
+    use std::time::Duration;
+
+    use super::*;
+    use crate::filter::UnmatchedPakAction;
+    use crate::logger::LogLevel;
+    use crate::signatures::Signature;
+
+    fn config() -> Config {
+        Config::new("test.exe", &[0x48], "x")
+    }
+
+    #[test]
+    fn test_downloaded_signatures_need_no_restart() {
+        let mut current = config();
+        current.downloaded_signatures = vec![Signature::new("remote", &[0x4A], "x", 0)];
+        let updated = config();
+
+        let outcome = apply_safe_changes(&mut current, &updated);
+        assert!(outcome.applied.is_empty());
+        assert!(!outcome.restart_required);
+        assert_eq!(current.downloaded_signatures.len(), 1);
+    }
+
+    #[test]
+    fn test_applies_only_safe_settings() {
+        let mut current = config();
+        let mut updated = config();
+        updated.log_level = LogLevel::Hook;
+        updated.pak_deny = vec!["pakchunk0*".to_string()];
+        updated.unmatched_pak_action = UnmatchedPakAction::Fail;
+
+        let outcome = apply_safe_changes(&mut current, &updated);
+        assert_eq!(
+            outcome.applied,
+            [ReloadableSetting::LogLevel, ReloadableSetting::PakFilter]
+        );
+        assert!(!outcome.restart_required);
+        assert_eq!(current, updated);
+    }
+
+    #[test]
+    fn test_other_changes_require_restart() {
+        let mut current = config();
+        let mut updated = config();
+        updated.watchdog_interval_ms = 250;
+        updated.dry_run = true;
+
+        let outcome = apply_safe_changes(&mut current, &updated);
+        assert_eq!(outcome.applied, [ReloadableSetting::WatchdogInterval]);
+        assert!(outcome.restart_required);
+        assert_eq!(current.watchdog_interval_ms, 250);
+        assert!(!current.dry_run);
+    }
+
+    #[test]
+    fn test_watcher_reloads_after_modification() {
+        let path =
+            std::env::temp_dir().join(format!("wuwa-sig-reload-{}.toml", std::process::id()));
+        std::fs::write(&path, "log_level = \"info\"\n").unwrap();
+        let mut watcher = ConfigWatcher::new(&path);
+        assert!(watcher.poll().is_none());
+
+        std::fs::write(&path, "log_level = \"hook\"\n").unwrap();
+        let later = SystemTime::now() + Duration::from_secs(5);
+        File::options()
+            .write(true)
+            .open(&path)
+            .and_then(|file| file.set_modified(later))
+            .unwrap();
+        let reloaded = watcher.poll();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(reloaded.unwrap().unwrap().log_level, LogLevel::Hook);
+        assert!(watcher.poll().is_none());
+    }
+}
@@ -0,0 +1,160 @@
+//! Crash report written when initialization fails
+//!
+//! A failure usually ends with the console closing along with the game, so
+//! the error on screen is gone before anyone can read it. The report keeps
+//! everything needed to triage it in one timestamped file next to the DLL:
+//! the error and its causes, the configuration in effect, the loaded
+//! modules and the recent log lines.
+
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use crate::build_info::BuildInfo;
+use crate::config::Config;
+use crate::constants::constants::{CRASH_REPORT_PREFIX, RECENT_LOG_CAPACITY};
+use crate::error::{AppError, Result};
+use crate::logger::{self, CivilTime};
+use crate::memory::peb::{self, LoadedModule};
+use crate::startup;
+
+/// Everything gathered about a failed initialization
+#[derive(Debug)]
+pub struct CrashReport<'a> {
+    pub error: &'a AppError,
+    /// Configuration in effect, or why it could not be loaded
+    pub config: std::result::Result<&'a Config, &'a AppError>,
+    pub modules: Vec<LoadedModule>,
+    pub recent_log: Vec<String>,
+}
+
+impl<'a> CrashReport<'a> {
+    /// Gather the modules and log lines of this process for `error`
+    pub fn collect(
+        error: &'a AppError,
+        config: std::result::Result<&'a Config, &'a AppError>,
+    ) -> Self {
+        Self {
+            error,
+            config,
+            modules: peb::loaded_modules(),
+            recent_log: logger::global_recent(RECENT_LOG_CAPACITY),
+        }
+    }
+
+    /// The report as text, one `== section ==` after another
+    pub fn render(&self) -> String {
+        let mut text = String::new();
+        let _ = writeln!(text, "wuwa-sig-rs {} crash report", BuildInfo::CURRENT);
+        let _ = writeln!(text, "Time: {}", CivilTime::local(SystemTime::now()));
+        let _ = writeln!(text, "Process: {}", std::process::id());
+        let _ = writeln!(text, "Phase: {}", startup::current_phase());
+
+        let _ = writeln!(text, "\n== Error ==");
+        let _ = writeln!(text, "Code: {}", self.error.code());
+        let _ = writeln!(text, "{}", self.error);
+        let mut source = std::error::Error::source(self.error);
+        while let Some(cause) = source {
+            let _ = writeln!(text, "Caused by: {}", cause);
+            source = cause.source();
+        }
+
+        let _ = writeln!(text, "\n== Configuration ==");
+        match self.config {
+            Ok(config) => text.push_str(&config.effective()),
+            Err(e) => {
+                let _ = write!(text, "Unavailable: {}", e);
+            }
+        }
+        text.push('\n');
+
+        let _ = writeln!(text, "\n== Modules ({}) ==", self.modules.len());
+        for module in &self.modules {
+            let _ = writeln!(
+                text,
+                "{:#018x} {:>10} {}",
+                module.base as usize, module.size, module.name
+            );
+        }
+
+        let _ = writeln!(text, "\n== Recent log ({} lines) ==", self.recent_log.len());
+        for line in &self.recent_log {
+            let _ = writeln!(text, "{}", line);
+        }
+        text
+    }
+
+    /// Write the report to a new timestamped file in `directory`
+    pub fn write(&self, directory: &Path) -> Result<PathBuf> {
+        let path = directory.join(file_name(CivilTime::local(SystemTime::now())));
+        std::fs::write(&path, self.render()).map_err(|source| AppError::ReportWriteFailed {
+            path: path.display().to_string(),
+            source,
+        })?;
+        Ok(path)
+    }
+}
+
+/// `wuwa-sig-crash-YYYYMMDD-HHMMSS.txt` for `time`
+fn file_name(time: CivilTime) -> String {
+    format!(
+        "{}-{:04}{:02}{:02}-{:02}{:02}{:02}.txt",
+        CRASH_REPORT_PREFIX, time.year, time.month, time.day, time.hour, time.minute, time.second
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_report_lists_every_section() {
+        let error = AppError::ConfigFileRead {
+            path: "wuwa-sig.toml".to_string(),
+            source: std::io::Error::other("disk on fire"),
+        };
+        let config = Config::new("test.exe", &[0x55, 0x53], "xx");
+        let report = CrashReport {
+            error: &error,
+            config: Ok(&config),
+            modules: vec![LoadedModule {
+                base: 0x7FF6_0000_0000usize as _,
+                size: 0x1000,
+                name: "test.exe".to_string(),
+            }],
+            recent_log: vec!["[INFO] last words".to_string()],
+        };
+        let text = report.render();
+
+        assert!(text.contains("Code: 401"));
+        assert!(text.contains("Caused by: disk on fire"));
+        assert!(text.contains("target_module = \"test.exe\""));
+        assert!(text.contains("== Modules (1) ==\n0x00007ff600000000"));
+        assert!(text.ends_with("[INFO] last words\n"));
+
+        let missing = CrashReport {
+            config: Err(&error),
+            ..report
+        };
+        assert!(
+            missing
+                .render()
+                .contains("Unavailable: Failed to read config file")
+        );
+    }
+
+    #[test]
+    fn test_report_file_name_sorts_by_time() {
+        let time = CivilTime {
+            year: 2026,
+            month: 3,
+            day: 7,
+            hour: 9,
+            minute: 5,
+            second: 1,
+            millis: 0,
+            utc: false,
+        };
+        assert_eq!(file_name(time), "wuwa-sig-crash-20260307-090501.txt");
+    }
+}
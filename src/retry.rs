@@ -0,0 +1,128 @@
+//! Retries with exponential backoff for early-startup steps
+//!
+//! Right after injection the game may not have mapped its module yet, or
+//! its protector may still be unpacking the pages a signature lies in.
+//! Such failures clear up on their own within moments, so the module
+//! lookup, the scan and the hook application are retried a few times under
+//! a [`RetryPolicy`] before initialization gives up.
+
+use std::thread;
+use std::time::Duration;
+
+use crate::constants::constants::MAX_RETRY_DELAY_MS;
+use crate::error::Result;
+use crate::logger::Logger;
+
+/// How often and how patiently an operation is retried
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Attempts in total, the first one included; at least 1
+    pub attempts: u32,
+    /// Delay before the first retry, doubled before each further one
+    pub initial_delay: Duration,
+    /// Longest delay between two attempts
+    pub max_delay: Duration,
+}
+
+impl RetryPolicy {
+    pub fn new(attempts: u32, initial_delay: Duration) -> Self {
+        Self {
+            attempts: attempts.max(1),
+            initial_delay,
+            max_delay: Duration::from_millis(MAX_RETRY_DELAY_MS),
+        }
+    }
+
+    /// Run the operation once, never retrying
+    pub fn once() -> Self {
+        Self::new(1, Duration::ZERO)
+    }
+
+    /// Delay after the failed attempt `attempt`, counted from 1
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let factor = 1u32
+            .checked_shl(attempt.saturating_sub(1))
+            .unwrap_or(u32::MAX);
+        self.initial_delay
+            .saturating_mul(factor)
+            .min(self.max_delay)
+    }
+}
+
+/// Run `op` until it succeeds or `policy` runs out of attempts
+///
+/// Each failure but the last is logged as a warning naming `operation`;
+/// the last one is returned.
+pub fn retry<T>(
+    policy: &RetryPolicy,
+    operation: &str,
+    mut op: impl FnMut() -> Result<T>,
+) -> Result<T> {
+    let mut attempt = 1;
+    loop {
+        match op() {
+            Ok(value) => return Ok(value),
+            Err(e) if attempt < policy.attempts => {
+                let delay = policy.delay(attempt);
+                Logger::warning(&format!(
+                    "{} failed (attempt {} of {}): {}; retrying in {:?}",
+                    operation, attempt, policy.attempts, e, delay
+                ));
+                thread::sleep(delay);
+                attempt += 1;
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::AppError;
+
+    #[test]
+    fn test_delay_doubles_up_to_the_cap() {
+        let policy = RetryPolicy::new(10, Duration::from_millis(100));
+        assert_eq!(policy.delay(1), Duration::from_millis(100));
+        assert_eq!(policy.delay(2), Duration::from_millis(200));
+        assert_eq!(policy.delay(3), Duration::from_millis(400));
+        assert_eq!(policy.delay(40), policy.max_delay);
+        assert_eq!(RetryPolicy::new(0, Duration::ZERO).attempts, 1);
+    }
+
+    #[test]
+    fn test_retry_stops_at_the_first_success() {
+        let policy = RetryPolicy::new(5, Duration::ZERO);
+        let mut calls = 0;
+        let result = retry(&policy, "Test", || {
+            calls += 1;
+            if calls < 3 {
+                Err(AppError::PatternNotFound { size: calls })
+            } else {
+                Ok(calls)
+            }
+        });
+        assert_eq!(result.unwrap(), 3);
+        assert_eq!(calls, 3);
+    }
+
+    #[test]
+    fn test_retry_returns_the_last_error() {
+        let policy = RetryPolicy::new(2, Duration::ZERO);
+        let mut calls = 0;
+        let result: Result<()> = retry(&policy, "Test", || {
+            calls += 1;
+            Err(AppError::PatternNotFound { size: calls })
+        });
+        assert!(matches!(result, Err(AppError::PatternNotFound { size: 2 })));
+        assert_eq!(calls, 2);
+
+        let mut calls = 0;
+        let _: Result<()> = retry(&RetryPolicy::once(), "Test", || {
+            calls += 1;
+            Err(AppError::LogBackendInstalled)
+        });
+        assert_eq!(calls, 1);
+    }
+}
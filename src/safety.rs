@@ -1,60 +1,268 @@
 /// Safe abstractions for unsafe operations
 use std::ptr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
 use winapi::shared::minwindef::{DWORD, LPVOID};
-use winapi::um::consoleapi::AllocConsole;
-use winapi::um::processthreadsapi::CreateThread;
+use winapi::shared::winerror::WAIT_TIMEOUT;
+use winapi::um::handleapi::{CloseHandle, INVALID_HANDLE_VALUE};
+use winapi::um::libloaderapi::{GetModuleHandleW, GetProcAddress};
+use winapi::um::memoryapi::{VirtualProtect, VirtualQuery};
+use winapi::um::minwinbase::STILL_ACTIVE;
+#[cfg(feature = "dll")]
+use winapi::um::processthreadsapi::{CreateThread, GetCurrentThread, GetCurrentThreadId};
+use winapi::um::processthreadsapi::{FlushInstructionCache, GetCurrentProcess, GetExitCodeThread};
+use winapi::um::synchapi::WaitForSingleObject;
+use winapi::um::winbase::{INFINITE, WAIT_OBJECT_0};
+use winapi::um::winnt::{
+    HANDLE, MEM_COMMIT, MEMORY_BASIC_INFORMATION, PAGE_EXECUTE, PAGE_EXECUTE_READ,
+    PAGE_EXECUTE_READWRITE, PAGE_EXECUTE_WRITECOPY, PAGE_GUARD, PAGE_NOACCESS, PAGE_READWRITE,
+};
 use windows::core::PCWSTR;
 
+use crate::constants::constants::memory::PAGE_SIZE;
 use crate::error::{AppError, Result};
 
-/// Safe wrapper for console initialization
-pub struct ConsoleManager;
+#[cfg(feature = "dll")]
+mod console;
+mod guarded;
+#[cfg(feature = "dll")]
+mod instance;
+pub mod layout;
+pub mod snapshot;
+pub mod unreal;
+
+pub use layout::{Field, Layout, Plain, StructReader};
+pub use snapshot::{ChangedRange, MemorySnapshot};
+
+#[cfg(feature = "dll")]
+pub(crate) use console::{ConsoleManager, ConsoleSource};
+#[cfg(feature = "dll")]
+pub(crate) use instance::InstanceGuard;
+
+/// Win32 kernel object handle, closed when dropped
+///
+/// For threads, processes, files, snapshots, mappings and the like; not for
+/// handles with their own close function, such as WinHTTP or module ones.
+#[derive(Debug)]
+pub struct OwnedHandle(HANDLE);
+
+impl OwnedHandle {
+    /// Take ownership of `handle`
+    ///
+    /// Returns `None` for null and `INVALID_HANDLE_VALUE`, the two ways
+    /// Win32 functions report failure, so the caller can read the error.
+    ///
+    /// # Safety
+    ///
+    /// `handle` must be open and not closed by anyone else.
+    pub unsafe fn from_raw(handle: HANDLE) -> Option<Self> {
+        (!handle.is_null() && handle != INVALID_HANDLE_VALUE).then_some(Self(handle))
+    }
 
-impl ConsoleManager {
-    /// Initialize the console with proper error handling
-    pub fn init() -> Result<()> {
-        let result = unsafe { AllocConsole() };
+    /// The handle, still owned by `self`
+    pub fn as_raw(&self) -> HANDLE {
+        self.0
+    }
 
-        if result == 0 {
-            let error = std::io::Error::last_os_error();
-            // Don't use logger here since it's not initialized yet
-            return Err(AppError::ConsoleInitFailed { source: error });
-        }
+    /// Give up ownership without closing the handle
+    pub fn into_raw(self) -> HANDLE {
+        let handle = self.0;
+        std::mem::forget(self);
+        handle
+    }
+}
 
-        // Console is ready, but don't log yet since logger isn't initialized
-        Ok(())
+impl Drop for OwnedHandle {
+    fn drop(&mut self) {
+        unsafe { CloseHandle(self.0) };
     }
 }
 
+// Kernel handles may be used and closed from any thread
+unsafe impl Send for OwnedHandle {}
+unsafe impl Sync for OwnedHandle {}
+
 /// Safe wrapper for thread creation
-pub struct ThreadManager;
+#[cfg(feature = "dll")]
+pub(crate) struct ThreadManager;
 
+#[cfg(feature = "dll")]
 impl ThreadManager {
     /// Create a thread with proper error handling
+    ///
+    /// Dropping the returned thread leaves it running.
     pub fn create_thread(
         start_address: unsafe extern "system" fn(LPVOID) -> DWORD,
         parameter: LPVOID,
-    ) -> Result<()> {
+    ) -> Result<ManagedThread> {
+        let mut id: DWORD = 0;
         let thread_handle = unsafe {
             CreateThread(
                 ptr::null_mut(), // Security attributes
                 0,               // Stack size (default)
                 Some(start_address),
                 parameter,
-                0,               // Creation flags
-                ptr::null_mut(), // Thread ID
+                0, // Creation flags
+                &mut id,
             )
         };
 
-        if thread_handle.is_null() {
-            let error = std::io::Error::last_os_error();
-            return Err(AppError::HookFailed {
-                message: format!("Thread creation failed: {}", error),
+        let handle = unsafe { OwnedHandle::from_raw(thread_handle) }
+            .ok_or_else(|| AppError::last_error("CreateThread"))?;
+        Ok(ManagedThread { handle, id })
+    }
+
+    /// Name the calling thread for debuggers and crash dumps
+    pub fn name_current_thread(name: &str) -> Result<()> {
+        set_thread_description(
+            unsafe { GetCurrentThread() },
+            unsafe { GetCurrentThreadId() },
+            name,
+        )
+    }
+}
+
+/// `SetThreadDescription`, resolved at run time as Windows before 10 1607
+/// lacks it
+type SetThreadDescriptionFn = unsafe extern "system" fn(HANDLE, *const u16) -> i32;
+
+/// A thread started by [`ThreadManager`] that can be named and waited for
+#[derive(Debug)]
+pub struct ManagedThread {
+    handle: OwnedHandle,
+    id: DWORD,
+}
+
+impl ManagedThread {
+    pub fn id(&self) -> DWORD {
+        self.id
+    }
+
+    /// Name the thread for debuggers and crash dumps
+    pub fn set_name(&self, name: &str) -> Result<()> {
+        set_thread_description(self.handle.as_raw(), self.id, name)
+    }
+
+    /// Wait up to `timeout` for the thread to exit, reporting whether it did
+    pub fn join(&self, timeout: Duration) -> Result<bool> {
+        match unsafe { WaitForSingleObject(self.handle.as_raw(), wait_millis(timeout)) } {
+            WAIT_OBJECT_0 => Ok(true),
+            WAIT_TIMEOUT => Ok(false),
+            _ => Err(AppError::ThreadFailed {
+                action: "wait for".to_string(),
+                thread_id: self.id,
+                source: std::io::Error::last_os_error(),
+            }),
+        }
+    }
+
+    /// Exit code of the thread, or `None` while it is still running
+    ///
+    /// A thread that returned `STILL_ACTIVE` itself reads as running.
+    pub fn exit_code(&self) -> Result<Option<DWORD>> {
+        let mut code: DWORD = 0;
+        if unsafe { GetExitCodeThread(self.handle.as_raw(), &mut code) } == 0 {
+            return Err(AppError::ThreadFailed {
+                action: "query the exit code of".to_string(),
+                thread_id: self.id,
+                source: std::io::Error::last_os_error(),
             });
         }
+        Ok((code != STILL_ACTIVE).then_some(code))
+    }
+}
 
-        // Thread created successfully
-        Ok(())
+/// Set the description of the thread behind `handle`, whose id is `id`
+fn set_thread_description(handle: HANDLE, id: DWORD, name: &str) -> Result<()> {
+    let failed = |source| AppError::ThreadFailed {
+        action: "name".to_string(),
+        thread_id: id,
+        source,
+    };
+
+    let kernel32 = StringConverter::to_wide_null("kernel32.dll");
+    let function = unsafe {
+        GetProcAddress(
+            GetModuleHandleW(kernel32.as_ptr()),
+            c"SetThreadDescription".as_ptr(),
+        )
+    };
+    if function.is_null() {
+        return Err(failed(std::io::ErrorKind::Unsupported.into()));
+    }
+
+    let set_description: SetThreadDescriptionFn = unsafe { std::mem::transmute(function) };
+    let name = StringConverter::to_wide_null(name);
+    let result = unsafe { set_description(handle, name.as_ptr()) };
+    if result < 0 {
+        let hresult = windows::core::HRESULT(result);
+        return Err(windows::core::Error::from_hresult(hresult).into());
+    }
+    Ok(())
+}
+
+/// `timeout` in milliseconds for a wait function, short of `INFINITE`
+pub(crate) fn wait_millis(timeout: Duration) -> DWORD {
+    timeout.as_millis().min(u128::from(INFINITE - 1)) as DWORD
+}
+
+/// Page protection changed for as long as the guard lives
+///
+/// The protection the range had before is put back when the guard is
+/// dropped. A range spanning pages of different protections gets the one of
+/// its first page back everywhere, as `VirtualProtect` only reports that.
+pub struct ProtectionGuard {
+    address: usize,
+    len: usize,
+    old_protect: DWORD,
+}
+
+impl ProtectionGuard {
+    /// Give `address..address + len` the protection `protect`, a `PAGE_*`
+    /// constant
+    ///
+    /// # Safety
+    ///
+    /// No other code may rely on the old protection of the range until the
+    /// guard is dropped, e.g. by executing it while it is not executable.
+    pub unsafe fn new(address: *const u8, len: usize, protect: DWORD) -> Result<Self> {
+        if address.is_null() {
+            return Err(AppError::MemoryAccessViolation { address: 0 });
+        }
+
+        let mut old_protect: DWORD = 0;
+        if unsafe { VirtualProtect(address as LPVOID, len, protect, &mut old_protect) } == 0 {
+            return Err(AppError::ProtectionChangeFailed {
+                address: address as usize,
+                source: std::io::Error::last_os_error(),
+            });
+        }
+
+        Ok(Self {
+            address: address as usize,
+            len,
+            old_protect,
+        })
+    }
+
+    /// Protection the range had before, restored on drop
+    pub fn old_protect(&self) -> DWORD {
+        self.old_protect
+    }
+}
+
+impl Drop for ProtectionGuard {
+    fn drop(&mut self) {
+        let mut ignored: DWORD = 0;
+        unsafe {
+            VirtualProtect(
+                self.address as LPVOID,
+                self.len,
+                self.old_protect,
+                &mut ignored,
+            )
+        };
     }
 }
 
@@ -62,29 +270,49 @@ impl ThreadManager {
 pub struct MemoryAccess;
 
 impl MemoryAccess {
-    /// Safely read a value from memory with bounds checking
+    /// Unregister the handler behind the guarded reads, before unloading
+    /// the module that made them
+    pub fn remove_fault_handler() {
+        guarded::remove_handler();
+    }
+
+    /// Safely read a value from memory, checking it is readable first
     pub unsafe fn read_volatile_safe<T>(ptr: *const T) -> Result<T>
     where
         T: Copy,
     {
-        if ptr.is_null() {
-            return Err(AppError::MemoryAccessViolation { address: 0 });
-        }
-
-        // In a real implementation, you might want to add more sophisticated
-        // bounds checking or use platform-specific APIs to verify the memory
-        // is readable before accessing it.
-
+        Self::check_readable(ptr as *const u8, std::mem::size_of::<T>())?;
         Ok(unsafe { ptr::read_volatile(ptr) })
     }
 
+    /// Read a `T` at `ptr`, turning an access violation into an error
+    ///
+    /// Meant for pointers from game structures: besides the readability
+    /// check, the read itself runs under an exception handler, so memory
+    /// freed or reprotected by another thread in between fails the read
+    /// instead of crashing the game.
+    ///
+    /// # Safety
+    ///
+    /// Any bit pattern read must be a valid `T`.
+    pub unsafe fn try_read<T: Copy>(ptr: *const T) -> Result<T> {
+        let len = std::mem::size_of::<T>();
+        Self::check_readable(ptr as *const u8, len)?;
+
+        let mut value = std::mem::MaybeUninit::<T>::uninit();
+        unsafe { guarded::copy(value.as_mut_ptr().cast(), ptr.cast(), len) }
+            .map_err(|address| AppError::MemoryAccessViolation { address })?;
+        Ok(unsafe { value.assume_init() })
+    }
+
     /// Safely read a pointer from memory
     pub unsafe fn read_pointer_safe(ptr: *const usize, offset: usize) -> Result<usize> {
         if ptr.is_null() {
             return Err(AppError::MemoryAccessViolation { address: 0 });
         }
 
-        let target_ptr = unsafe { ptr.add(offset) };
+        let target_ptr = ptr.wrapping_add(offset);
+        Self::check_readable(target_ptr as *const u8, std::mem::size_of::<usize>())?;
         let value = unsafe { ptr::read_volatile(target_ptr) };
 
         if value == 0 {
@@ -96,16 +324,171 @@ impl MemoryAccess {
         Ok(value)
     }
 
-    /// Safely dereference a pointer with null checking
+    /// Safely dereference a pointer, checking it is readable first
     pub unsafe fn deref_pointer_safe<T>(ptr: *const T) -> Result<T>
     where
         T: Copy,
     {
-        if ptr.is_null() {
-            return Err(AppError::MemoryAccessViolation { address: 0 });
+        Self::check_readable(ptr as *const u8, std::mem::size_of::<T>())?;
+        Ok(unsafe { *ptr })
+    }
+
+    /// Walk a pointer chain and read the value at its end
+    ///
+    /// Each offset is added to the current address; every step but the last
+    /// reads the pointer stored there, which must not be null. The last step
+    /// reads a `T`. Every read goes through [`Self::try_read`], so
+    /// `follow_chain::<usize>(rcx, &[0x10, 0, 0x8])` reads
+    /// `*(*(*(rcx + 0x10)) + 0x8)` or fails at the first bad link.
+    ///
+    /// # Safety
+    ///
+    /// Any bit pattern read at the end must be a valid `T`.
+    pub unsafe fn follow_chain<T: Copy>(base: usize, offsets: &[usize]) -> Result<T> {
+        let Some((&last, steps)) = offsets.split_last() else {
+            return unsafe { Self::try_read(base as *const T) };
+        };
+
+        let mut address = base;
+        for &offset in steps {
+            let pointer: usize =
+                unsafe { Self::try_read(address.wrapping_add(offset) as *const usize)? };
+            if pointer == 0 {
+                return Err(AppError::MemoryAccessViolation {
+                    address: address.wrapping_add(offset),
+                });
+            }
+            address = pointer;
         }
+        unsafe { Self::try_read(address.wrapping_add(last) as *const T) }
+    }
 
-        Ok(unsafe { *ptr })
+    /// Check whether `ptr..ptr + len` is committed memory that may be read
+    ///
+    /// The range may span several regions; each one must be neither
+    /// `PAGE_NOACCESS` nor a guard page. The start must be readable even
+    /// when `len` is zero.
+    pub fn is_readable(ptr: *const u8, len: usize) -> bool {
+        const NO_READ_MASK: DWORD = PAGE_NOACCESS | PAGE_GUARD;
+
+        let start = ptr as usize;
+        let Some(end) = start.checked_add(len) else {
+            return false;
+        };
+        if start == 0 {
+            return false;
+        }
+
+        let mut address = start;
+        loop {
+            let mut info: MEMORY_BASIC_INFORMATION = unsafe { std::mem::zeroed() };
+            let written = unsafe {
+                VirtualQuery(
+                    address as *const _,
+                    &mut info,
+                    std::mem::size_of::<MEMORY_BASIC_INFORMATION>(),
+                )
+            };
+            if written == 0 || info.State != MEM_COMMIT || info.Protect & NO_READ_MASK != 0 {
+                return false;
+            }
+
+            let region_end = (info.BaseAddress as usize).saturating_add(info.RegionSize);
+            if region_end >= end || region_end <= address {
+                return region_end >= end;
+            }
+            address = region_end;
+        }
+    }
+
+    /// [`Self::is_readable`] as an error naming the start of the range
+    fn check_readable(ptr: *const u8, len: usize) -> Result<()> {
+        if Self::is_readable(ptr, len) {
+            Ok(())
+        } else {
+            Err(AppError::MemoryAccessViolation {
+                address: ptr as usize,
+            })
+        }
+    }
+
+    /// Copy `len` bytes starting at `address` out of process memory
+    ///
+    /// The copy runs under the same fault guard as [`Self::try_read`].
+    ///
+    /// # Safety
+    ///
+    /// The range may be unmapped or freed while it is copied, which fails
+    /// with the faulting address, but it must not be memory whose reads
+    /// have side effects, such as a device mapping.
+    pub unsafe fn read_bytes(address: *const u8, len: usize) -> Result<Vec<u8>> {
+        Self::check_readable(address, len)?;
+
+        let mut bytes = vec![0; len];
+        unsafe { guarded::copy(bytes.as_mut_ptr(), address, len) }
+            .map_err(|address| AppError::MemoryAccessViolation { address })?;
+        Ok(bytes)
+    }
+
+    /// Overwrite code at `address`, restoring the page protection afterwards
+    ///
+    /// The instruction cache is flushed so the new bytes are picked up by
+    /// threads that already executed the old ones.
+    ///
+    /// # Safety
+    ///
+    /// No thread may be executing the overwritten instructions, and `bytes`
+    /// must leave them in a consistent state.
+    pub unsafe fn write_bytes_patched(address: *mut u8, bytes: &[u8]) -> Result<()> {
+        let guard = unsafe { ProtectionGuard::new(address, bytes.len(), PAGE_EXECUTE_READWRITE)? };
+        unsafe { ptr::copy_nonoverlapping(bytes.as_ptr(), address, bytes.len()) };
+        drop(guard);
+
+        unsafe { FlushInstructionCache(GetCurrentProcess(), address as LPVOID, bytes.len()) };
+        Ok(())
+    }
+
+    /// Replace a pointer-sized data slot, returning the value it held
+    ///
+    /// Used for function pointer tables (IAT, vtables) that are mapped
+    /// read-only after loading. The slot is swapped atomically so callers
+    /// racing the write see either the old or the new pointer.
+    ///
+    /// # Safety
+    ///
+    /// `slot` must be aligned and point into mapped memory that is valid
+    /// to hold `value`.
+    pub unsafe fn swap_pointer(slot: *mut usize, value: usize) -> Result<usize> {
+        if slot.is_null() || !slot.is_aligned() {
+            return Err(AppError::MemoryAccessViolation {
+                address: slot as usize,
+            });
+        }
+
+        let _guard =
+            unsafe { ProtectionGuard::new(slot as *const u8, size_of::<usize>(), PAGE_READWRITE)? };
+        Ok(unsafe { AtomicUsize::from_ptr(slot) }.swap(value, Ordering::SeqCst))
+    }
+
+    /// Check whether an address lies in committed, executable memory
+    pub fn is_executable(address: *const u8) -> bool {
+        const EXECUTE_MASK: DWORD =
+            PAGE_EXECUTE | PAGE_EXECUTE_READ | PAGE_EXECUTE_READWRITE | PAGE_EXECUTE_WRITECOPY;
+
+        let mut info: MEMORY_BASIC_INFORMATION = unsafe { std::mem::zeroed() };
+        let written = unsafe {
+            VirtualQuery(
+                address as *const _,
+                &mut info,
+                std::mem::size_of::<MEMORY_BASIC_INFORMATION>(),
+            )
+        };
+
+        if written == 0 {
+            return false;
+        }
+
+        info.State == MEM_COMMIT && info.Protect & EXECUTE_MASK != 0
     }
 }
 
@@ -128,6 +511,74 @@ impl StringConverter {
             }),
         }
     }
+
+    /// Convert a PCWSTR read from untrusted memory, such as a game
+    /// structure, to a Rust String
+    ///
+    /// The string must end within `max_chars` UTF-16 units. Memory is read
+    /// a page at a time, each page checked readable and copied through the
+    /// same fault guard as [`MemoryAccess::try_read`], so a stray pointer
+    /// fails here instead of scanning megabytes or crashing.
+    ///
+    /// # Safety
+    ///
+    /// `pcwstr` may be dangling. Up to `max_chars + 1` units are read from
+    /// it, and every page they touch must be free of read side effects.
+    pub unsafe fn pcwstr_to_string_bounded(pcwstr: *const u16, max_chars: usize) -> Result<String> {
+        if pcwstr.is_null() {
+            return Err(AppError::StringConversion {
+                details: "PCWSTR pointer is null".to_string(),
+            });
+        }
+
+        let mut units: Vec<u16> = Vec::new();
+        let mut address = pcwstr as usize;
+        // Room for the terminator after `max_chars` units
+        while units.len() <= max_chars {
+            let page_left = PAGE_SIZE - address % PAGE_SIZE;
+            let count = (page_left / 2).max(1).min(max_chars + 1 - units.len());
+            let len = count * 2;
+            MemoryAccess::check_readable(address as *const u8, len)?;
+
+            let start = units.len();
+            units.resize(start + count, 0);
+            unsafe {
+                guarded::copy(
+                    units[start..].as_mut_ptr().cast(),
+                    address as *const u8,
+                    len,
+                )
+            }
+            .map_err(|address| AppError::MemoryAccessViolation { address })?;
+
+            if let Some(end) = units[start..].iter().position(|&unit| unit == 0) {
+                units.truncate(start + end);
+                return String::from_utf16(&units).map_err(|e| AppError::StringConversion {
+                    details: format!("UTF-16 to UTF-8 conversion failed: {}", e),
+                });
+            }
+            address += len;
+        }
+
+        Err(AppError::StringConversion {
+            details: format!("No terminator within {} characters", max_chars),
+        })
+    }
+
+    /// Encode a string as a NUL-terminated UTF-16 buffer for wide Win32 APIs
+    pub fn to_wide_null(value: &str) -> Vec<u16> {
+        value.encode_utf16().chain(std::iter::once(0)).collect()
+    }
+
+    /// Compare two module or file names the way the Windows loader does
+    ///
+    /// Case-insensitive over all of Unicode rather than just ASCII, so names
+    /// like `Ärger.dll` and `ÄRGER.DLL` refer to the same module.
+    pub fn names_equal(a: &str, b: &str) -> bool {
+        a.chars()
+            .flat_map(char::to_uppercase)
+            .eq(b.chars().flat_map(char::to_uppercase))
+    }
 }
 
 /// Safe pattern matching utilities
@@ -175,18 +626,156 @@ impl PatternMatcher {
 mod tests {
     use super::*;
 
+    #[test]
+    #[cfg(feature = "dll")]
+    fn test_managed_thread_joins_and_reports_its_exit_code() {
+        use std::sync::atomic::AtomicBool;
+
+        static RELEASE: AtomicBool = AtomicBool::new(false);
+
+        unsafe extern "system" fn wait_for_release(_: LPVOID) -> DWORD {
+            while !RELEASE.load(Ordering::Acquire) {
+                std::thread::sleep(Duration::from_millis(1));
+            }
+            7
+        }
+
+        let thread = ThreadManager::create_thread(wait_for_release, ptr::null_mut()).unwrap();
+        assert_eq!(thread.exit_code().unwrap(), None);
+        assert!(!thread.join(Duration::from_millis(10)).unwrap());
+
+        RELEASE.store(true, Ordering::Release);
+        assert!(thread.join(Duration::from_secs(5)).unwrap());
+        assert_eq!(thread.exit_code().unwrap(), Some(7));
+        assert!(thread.join(Duration::ZERO).unwrap());
+    }
+
     #[test]
     fn test_memory_access_null_pointer() {
         let result = unsafe { MemoryAccess::read_volatile_safe::<u32>(ptr::null()) };
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_readability_follows_the_memory_map() {
+        let values = vec![7u64; 1024];
+        let start = values.as_ptr() as *const u8;
+        assert!(MemoryAccess::is_readable(start, 1024 * 8));
+        assert!(!MemoryAccess::is_readable(ptr::null(), 0));
+        assert!(!MemoryAccess::is_readable(0x1000 as *const u8, 8));
+        assert!(!MemoryAccess::is_readable(start, usize::MAX));
+
+        let read = unsafe { MemoryAccess::read_volatile_safe(values.as_ptr().add(3)) };
+        assert_eq!(read.unwrap(), 7);
+        assert!(matches!(
+            unsafe { MemoryAccess::read_bytes(0x1000 as *const u8, 4) },
+            Err(AppError::MemoryAccessViolation { address: 0x1000 })
+        ));
+    }
+
+    #[test]
+    fn test_try_read_copies_unaligned_values() {
+        let bytes: [u8; 9] = [0, 1, 2, 3, 4, 5, 6, 7, 8];
+        let read = unsafe { MemoryAccess::try_read(bytes.as_ptr().add(1) as *const u64) };
+        assert_eq!(read.unwrap(), u64::from_le_bytes([1, 2, 3, 4, 5, 6, 7, 8]));
+        assert!(unsafe { MemoryAccess::try_read::<()>(bytes.as_ptr().cast()) }.is_ok());
+        assert!(matches!(
+            unsafe { MemoryAccess::try_read::<u32>(ptr::null()) },
+            Err(AppError::MemoryAccessViolation { address: 0 })
+        ));
+    }
+
+    #[test]
+    fn test_patched_write_restores_protection() {
+        let mut code = vec![0x90u8; 16];
+        unsafe { MemoryAccess::write_bytes_patched(code.as_mut_ptr().add(4), &[0xCC, 0xC3]) }
+            .unwrap();
+        assert_eq!(&code[3..7], [0x90, 0xCC, 0xC3, 0x90]);
+
+        let guard = unsafe { ProtectionGuard::new(code.as_ptr(), code.len(), PAGE_READWRITE) };
+        assert_eq!(guard.unwrap().old_protect(), PAGE_READWRITE);
+        assert!(unsafe { ProtectionGuard::new(ptr::null(), 1, PAGE_READWRITE) }.is_err());
+    }
+
+    #[test]
+    fn test_follow_chain_reads_through_pointers() {
+        let value: u32 = 0xC0FFEE;
+        let inner: [usize; 2] = [0, &value as *const u32 as usize];
+        let outer: Box<usize> = Box::new(inner.as_ptr() as usize);
+        let base: [usize; 3] = [0, 0, &*outer as *const usize as usize];
+
+        let read: u32 =
+            unsafe { MemoryAccess::follow_chain(base.as_ptr() as usize, &[0x10, 0, 0x8, 0]) }
+                .unwrap();
+        assert_eq!(read, 0xC0FFEE);
+    }
+
+    #[test]
+    fn test_follow_chain_stops_at_null_link() {
+        let base: [usize; 2] = [0, 0];
+        let result =
+            unsafe { MemoryAccess::follow_chain::<usize>(base.as_ptr() as usize, &[0x8, 0]) };
+        assert!(matches!(
+            result,
+            Err(AppError::MemoryAccessViolation { address }) if address == base.as_ptr() as usize + 8
+        ));
+        assert!(unsafe { MemoryAccess::follow_chain::<usize>(0, &[]) }.is_err());
+    }
+
+    #[test]
+    fn test_wait_timeout_stays_finite() {
+        assert_eq!(wait_millis(Duration::from_millis(1500)), 1500);
+        assert_eq!(wait_millis(Duration::MAX), INFINITE - 1);
+    }
+
+    #[test]
+    fn test_owned_handle_rejects_failure_values() {
+        assert!(unsafe { OwnedHandle::from_raw(ptr::null_mut()) }.is_none());
+        assert!(unsafe { OwnedHandle::from_raw(INVALID_HANDLE_VALUE) }.is_none());
+    }
+
     #[test]
     fn test_string_converter_null_pointer() {
         let result = unsafe { StringConverter::pcwstr_to_string(ptr::null()) };
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_bounded_string_needs_a_terminator_in_range() {
+        let wide = StringConverter::to_wide_null("pakchunk0-WindowsNoEditor.pak");
+        let read = unsafe { StringConverter::pcwstr_to_string_bounded(wide.as_ptr(), 64) };
+        assert_eq!(read.unwrap(), "pakchunk0-WindowsNoEditor.pak");
+
+        let exact = unsafe { StringConverter::pcwstr_to_string_bounded(wide.as_ptr(), 29) };
+        assert_eq!(exact.unwrap().len(), 29);
+        let short = unsafe { StringConverter::pcwstr_to_string_bounded(wide.as_ptr(), 28) };
+        assert!(matches!(short, Err(AppError::StringConversion { .. })));
+
+        // A string crossing page boundaries is read in several chunks
+        let long: Vec<u16> = std::iter::repeat_n(u16::from(b'a'), 5000)
+            .chain([0])
+            .collect();
+        let read = unsafe { StringConverter::pcwstr_to_string_bounded(long.as_ptr(), 5000) };
+        assert_eq!(read.unwrap().len(), 5000);
+        assert!(unsafe { StringConverter::pcwstr_to_string_bounded(ptr::null(), 8) }.is_err());
+    }
+
+    #[test]
+    fn test_string_converter_to_wide_null() {
+        assert_eq!(StringConverter::to_wide_null("ab"), vec![0x61, 0x62, 0]);
+        assert_eq!(StringConverter::to_wide_null(""), vec![0]);
+    }
+
+    #[test]
+    fn test_string_converter_names_equal_unicode() {
+        assert!(StringConverter::names_equal(
+            "Client-Win64-Shipping.exe",
+            "CLIENT-WIN64-SHIPPING.EXE"
+        ));
+        assert!(StringConverter::names_equal("Ärger.dll", "äRGER.DLL"));
+        assert!(!StringConverter::names_equal("Ärger.dll", "Arger.dll"));
+    }
+
     #[test]
     fn test_pattern_matcher_bounds_check() {
         let buffer = vec![0x55, 0x53, 0x56];
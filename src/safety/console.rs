@@ -0,0 +1,116 @@
+//! The console the DLL logs to
+//!
+//! Only the DLL flow opens one; a library user keeps whatever console the
+//! host process has.
+
+use std::ptr;
+
+use winapi::shared::winerror::ERROR_ACCESS_DENIED;
+use winapi::um::consoleapi::AllocConsole;
+use winapi::um::fileapi::{CreateFileW, OPEN_EXISTING};
+use winapi::um::handleapi::INVALID_HANDLE_VALUE;
+use winapi::um::processenv::{GetStdHandle, SetStdHandle};
+use winapi::um::winbase::{STD_ERROR_HANDLE, STD_OUTPUT_HANDLE};
+use winapi::um::wincon::{
+    ATTACH_PARENT_PROCESS, AttachConsole, GetConsoleWindow, SetConsoleOutputCP,
+};
+use winapi::um::winnls::CP_UTF8;
+use winapi::um::winnt::{FILE_SHARE_WRITE, GENERIC_READ, GENERIC_WRITE};
+use winapi::um::winuser::{IsWindowVisible, SW_HIDE, SW_SHOW, ShowWindow};
+
+use super::StringConverter;
+use crate::error::{AppError, Result};
+
+/// Where the console the DLL logs to came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ConsoleSource {
+    /// The terminal the game was launched from
+    Parent,
+    /// A new console window
+    Allocated,
+    /// The console the process already had
+    Existing,
+}
+
+impl ConsoleSource {
+    pub fn describe(self) -> &'static str {
+        match self {
+            Self::Parent => "attached to the parent console",
+            Self::Allocated => "new console window",
+            Self::Existing => "existing console",
+        }
+    }
+}
+
+/// Safe wrapper for console initialization
+pub(crate) struct ConsoleManager;
+
+impl ConsoleManager {
+    /// Initialize the console with proper error handling
+    ///
+    /// Attaches to the console of the launching terminal if there is one,
+    /// otherwise opens a new window. A process that already has a console
+    /// keeps it.
+    pub fn init() -> Result<ConsoleSource> {
+        let source = if unsafe { AttachConsole(ATTACH_PARENT_PROCESS) } != 0 {
+            Self::reopen_std_handles();
+            ConsoleSource::Parent
+        } else if unsafe { AllocConsole() } != 0 {
+            ConsoleSource::Allocated
+        } else {
+            let error = std::io::Error::last_os_error();
+            // Both calls fail with access denied when a console is attached
+            if error.raw_os_error() != Some(ERROR_ACCESS_DENIED as i32) {
+                // Don't use logger here since it's not initialized yet
+                return Err(AppError::ConsoleInitFailed { source: error });
+            }
+            ConsoleSource::Existing
+        };
+
+        // The logger writes UTF-16 itself; this covers anything else printed
+        unsafe { SetConsoleOutputCP(CP_UTF8) };
+
+        // Console is ready, but don't log yet since logger isn't initialized
+        Ok(source)
+    }
+
+    /// Show the console window if hidden and hide it otherwise
+    ///
+    /// Returns whether it is now visible, or `None` when the process has no
+    /// console window.
+    pub fn toggle_visibility() -> Option<bool> {
+        let window = unsafe { GetConsoleWindow() };
+        if window.is_null() {
+            return None;
+        }
+        let visible = unsafe { IsWindowVisible(window) } == 0;
+        unsafe { ShowWindow(window, if visible { SW_SHOW } else { SW_HIDE }) };
+        Some(visible)
+    }
+
+    /// Point standard output and error at an attached console when the game,
+    /// a GUI process, started without them
+    fn reopen_std_handles() {
+        for std_handle in [STD_OUTPUT_HANDLE, STD_ERROR_HANDLE] {
+            let current = unsafe { GetStdHandle(std_handle) };
+            if !current.is_null() && current != INVALID_HANDLE_VALUE {
+                continue;
+            }
+            let name = StringConverter::to_wide_null("CONOUT$");
+            let handle = unsafe {
+                CreateFileW(
+                    name.as_ptr(),
+                    GENERIC_READ | GENERIC_WRITE,
+                    FILE_SHARE_WRITE,
+                    ptr::null_mut(),
+                    OPEN_EXISTING,
+                    0,
+                    ptr::null_mut(),
+                )
+            };
+            if handle != INVALID_HANDLE_VALUE {
+                unsafe { SetStdHandle(std_handle, handle) };
+            }
+        }
+    }
+}
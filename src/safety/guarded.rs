@@ -0,0 +1,94 @@
+//! Memory reads that survive bad addresses
+//!
+//! Pointers read out of game structures can be stale or garbage, and the
+//! page they point to can be freed between a readability check and the
+//! read. The copy loop is written in assembly so the one instruction that
+//! touches the source is known: a vectored exception handler turns an
+//! access violation on that instruction into an early return, and leaves
+//! every other exception, guard page hits included, to the game.
+
+use std::arch::global_asm;
+use std::sync::OnceLock;
+
+use winapi::um::errhandlingapi::{AddVectoredExceptionHandler, RemoveVectoredExceptionHandler};
+use winapi::um::minwinbase::EXCEPTION_ACCESS_VIOLATION;
+use winapi::um::winnt::{EXCEPTION_POINTERS, LONG};
+use winapi::vc::excpt::{EXCEPTION_CONTINUE_EXECUTION, EXCEPTION_CONTINUE_SEARCH};
+
+// Copies `r8` bytes from `rdx` to `rcx` and returns how many were left
+// uncopied, 0 once done. The load never touches the stack, so the handler
+// can resume at the final `ret` with the remaining count in `rax`.
+global_asm!(
+    ".globl wuwa_sig_guarded_copy",
+    ".globl wuwa_sig_guarded_copy_load",
+    ".globl wuwa_sig_guarded_copy_return",
+    "wuwa_sig_guarded_copy:",
+    "    xor eax, eax",
+    "    test r8, r8",
+    "    jz wuwa_sig_guarded_copy_return",
+    "wuwa_sig_guarded_copy_load:",
+    "    mov r9b, byte ptr [rdx]",
+    "    mov byte ptr [rcx], r9b",
+    "    inc rdx",
+    "    inc rcx",
+    "    dec r8",
+    "    jnz wuwa_sig_guarded_copy_load",
+    "wuwa_sig_guarded_copy_return:",
+    "    ret",
+);
+
+unsafe extern "win64" {
+    fn wuwa_sig_guarded_copy(dst: *mut u8, src: *const u8, len: usize) -> usize;
+}
+
+unsafe extern "C" {
+    static wuwa_sig_guarded_copy_load: u8;
+    static wuwa_sig_guarded_copy_return: u8;
+}
+
+/// Handle of the registered handler; zero if Windows refused it
+static HANDLER: OnceLock<usize> = OnceLock::new();
+
+/// Copy `len` bytes from `src` to `dst`, returning the address that faulted
+/// if the source could not be read
+///
+/// Without the exception handler a fault still crashes the process, so
+/// callers check the range is readable first.
+///
+/// # Safety
+///
+/// `dst` must be valid for `len` bytes of writes. On a fault, the bytes
+/// before the faulting address have already been copied.
+pub(super) unsafe fn copy(dst: *mut u8, src: *const u8, len: usize) -> Result<(), usize> {
+    HANDLER.get_or_init(|| unsafe {
+        AddVectoredExceptionHandler(1, Some(resume_faulted_copy)) as usize
+    });
+    // A fault leaves the load at the first byte not copied
+    match unsafe { wuwa_sig_guarded_copy(dst, src, len) } {
+        0 => Ok(()),
+        remaining => Err(src as usize + (len - remaining)),
+    }
+}
+
+/// Unregister the handler before the DLL unloads
+pub(super) fn remove_handler() {
+    if let Some(&handle) = HANDLER.get().filter(|&&handle| handle != 0) {
+        unsafe { RemoveVectoredExceptionHandler(handle as _) };
+    }
+}
+
+/// Resume a copy that faulted on its load at its return
+unsafe extern "system" fn resume_faulted_copy(info: *mut EXCEPTION_POINTERS) -> LONG {
+    let (record, context) = unsafe { (&*(*info).ExceptionRecord, &mut *(*info).ContextRecord) };
+    let load = &raw const wuwa_sig_guarded_copy_load as u64;
+    // A guard page hit belongs to whoever armed the page, such as a guard
+    // page hook, so only plain access violations are taken
+    if record.ExceptionCode != EXCEPTION_ACCESS_VIOLATION || context.Rip != load {
+        return EXCEPTION_CONTINUE_SEARCH;
+    }
+
+    // The loop only loads while bytes remain, so the count is never 0
+    context.Rax = context.R8;
+    context.Rip = &raw const wuwa_sig_guarded_copy_return as u64;
+    EXCEPTION_CONTINUE_EXECUTION
+}
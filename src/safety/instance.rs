@@ -0,0 +1,68 @@
+//! Marker keeping a second copy of the DLL from initializing
+
+use std::ptr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use winapi::shared::winerror::ERROR_ALREADY_EXISTS;
+use winapi::um::errhandlingapi::GetLastError;
+use winapi::um::processthreadsapi::GetCurrentProcessId;
+use winapi::um::synchapi::CreateMutexW;
+use winapi::um::winnt::HANDLE;
+
+use super::{OwnedHandle, StringConverter};
+use crate::constants::constants::INSTANCE_MUTEX_PREFIX;
+use crate::error::{AppError, Result};
+
+/// Per-process marker preventing a second copy of the DLL from initializing
+///
+/// Copies loaded from different paths get separate statics, so the marker
+/// is a named mutex every copy can see. Its handle stays open until the
+/// DLL unloads itself, so a later injection can initialize again.
+pub(crate) struct InstanceGuard;
+
+/// Handle of the marker claimed by this copy; zero when none
+static INSTANCE_MARKER: AtomicUsize = AtomicUsize::new(0);
+
+impl InstanceGuard {
+    /// Claim the marker for this process
+    ///
+    /// Fails with `AlreadyLoaded` if another copy claimed it first. Safe to
+    /// call from `DllMain`.
+    pub fn acquire() -> Result<()> {
+        let process_id = unsafe { GetCurrentProcessId() };
+        let name = StringConverter::to_wide_null(&Self::name(process_id));
+
+        let handle =
+            unsafe { OwnedHandle::from_raw(CreateMutexW(ptr::null_mut(), 0, name.as_ptr())) }
+                .ok_or_else(|| AppError::last_error("CreateMutexW"))?;
+
+        if unsafe { GetLastError() } == ERROR_ALREADY_EXISTS {
+            return Err(AppError::AlreadyLoaded { process_id });
+        }
+        // Kept open until released, see above
+        INSTANCE_MARKER.store(handle.into_raw() as usize, Ordering::Release);
+        Ok(())
+    }
+
+    /// Give up the marker claimed by [`InstanceGuard::acquire`], if any
+    pub fn release() {
+        let handle = INSTANCE_MARKER.swap(0, Ordering::AcqRel);
+        drop(unsafe { OwnedHandle::from_raw(handle as HANDLE) });
+    }
+
+    /// Name of the marker for `process_id`
+    fn name(process_id: u32) -> String {
+        format!("{}{}", INSTANCE_MUTEX_PREFIX, process_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_instance_guard_name_is_per_process() {
+        assert_eq!(InstanceGuard::name(1234), "Local\\WuwaSigInstance-1234");
+        assert_ne!(InstanceGuard::name(1), InstanceGuard::name(2));
+    }
+}
@@ -0,0 +1,185 @@
+//! Declarative layouts of structures read from game memory
+//!
+//! Rather than adding raw offsets to pointers, a structure is described
+//! once by a [`Layout`] giving its size and by [`Field`]s giving each
+//! field's offset and type. A [`StructReader`] reads fields by descriptor:
+//! every field is checked to lie within the declared size and read through
+//! [`MemoryAccess::try_read`], and pointer fields lead on to readers of the
+//! structures they point to.
+
+use std::marker::PhantomData;
+
+use super::MemoryAccess;
+use crate::error::{AppError, Result};
+
+/// Types for which any bit pattern read from memory is a valid value
+///
+/// # Safety
+///
+/// Implementors must have no invalid bit patterns, so no `bool`, `char`,
+/// enums or references, and no padding.
+pub unsafe trait Plain: Copy {}
+
+macro_rules! impl_plain {
+    ($($ty:ty),*) => {
+        $(unsafe impl Plain for $ty {})*
+    };
+}
+
+impl_plain!(u8, u16, u32, u64, usize, i8, i16, i32, i64, isize, f32, f64);
+
+unsafe impl<T: Plain, const N: usize> Plain for [T; N] {}
+
+/// Name and size of a structure
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Layout {
+    pub name: &'static str,
+    /// Bytes of the structure that fields may occupy
+    pub size: usize,
+}
+
+impl Layout {
+    pub const fn new(name: &'static str, size: usize) -> Self {
+        Self { name, size }
+    }
+}
+
+/// A field of type `T` at `offset` bytes into a structure
+#[derive(Debug)]
+pub struct Field<T> {
+    pub name: &'static str,
+    pub offset: usize,
+    _type: PhantomData<fn() -> T>,
+}
+
+impl<T> Field<T> {
+    pub const fn new(name: &'static str, offset: usize) -> Self {
+        Self {
+            name,
+            offset,
+            _type: PhantomData,
+        }
+    }
+}
+
+impl<T> Clone for Field<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for Field<T> {}
+
+/// Fields of one structure instance, read by descriptor
+#[derive(Debug, Clone, Copy)]
+pub struct StructReader {
+    address: usize,
+    layout: &'static Layout,
+}
+
+impl StructReader {
+    /// Read the structure described by `layout` at `address`
+    ///
+    /// # Safety
+    ///
+    /// Nothing is read here. `address` must stay where an instance of
+    /// `layout` is expected for as long as the reader and the readers it
+    /// [`follow`](Self::follow)s are used; their reads are checked, so a
+    /// freed instance only fails them.
+    pub unsafe fn new(address: usize, layout: &'static Layout) -> Result<Self> {
+        if address == 0 {
+            return Err(AppError::MemoryAccessViolation { address });
+        }
+        Ok(Self { address, layout })
+    }
+
+    pub fn address(&self) -> usize {
+        self.address
+    }
+
+    pub fn layout(&self) -> &'static Layout {
+        self.layout
+    }
+
+    /// Address of `field`, checked to lie within the layout
+    pub fn field_address<T>(&self, field: Field<T>) -> Result<usize> {
+        let fits = field
+            .offset
+            .checked_add(size_of::<T>())
+            .is_some_and(|end| end <= self.layout.size);
+        if !fits {
+            return Err(AppError::FieldOutOfBounds {
+                layout: self.layout.name.to_string(),
+                field: field.name.to_string(),
+                offset: field.offset,
+                size: self.layout.size,
+            });
+        }
+        Ok(self.address.wrapping_add(field.offset))
+    }
+
+    /// Read `field`
+    pub fn read<T: Plain>(&self, field: Field<T>) -> Result<T> {
+        let address = self.field_address(field)?;
+        unsafe { MemoryAccess::try_read(address as *const T) }
+    }
+
+    /// Follow the pointer in `field` to a structure described by `layout`
+    ///
+    /// A null pointer fails with the address of the field.
+    pub fn follow(&self, field: Field<usize>, layout: &'static Layout) -> Result<Self> {
+        let pointer = self.read(field)?;
+        if pointer == 0 {
+            return Err(AppError::MemoryAccessViolation {
+                address: self.address.wrapping_add(field.offset),
+            });
+        }
+        Ok(Self {
+            address: pointer,
+            layout,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OUTER: Layout = Layout::new("outer", 16);
+    const OUTER_COUNT: Field<u32> = Field::new("count", 4);
+    const OUTER_INNER: Field<usize> = Field::new("inner", 8);
+    const OUTER_PAST_END: Field<u64> = Field::new("past end", 12);
+    const INNER: Layout = Layout::new("inner", 8);
+    const INNER_ID: Field<[u16; 2]> = Field::new("id", 2);
+
+    #[test]
+    fn test_fields_read_through_pointers() {
+        let inner: [u16; 4] = [0, 0x11, 0x22, 0];
+        let outer: [usize; 2] = [7 << 32, inner.as_ptr() as usize];
+        let reader = unsafe { StructReader::new(outer.as_ptr() as usize, &OUTER) }.unwrap();
+
+        assert_eq!(reader.read(OUTER_COUNT).unwrap(), 7);
+        let inner = reader.follow(OUTER_INNER, &INNER).unwrap();
+        assert_eq!(inner.layout().name, "inner");
+        assert_eq!(inner.read(INNER_ID).unwrap(), [0x11, 0x22]);
+    }
+
+    #[test]
+    fn test_fields_must_fit_the_layout() {
+        let outer: [usize; 2] = [0, 0];
+        let reader = unsafe { StructReader::new(outer.as_ptr() as usize, &OUTER) }.unwrap();
+        assert!(matches!(
+            reader.read(OUTER_PAST_END),
+            Err(AppError::FieldOutOfBounds {
+                offset: 12,
+                size: 16,
+                ..
+            })
+        ));
+        assert!(matches!(
+            reader.follow(OUTER_INNER, &INNER),
+            Err(AppError::MemoryAccessViolation { .. })
+        ));
+        assert!(unsafe { StructReader::new(0, &OUTER) }.is_err());
+    }
+}
@@ -0,0 +1,143 @@
+//! Copies of memory taken at one moment, and the bytes that changed since
+//!
+//! A [`MemorySnapshot`] holds the bytes of a range as read at capture time.
+//! Comparing two captures of the same range with [`MemorySnapshot::diff`]
+//! shows what was written in between, e.g. what a hook or the anti-cheat
+//! did to a function's first instructions.
+
+use std::fmt;
+
+use super::MemoryAccess;
+use crate::error::Result;
+
+/// Bytes of a memory range as read at one moment
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MemorySnapshot {
+    address: usize,
+    bytes: Vec<u8>,
+}
+
+/// A run of consecutive bytes that differ between two snapshots
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChangedRange {
+    pub address: usize,
+    pub before: Vec<u8>,
+    pub after: Vec<u8>,
+}
+
+impl fmt::Display for ChangedRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:#x}: {:02X?} -> {:02X?}",
+            self.address, self.before, self.after
+        )
+    }
+}
+
+impl MemorySnapshot {
+    /// Copy `len` bytes starting at `address`
+    ///
+    /// # Safety
+    ///
+    /// `len` bytes at `address` are copied through
+    /// [`MemoryAccess::read_bytes`]; an unreadable range fails the capture.
+    /// The range must not cover memory whose reads change its state.
+    pub unsafe fn capture(address: *const u8, len: usize) -> Result<Self> {
+        let bytes = unsafe { MemoryAccess::read_bytes(address, len)? };
+        Ok(Self {
+            address: address as usize,
+            bytes,
+        })
+    }
+
+    /// Capture the same range again
+    ///
+    /// # Safety
+    ///
+    /// Same as [`capture`](Self::capture).
+    pub unsafe fn recapture(&self) -> Result<Self> {
+        unsafe { Self::capture(self.address as *const u8, self.bytes.len()) }
+    }
+
+    pub fn address(&self) -> usize {
+        self.address
+    }
+
+    pub fn bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    /// Ranges whose bytes differ in `other`, lowest address first
+    ///
+    /// Only addresses covered by both snapshots are compared.
+    pub fn diff(&self, other: &MemorySnapshot) -> Vec<ChangedRange> {
+        let start = self.address.max(other.address);
+        let end = (self.address + self.bytes.len()).min(other.address + other.bytes.len());
+        if start >= end {
+            return Vec::new();
+        }
+        let before = &self.bytes[start - self.address..end - self.address];
+        let after = &other.bytes[start - other.address..end - other.address];
+
+        let mut changes = Vec::new();
+        let mut i = 0;
+        while i < before.len() {
+            if before[i] == after[i] {
+                i += 1;
+                continue;
+            }
+            let run = i;
+            while i < before.len() && before[i] != after[i] {
+                i += 1;
+            }
+            changes.push(ChangedRange {
+                address: start + run,
+                before: before[run..i].to_vec(),
+                after: after[run..i].to_vec(),
+            });
+        }
+        changes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diff_reports_changed_runs() {
+        let mut code = [0x48u8, 0x89, 0x5C, 0x24, 0x08, 0x57, 0xC3, 0xCC];
+        let address = code.as_ptr();
+        let before = unsafe { MemorySnapshot::capture(address, code.len()) }.unwrap();
+        assert!(before.diff(&before).is_empty());
+
+        code[..2].copy_from_slice(&[0xE9, 0x00]);
+        code[5] = 0x90;
+        let after = unsafe { before.recapture() }.unwrap();
+        assert_eq!(after.bytes(), code);
+        let changes = before.diff(&after);
+
+        assert_eq!(changes.len(), 2);
+        assert_eq!(changes[0].address, address as usize);
+        assert_eq!(changes[0].before, [0x48, 0x89]);
+        assert_eq!(changes[0].after, [0xE9, 0x00]);
+        assert_eq!(changes[1].address, address as usize + 5);
+        assert_eq!(changes[1].after, [0x90]);
+    }
+
+    #[test]
+    fn test_diff_compares_only_the_overlap() {
+        let code = [1u8, 2, 3, 4];
+        let whole = unsafe { MemorySnapshot::capture(code.as_ptr(), 4) }.unwrap();
+        let tail = MemorySnapshot {
+            address: code.as_ptr() as usize + 2,
+            bytes: vec![3, 9, 9],
+        };
+        let changes = whole.diff(&tail);
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].address, code.as_ptr() as usize + 3);
+        assert_eq!(changes[0].before, [4]);
+        assert_eq!(changes[0].after, [9]);
+    }
+}
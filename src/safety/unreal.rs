@@ -0,0 +1,223 @@
+//! Readers for Unreal Engine strings in game memory
+//!
+//! An `FString` is a `TArray<TCHAR>`: a data pointer followed by the number
+//! of UTF-16 units in use, terminator included, and the number allocated.
+//! Reading it by that count instead of scanning for a NUL stops a corrupt
+//! string from running on into unrelated memory.
+//!
+//! An `FName` is an index into the engine's name pool, laid out as in
+//! UE 4.23 and later: entries in 64 KiB blocks, addressed by block number
+//! and 2-byte-aligned offset. The pool itself has to be located first, e.g.
+//! by a signature scan, before [`NamePool::at`] can be used.
+
+use super::MemoryAccess;
+use super::layout::Plain;
+use crate::error::{AppError, Result};
+
+/// Longest `FString` read, in UTF-16 units
+pub const MAX_FSTRING_LEN: usize = 0x8000;
+
+/// Bits of an `FName` index holding the offset within its block
+const NAME_BLOCK_OFFSET_BITS: u32 = 16;
+
+/// Alignment of name entries within a block
+const NAME_ENTRY_STRIDE: usize = 2;
+
+/// Block pointers the pool can hold
+const NAME_MAX_BLOCKS: usize = 8192;
+
+/// Offset of the number of the last used block, after the pool's lock
+const NAME_CURRENT_BLOCK_OFFSET: usize = 8;
+
+/// Offset of the block pointer array
+const NAME_BLOCKS_OFFSET: usize = 16;
+
+/// Bits of an entry header below the length
+const NAME_LEN_SHIFT: u16 = 6;
+
+/// `FString` header as it sits in memory
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(C)]
+pub struct FString {
+    pub data: usize,
+    /// UTF-16 units in use, terminator included
+    pub num: i32,
+    /// UTF-16 units allocated
+    pub max: i32,
+}
+
+// Any bits make a header; `text` validates them
+unsafe impl Plain for FString {}
+
+impl FString {
+    /// Read the characters the header points to
+    ///
+    /// # Safety
+    ///
+    /// `data` is read for `num` UTF-16 units once the header passes the
+    /// length checks. A dangling pointer fails the read, but the units must
+    /// not lie in memory where reading has an effect, such as a guard page.
+    pub unsafe fn text(&self) -> Result<String> {
+        let len = usize::try_from(self.num).unwrap_or(usize::MAX);
+        if self.num > self.max || len > MAX_FSTRING_LEN {
+            return Err(AppError::StringConversion {
+                details: format!("FString claims {} of {} characters", self.num, self.max),
+            });
+        }
+        if len == 0 {
+            return Ok(String::new());
+        }
+
+        let bytes = unsafe { MemoryAccess::read_bytes(self.data as *const u8, len * 2)? };
+        let units: Vec<u16> = bytes
+            .chunks_exact(2)
+            .map(|unit| u16::from_le_bytes([unit[0], unit[1]]))
+            .collect();
+        let end = units
+            .iter()
+            .rposition(|&unit| unit != 0)
+            .map_or(0, |last| last + 1);
+        String::from_utf16(&units[..end]).map_err(|e| AppError::StringConversion {
+            details: format!("UTF-16 to UTF-8 conversion failed: {}", e),
+        })
+    }
+}
+
+/// Read the `FString` at `address`
+///
+/// # Safety
+///
+/// `address` is read as an `FString` header, which may be dangling but
+/// must not be memory with read side effects; the characters it points to
+/// are then read under the rules of [`FString::text`].
+pub unsafe fn read_fstring(address: usize) -> Result<String> {
+    let header: FString = unsafe { MemoryAccess::try_read(address as *const _)? };
+    unsafe { header.text() }
+}
+
+/// The engine's global name pool
+#[derive(Debug, Clone, Copy)]
+pub struct NamePool {
+    address: usize,
+}
+
+impl NamePool {
+    /// Use the name pool at `address`
+    ///
+    /// # Safety
+    ///
+    /// `address` must be the engine's `FNamePool`; reads through it are
+    /// still checked.
+    pub unsafe fn at(address: usize) -> Self {
+        Self { address }
+    }
+
+    /// Text of the name entry `index`, an `FName`'s comparison index
+    pub fn read_fname(&self, index: u32) -> Result<String> {
+        let block = (index >> NAME_BLOCK_OFFSET_BITS) as usize;
+        let offset = (index & ((1 << NAME_BLOCK_OFFSET_BITS) - 1)) as usize * NAME_ENTRY_STRIDE;
+
+        let current_block: u32 = unsafe {
+            MemoryAccess::try_read((self.address + NAME_CURRENT_BLOCK_OFFSET) as *const u32)?
+        };
+        if block > current_block as usize || block >= NAME_MAX_BLOCKS {
+            return Err(AppError::StringConversion {
+                details: format!(
+                    "FName index {:#x} lies in block {}, past the last one ({})",
+                    index, block, current_block
+                ),
+            });
+        }
+
+        let slot = self.address + NAME_BLOCKS_OFFSET + block * size_of::<usize>();
+        let block_address: usize = unsafe { MemoryAccess::try_read(slot as *const usize)? };
+        if block_address == 0 {
+            return Err(AppError::MemoryAccessViolation { address: slot });
+        }
+
+        let entry = block_address + offset;
+        let header: u16 = unsafe { MemoryAccess::try_read(entry as *const u16)? };
+        let len = usize::from(header >> NAME_LEN_SHIFT);
+        let wide = header & 1 != 0;
+
+        let chars = entry + size_of::<u16>();
+        if wide {
+            let bytes = unsafe { MemoryAccess::read_bytes(chars as *const u8, len * 2)? };
+            let units: Vec<u16> = bytes
+                .chunks_exact(2)
+                .map(|unit| u16::from_le_bytes([unit[0], unit[1]]))
+                .collect();
+            String::from_utf16(&units).map_err(|e| AppError::StringConversion {
+                details: format!("UTF-16 to UTF-8 conversion failed: {}", e),
+            })
+        } else {
+            // Narrow entries are Latin-1
+            let bytes = unsafe { MemoryAccess::read_bytes(chars as *const u8, len)? };
+            Ok(bytes.into_iter().map(char::from).collect())
+        }
+    }
+
+    /// Text of the `FName` at `address`, with its number as a `_N` suffix
+    ///
+    /// # Safety
+    ///
+    /// `address` is read as the index and number of an `FName`, under the
+    /// same rules as [`read_fstring`]; an index outside the pool only fails
+    /// the lookup.
+    pub unsafe fn read_name(&self, address: usize) -> Result<String> {
+        let [index, number]: [u32; 2] = unsafe { MemoryAccess::try_read(address as *const _)? };
+        let name = self.read_fname(index)?;
+        Ok(match number {
+            0 => name,
+            number => format!("{}_{}", name, number - 1),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fstring_is_read_by_its_length() {
+        // No terminator within the data: the count alone ends the string
+        let data: Vec<u16> = "Paks/pakchunk0.pak!".encode_utf16().collect();
+        let read = |num| {
+            let header = FString {
+                data: data.as_ptr() as usize,
+                num,
+                max: 32,
+            };
+            unsafe { read_fstring(&header as *const FString as usize) }
+        };
+
+        assert_eq!(read(18).unwrap(), "Paks/pakchunk0.pak");
+        assert_eq!(read(0).unwrap(), "");
+        assert!(read(40).is_err());
+        assert!(read(-1).is_err());
+    }
+
+    #[test]
+    fn test_names_resolve_through_the_pool() {
+        let mut block = [0u8; 64];
+        // Narrow "None" at offset 0, wide "Pak" at offset 10 (index 5)
+        block[..2].copy_from_slice(&(4u16 << NAME_LEN_SHIFT).to_le_bytes());
+        block[2..6].copy_from_slice(b"None");
+        block[10..12].copy_from_slice(&((3u16 << NAME_LEN_SHIFT) | 1).to_le_bytes());
+        for (i, unit) in "Pak".encode_utf16().enumerate() {
+            block[12 + i * 2..14 + i * 2].copy_from_slice(&unit.to_le_bytes());
+        }
+
+        let mut memory = vec![0usize; 2 + NAME_MAX_BLOCKS];
+        memory[2] = block.as_ptr() as usize;
+        let pool = unsafe { NamePool::at(memory.as_ptr() as usize) };
+
+        assert_eq!(pool.read_fname(0).unwrap(), "None");
+        assert_eq!(pool.read_fname(5).unwrap(), "Pak");
+        assert!(pool.read_fname(1 << NAME_BLOCK_OFFSET_BITS).is_err());
+
+        let fname: [u32; 2] = [5, 3];
+        let address = fname.as_ptr() as usize;
+        assert_eq!(unsafe { pool.read_name(address) }.unwrap(), "Pak_2");
+    }
+}
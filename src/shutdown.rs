@@ -0,0 +1,119 @@
+//! Shutdown requests and the event the maintenance loop waits on
+//!
+//! Once the bypass is running, the hook thread sleeps on a manual-reset
+//! event between watchdog checks instead of looping forever. The unload
+//! hotkey, the IPC pipe or the DLL detach notification set the event; the
+//! thread then removes its hooks and, if an unload was asked for, frees the
+//! library and exits with `FreeLibraryAndExitThread`.
+
+use std::ptr;
+use std::sync::OnceLock;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::Duration;
+
+use winapi::um::synchapi::{CreateEventW, SetEvent, WaitForSingleObject};
+
+use crate::safety::{OwnedHandle, wait_millis};
+
+/// A one-way switch from running to shutting down
+#[derive(Debug)]
+pub struct ShutdownSignal {
+    /// Manual-reset event, set once shutdown is requested; `None` if it
+    /// could not be created, in which case waits poll `requested`
+    event: Option<OwnedHandle>,
+    requested: AtomicBool,
+    unload: AtomicBool,
+}
+
+impl ShutdownSignal {
+    pub fn new() -> Self {
+        let event =
+            unsafe { OwnedHandle::from_raw(CreateEventW(ptr::null_mut(), 1, 0, ptr::null())) };
+        Self {
+            event,
+            requested: AtomicBool::new(false),
+            unload: AtomicBool::new(false),
+        }
+    }
+
+    /// Ask the maintenance loop to stop, and to unload the DLL if `unload`
+    ///
+    /// Later requests can add an unload but never take one back.
+    pub fn request(&self, unload: bool) {
+        if unload {
+            self.unload.store(true, Ordering::Release);
+        }
+        self.requested.store(true, Ordering::Release);
+        if let Some(event) = &self.event {
+            unsafe { SetEvent(event.as_raw()) };
+        }
+    }
+
+    pub fn is_requested(&self) -> bool {
+        self.requested.load(Ordering::Acquire)
+    }
+
+    /// Whether the DLL should be freed once the hooks are removed
+    pub fn unload_requested(&self) -> bool {
+        self.unload.load(Ordering::Acquire)
+    }
+
+    /// Sleep up to `timeout`, returning early with `true` on a request
+    pub fn wait(&self, timeout: Duration) -> bool {
+        if self.is_requested() {
+            return true;
+        }
+        match &self.event {
+            // The flag is set before the event, so it decides either way
+            Some(event) => {
+                unsafe { WaitForSingleObject(event.as_raw(), wait_millis(timeout)) };
+            }
+            None => thread::sleep(timeout),
+        }
+        self.is_requested()
+    }
+}
+
+impl Default for ShutdownSignal {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+static SHUTDOWN: OnceLock<ShutdownSignal> = OnceLock::new();
+
+/// The process-wide shutdown signal
+pub fn signal() -> &'static ShutdownSignal {
+    SHUTDOWN.get_or_init(ShutdownSignal::new)
+}
+
+/// Request a shutdown of the bypass, see [`ShutdownSignal::request`]
+pub fn request(unload: bool) {
+    signal().request(unload);
+}
+
+/// Check if a shutdown of the bypass was requested
+pub fn is_requested() -> bool {
+    signal().is_requested()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_request_wakes_waiters() {
+        let signal = ShutdownSignal::new();
+        assert!(!signal.wait(Duration::from_millis(1)));
+
+        signal.request(false);
+        assert!(signal.is_requested());
+        assert!(signal.wait(Duration::from_secs(60)));
+        assert!(!signal.unload_requested());
+
+        signal.request(true);
+        signal.request(false);
+        assert!(signal.unload_requested());
+    }
+}
@@ -0,0 +1,229 @@
+//! Bypass of the `.sig` companion file check
+//!
+//! Besides the per-pak verification hooked by the main routine, the client
+//! validates each pak's `.sig` file in a separate routine, so modified paks
+//! can still be rejected there. This hooks that routine through the global
+//! [`HookManager`](crate::hooks::HookManager) under [`SIG_CHECK_HOOK_NAME`],
+//! with its own signature and spoofed result in [`Config`].
+//!
+//! The routine has no stable export, so it is found through the signature
+//! check pattern, or the named signature of its `[hooks.sig_file_check]`
+//! table; the hook is off while neither is configured.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, OnceLock};
+
+use ilhook::x64::Registers;
+use winapi::shared::minwindef::HMODULE;
+use winapi::um::psapi::MODULEINFO;
+
+use crate::config::Config;
+use crate::constants::constants::{BYPASS_FAILURE, SIG_CHECK_HOOK_NAME};
+use crate::error::{AppError, Result};
+use crate::hooks::{self, ArgReader, HookAction, HookSpec, HookStats, SpoofSetting};
+use crate::logger::{LogOnce, Logger};
+use crate::memory::{PatternScanner, describe_address};
+use crate::signatures::Signature;
+use crate::{bypass, memory};
+
+/// Signature of the original `.sig` check; the four register arguments are
+/// forwarded unchanged
+type SigCheckFn = unsafe extern "win64" fn(usize, usize, usize, usize) -> usize;
+
+/// Statistics of the installed signature check hook, set once it is applied
+static SIG_CHECK_STATS: OnceLock<Arc<HookStats>> = OnceLock::new();
+
+/// Spoofed return value of the installed signature check hook
+static SIG_CHECK_SPOOF: OnceLock<Arc<SpoofSetting>> = OnceLock::new();
+
+/// Whether the signature check hook only observes, set before it is applied
+static SIG_CHECK_DRY_RUN: AtomicBool = AtomicBool::new(false);
+
+/// Warning for a missing original check, logged on the first call only
+static MISSING_ORIGINAL: LogOnce = LogOnce::new();
+
+/// Find the signature check routine in the game module, through the
+/// signature `spec` names or else `sig_pattern`
+///
+/// A named signature must apply to the game's file version and match in its
+/// section; the routine start it yields must lie inside the module.
+pub fn locate(config: &Config, spec: &HookSpec, module_info: &MODULEINFO) -> Result<usize> {
+    let signature = match &spec.signature {
+        Some(name) => {
+            let signature = config.signature_db().get(name).cloned().ok_or_else(|| {
+                AppError::InvalidConfig {
+                    field: format!("hooks.{}.signature", spec.name),
+                    reason: format!("no signature named '{}'", name),
+                }
+            })?;
+            check_version(&signature, spec, module_info)?;
+            signature
+        }
+        None if config.sig_pattern.is_empty() => {
+            return Err(AppError::InvalidConfig {
+                field: "sig_pattern".to_string(),
+                reason: "No signature check pattern configured".to_string(),
+            });
+        }
+        None => config.sig_check_signature(),
+    };
+    signature.validate()?;
+
+    let mut scanner = PatternScanner::with_caching(config.cache_patterns);
+    let found = bypass::find_signature(&mut scanner, &signature, module_info)?;
+    bypass::function_start(found, &signature, module_info).map(|start| start as usize)
+}
+
+/// Check `signature` applies to the game's file version, when it is known
+fn check_version(signature: &Signature, spec: &HookSpec, module_info: &MODULEINFO) -> Result<()> {
+    if signature.versions.is_any() {
+        return Ok(());
+    }
+    let Ok(version) = memory::version::module_version(module_info.lpBaseOfDll as HMODULE) else {
+        return Ok(());
+    };
+    if signature.versions.contains(version.file_version) {
+        return Ok(());
+    }
+    Err(AppError::InvalidConfig {
+        field: format!("hooks.{}.signature", spec.name),
+        reason: format!(
+            "signature '{}' applies to {}, not game version {}",
+            signature.name, signature.versions, version.file_version
+        ),
+    })
+}
+
+/// Register the signature check hook on the routine at `address`
+///
+/// The hook is applied together with the pak check hook by
+/// [`bypass::install_hooks`](crate::bypass). Uses the backend already
+/// selected on the global hook manager.
+pub fn register(action: HookAction, address: usize) -> Result<()> {
+    let manager = hooks::global_hook_manager();
+    SIG_CHECK_DRY_RUN.store(action.is_observe(), Ordering::Relaxed);
+
+    manager.register(SIG_CHECK_HOOK_NAME, address, sig_check_replacement)?;
+    if let Some(stats) = manager.stats(SIG_CHECK_HOOK_NAME) {
+        let _ = SIG_CHECK_STATS.set(stats);
+    }
+    if let Some(spoof) = manager.spoof(SIG_CHECK_HOOK_NAME) {
+        spoof.set(action.spoof_value());
+        let _ = SIG_CHECK_SPOOF.set(spoof);
+    }
+    Ok(())
+}
+
+/// Replacement for the `.sig` check
+///
+/// # Returns
+///
+/// The configured signature spoof value, or the result of the original
+/// check when the hook observes or calls the original
+unsafe extern "win64" fn sig_check_replacement(
+    reg: *mut Registers,
+    original: usize,
+    _: usize,
+) -> usize {
+    // Unwinding into the game would abort it; a panic gets the real check
+    std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| unsafe {
+        check_sig(reg, original)
+    }))
+    .unwrap_or_else(|_| unsafe { call_original(reg, original) }.unwrap_or(BYPASS_FAILURE))
+}
+
+/// Body of [`sig_check_replacement`]
+unsafe fn check_sig(reg: *mut Registers, original: usize) -> usize {
+    if let Some(stats) = SIG_CHECK_STATS.get() {
+        stats.record(None);
+    }
+    let caller = unsafe { ArgReader::new(reg) }
+        .and_then(|args| args.return_address())
+        .unwrap_or(0);
+
+    let spoof_value = SIG_CHECK_SPOOF.get().and_then(|spoof| spoof.value());
+    match spoof_value {
+        Some(value) if !SIG_CHECK_DRY_RUN.load(Ordering::Relaxed) => {
+            Logger::info(&format!(
+                "Signature check from {} -> OK",
+                describe_address(caller)
+            ));
+            value
+        }
+        _ => {
+            let result = unsafe { call_original(reg, original) }.unwrap_or(BYPASS_FAILURE);
+            Logger::info(&format!(
+                "Signature check from {} -> returned {:#x}",
+                describe_address(caller),
+                result
+            ));
+            result
+        }
+    }
+}
+
+/// Run the original signature check with the intercepted arguments
+unsafe fn call_original(reg: *mut Registers, original: usize) -> Option<usize> {
+    if original == 0 {
+        if MISSING_ORIGINAL.first() {
+            Logger::warning("Original signature check is unavailable, reporting failure");
+        }
+        return None;
+    }
+
+    let args = unsafe { ArgReader::new(reg) }.ok()?;
+    let original: SigCheckFn = unsafe { std::mem::transmute(original) };
+    Some(unsafe {
+        original(
+            args.arg(0).ok()?,
+            args.arg(1).ok()?,
+            args.arg(2).ok()?,
+            args.arg(3).ok()?,
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::constants::memory::MAX_FUNCTION_OFFSET;
+
+    #[test]
+    fn test_locate_requires_a_signature() {
+        let config = Config::new("test.exe", &[0x48], "x");
+        let info: MODULEINFO = unsafe { std::mem::zeroed() };
+        let mut spec = config.hook_spec(SIG_CHECK_HOOK_NAME).unwrap();
+        assert!(matches!(
+            locate(&config, &spec, &info),
+            Err(AppError::InvalidConfig { .. })
+        ));
+
+        spec.signature = Some("missing".to_string());
+        assert!(matches!(
+            locate(&config, &spec, &info),
+            Err(AppError::InvalidConfig { .. })
+        ));
+    }
+
+    #[test]
+    fn test_locate_rejects_far_function_offsets() {
+        let mut config = Config::new("test.exe", &[0x48], "x");
+        config.sig_pattern = vec![0x48, 0x8B];
+        config.sig_mask = "xx".to_string();
+        config.sig_function_offset = -(MAX_FUNCTION_OFFSET as isize) - 1;
+        assert!(config.validate().is_err());
+
+        let info: MODULEINFO = unsafe { std::mem::zeroed() };
+        let spec = config.hook_spec(SIG_CHECK_HOOK_NAME).unwrap();
+        assert!(matches!(
+            locate(&config, &spec, &info),
+            Err(AppError::InvalidConfig { .. })
+        ));
+    }
+
+    #[test]
+    fn test_missing_original_reports_failure() {
+        let mut registers: Registers = unsafe { std::mem::zeroed() };
+        assert!(unsafe { call_original(&mut registers, 0) }.is_none());
+    }
+}
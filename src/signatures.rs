@@ -0,0 +1,309 @@
+//! Named signatures for locating the pak check
+//!
+//! Game patches move or rewrite the pak check, so instead of one pattern the
+//! scan tries a list of named signatures in order and hooks the first that
+//! matches. Each entry carries its own distance back to the function start,
+//! optionally the PE section its match must lie in, and the range of game
+//! file versions it was written for.
+//!
+//! The scan reads the game's file version and only tries the entries whose
+//! range includes it, so CN and global clients can share one configuration.
+//!
+//! Entries come from `[signatures.<name>]` tables in the configuration file;
+//! the configured `pattern` is always tried last under [`DEFAULT_SIGNATURE`].
+
+use std::fmt;
+
+use crate::constants::constants::memory::MAX_FUNCTION_OFFSET;
+use crate::error::{AppError, Result};
+use crate::memory::version::VersionNumber;
+
+/// Name of the entry built from the configuration's own pattern
+pub const DEFAULT_SIGNATURE: &str = "default";
+
+/// Parse a pattern written as in the configuration file, such as
+/// `"48 8B ?? 05"`, into its bytes and mask
+///
+/// `?` and `??` are wildcards; the error names the first invalid token.
+pub fn parse_pattern(text: &str) -> std::result::Result<(Vec<u8>, String), String> {
+    let mut pattern = Vec::new();
+    let mut mask = String::new();
+    for token in text.split_whitespace() {
+        if token == "?" || token == "??" {
+            pattern.push(0);
+            mask.push('?');
+        } else {
+            let byte = u8::from_str_radix(token, 16)
+                .map_err(|_| format!("invalid signature byte '{}'", token))?;
+            pattern.push(byte);
+            mask.push('x');
+        }
+    }
+    Ok((pattern, mask))
+}
+
+/// Inclusive range of game file versions; open ends match everything
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct VersionRange {
+    pub min: Option<VersionNumber>,
+    pub max: Option<VersionNumber>,
+}
+
+impl VersionRange {
+    /// Range matching every version
+    pub fn any() -> Self {
+        Self::default()
+    }
+
+    /// Check if `version` lies inside the range
+    pub fn contains(&self, version: VersionNumber) -> bool {
+        self.min.is_none_or(|min| version >= min) && self.max.is_none_or(|max| version <= max)
+    }
+
+    /// Check if the range has no bounds
+    pub fn is_any(&self) -> bool {
+        self.min.is_none() && self.max.is_none()
+    }
+}
+
+impl fmt::Display for VersionRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.min, self.max) {
+            (None, None) => f.write_str("any version"),
+            (Some(min), None) => write!(f, "{} and later", min),
+            (None, Some(max)) => write!(f, "up to {}", max),
+            (Some(min), Some(max)) => write!(f, "{} to {}", min, max),
+        }
+    }
+}
+
+/// One named way of finding the pak check
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Signature {
+    pub name: String,
+    pub pattern: Vec<u8>,
+    /// Mask where 'x' means exact match and '?' a wildcard
+    pub mask: String,
+    /// Distance from the match back to the function start; negative when
+    /// the function starts after the match
+    pub function_offset: isize,
+    /// Section the match must lie in, such as `.text`; any when `None`
+    pub section: Option<String>,
+    /// Game file versions the signature applies to
+    pub versions: VersionRange,
+}
+
+impl Signature {
+    /// Create a signature matching in any section and version
+    pub fn new(name: &str, pattern: &[u8], mask: &str, function_offset: isize) -> Self {
+        Self {
+            name: name.to_string(),
+            pattern: pattern.to_vec(),
+            mask: mask.to_string(),
+            function_offset,
+            section: None,
+            versions: VersionRange::any(),
+        }
+    }
+
+    /// Check the pattern, mask, function offset and version range are
+    /// consistent
+    pub fn validate(&self) -> Result<()> {
+        let invalid = |reason: String| AppError::InvalidConfig {
+            field: format!("signatures.{}", self.name),
+            reason,
+        };
+
+        if self.name.is_empty() {
+            return Err(invalid("Signature name cannot be empty".to_string()));
+        }
+        if self.pattern.is_empty() {
+            return Err(invalid("Pattern cannot be empty".to_string()));
+        }
+        if self.pattern.len() != self.mask.len() {
+            return Err(invalid(format!(
+                "Pattern length ({}) must match mask length ({})",
+                self.pattern.len(),
+                self.mask.len()
+            )));
+        }
+        if let Some(position) = self.mask.chars().position(|ch| ch != 'x' && ch != '?') {
+            return Err(invalid(format!(
+                "Invalid mask character at position {} (only 'x' and '?' allowed)",
+                position
+            )));
+        }
+        if self.function_offset.unsigned_abs() > MAX_FUNCTION_OFFSET {
+            return Err(invalid(format!(
+                "Function offset {} is further than {:#x} bytes from the match",
+                self.function_offset, MAX_FUNCTION_OFFSET
+            )));
+        }
+        if let (Some(min), Some(max)) = (self.versions.min, self.versions.max)
+            && min > max
+        {
+            return Err(invalid(format!(
+                "Minimum version {} is above maximum version {}",
+                min, max
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Ordered list of signatures tried by the scan
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct SignatureDb {
+    entries: Vec<Signature>,
+}
+
+impl SignatureDb {
+    pub fn new(entries: Vec<Signature>) -> Self {
+        Self { entries }
+    }
+
+    /// Entries in the order they are tried
+    pub fn entries(&self) -> &[Signature] {
+        &self.entries
+    }
+
+    /// Find an entry by name
+    pub fn get(&self, name: &str) -> Option<&Signature> {
+        self.entries.iter().find(|entry| entry.name == name)
+    }
+
+    /// Number of entries
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Check if the database has no entries
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Entries applying to a game file version, in order
+    ///
+    /// With the version unknown every entry is kept, so the scan can still
+    /// find the function on clients without a version resource.
+    pub fn for_version(&self, version: Option<VersionNumber>) -> SignatureDb {
+        let entries = self
+            .entries
+            .iter()
+            .filter(|entry| version.is_none_or(|version| entry.versions.contains(version)))
+            .cloned()
+            .collect();
+        Self { entries }
+    }
+
+    /// Validate every entry and check names are unique
+    pub fn validate(&self) -> Result<()> {
+        for (index, entry) in self.entries.iter().enumerate() {
+            entry.validate()?;
+            if self.entries[..index]
+                .iter()
+                .any(|earlier| earlier.name == entry.name)
+            {
+                return Err(AppError::InvalidConfig {
+                    field: format!("signatures.{}", entry.name),
+                    reason: "Signature names must be unique".to_string(),
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn version(text: &str) -> VersionNumber {
+        text.parse().unwrap()
+    }
+
+    #[test]
+    fn test_version_range_bounds_are_inclusive() {
+        let range = VersionRange {
+            min: Some(version("2.4")),
+            max: Some(version("2.5.99")),
+        };
+        assert!(range.contains(version("2.4")));
+        assert!(range.contains(version("2.5.99")));
+        assert!(!range.contains(version("2.3.9")));
+        assert!(!range.contains(version("2.6")));
+        assert!(VersionRange::any().contains(version("1.0")));
+        assert_eq!(range.to_string(), "2.4.0.0 to 2.5.99.0");
+    }
+
+    #[test]
+    fn test_parse_pattern() {
+        let (pattern, mask) = parse_pattern("48 8b ?? 05 ?").unwrap();
+        assert_eq!(pattern, [0x48, 0x8B, 0, 0x05, 0]);
+        assert_eq!(mask, "xx?x?");
+        assert_eq!(
+            parse_pattern("48 zz").unwrap_err(),
+            "invalid signature byte 'zz'"
+        );
+    }
+
+    #[test]
+    fn test_signature_validation() {
+        let mut signature = Signature::new("global", &[0x49, 0x81], "x?", 0x45);
+        assert!(signature.validate().is_ok());
+
+        signature.mask = "x".to_string();
+        assert!(signature.validate().is_err());
+
+        signature.mask = "xy".to_string();
+        assert!(signature.validate().is_err());
+
+        signature.mask = "xx".to_string();
+        signature.function_offset = -0x20;
+        assert!(signature.validate().is_ok());
+
+        signature.function_offset = MAX_FUNCTION_OFFSET as isize + 1;
+        assert!(signature.validate().is_err());
+
+        signature.function_offset = 0x45;
+        signature.versions = VersionRange {
+            min: Some(version("2.5")),
+            max: Some(version("2.4")),
+        };
+        assert!(signature.validate().is_err());
+    }
+
+    #[test]
+    fn test_for_version_keeps_matching_entries_in_order() {
+        let mut global = Signature::new("global", &[0x49], "x", 0);
+        global.versions.min = Some(version("2.5"));
+        let mut cn = Signature::new("cn", &[0x48], "x", 0);
+        cn.versions.max = Some(version("2.4.99"));
+        let fallback = Signature::new(DEFAULT_SIGNATURE, &[0x40], "x", 0);
+        let db = SignatureDb::new(vec![global, cn, fallback]);
+
+        let names = |db: SignatureDb| -> Vec<String> {
+            db.entries()
+                .iter()
+                .map(|entry| entry.name.clone())
+                .collect()
+        };
+        assert_eq!(
+            names(db.for_version(Some(version("2.5.1")))),
+            ["global", DEFAULT_SIGNATURE]
+        );
+        assert_eq!(
+            names(db.for_version(Some(version("2.4")))),
+            ["cn", DEFAULT_SIGNATURE]
+        );
+        assert_eq!(db.for_version(None).len(), 3);
+    }
+
+    #[test]
+    fn test_db_rejects_duplicate_names() {
+        let entry = Signature::new("global", &[0x49], "x", 0);
+        let db = SignatureDb::new(vec![entry.clone(), entry]);
+        assert!(matches!(db.validate(), Err(AppError::InvalidConfig { .. })));
+        assert_eq!(db.get("global").map(|entry| entry.pattern.len()), Some(1));
+    }
+}
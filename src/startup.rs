@@ -0,0 +1,329 @@
+use std::fmt;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU8, AtomicU32, Ordering};
+
+use crate::error::AppError;
+#[cfg(feature = "dll")]
+use crate::logger::Logger;
+use crate::memory::Rva;
+#[cfg(feature = "dll")]
+use crate::{overlay, ready};
+
+/// Phases of the bypass initialization, in the order they are entered
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[repr(u8)]
+pub enum StartupPhase {
+    NotStarted = 0,
+    ConsoleInit = 1,
+    ConfigLoad = 2,
+    ModuleWait = 3,
+    Scan = 4,
+    AceWait = 5,
+    Hook = 6,
+    Running = 7,
+    Degraded = 8,
+    Failed = 9,
+}
+
+impl StartupPhase {
+    /// Convert a raw phase value back into a phase
+    pub fn from_u8(value: u8) -> Option<Self> {
+        match value {
+            0 => Some(Self::NotStarted),
+            1 => Some(Self::ConsoleInit),
+            2 => Some(Self::ConfigLoad),
+            3 => Some(Self::ModuleWait),
+            4 => Some(Self::Scan),
+            5 => Some(Self::AceWait),
+            6 => Some(Self::Hook),
+            7 => Some(Self::Running),
+            8 => Some(Self::Degraded),
+            9 => Some(Self::Failed),
+            _ => None,
+        }
+    }
+
+    /// Check if no further transitions are expected from this phase
+    pub fn is_terminal(self) -> bool {
+        self == Self::Failed
+    }
+
+    /// Check if the bypass is in place in this phase
+    pub fn is_operational(self) -> bool {
+        matches!(self, Self::Running | Self::Degraded)
+    }
+
+    /// Check if moving from this phase to `next` is a valid transition
+    ///
+    /// Initialization only moves forward; any non-terminal phase may fail,
+    /// and only a hooked or running bypass may become degraded.
+    pub fn can_transition_to(self, next: StartupPhase) -> bool {
+        match next {
+            Self::Failed => !self.is_terminal(),
+            Self::Degraded => matches!(self, Self::Hook | Self::Running),
+            Self::NotStarted => false,
+            _ => !self.is_terminal() && self != Self::Degraded && next > self,
+        }
+    }
+}
+
+impl fmt::Display for StartupPhase {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::NotStarted => "NotStarted",
+            Self::ConsoleInit => "ConsoleInit",
+            Self::ConfigLoad => "ConfigLoad",
+            Self::ModuleWait => "ModuleWait",
+            Self::Scan => "Scan",
+            Self::AceWait => "AceWait",
+            Self::Hook => "Hook",
+            Self::Running => "Running",
+            Self::Degraded => "Degraded",
+            Self::Failed => "Failed",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Why initialization failed or degraded, and in which phase
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StartupIssue {
+    pub phase: StartupPhase,
+    pub message: String,
+}
+
+/// Thread-safe tracker for the current startup phase
+pub struct StartupStatus {
+    phase: AtomicU8,
+    issue: Mutex<Option<StartupIssue>>,
+    /// RVA of the pak check in its module; zero until found
+    target: AtomicU32,
+    /// [`AppError::code`] of the failure; zero unless failed
+    error_code: AtomicU32,
+}
+
+impl StartupStatus {
+    /// Create a tracker in the `NotStarted` phase
+    pub const fn new() -> Self {
+        Self {
+            phase: AtomicU8::new(StartupPhase::NotStarted as u8),
+            issue: Mutex::new(None),
+            target: AtomicU32::new(0),
+            error_code: AtomicU32::new(0),
+        }
+    }
+
+    /// Get the current phase
+    pub fn phase(&self) -> StartupPhase {
+        StartupPhase::from_u8(self.phase.load(Ordering::Acquire)).unwrap_or(StartupPhase::Failed)
+    }
+
+    /// Move to the next phase, returning the previous one
+    ///
+    /// Invalid transitions are rejected and leave the phase unchanged.
+    pub fn transition(&self, next: StartupPhase) -> Option<StartupPhase> {
+        let current = self.phase();
+        if !current.can_transition_to(next) {
+            return None;
+        }
+
+        self.phase.store(next as u8, Ordering::Release);
+        Some(current)
+    }
+
+    /// Record an issue for the current phase and move to `next`
+    pub fn record(&self, next: StartupPhase, message: String) -> Option<StartupPhase> {
+        let phase = self.phase();
+        let previous = self.transition(next)?;
+
+        if let Ok(mut issue) = self.issue.lock() {
+            *issue = Some(StartupIssue { phase, message });
+        }
+
+        Some(previous)
+    }
+
+    /// Get the issue that caused a failure or degradation, if any
+    pub fn issue(&self) -> Option<StartupIssue> {
+        self.issue.lock().ok().and_then(|issue| issue.clone())
+    }
+
+    /// Record where the pak check was found
+    pub fn set_target(&self, rva: Rva) {
+        self.target.store(rva.0, Ordering::Release);
+    }
+
+    /// Get where the pak check was found, once the scan succeeded
+    pub fn target(&self) -> Option<Rva> {
+        match self.target.load(Ordering::Acquire) {
+            0 => None,
+            rva => Some(Rva(rva)),
+        }
+    }
+
+    /// Mark initialization as failed with `error` in the current phase
+    pub fn record_failure(&self, error: &AppError) -> Option<StartupPhase> {
+        let previous = self.record(StartupPhase::Failed, error.to_string())?;
+        self.error_code.store(error.code(), Ordering::Release);
+        Some(previous)
+    }
+
+    /// Get the [`AppError::code`] initialization failed with, if it did
+    pub fn error_code(&self) -> Option<u32> {
+        match self.error_code.load(Ordering::Acquire) {
+            0 => None,
+            code => Some(code),
+        }
+    }
+}
+
+impl Default for StartupStatus {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Global startup status shared with status APIs
+static STARTUP_STATUS: StartupStatus = StartupStatus::new();
+
+/// Get the global startup status
+pub fn status() -> &'static StartupStatus {
+    &STARTUP_STATUS
+}
+
+/// Get the current global startup phase
+pub fn current_phase() -> StartupPhase {
+    STARTUP_STATUS.phase()
+}
+
+/// Mirror the status into the shared-memory overlay, if published
+#[cfg(feature = "dll")]
+fn publish() {
+    if let Some(overlay) = overlay::global_overlay() {
+        overlay.set_status(&STARTUP_STATUS);
+    }
+}
+
+/// Enter the next startup phase and log the transition
+#[cfg(feature = "dll")]
+pub(crate) fn enter(next: StartupPhase) {
+    match STARTUP_STATUS.transition(next) {
+        Some(previous) => {
+            publish();
+            if next == StartupPhase::Running {
+                ready::signal_ready();
+            }
+            Logger::info(&format!("Startup phase: {} -> {}", previous, next));
+        }
+        None => Logger::warning(&format!(
+            "Ignoring invalid startup phase transition: {} -> {}",
+            current_phase(),
+            next
+        )),
+    }
+}
+
+/// Mark initialization as failed in the current phase
+#[cfg(feature = "dll")]
+pub(crate) fn fail(error: &AppError) {
+    let phase = current_phase();
+    if STARTUP_STATUS.record_failure(error).is_some() {
+        publish();
+        ready::signal_failed(error.code());
+        Logger::error(&format!(
+            "Startup phase: {} -> {} ({})",
+            phase,
+            StartupPhase::Failed,
+            error
+        ));
+    }
+}
+
+/// Mark the bypass as running with reduced confidence
+#[cfg(feature = "dll")]
+pub(crate) fn degrade(reason: &str) {
+    let phase = current_phase();
+    if STARTUP_STATUS
+        .record(StartupPhase::Degraded, reason.to_string())
+        .is_some()
+    {
+        publish();
+        Logger::warning(&format!(
+            "Startup phase: {} -> {} ({})",
+            phase,
+            StartupPhase::Degraded,
+            reason
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_phase_moves_forward_only() {
+        assert!(StartupPhase::NotStarted.can_transition_to(StartupPhase::ConsoleInit));
+        assert!(StartupPhase::Scan.can_transition_to(StartupPhase::AceWait));
+        assert!(!StartupPhase::AceWait.can_transition_to(StartupPhase::Scan));
+        assert!(!StartupPhase::Running.can_transition_to(StartupPhase::NotStarted));
+    }
+
+    #[test]
+    fn test_phase_failure_and_degradation() {
+        assert!(StartupPhase::ModuleWait.can_transition_to(StartupPhase::Failed));
+        assert!(StartupPhase::Running.can_transition_to(StartupPhase::Degraded));
+        assert!(!StartupPhase::Scan.can_transition_to(StartupPhase::Degraded));
+        assert!(!StartupPhase::Failed.can_transition_to(StartupPhase::Running));
+    }
+
+    #[test]
+    fn test_phase_round_trip() {
+        for value in 0..=9 {
+            let phase = StartupPhase::from_u8(value).unwrap();
+            assert_eq!(phase as u8, value);
+        }
+        assert!(StartupPhase::from_u8(10).is_none());
+    }
+
+    #[test]
+    fn test_status_records_failure_phase() {
+        let status = StartupStatus::new();
+        assert_eq!(
+            status.transition(StartupPhase::ConsoleInit),
+            Some(StartupPhase::NotStarted)
+        );
+        assert_eq!(
+            status.transition(StartupPhase::Scan),
+            Some(StartupPhase::ConsoleInit)
+        );
+        assert!(status.transition(StartupPhase::ConfigLoad).is_none());
+
+        status.record(StartupPhase::Failed, "pattern not found".to_string());
+        assert_eq!(status.phase(), StartupPhase::Failed);
+        assert_eq!(
+            status.issue(),
+            Some(StartupIssue {
+                phase: StartupPhase::Scan,
+                message: "pattern not found".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn test_status_records_target_and_error_code() {
+        let status = StartupStatus::new();
+        assert_eq!(status.target(), None);
+        status.set_target(Rva(0x1234));
+        assert_eq!(status.target(), Some(Rva(0x1234)));
+
+        assert_eq!(status.error_code(), None);
+        let error = AppError::Timeout {
+            operation: "ACE initialization".to_string(),
+            elapsed: std::time::Duration::from_secs(1),
+        };
+        status.record_failure(&error);
+        assert_eq!(status.phase(), StartupPhase::Failed);
+        assert_eq!(status.error_code(), Some(error.code()));
+    }
+}
@@ -0,0 +1,509 @@
+//! Opt-in download of the signature database
+//!
+//! With `signature_update_url` set, the scan's signatures are refreshed at
+//! startup from a file of `[signatures.<name>]` tables at that URL. The file
+//! needs a detached signature at `<url>.sig`: the 64 bytes `r || s` of an
+//! ECDSA P-256 signature over its SHA-256, checked against
+//! `signature_update_key`. A verified download is cached next to the DLL and
+//! used, checked again, whenever the server cannot be reached.
+//!
+//! The database's top-level `version` must not be lower than the cached
+//! one's, so an old signed file cannot be replayed over a newer one.
+//!
+//! Downloaded entries are tried before the configuration's own; an entry
+//! named like one of those is skipped so local fixes always win.
+
+use std::path::Path;
+use std::ptr;
+
+use sha2::{Digest, Sha256};
+use winapi::shared::bcrypt::{
+    BCRYPT_ALG_HANDLE, BCRYPT_ECCKEY_BLOB, BCRYPT_ECCPUBLIC_BLOB, BCRYPT_ECDSA_P256_ALGORITHM,
+    BCRYPT_ECDSA_PUBLIC_P256_MAGIC, BCRYPT_KEY_HANDLE, BCryptCloseAlgorithmProvider,
+    BCryptDestroyKey, BCryptImportKeyPair, BCryptOpenAlgorithmProvider, BCryptVerifySignature,
+};
+use winapi::shared::minwindef::DWORD;
+use winapi::um::winhttp::{
+    HINTERNET, WINHTTP_ACCESS_TYPE_AUTOMATIC_PROXY, WINHTTP_FLAG_SECURE, WINHTTP_QUERY_FLAG_NUMBER,
+    WINHTTP_QUERY_STATUS_CODE, WinHttpCloseHandle, WinHttpConnect, WinHttpOpen, WinHttpOpenRequest,
+    WinHttpQueryHeaders, WinHttpReadData, WinHttpReceiveResponse, WinHttpSendRequest,
+    WinHttpSetTimeouts,
+};
+
+use crate::config::Config;
+use crate::constants::constants::{MAX_SIGNATURE_DB_SIZE, SIGNATURE_CACHE_FILE_NAME};
+use crate::error::{AppError, Result};
+use crate::logger::Logger;
+use crate::safety::StringConverter;
+use crate::signatures::Signature;
+
+/// Length of an uncompressed P-256 public key without its prefix: X then Y
+pub const PUBLIC_KEY_LEN: usize = 64;
+
+/// Length of a P-256 signature: r then s
+pub const SIGNATURE_LEN: usize = 64;
+
+/// Parts of an update URL
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UpdateUrl {
+    pub secure: bool,
+    pub host: String,
+    pub port: u16,
+    /// Path and query, starting with `/`
+    pub path: String,
+}
+
+/// Split an `http://` or `https://` URL into its parts
+pub fn parse_url(url: &str) -> Result<UpdateUrl> {
+    let invalid = |reason: &str| AppError::InvalidConfig {
+        field: "signature_update_url".to_string(),
+        reason: format!("{} in '{}'", reason, url),
+    };
+
+    let (secure, rest) = if let Some(rest) = url.strip_prefix("https://") {
+        (true, rest)
+    } else if let Some(rest) = url.strip_prefix("http://") {
+        (false, rest)
+    } else {
+        return Err(invalid("Expected an http:// or https:// URL"));
+    };
+
+    let (authority, path) = match rest.find('/') {
+        Some(index) => rest.split_at(index),
+        None => (rest, "/"),
+    };
+    let (host, port) = match authority.rsplit_once(':') {
+        Some((host, port)) => (host, port.parse().map_err(|_| invalid("Invalid port"))?),
+        None => (authority, if secure { 443 } else { 80 }),
+    };
+    if host.is_empty() {
+        return Err(invalid("Missing host"));
+    }
+
+    Ok(UpdateUrl {
+        secure,
+        host: host.to_string(),
+        port,
+        path: path.to_string(),
+    })
+}
+
+/// Decode a public key written as 128 hex digits
+pub fn parse_public_key(hex: &str) -> Result<[u8; PUBLIC_KEY_LEN]> {
+    let invalid = || AppError::InvalidConfig {
+        field: "signature_update_key".to_string(),
+        reason: format!(
+            "Expected {} hex digits of the P-256 public key",
+            PUBLIC_KEY_LEN * 2
+        ),
+    };
+
+    let digits: Vec<u8> = hex.bytes().filter(|b| !b.is_ascii_whitespace()).collect();
+    if digits.len() != PUBLIC_KEY_LEN * 2 {
+        return Err(invalid());
+    }
+    let mut key = [0u8; PUBLIC_KEY_LEN];
+    for (byte, pair) in key.iter_mut().zip(digits.chunks(2)) {
+        let pair = std::str::from_utf8(pair).map_err(|_| invalid())?;
+        *byte = u8::from_str_radix(pair, 16).map_err(|_| invalid())?;
+    }
+    Ok(key)
+}
+
+/// Check `signature` is a valid signature of `data` under `public_key`
+pub fn verify(data: &[u8], signature: &[u8], public_key: &[u8; PUBLIC_KEY_LEN]) -> Result<()> {
+    if signature.len() != SIGNATURE_LEN {
+        return Err(AppError::SignatureInvalid {
+            reason: format!(
+                "signature is {} bytes, expected {}",
+                signature.len(),
+                SIGNATURE_LEN
+            ),
+        });
+    }
+
+    let failed = |step: &str, status: i32| AppError::SignatureInvalid {
+        reason: format!("{} failed with status {:#x}", step, status),
+    };
+    let digest = Sha256::digest(data);
+
+    let mut blob = Vec::with_capacity(size_of::<BCRYPT_ECCKEY_BLOB>() + PUBLIC_KEY_LEN);
+    blob.extend_from_slice(&BCRYPT_ECDSA_PUBLIC_P256_MAGIC.to_le_bytes());
+    blob.extend_from_slice(&((PUBLIC_KEY_LEN / 2) as u32).to_le_bytes());
+    blob.extend_from_slice(public_key);
+
+    let algorithm_name = StringConverter::to_wide_null(BCRYPT_ECDSA_P256_ALGORITHM);
+    let blob_type = StringConverter::to_wide_null(BCRYPT_ECCPUBLIC_BLOB);
+    let mut algorithm: BCRYPT_ALG_HANDLE = ptr::null_mut();
+    let status = unsafe {
+        BCryptOpenAlgorithmProvider(&mut algorithm, algorithm_name.as_ptr(), ptr::null(), 0)
+    };
+    if status != 0 {
+        return Err(failed("BCryptOpenAlgorithmProvider", status));
+    }
+
+    let mut key: BCRYPT_KEY_HANDLE = ptr::null_mut();
+    let mut status = unsafe {
+        BCryptImportKeyPair(
+            algorithm,
+            ptr::null_mut(),
+            blob_type.as_ptr(),
+            &mut key,
+            blob.as_mut_ptr(),
+            blob.len() as u32,
+            0,
+        )
+    };
+    let mut step = "BCryptImportKeyPair";
+    if status == 0 {
+        let mut digest = digest.to_vec();
+        let mut signature = signature.to_vec();
+        status = unsafe {
+            BCryptVerifySignature(
+                key,
+                ptr::null_mut(),
+                digest.as_mut_ptr(),
+                digest.len() as u32,
+                signature.as_mut_ptr(),
+                signature.len() as u32,
+                0,
+            )
+        };
+        step = "BCryptVerifySignature";
+        unsafe { BCryptDestroyKey(key) };
+    }
+    unsafe { BCryptCloseAlgorithmProvider(algorithm, 0) };
+
+    if status == 0 {
+        Ok(())
+    } else {
+        Err(failed(step, status))
+    }
+}
+
+/// WinHTTP handle closed on drop
+struct Internet(HINTERNET);
+
+impl Internet {
+    fn open(handle: HINTERNET, url: &str, step: &str) -> Result<Self> {
+        if handle.is_null() {
+            Err(AppError::SignatureUpdate {
+                url: url.to_string(),
+                reason: format!("{} failed: {}", step, std::io::Error::last_os_error()),
+            })
+        } else {
+            Ok(Self(handle))
+        }
+    }
+}
+
+impl Drop for Internet {
+    fn drop(&mut self) {
+        unsafe { WinHttpCloseHandle(self.0) };
+    }
+}
+
+/// Download `url` with a GET request, up to [`MAX_SIGNATURE_DB_SIZE`] bytes
+pub fn download(url: &str, timeout_ms: u64) -> Result<Vec<u8>> {
+    let parts = parse_url(url)?;
+    let failed = |reason: String| AppError::SignatureUpdate {
+        url: url.to_string(),
+        reason,
+    };
+    let last_error = |step: &str| {
+        failed(format!(
+            "{} failed: {}",
+            step,
+            std::io::Error::last_os_error()
+        ))
+    };
+
+    let agent = StringConverter::to_wide_null(concat!("wuwa-sig-rs/", env!("CARGO_PKG_VERSION")));
+    let session = Internet::open(
+        unsafe {
+            WinHttpOpen(
+                agent.as_ptr(),
+                WINHTTP_ACCESS_TYPE_AUTOMATIC_PROXY,
+                ptr::null(),
+                ptr::null(),
+                0,
+            )
+        },
+        url,
+        "WinHttpOpen",
+    )?;
+    let timeout = timeout_ms.min(i32::MAX as u64) as i32;
+    unsafe { WinHttpSetTimeouts(session.0, timeout, timeout, timeout, timeout) };
+
+    let host = StringConverter::to_wide_null(&parts.host);
+    let connection = Internet::open(
+        unsafe { WinHttpConnect(session.0, host.as_ptr(), parts.port, 0) },
+        url,
+        "WinHttpConnect",
+    )?;
+
+    let verb = StringConverter::to_wide_null("GET");
+    let path = StringConverter::to_wide_null(&parts.path);
+    let flags = if parts.secure { WINHTTP_FLAG_SECURE } else { 0 };
+    let request = Internet::open(
+        unsafe {
+            WinHttpOpenRequest(
+                connection.0,
+                verb.as_ptr(),
+                path.as_ptr(),
+                ptr::null(),
+                ptr::null(),
+                ptr::null_mut(),
+                flags,
+            )
+        },
+        url,
+        "WinHttpOpenRequest",
+    )?;
+
+    if unsafe { WinHttpSendRequest(request.0, ptr::null(), 0, ptr::null_mut(), 0, 0, 0) } == 0 {
+        return Err(last_error("WinHttpSendRequest"));
+    }
+    if unsafe { WinHttpReceiveResponse(request.0, ptr::null_mut()) } == 0 {
+        return Err(last_error("WinHttpReceiveResponse"));
+    }
+
+    let mut status: DWORD = 0;
+    let mut status_len = size_of::<DWORD>() as DWORD;
+    let queried = unsafe {
+        WinHttpQueryHeaders(
+            request.0,
+            WINHTTP_QUERY_STATUS_CODE | WINHTTP_QUERY_FLAG_NUMBER,
+            ptr::null(),
+            &mut status as *mut DWORD as *mut _,
+            &mut status_len,
+            ptr::null_mut(),
+        )
+    };
+    if queried == 0 {
+        return Err(last_error("WinHttpQueryHeaders"));
+    }
+    if status != 200 {
+        return Err(failed(format!("server answered with status {}", status)));
+    }
+
+    let mut body = Vec::new();
+    let mut chunk = [0u8; 8192];
+    loop {
+        let mut read: DWORD = 0;
+        let ok = unsafe {
+            WinHttpReadData(
+                request.0,
+                chunk.as_mut_ptr() as *mut _,
+                chunk.len() as DWORD,
+                &mut read,
+            )
+        };
+        if ok == 0 {
+            return Err(last_error("WinHttpReadData"));
+        }
+        if read == 0 {
+            return Ok(body);
+        }
+        body.extend_from_slice(&chunk[..read as usize]);
+        if body.len() > MAX_SIGNATURE_DB_SIZE {
+            return Err(failed(format!(
+                "database is larger than {} bytes",
+                MAX_SIGNATURE_DB_SIZE
+            )));
+        }
+    }
+}
+
+/// Parse a verified database into its version and signatures
+fn parse_database(data: &[u8]) -> Result<(u32, Vec<Signature>)> {
+    let text = std::str::from_utf8(data).map_err(|e| AppError::StringConversion {
+        details: format!("signature database is not UTF-8: {}", e),
+    })?;
+    let (version, signatures) = Config::signatures_from_toml(text)?;
+    for signature in &signatures {
+        signature.validate()?;
+    }
+    Ok((version, signatures))
+}
+
+/// Verify and parse the cached database, `None` when there is no cache
+fn read_cache(
+    data_path: &Path,
+    signature_path: &Path,
+    key: &[u8; PUBLIC_KEY_LEN],
+) -> Option<Result<(u32, Vec<Signature>)>> {
+    let (Ok(data), Ok(signature)) = (std::fs::read(data_path), std::fs::read(signature_path))
+    else {
+        return None;
+    };
+    Some(verify(&data, &signature, key).and_then(|()| parse_database(&data)))
+}
+
+/// Reject a database older than the cached one
+fn check_not_older(version: u32, cached_version: Option<u32>) -> Result<()> {
+    match cached_version {
+        Some(cached_version) if version < cached_version => Err(AppError::SignatureInvalid {
+            reason: format!(
+                "database version {} is older than the cached version {}",
+                version, cached_version
+            ),
+        }),
+        _ => Ok(()),
+    }
+}
+
+/// Download, verify and cache the database, falling back to the cache
+///
+/// `cache_dir` is where [`SIGNATURE_CACHE_FILE_NAME`] and its `.sig` are
+/// kept, normally the DLL's directory.
+pub fn fetch(config: &Config, cache_dir: Option<&Path>) -> Result<Vec<Signature>> {
+    let url = config.signature_update_url.as_deref().unwrap_or_default();
+    let key = parse_public_key(config.signature_update_key.as_deref().unwrap_or_default())?;
+    let cache = cache_dir.map(|dir| {
+        let data = dir.join(SIGNATURE_CACHE_FILE_NAME);
+        let signature = data.with_extension("toml.sig");
+        (data, signature)
+    });
+    let cached = cache
+        .as_ref()
+        .and_then(|(data_path, signature_path)| read_cache(data_path, signature_path, &key));
+    let cached_version = match &cached {
+        Some(Ok((version, _))) => Some(*version),
+        _ => None,
+    };
+
+    let timeout = config.signature_update_timeout_ms;
+    let downloaded = download(url, timeout).and_then(|data| {
+        let signature = download(&format!("{}.sig", url), timeout)?;
+        verify(&data, &signature, &key)?;
+        let (version, signatures) = parse_database(&data)?;
+        check_not_older(version, cached_version)?;
+        Ok((data, signature, version, signatures))
+    });
+
+    match downloaded {
+        Ok((data, signature, version, signatures)) => {
+            Logger::info(&format!(
+                "Downloaded {} signature(s), database version {}, from '{}'",
+                signatures.len(),
+                version,
+                url
+            ));
+            if let Some((data_path, signature_path)) = &cache {
+                let written = std::fs::write(data_path, &data)
+                    .and_then(|()| std::fs::write(signature_path, &signature));
+                if let Err(e) = written {
+                    Logger::warning(&format!("Signature database not cached: {}", e));
+                }
+            }
+            Ok(signatures)
+        }
+        Err(e) => {
+            Logger::warning(&format!("Signature update unavailable: {}", e));
+            let (Some((data_path, _)), Some(cached)) = (&cache, cached) else {
+                return Err(e);
+            };
+            let (version, signatures) = cached?;
+            Logger::info(&format!(
+                "Using {} cached signature(s), database version {}, from '{}'",
+                signatures.len(),
+                version,
+                data_path.display()
+            ));
+            Ok(signatures)
+        }
+    }
+}
+
+/// Keep downloaded signatures on `config`, to be tried ahead of the
+/// configured ones
+///
+/// Returns how many are used; names the configuration already uses are
+/// skipped. They are kept apart from `signatures`, so a reloaded
+/// configuration file compares equal without them.
+pub fn merge(config: &mut Config, downloaded: Vec<Signature>) -> usize {
+    config.downloaded_signatures = downloaded;
+    let local = config.signatures.len() + 1;
+    config.signature_db().len() - local
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::signatures::DEFAULT_SIGNATURE;
+
+    #[test]
+    fn test_parse_url() {
+        assert_eq!(
+            parse_url("https://example.com/sigs/db.toml").unwrap(),
+            UpdateUrl {
+                secure: true,
+                host: "example.com".to_string(),
+                port: 443,
+                path: "/sigs/db.toml".to_string(),
+            }
+        );
+        let plain = parse_url("http://localhost:8080").unwrap();
+        assert_eq!((plain.secure, plain.port), (false, 8080));
+        assert_eq!(plain.path, "/");
+
+        for url in ["ftp://example.com/db", "https:///db", "http://host:port/db"] {
+            assert!(parse_url(url).is_err(), "{}", url);
+        }
+    }
+
+    #[test]
+    fn test_parse_public_key() {
+        let hex = "0A".repeat(PUBLIC_KEY_LEN);
+        assert_eq!(parse_public_key(&hex).unwrap(), [0x0A; PUBLIC_KEY_LEN]);
+        assert!(parse_public_key(&hex[2..]).is_err());
+        assert!(parse_public_key(&"ZZ".repeat(PUBLIC_KEY_LEN)).is_err());
+    }
+
+    #[test]
+    fn test_verify_rejects_wrong_signature_length() {
+        let key = [0u8; PUBLIC_KEY_LEN];
+        assert!(matches!(
+            verify(b"data", &[0; 10], &key),
+            Err(AppError::SignatureInvalid { .. })
+        ));
+    }
+
+    #[test]
+    fn test_merge_puts_downloads_first_and_keeps_local_names() {
+        let mut config = Config::new("test.exe", &[0x48], "x");
+        config.signatures = vec![Signature::new("local", &[0x49], "x", 0)];
+
+        let added = merge(
+            &mut config,
+            vec![
+                Signature::new("remote", &[0x4A], "x", 0),
+                Signature::new("local", &[0x4B], "x", 0),
+                Signature::new(DEFAULT_SIGNATURE, &[0x4C], "x", 0),
+            ],
+        );
+        assert_eq!(added, 1);
+
+        let db = config.signature_db();
+        let names: Vec<&str> = db
+            .entries()
+            .iter()
+            .map(|signature| signature.name.as_str())
+            .collect();
+        assert_eq!(names, ["remote", "local", DEFAULT_SIGNATURE]);
+        assert_eq!(db.get("local").unwrap().pattern, [0x49]);
+        assert_eq!(config.signatures.len(), 1);
+    }
+
+    #[test]
+    fn test_older_database_is_rejected() {
+        assert!(check_not_older(3, None).is_ok());
+        assert!(check_not_older(3, Some(3)).is_ok());
+        assert!(check_not_older(4, Some(3)).is_ok());
+        assert!(matches!(
+            check_not_older(2, Some(3)),
+            Err(AppError::SignatureInvalid { .. })
+        ));
+    }
+}